@@ -13,25 +13,56 @@
 // limitations under the License.
 
 use crate::error::ContractError;
+use crate::migration::{
+    is_migrating, migrate_positions, start_default_vaults_migration, start_legacy_migration,
+};
+use crate::position::{
+    approve_position, query_all_tokens, query_contract_info, query_nft_info, query_num_tokens,
+    query_owner_of, query_tokens, revoke_position, send_position, transfer_position,
+};
 use crate::state::{
-    read_collaterals, read_config, read_minter_loan_info, read_new_owner, read_redemeption_list,
-    read_whitelist, read_whitelist_elem, store_collaterals, store_config, store_minter_loan_info,
-    store_new_owner, store_whitelist_elem, Config, WhitelistElem, NewOwnerAddr,
+    mint_default_vault_token, open_vault, read_collaterals, read_config, read_last_liquidation,
+    read_liquidation_batch, read_liquidation_record, read_liquidation_records,
+    read_minter_loan_info, read_new_owner, read_operator, read_operators, read_redemeption_list,
+    read_vaults, read_whitelist, read_whitelist_elem, remove_operator, store_collaterals,
+    store_config, store_last_liquidation, store_liquidation_batch, store_liquidation_record,
+    store_minter_loan_info, store_new_owner, store_operator, store_whitelist_elem,
+    update_liquidation_record, vault_exists, Config, LiquidatedCollateralElem, LiquidationRecord,
+    NewOwnerAddr, OperatorApproval, WhitelistElem,
 };
 use cdp::central_control::{
-    CollateralAvailableRespone, ConfigResponse, ExecuteMsg, InstantiateMsg, LoanInfoResponse,
-    MigrateMsg, MinterCollateralResponse, MinterLoanResponse, QueryMsg,
-    RedemptionProviderListRespone, WhitelistElemResponse, WhitelistResponse,
+    CollateralAvailableRespone, ConfigResponse, ExecuteMsg, InstantiateMsg, KeeperReward,
+    LiquidateCollateralsResponse, LiquidatedCollateral, LiquidationHistoryResponse,
+    LiquidationRecordResponse, LiquidationResult, LiquidationStatus, LoanInfoResponse,
+    MigrateMsg, MinterCollateralResponse, MinterLoanResponse, OperatorPermission,
+    OperatorResponse, OperatorsResponse, QueryMsg, RedemptionProviderListRespone, VaultsResponse,
+    WhitelistElemResponse, WhitelistResponse, DEFAULT_LIQUIDATION_RECORD_RETENTION,
+    DEFAULT_MAX_LIQUIDATION_BATCH_SIZE, DEFAULT_VAULT_ID,
 };
 use cdp::handle::optional_addr_validate;
 use cdp::liquidation_queue::LiquidationAmountResponse;
-use cdp::querier::{query_balance, query_liquidation_amount, query_price};
+use cdp::querier::{
+    query_balance, query_liquidation_amount, query_price, query_simulate_execute_bid,
+};
 use cdp::reward_book::ExecuteMsg as RewardBookExecuteMsg;
+use cdp::events::{config_change_event, CdpEvent, MODULE_CENTRAL_CONTROL};
+use cdp::migration::{assert_legacy_deployment, assert_upgrade};
+use cdp::rbac::{
+    assert_not_paused, execute_grant_role, execute_pause, execute_revoke_role, execute_unpause, grant_role,
+    has_any_role, query_has_role, query_paused, query_role_members, revoke_role, Role,
+};
+use cdp::timelock::{
+    execute_cancel_change, execute_matured_change, execute_propose_change, query_pending_change,
+    query_pending_changes, query_timelock_config, store_timelock_config, update_timelock_config,
+    TimelockConfig,
+};
 use cdp::tokens::{Tokens, TokensMath, TokensToHuman, TokensToRaw};
 
+use cw2::set_contract_version;
 use cosmwasm_std::{
-    attr, entry_point, to_binary, Addr, Binary, CanonicalAddr, CosmosMsg, Deps, DepsMut, Env,
-    MessageInfo, Response, StdResult, Uint128, WasmMsg,
+    attr, entry_point, to_binary, Addr, Api, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg,
+    Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult, Storage, SubMsg,
+    SubMsgResult, Uint128, WasmMsg,
 };
 use std::ops::Mul;
 #[cfg(not(feature = "library"))]
@@ -41,6 +72,10 @@ use cdp::custody::ExecuteMsg as CustodyExecuteMsg;
 use cdp::stable_pool::ExecuteMsg as PoolExecuteMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 
+// version info for migration
+const CONTRACT_NAME: &str = "crates.io:cdp-central-control";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -58,14 +93,46 @@ pub fn instantiate(
         epoch_period: msg.epoch_period,
         redeem_fee: msg.redeem_fee,
         stable_denom: msg.stable_denom,
+        max_liquidation_batch_size: msg
+            .max_liquidation_batch_size
+            .unwrap_or(DEFAULT_MAX_LIQUIDATION_BATCH_SIZE),
+        keeper_reward: msg.keeper_reward.unwrap_or_default(),
+        liquidation_cooldown: msg.liquidation_cooldown.unwrap_or_default(),
+        liquidation_record_retention: msg
+            .liquidation_record_retention
+            .unwrap_or(DEFAULT_LIQUIDATION_RECORD_RETENTION),
     };
 
     if msg.redeem_fee >= Decimal256::one() {
         return Err(ContractError::RedeemFeeExceedsLimit {});
     }
 
+    if config.max_liquidation_batch_size == 0 {
+        return Err(ContractError::InvalidLiquidationBatchSize {});
+    }
+
+    if config.keeper_reward.rate > Decimal256::one() {
+        return Err(ContractError::KeeperRewardRateExceedsLimit {});
+    }
+
     store_config(deps.storage, &config)?;
-    
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    store_timelock_config(
+        deps.storage,
+        &TimelockConfig {
+            delay: msg.timelock_delay,
+        },
+    )?;
+
+    grant_role(deps.storage, Role::Admin, &config.owner_addr)?;
+    grant_role(
+        deps.storage,
+        Role::Guardian,
+        &deps.api.addr_canonicalize(&msg.guardian_addr)?,
+    )?;
+
     store_new_owner(deps.storage, &{
         NewOwnerAddr {
             new_owner_addr: config.owner_addr.clone(),
@@ -82,41 +149,83 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // positions left in a former layout would read as empty
+    if !matches!(msg, ExecuteMsg::MigratePositions { .. }) && is_migrating(deps.storage)? {
+        return Err(ContractError::MigrationPending {});
+    }
+
     match msg {
-        ExecuteMsg::UpdateConfig {
-            oracle_contract,
-            pool_contract,
-            liquidation_contract,
-            custody_contract,
-            stable_denom,
-            epoch_period,
-            redeem_fee,
-        } => {
+        ExecuteMsg::UpdateConfig { .. }
+        | ExecuteMsg::UpdateTimelockConfig { .. }
+        | ExecuteMsg::SetOwner { .. }
+        | ExecuteMsg::GrantRole {
+            role: Role::Admin | Role::Guardian,
+            ..
+        } => propose_timelocked_change(deps, env, info, msg),
+        ExecuteMsg::ExecuteChange { id } => execute_timelocked_change(deps, env, info, id),
+        ExecuteMsg::CancelChange { id } => {
+            execute_cancel_change(deps, info, MODULE_CENTRAL_CONTROL, id)
+        }
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => {
             let api = deps.api;
-            update_config(
+            execute_grant_role(
                 deps,
                 info,
-                optional_addr_validate(api, oracle_contract)?,
-                optional_addr_validate(api, pool_contract)?,
-                optional_addr_validate(api, liquidation_contract)?,
-                optional_addr_validate(api, custody_contract)?,
-                stable_denom,
-                epoch_period,
-                redeem_fee,
+                MODULE_CENTRAL_CONTROL,
+                role,
+                api.addr_validate(&address)?,
             )
         }
-        ExecuteMsg::SetOwner { new_owner_addr } => {
+        ExecuteMsg::RevokeRole { role, address } => {
             let api = deps.api;
-            set_new_owner(deps, info, api.addr_validate(&new_owner_addr)?)
+            execute_revoke_role(
+                deps,
+                info,
+                MODULE_CENTRAL_CONTROL,
+                role,
+                api.addr_validate(&address)?,
+            )
         }
-        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
-        ExecuteMsg::RepayStableCoin { sender, amount } => {
-            repay_stable_coin(deps, info, sender, amount)
+        ExecuteMsg::Pause {} => execute_pause(deps, info, MODULE_CENTRAL_CONTROL),
+        ExecuteMsg::Unpause {} => execute_unpause(deps, info, MODULE_CENTRAL_CONTROL),
+        ExecuteMsg::RepayStableCoin {
+            minter,
+            vault_id,
+            payer,
+            amount,
+        } => {
+            let api = deps.api;
+            repay_stable_coin(
+                deps,
+                info,
+                api.addr_validate(&minter)?,
+                vault_id.unwrap_or(DEFAULT_VAULT_ID),
+                api.addr_validate(&payer)?,
+                amount,
+            )
+        }
+        ExecuteMsg::ClosePosition {
+            minter,
+            vault_id,
+            payer,
+            amount,
+        } => {
+            let api = deps.api;
+            close_position(
+                deps,
+                info,
+                api.addr_validate(&minter)?,
+                vault_id.unwrap_or(DEFAULT_VAULT_ID),
+                api.addr_validate(&payer)?,
+                amount,
+            )
         }
         ExecuteMsg::RedeemStableCoin {
             redeemer,
             amount,
             minter,
+            vault_id,
         } => {
             let api = deps.api;
             redeem_stable_coin(
@@ -125,18 +234,24 @@ pub fn execute(
                 api.addr_validate(redeemer.as_str())?,
                 amount,
                 api.addr_validate(minter.as_str())?,
+                vault_id.unwrap_or(DEFAULT_VAULT_ID),
             )
         }
         ExecuteMsg::WithdrawCollateral {
             collateral_contract,
             collateral_amount,
+            minter,
+            vault_id,
         } => {
             let api = deps.api;
             withdraw_collateral(
                 deps,
+                env,
                 info,
                 api.addr_validate(collateral_contract.as_str())?,
                 collateral_amount,
+                optional_addr_validate(api, minter)?,
+                vault_id.unwrap_or(DEFAULT_VAULT_ID),
             )
         }
         ExecuteMsg::WhitelistCollateral {
@@ -165,22 +280,38 @@ pub fn execute(
             collateral_amount,
             collateral_contract,
             is_redemption_provider,
+            vault_id,
         } => mint_stable_coin(
             deps,
+            env,
             info,
             minter,
             stable_amount,
             collateral_amount,
             collateral_contract,
             is_redemption_provider,
+            vault_id.unwrap_or(DEFAULT_VAULT_ID),
         ),
         ExecuteMsg::BecomeRedemptionProvider {
             is_redemption_provider,
-        } => become_redemption_provider(deps, info, is_redemption_provider),
+            minter,
+            vault_id,
+        } => {
+            let api = deps.api;
+            become_redemption_provider(
+                deps,
+                env,
+                info,
+                is_redemption_provider,
+                optional_addr_validate(api, minter)?,
+                vault_id.unwrap_or(DEFAULT_VAULT_ID),
+            )
+        }
         ExecuteMsg::DepositCollateral {
             minter,
             collateral_contract,
             collateral_amount,
+            vault_id,
         } => {
             let api = deps.api;
             deposit_collateral(
@@ -189,22 +320,120 @@ pub fn execute(
                 api.addr_validate(minter.as_str())?,
                 api.addr_validate(collateral_contract.as_str())?,
                 collateral_amount,
+                vault_id.unwrap_or(DEFAULT_VAULT_ID),
+            )
+        }
+        ExecuteMsg::LiquidateCollateral { minter, vault_id } => {
+            let api = deps.api;
+            liquidate_collateral(
+                deps,
+                env,
+                info,
+                api.addr_validate(&minter)?,
+                vault_id.unwrap_or(DEFAULT_VAULT_ID),
             )
         }
-        ExecuteMsg::LiquidateCollateral { minter } => {
+        ExecuteMsg::LiquidateCollaterals { minters } => {
             let api = deps.api;
-            liquidate_collateral(deps, env, info, api.addr_validate(&minter)?)
+            let targets = minters
+                .into_iter()
+                .map(|target| {
+                    Ok((
+                        api.addr_validate(&target.minter)?,
+                        target.vault_id.unwrap_or(DEFAULT_VAULT_ID),
+                    ))
+                })
+                .collect::<StdResult<Vec<(Addr, u64)>>>()?;
+            liquidate_collaterals(deps, env, info, targets)
+        }
+        ExecuteMsg::BatchLiquidateCollateral {
+            minter,
+            vault_id,
+            liquidator,
+        } => {
+            let api = deps.api;
+            if info.sender != env.contract.address {
+                return Err(ContractError::Unauthorized(
+                    "batch_liquidate_collateral".to_string(),
+                    info.sender.to_string(),
+                ));
+            }
+            execute_liquidation(
+                deps,
+                env,
+                api.addr_validate(&minter)?,
+                vault_id,
+                api.addr_validate(&liquidator)?,
+            )
+        }
+        ExecuteMsg::MigratePositions { limit } => execute_migrate_positions(deps, limit),
+        ExecuteMsg::SettleLiquidationRecord { id } => {
+            if info.sender != env.contract.address {
+                return Err(ContractError::Unauthorized(
+                    "settle_liquidation_record".to_string(),
+                    info.sender.to_string(),
+                ));
+            }
+            settle_liquidation_record(deps, id)
+        }
+        ExecuteMsg::OpenVault {} => execute_open_vault(deps, info),
+        ExecuteMsg::ApproveOperator {
+            operator,
+            permissions,
+            expires_at,
+        } => {
+            let api = deps.api;
+            approve_operator(
+                deps,
+                env,
+                info,
+                api.addr_validate(&operator)?,
+                permissions,
+                expires_at,
+            )
+        }
+        ExecuteMsg::RevokeOperator { operator } => {
+            let api = deps.api;
+            revoke_operator(deps, info, api.addr_validate(&operator)?)
+        }
+        ExecuteMsg::TransferNft {
+            recipient,
+            token_id,
+        } => {
+            let api = deps.api;
+            transfer_position(deps, env, info, api.addr_validate(&recipient)?, token_id)
+        }
+        ExecuteMsg::SendNft {
+            contract,
+            token_id,
+            msg,
+        } => {
+            let api = deps.api;
+            send_position(deps, env, info, api.addr_validate(&contract)?, token_id, msg)
+        }
+        ExecuteMsg::Approve {
+            spender,
+            token_id,
+            expires,
+        } => {
+            let api = deps.api;
+            approve_position(deps, env, info, api.addr_validate(&spender)?, token_id, expires)
+        }
+        ExecuteMsg::Revoke { spender, token_id } => {
+            let api = deps.api;
+            revoke_position(deps, info, api.addr_validate(&spender)?, token_id)
         }
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
-        QueryMsg::LoanInfo { minter } => to_binary(&query_loan_info(
+        QueryMsg::LoanInfo { minter, vault_id } => to_binary(&query_loan_info(
             deps,
             deps.api.addr_validate(&minter.as_str())?,
+            vault_id.unwrap_or(DEFAULT_VAULT_ID),
         )?),
         QueryMsg::CollateralElem { collateral } => to_binary(&query_whitelist_elem(
             deps,
@@ -221,18 +450,21 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             limit,
         )?),
 
-        QueryMsg::MinterCollateral { minter } => to_binary(&query_minter_collateral(
+        QueryMsg::MinterCollateral { minter, vault_id } => to_binary(&query_minter_collateral(
             deps,
             deps.api.addr_validate(&minter.as_str())?,
+            vault_id.unwrap_or(DEFAULT_VAULT_ID),
         )?),
 
         QueryMsg::RedemptionProviderList {
             minter,
+            vault_id,
             start_after,
             limit,
         } => to_binary(&query_redemption_provider_list(
             deps,
             optional_addr_validate(deps.api, minter)?,
+            vault_id.unwrap_or(DEFAULT_VAULT_ID),
             optional_addr_validate(deps.api, start_after)?,
             limit,
         )?),
@@ -240,35 +472,313 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::CollateralAvailable {
             minter,
             collateral_contract,
+            vault_id,
         } => to_binary(&query_collateral_available(
             deps,
             deps.api.addr_validate(minter.as_str())?,
             deps.api.addr_validate(collateral_contract.as_str())?,
+            vault_id.unwrap_or(DEFAULT_VAULT_ID),
         )?),
+
+        QueryMsg::Vaults {
+            minter,
+            start_after,
+            limit,
+        } => to_binary(&query_vaults(
+            deps,
+            deps.api.addr_validate(&minter)?,
+            start_after,
+            limit,
+        )?),
+
+        QueryMsg::LiquidationRecord { id } => to_binary(&query_liquidation_record(deps, id)?),
+
+        QueryMsg::LiquidationHistory {
+            minter,
+            start_time,
+            end_time,
+            start_after,
+            limit,
+        } => to_binary(&query_liquidation_history(
+            deps,
+            optional_addr_validate(deps.api, minter)?,
+            start_time,
+            end_time,
+            start_after,
+            limit,
+        )?),
+
+        QueryMsg::TimelockConfig {} => to_binary(&query_timelock_config(deps)?),
+
+        QueryMsg::PendingChange { id } => to_binary(&query_pending_change(deps, id)?),
+
+        QueryMsg::PendingChanges { start_after, limit } => {
+            to_binary(&query_pending_changes(deps, start_after, limit)?)
+        }
+        QueryMsg::HasRole { role, address } => to_binary(&query_has_role(deps, role, address)?),
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&query_role_members(deps, role, start_after, limit)?),
+        QueryMsg::Paused {} => to_binary(&query_paused(deps)?),
+        QueryMsg::Operator { minter, operator } => to_binary(&query_operator(
+            deps,
+            deps.api.addr_validate(&minter)?,
+            deps.api.addr_validate(&operator)?,
+        )?),
+        QueryMsg::Operators {
+            minter,
+            start_after,
+            limit,
+        } => to_binary(&query_operators(
+            deps,
+            deps.api.addr_validate(&minter)?,
+            optional_addr_validate(deps.api, start_after)?,
+            limit,
+        )?),
+        QueryMsg::OwnerOf {
+            token_id,
+            include_expired,
+        } => to_binary(&query_owner_of(
+            deps,
+            env,
+            token_id,
+            include_expired.unwrap_or(false),
+        )?),
+        QueryMsg::NftInfo { token_id } => to_binary(&query_nft_info(deps, token_id)?),
+        QueryMsg::Tokens {
+            owner,
+            start_after,
+            limit,
+        } => to_binary(&query_tokens(
+            deps,
+            deps.api.addr_validate(&owner)?,
+            start_after,
+            limit,
+        )?),
+        QueryMsg::AllTokens { start_after, limit } => {
+            to_binary(&query_all_tokens(deps, start_after, limit)?)
+        }
+        QueryMsg::NumTokens {} => to_binary(&query_num_tokens(deps)?),
+        QueryMsg::ContractInfo {} => to_binary(&query_contract_info()?),
     }
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    match msg {
+        MigrateMsg::FromLegacy {
+            guardian_addr,
+            timelock_delay,
+        } => {
+            assert_legacy_deployment(deps.storage)?;
+            start_legacy_migration(deps.storage)?;
+
+            let config = read_config(deps.storage)?;
+            store_timelock_config(
+                deps.storage,
+                &TimelockConfig {
+                    delay: timelock_delay,
+                },
+            )?;
+            grant_role(deps.storage, Role::Admin, &config.owner_addr)?;
+            grant_role(
+                deps.storage,
+                Role::Guardian,
+                &deps.api.addr_canonicalize(&guardian_addr)?,
+            )?;
+        }
+        MigrateMsg::Upgrade {} => {
+            assert_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+            start_default_vaults_migration(deps.storage)?;
+        }
+    }
+
+    // the remaining pages are moved through MigratePositions
+    let done = migrate_positions(deps.storage, None)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("version", CONTRACT_VERSION),
+        attr("done", done.to_string()),
+    ]))
+}
+
+pub fn execute_migrate_positions(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let done = migrate_positions(deps.storage, limit)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate_positions"),
+        attr("done", done.to_string()),
+    ]))
 }
 
 pub fn liquidate_collateral(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     minter: Addr,
+    vault_id: u64,
+) -> Result<Response, ContractError> {
+    execute_liquidation(deps, env, minter, vault_id, info.sender)
+}
+
+/// Reply ids of the liquidations of a batch, offset by the index of the position in the batch
+pub const BATCH_LIQUIDATION: u64 = 1u64;
+
+/// Liquidate the batch of positions, each unsafe position is liquidated by a
+/// message to the contract itself so that its stable pool balance snapshot is
+/// taken right before its collaterals are sold. A failed liquidation is reverted
+/// alone and reported by the reply of its message
+pub fn liquidate_collaterals(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    targets: Vec<(Addr, u64)>,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    if targets.len() > config.max_liquidation_batch_size as usize {
+        return Err(ContractError::LiquidationBatchTooLarge(
+            config.max_liquidation_batch_size,
+        ));
+    }
+
+    let mut results: Vec<LiquidationResult> = vec![];
+    let mut messages: Vec<SubMsg> = vec![];
+    for (minter, vault_id) in targets {
+        // a position listed twice is liquidated once
+        if results
+            .iter()
+            .any(|result| result.minter == minter.as_str() && result.vault_id == vault_id)
+        {
+            continue;
+        }
+
+        let minter_raw = deps.api.addr_canonicalize(minter.as_str())?;
+        let status = if cooldown_end(deps.as_ref(), &env, &config, &minter_raw, vault_id)?.is_some() {
+            LiquidationStatus::Cooldown
+        } else if is_liquidatable(deps.as_ref(), &minter_raw, vault_id)? {
+            messages.push(SubMsg::reply_on_error(
+                CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: env.contract.address.to_string(),
+                    msg: to_binary(&ExecuteMsg::BatchLiquidateCollateral {
+                        minter: minter.to_string(),
+                        vault_id,
+                        liquidator: info.sender.to_string(),
+                    })?,
+                    funds: vec![],
+                }),
+                BATCH_LIQUIDATION + results.len() as u64,
+            ));
+            LiquidationStatus::Liquidated
+        } else {
+            LiquidationStatus::Safe
+        };
+        results.push(LiquidationResult {
+            minter: minter.to_string(),
+            vault_id,
+            status,
+        });
+    }
+    store_liquidation_batch(deps.storage, &results)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "liquidate_collaterals"),
+            attr("liquidator", info.sender.to_string()),
+            attr("liquidated", messages.len().to_string()),
+            attr("skipped", (results.len() - messages.len()).to_string()),
+        ])
+        .add_submessages(messages)
+        .set_data(to_binary(&LiquidateCollateralsResponse { results })?))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    match (msg.id.checked_sub(BATCH_LIQUIDATION), msg.result) {
+        (Some(index), SubMsgResult::Err(error)) => batch_liquidation_failed(deps, index, error),
+        _ => Err(ContractError::InvalidReplyId {}),
+    }
+}
+
+/// Record the failure of a liquidation of the batch, the response data replaces the one of
+/// the batch so that it reports the positions actually liquidated
+fn batch_liquidation_failed(
+    deps: DepsMut,
+    index: u64,
+    error: String,
+) -> Result<Response, ContractError> {
+    let mut results = read_liquidation_batch(deps.storage)?;
+    let result = results
+        .get_mut(index as usize)
+        .ok_or(ContractError::InvalidReplyId {})?;
+    result.status = LiquidationStatus::Failed {
+        error: error.clone(),
+    };
+    let (minter, vault_id) = (result.minter.clone(), result.vault_id);
+    store_liquidation_batch(deps.storage, &results)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "batch_liquidation_failed"),
+            attr("minter", minter),
+            attr("vault_id", vault_id.to_string()),
+            attr("error", error),
+        ])
+        .set_data(to_binary(&LiquidateCollateralsResponse { results })?))
+}
+
+// borrow limit is smaller than loan amount
+fn is_liquidatable(deps: Deps, minter: &CanonicalAddr, vault_id: u64) -> StdResult<bool> {
+    let collaterals = read_collaterals(deps.storage, minter, vault_id);
+    let loan_info = read_minter_loan_info(deps.storage, minter, vault_id)?;
+    Ok(compute_mint_max_value(deps, &collaterals)? < loan_info.loans)
+}
+
+/// End of the cooldown started by the last liquidation of the position,
+/// none once the position can be liquidated again
+fn cooldown_end(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    minter: &CanonicalAddr,
+    vault_id: u64,
+) -> StdResult<Option<u64>> {
+    if config.liquidation_cooldown == 0 {
+        return Ok(None);
+    }
+    Ok(read_last_liquidation(deps.storage, minter, vault_id)?
+        .map(|time| time + config.liquidation_cooldown)
+        .filter(|end| *end > env.block.time.seconds()))
+}
+
+fn execute_liquidation(
+    deps: DepsMut,
+    env: Env,
+    minter: Addr,
+    vault_id: u64,
+    liquidator: Addr,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
     let minter_raw = deps.api.addr_canonicalize(minter.as_str())?;
-    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &minter_raw);
-    let minter_loan_info = read_minter_loan_info(deps.storage, &minter_raw)?;
+    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &minter_raw, vault_id);
+    let minter_loan_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
     let max_loan_info = compute_mint_max_value(deps.as_ref(), &cur_collaterals)?;
     // borrow limit is equal or bigger than loan amount
     // cannot liquidation collaterals
     if max_loan_info >= minter_loan_info.loans {
         return Err(ContractError::CannotLiquidateSafeLoan {});
     }
+    if let Some(end) = cooldown_end(deps.as_ref(), &env, &config, &minter_raw, vault_id)? {
+        return Err(ContractError::LiquidationCooldown(end));
+    }
+    store_last_liquidation(deps.storage, &minter_raw, vault_id, env.block.time.seconds())?;
     let pre_balance: Uint256 = query_balance(
         deps.as_ref(),
         deps.api.addr_humanize(&config.pool_contract)?,
@@ -285,12 +795,69 @@ pub fn liquidate_collateral(
         minter_loan_info.loans,
         max_loan_info,
         &cur_collaterals.to_human(deps.as_ref())?,
-        collateral_prices,
+        collateral_prices.clone(),
     )?;
     let liquidation_amount = liquidation_amount_res.collaterals.to_raw(deps.as_ref())?;
+
+    let liquidation_contract = deps.api.addr_humanize(&config.liquidation_contract)?;
+    let mut liquidated_value = Uint256::zero();
+    let mut bid_fee = Uint256::zero();
+    let mut liquidator_fee = Uint256::zero();
+    let mut liquidated_collaterals: Vec<LiquidatedCollateralElem> = vec![];
+    for (collateral_contract, amount) in liquidation_amount.iter() {
+        if let Some(idx) = cur_collaterals
+            .iter()
+            .position(|elem| elem.0 == *collateral_contract)
+        {
+            liquidated_value += *amount * collateral_prices[idx];
+            // the fees charged by the liquidation queue are not reported back to this contract
+            if !amount.is_zero() {
+                let simulation = query_simulate_execute_bid(
+                    deps.as_ref(),
+                    liquidation_contract.clone(),
+                    deps.api.addr_humanize(collateral_contract)?.to_string(),
+                    *amount,
+                    collateral_prices[idx],
+                )?;
+                bid_fee += simulation.bid_fee;
+                liquidator_fee += simulation.liquidator_fee;
+            }
+            liquidated_collaterals.push(LiquidatedCollateralElem {
+                collateral_contract: collateral_contract.clone(),
+                amount: *amount,
+                price: collateral_prices[idx],
+            });
+        }
+    }
+    let (keeper_reward, keeper_reward_unfunded) = compute_keeper_reward(
+        deps.as_ref(),
+        &env,
+        &config,
+        liquidated_value,
+    )?;
+
+    // the repaid amount is only known once the pool has repaid, it is settled afterwards
+    let record_id = store_liquidation_record(
+        deps.storage,
+        &LiquidationRecord {
+            minter: minter_raw.clone(),
+            vault_id,
+            liquidator: deps.api.addr_canonicalize(liquidator.as_str())?,
+            collaterals: liquidated_collaterals,
+            loans: minter_loan_info.loans,
+            repaid: Uint256::zero(),
+            keeper_reward,
+            estimated_bid_fee: bid_fee,
+            estimated_liquidator_fee: liquidator_fee,
+            height: env.block.height,
+            time: env.block.time.seconds(),
+        },
+        config.liquidation_record_retention,
+    )?;
+
     // Store left collaterals
     cur_collaterals.sub(liquidation_amount.clone())?;
-    store_collaterals(deps.storage, &minter_raw, &cur_collaterals)?;
+    store_collaterals(deps.storage, &minter_raw, vault_id, &cur_collaterals)?;
 
     let pool_contract = deps.api.addr_humanize(&config.pool_contract)?;
     let mut liquidation_messages: Vec<CosmosMsg> = vec![];
@@ -304,7 +871,7 @@ pub fn liquidate_collateral(
                     .to_string(),
                 funds: vec![],
                 msg: to_binary(&CustodyExecuteMsg::LiquidateCollateral {
-                    liquidator: info.sender.to_string(),
+                    liquidator: liquidator.to_string(),
                     amount: collateral.1.into(),
                 })?,
             }));
@@ -321,30 +888,111 @@ pub fn liquidate_collateral(
         }
     }
 
+    liquidation_messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: pool_contract.to_string(),
+        funds: vec![],
+        msg: to_binary(&PoolExecuteMsg::RepayStableFromLiquidation {
+            minter: minter.to_string(),
+            vault_id: Some(vault_id),
+            pre_balance,
+        })?,
+    }));
+
+    if let Some(id) = record_id {
+        liquidation_messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: env.contract.address.to_string(),
+            funds: vec![],
+            msg: to_binary(&ExecuteMsg::SettleLiquidationRecord { id })?,
+        }));
+    }
+
+    if !keeper_reward.is_zero() {
+        liquidation_messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: liquidator.to_string(),
+            amount: vec![Coin {
+                denom: config.stable_denom.clone(),
+                amount: keeper_reward,
+            }],
+        }));
+    }
+
     Ok(Response::new()
         .add_messages(liquidation_messages)
-        .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: pool_contract.to_string(),
-            funds: vec![],
-            msg: to_binary(&PoolExecuteMsg::RepayStableFromLiquidation {
+        .add_attributes(vec![
+            attr("action", "liquidate_collateral"),
+            attr("minter", minter.to_string()),
+            attr("vault_id", vault_id.to_string()),
+            attr("liquidator", liquidator.to_string()),
+            attr("keeper_reward", keeper_reward.to_string()),
+        ])
+        .add_attributes(
+            (!keeper_reward_unfunded.is_zero())
+                .then(|| attr("keeper_reward_unfunded", keeper_reward_unfunded.to_string())),
+        )
+        .add_attributes(record_id.map(|id| attr("liquidation_record_id", id.to_string())))
+        .add_event(
+            CdpEvent::Liquidate {
                 minter: minter.to_string(),
-                pre_balance,
-            })?,
-        })))
+                vault_id,
+                liquidator: liquidator.to_string(),
+                loans: minter_loan_info.loans,
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+        ))
+}
+
+/// Record the kUSD repaid by a liquidation, the loans repaid since the record was stored
+pub fn settle_liquidation_record(deps: DepsMut, id: u64) -> Result<Response, ContractError> {
+    let mut record = read_liquidation_record(deps.storage, id)?;
+    let loan_info = read_minter_loan_info(deps.storage, &record.minter, record.vault_id)?;
+    record.repaid = record.loans - loan_info.loans.min(record.loans);
+    update_liquidation_record(deps.storage, id, &record)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "settle_liquidation_record"),
+        attr("liquidation_record_id", id.to_string()),
+        attr("repaid", record.repaid.to_string()),
+    ]))
+}
+
+/// Keeper reward for liquidating `liquidated_value` of collaterals and the part of it
+/// left unpaid. The reward is limited by the stable balance of the contract so that a
+/// liquidation never fails for lack of funds
+fn compute_keeper_reward(
+    deps: Deps,
+    env: &Env,
+    config: &Config,
+    liquidated_value: Uint256,
+) -> StdResult<(Uint128, Uint128)> {
+    let keeper_reward = &config.keeper_reward;
+    let reward = (Uint256::from(keeper_reward.flat_amount) + liquidated_value * keeper_reward.rate)
+        .min(Uint256::from(keeper_reward.cap));
+    if reward.is_zero() {
+        return Ok((Uint128::zero(), Uint128::zero()));
+    }
+
+    let balance = query_balance(
+        deps,
+        env.contract.address.clone(),
+        config.stable_denom.to_string(),
+    )?;
+    let paid = reward.min(balance);
+    Ok((paid.into(), (reward - paid).into()))
 }
 
 pub fn query_collateral_available(
     deps: Deps,
     minter: Addr,
     collateral_contract: Addr,
+    vault_id: u64,
 ) -> StdResult<CollateralAvailableRespone> {
     let config = read_config(deps.storage)?;
     let minter_raw = deps.api.addr_canonicalize(minter.as_str())?;
-    let minter_loan_info = read_minter_loan_info(deps.storage, &minter_raw)?;
+    let minter_loan_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
 
     let collateral_raw = deps.api.addr_canonicalize(collateral_contract.as_str())?;
 
-    let collaterals = read_collaterals(deps.storage, &minter_raw);
+    let collaterals = read_collaterals(deps.storage, &minter_raw, vault_id);
     let mut max_loans_value = Uint256::zero();
 
     let mut collateral_price = Decimal256::zero();
@@ -390,9 +1038,13 @@ pub fn query_collateral_available(
     }
 }
 
-pub fn query_minter_collateral(deps: Deps, minter: Addr) -> StdResult<MinterCollateralResponse> {
+pub fn query_minter_collateral(
+    deps: Deps,
+    minter: Addr,
+    vault_id: u64,
+) -> StdResult<MinterCollateralResponse> {
     let minter_raw = deps.api.addr_canonicalize(minter.as_str())?;
-    let minter_collateral = read_collaterals(deps.storage, &minter_raw);
+    let minter_collateral = read_collaterals(deps.storage, &minter_raw, vault_id);
 
     Ok(MinterCollateralResponse {
         collaterals: minter_collateral.to_human(deps)?,
@@ -415,24 +1067,160 @@ pub fn query_collateral_prices(
         )?;
         collateral_prices.push(price_resp.emv_price);
     }
-    Ok(collateral_prices)
+    Ok(collateral_prices)
+}
+
+pub fn become_redemption_provider(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    is_redemption_provider: bool,
+    minter: Option<Addr>,
+    vault_id: u64,
+) -> Result<Response, ContractError> {
+    assert_not_paused::<ContractError>(deps.storage)?;
+
+    let minter = assert_position_access(
+        deps.as_ref(),
+        &env,
+        &info,
+        minter,
+        OperatorPermission::ToggleRedemption,
+        "become_redemption_provider",
+    )?;
+    let api = deps.api;
+    let minter_raw = api.addr_canonicalize(minter.as_str())?;
+    assert_vault_exists(deps.as_ref(), &minter_raw, vault_id)?;
+    let mut minter_loan_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
+
+    minter_loan_info.is_redemption_provider = is_redemption_provider;
+    store_minter_loan_info(deps.storage, &minter_raw, vault_id, &minter_loan_info)?;
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "become_redemption_provider"),
+        attr("minter", minter.to_string()),
+        attr("vault_id", vault_id.to_string()),
+        attr("is_redemption_provider", is_redemption_provider.to_string()),
+    ]))
+}
+
+pub fn execute_open_vault(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
+    assert_not_paused::<ContractError>(deps.storage)?;
+
+    let minter_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let (vault_id, token_id) = open_vault(deps.storage, &minter_raw)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "open_vault"),
+            attr("minter", info.sender.to_string()),
+            attr("vault_id", vault_id.to_string()),
+            attr("token_id", token_id.clone()),
+        ])
+        .add_event(
+            CdpEvent::PositionOpen {
+                minter: info.sender.to_string(),
+                vault_id,
+                token_id,
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+        ))
+}
+
+fn assert_vault_exists(deps: Deps, minter: &CanonicalAddr, vault_id: u64) -> Result<(), ContractError> {
+    if !vault_exists(deps.storage, minter, vault_id)? {
+        return Err(ContractError::VaultNotFound(vault_id));
+    }
+    Ok(())
+}
+
+/// Resolve the position a handler acts on. It is the sender's own position unless
+/// `minter` names another minter, which requires an unexpired operator approval
+/// holding `permission`
+fn assert_position_access(
+    deps: Deps,
+    env: &Env,
+    info: &MessageInfo,
+    minter: Option<Addr>,
+    permission: OperatorPermission,
+    fn_name: &str,
+) -> Result<Addr, ContractError> {
+    let minter = match minter {
+        Some(minter) if minter != info.sender => minter,
+        _ => return Ok(info.sender.clone()),
+    };
+
+    let approval = read_operator(
+        deps.storage,
+        &deps.api.addr_canonicalize(minter.as_str())?,
+        &deps.api.addr_canonicalize(info.sender.as_str())?,
+    )?;
+    match approval {
+        Some(approval) if approval.allows(permission, env.block.time.seconds()) => Ok(minter),
+        _ => Err(ContractError::Unauthorized(
+            fn_name.to_string(),
+            info.sender.to_string(),
+        )),
+    }
+}
+
+pub fn approve_operator(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    operator: Addr,
+    permissions: Option<Vec<OperatorPermission>>,
+    expires_at: Option<u64>,
+) -> Result<Response, ContractError> {
+    if operator == info.sender {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Cannot approve the sender as its own operator",
+        )));
+    }
+    if matches!(expires_at, Some(expires_at) if expires_at <= env.block.time.seconds()) {
+        return Err(ContractError::Std(StdError::generic_err(
+            "Operator approval already expired",
+        )));
+    }
+
+    let permissions = permissions.unwrap_or_else(|| {
+        vec![
+            OperatorPermission::Mint,
+            OperatorPermission::Withdraw,
+            OperatorPermission::ToggleRedemption,
+        ]
+    });
+    store_operator(
+        deps.storage,
+        &deps.api.addr_canonicalize(info.sender.as_str())?,
+        &deps.api.addr_canonicalize(operator.as_str())?,
+        &OperatorApproval {
+            permissions,
+            expires_at,
+        },
+    )?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "approve_operator"),
+        attr("minter", info.sender.to_string()),
+        attr("operator", operator.to_string()),
+    ]))
 }
 
-pub fn become_redemption_provider(
+pub fn revoke_operator(
     deps: DepsMut,
     info: MessageInfo,
-    is_redemption_provider: bool,
+    operator: Addr,
 ) -> Result<Response, ContractError> {
-    let api = deps.api;
-    let minter_raw = api.addr_canonicalize(&info.sender.as_str())?;
-    let mut minter_loan_info = read_minter_loan_info(deps.storage, &minter_raw.clone())?;
+    remove_operator(
+        deps.storage,
+        &deps.api.addr_canonicalize(info.sender.as_str())?,
+        &deps.api.addr_canonicalize(operator.as_str())?,
+    );
 
-    minter_loan_info.is_redemption_provider = is_redemption_provider;
-    store_minter_loan_info(deps.storage, &minter_raw.clone(), &minter_loan_info)?;
     Ok(Response::new().add_attributes(vec![
-        attr("action", "become_redemption_provider"),
+        attr("action", "revoke_operator"),
         attr("minter", info.sender.to_string()),
-        attr("is_redemption_provider", is_redemption_provider.to_string()),
+        attr("operator", operator.to_string()),
     ]))
 }
 
@@ -442,6 +1230,7 @@ pub fn deposit_collateral(
     minter: Addr,
     collateral_contract: Addr,
     collateral_amount: Uint128,
+    vault_id: u64,
 ) -> Result<Response, ContractError> {
     let api = deps.api;
     let collateral_contract_raw = api.addr_canonicalize(&collateral_contract.as_str())?;
@@ -452,13 +1241,20 @@ pub fn deposit_collateral(
     }
 
     let minter_raw = api.addr_canonicalize(&minter.as_str())?;
-    let mut minter_collaterals = read_collaterals(deps.storage, &minter_raw);
+    assert_vault_exists(deps.as_ref(), &minter_raw, vault_id)?;
+    let mut minter_collaterals = read_collaterals(deps.storage, &minter_raw, vault_id);
     let collateral = vec![(
         collateral_contract_raw.clone(),
         Uint256::from(collateral_amount),
     )];
     minter_collaterals.add(collateral);
-    store_collaterals(deps.storage, &minter_raw, &minter_collaterals)?;
+    store_collaterals(deps.storage, &minter_raw, vault_id, &minter_collaterals)?;
+    // the default vault is opened by its first deposit
+    let token_id = if vault_id == DEFAULT_VAULT_ID {
+        mint_default_vault_token(deps.storage, &minter_raw)?
+    } else {
+        None
+    };
 
     Ok(Response::new()
         .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
@@ -474,33 +1270,40 @@ pub fn deposit_collateral(
         }))
         .add_attributes(vec![
             attr("action", "deposit_collateral"),
-            attr("contract_name", "control"),
             attr("minter", minter.to_string()),
+            attr("vault_id", vault_id.to_string()),
             attr("collateral_contract", collateral_contract.to_string()),
             attr("collateral_amount", collateral_amount.to_string()),
-        ]))
+        ])
+        .add_attributes(token_id.clone().map(|token_id| attr("token_id", token_id)))
+        .add_events(token_id.map(|token_id| {
+            CdpEvent::PositionOpen {
+                minter: minter.to_string(),
+                vault_id,
+                token_id,
+            }
+            .into_event(MODULE_CENTRAL_CONTROL)
+        }))
+        .add_event(
+            CdpEvent::Deposit {
+                minter: minter.to_string(),
+                vault_id,
+                collateral_contract: collateral_contract.to_string(),
+                amount: collateral_amount,
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+        ))
 }
 
-pub fn set_new_owner(
-    deps: DepsMut,
-    info: MessageInfo,
-    new_owner_addr: Addr,
-) -> Result<Response, ContractError> {
-    let config = read_config(deps.as_ref().storage)?;
-    let mut new_owner = read_new_owner(deps.as_ref().storage)?;
-    let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
-    if sender_raw != config.owner_addr {
-        return Err(ContractError::Unauthorized(
-            "set_new_owner".to_string(),
-            info.sender.to_string(),
-        ));
-    }
-    new_owner.new_owner_addr = deps.api.addr_canonicalize(&new_owner_addr.to_string())?;
-    store_new_owner(deps.storage, &new_owner)?;
-
-    Ok(Response::default())
+/// Applied by a matured `SetOwner` change, the new owner still has to accept
+pub fn set_new_owner(storage: &mut dyn Storage, new_owner_addr: CanonicalAddr) -> StdResult<()> {
+    let mut new_owner = read_new_owner(storage)?;
+    new_owner.new_owner_addr = new_owner_addr;
+    store_new_owner(storage, &new_owner)
 }
 
+/// Only the admin role of the previous owner is revoked, admins granted apart from the
+/// ownership keep their role until revoked
 pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
     let new_owner = read_new_owner(deps.as_ref().storage)?;
     let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
@@ -512,15 +1315,175 @@ pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, Co
         ));
     }
 
+    revoke_role(deps.storage, Role::Admin, &config.owner_addr);
+    grant_role(deps.storage, Role::Admin, &new_owner.new_owner_addr)?;
     config.owner_addr = new_owner.new_owner_addr;
     store_config(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "accept_ownership"),
+            attr("owner", info.sender.to_string()),
+        ])
+        .add_event(config_change_event(MODULE_CENTRAL_CONTROL, "accept_ownership", info.sender)))
 }
 
-pub fn update_config(
+/// Roles allowed to propose and execute a timelocked change, changes touching only
+/// the redeem fee are open to fee managers, changes touching only liquidation and
+/// keeper parameters to risk managers
+fn change_roles(msg: &ExecuteMsg) -> &'static [Role] {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            oracle_contract: None,
+            pool_contract: None,
+            liquidation_contract: None,
+            custody_contract: None,
+            stable_denom: None,
+            epoch_period: None,
+            max_liquidation_batch_size: None,
+            keeper_reward: None,
+            liquidation_cooldown: None,
+            liquidation_record_retention: None,
+            ..
+        } => &[Role::Admin, Role::FeeManager],
+        ExecuteMsg::UpdateConfig {
+            oracle_contract: None,
+            pool_contract: None,
+            liquidation_contract: None,
+            custody_contract: None,
+            stable_denom: None,
+            epoch_period: None,
+            redeem_fee: None,
+            ..
+        } => &[Role::Admin, Role::RiskManager],
+        _ => &[Role::Admin],
+    }
+}
+
+/// Admin queues a config change, it can be executed once the timelock delay has passed
+pub fn propose_timelocked_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    validate_change(deps.api, &msg)?;
+    let roles = change_roles(&msg);
+    execute_propose_change(deps, &env, info, MODULE_CENTRAL_CONTROL, roles, to_binary(&msg)?)
+}
+
+pub fn execute_timelocked_change(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    execute_matured_change(deps, &env, info, MODULE_CENTRAL_CONTROL, id, change_roles, apply_change)
+}
+
+// addresses and parameters are validated when a change is proposed rather than once its
+// delay has passed
+fn validate_change(api: &dyn Api, msg: &ExecuteMsg) -> Result<(), ContractError> {
+    if let ExecuteMsg::UpdateConfig {
+        oracle_contract,
+        pool_contract,
+        liquidation_contract,
+        custody_contract,
+        redeem_fee,
+        max_liquidation_batch_size,
+        keeper_reward,
+        ..
+    } = msg
+    {
+        let addrs = [oracle_contract, pool_contract, liquidation_contract, custody_contract];
+        for addr in addrs.iter().copied().flatten() {
+            api.addr_validate(addr)?;
+        }
+        assert_config_params(*redeem_fee, *max_liquidation_batch_size, keeper_reward.as_ref())?;
+    }
+    if let ExecuteMsg::SetOwner {
+        new_owner_addr: address,
+    }
+    | ExecuteMsg::GrantRole { address, .. } = msg
+    {
+        api.addr_validate(address)?;
+    }
+    Ok(())
+}
+
+fn assert_config_params(
+    redeem_fee: Option<Decimal256>,
+    max_liquidation_batch_size: Option<u32>,
+    keeper_reward: Option<&KeeperReward>,
+) -> Result<(), ContractError> {
+    if matches!(redeem_fee, Some(redeem_fee) if redeem_fee >= Decimal256::one()) {
+        return Err(ContractError::RedeemFeeExceedsLimit {});
+    }
+    if max_liquidation_batch_size == Some(0) {
+        return Err(ContractError::InvalidLiquidationBatchSize {});
+    }
+    if matches!(keeper_reward, Some(keeper_reward) if keeper_reward.rate > Decimal256::one()) {
+        return Err(ContractError::KeeperRewardRateExceedsLimit {});
+    }
+    Ok(())
+}
+
+fn apply_change(deps: DepsMut, msg: ExecuteMsg) -> Result<&'static str, ContractError> {
+    let api = deps.api;
+    let action = match msg {
+        ExecuteMsg::UpdateConfig {
+            oracle_contract,
+            pool_contract,
+            liquidation_contract,
+            custody_contract,
+            stable_denom,
+            epoch_period,
+            redeem_fee,
+            max_liquidation_batch_size,
+            keeper_reward,
+            liquidation_cooldown,
+            liquidation_record_retention,
+        } => {
+            update_config(
+                deps,
+                optional_addr_validate(api, oracle_contract)?,
+                optional_addr_validate(api, pool_contract)?,
+                optional_addr_validate(api, liquidation_contract)?,
+                optional_addr_validate(api, custody_contract)?,
+                stable_denom,
+                epoch_period,
+                redeem_fee,
+                max_liquidation_batch_size,
+                keeper_reward,
+                liquidation_cooldown,
+                liquidation_record_retention,
+            )?;
+            "update_config"
+        }
+        ExecuteMsg::UpdateTimelockConfig { delay } => {
+            update_timelock_config(deps.storage, delay)?;
+            "update_timelock_config"
+        }
+        ExecuteMsg::SetOwner { new_owner_addr } => {
+            set_new_owner(deps.storage, deps.api.addr_canonicalize(&new_owner_addr)?)?;
+            "set_new_owner"
+        }
+        ExecuteMsg::GrantRole { role, address } => {
+            grant_role(deps.storage, role, &deps.api.addr_canonicalize(&address)?)?;
+            "grant_role"
+        }
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Unsupported timelocked change",
+            )))
+        }
+    };
+    Ok(action)
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn update_config(
+    deps: DepsMut,
     oracle_contract: Option<Addr>,
     pool_contract: Option<Addr>,
     liquidation_contract: Option<Addr>,
@@ -528,16 +1491,13 @@ pub fn update_config(
     stable_denom: Option<String>,
     epoch_period: Option<u64>,
     redeem_fee: Option<Decimal256>,
-) -> Result<Response, ContractError> {
+    max_liquidation_batch_size: Option<u32>,
+    keeper_reward: Option<KeeperReward>,
+    liquidation_cooldown: Option<u64>,
+    liquidation_record_retention: Option<u64>,
+) -> Result<(), ContractError> {
+    assert_config_params(redeem_fee, max_liquidation_batch_size, keeper_reward.as_ref())?;
     let mut config = read_config(deps.as_ref().storage)?;
-    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
-
-    if sender_raw != config.owner_addr {
-        return Err(ContractError::Unauthorized(
-            "update_config".to_string(),
-            info.sender.to_string(),
-        ));
-    }
 
     if let Some(oracle_contact) = oracle_contract {
         config.oracle_contract = deps.api.addr_canonicalize(oracle_contact.as_str())?
@@ -564,40 +1524,61 @@ pub fn update_config(
     }
 
     if let Some(redeem_fee) = redeem_fee {
-        if redeem_fee >= Decimal256::one() {
-            return Err(ContractError::RedeemFeeExceedsLimit {});
-        }
         config.redeem_fee = redeem_fee;
     }
 
+    if let Some(max_liquidation_batch_size) = max_liquidation_batch_size {
+        config.max_liquidation_batch_size = max_liquidation_batch_size;
+    }
+
+    if let Some(keeper_reward) = keeper_reward {
+        config.keeper_reward = keeper_reward;
+    }
+
+    if let Some(liquidation_cooldown) = liquidation_cooldown {
+        config.liquidation_cooldown = liquidation_cooldown;
+    }
+
+    if let Some(liquidation_record_retention) = liquidation_record_retention {
+        config.liquidation_record_retention = liquidation_record_retention;
+    }
+
     store_config(deps.storage, &config)?;
-    Ok(Response::default())
+    Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn mint_stable_coin(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     minter: String,
     stable_amount: Uint128,
     collateral_amount: Option<Uint128>,
     collateral_contract: Option<String>,
     is_redemption_provider: Option<bool>,
+    vault_id: u64,
 ) -> Result<Response, ContractError> {
+    assert_not_paused::<ContractError>(deps.storage)?;
+
     let config = read_config(deps.as_ref().storage)?;
     let api = deps.api;
     let sender_raw = api.addr_canonicalize(info.sender.as_str())?;
 
     if sender_raw != config.custody_contract {
-        if info.sender.to_string() != minter {
-            return Err(ContractError::Unauthorized(
-                "mint_stable_coin".to_string(),
-                info.sender.to_string(),
-            ));
-        }
+        assert_position_access(
+            deps.as_ref(),
+            &env,
+            &info,
+            Some(api.addr_validate(&minter)?),
+            OperatorPermission::Mint,
+            "mint_stable_coin",
+        )?;
     }
 
     let minter_raw = api.addr_canonicalize(minter.as_str())?;
-    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &minter_raw);
+    assert_vault_exists(deps.as_ref(), &minter_raw, vault_id)?;
+    let mut cur_collaterals: Tokens = read_collaterals(deps.storage, &minter_raw, vault_id);
 
     let mut messages: Vec<CosmosMsg> = vec![];
 
@@ -614,7 +1595,7 @@ pub fn mint_stable_coin(
                 Uint256::from(collateral_amount),
             )]);
             //update minter collaterals info
-            store_collaterals(deps.storage, &minter_raw, &cur_collaterals)?;
+            store_collaterals(deps.storage, &minter_raw, vault_id, &cur_collaterals)?;
 
             // if deposit collateral, we need update balance at reward book contract
             let collateral_elem =
@@ -651,7 +1632,7 @@ pub fn mint_stable_coin(
         max_loan_to_value += collaterals_value * collateral_info.max_ltv;
     }
 
-    let mut minter_loans_info = read_minter_loan_info(deps.storage, &minter_raw)?;
+    let mut minter_loans_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
     if Uint256::from(stable_amount) + minter_loans_info.loans > max_loan_to_value {
         return Err(ContractError::MintkUSDTooLarge(max_loan_to_value));
     }
@@ -661,7 +1642,7 @@ pub fn mint_stable_coin(
         minter_loans_info.is_redemption_provider = is_redemption_provider;
     }
     //update minter loan info
-    store_minter_loan_info(deps.storage, &minter_raw, &minter_loans_info)?;
+    store_minter_loan_info(deps.storage, &minter_raw, vault_id, &minter_loans_info)?;
 
     let mint_msg = PoolExecuteMsg::MintStableCoin {
         minter: minter.clone().to_string(),
@@ -673,17 +1654,30 @@ pub fn mint_stable_coin(
         funds: vec![],
     }));
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "mint_stable_coin"),
-        attr("minter", minter.to_string()),
-        attr("stable_amount", stable_amount.to_string()),
-    ]))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "mint_stable_coin"),
+            attr("minter", minter.to_string()),
+            attr("vault_id", vault_id.to_string()),
+            attr("stable_amount", stable_amount.to_string()),
+        ])
+        .add_event(
+            CdpEvent::Mint {
+                minter: minter.to_string(),
+                vault_id,
+                stable_amount,
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+        ))
 }
 
 pub fn repay_stable_coin(
     deps: DepsMut,
     info: MessageInfo,
-    sender: String,
+    minter: Addr,
+    vault_id: u64,
+    payer: Addr,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
     let config = read_config(deps.storage)?;
@@ -695,17 +1689,120 @@ pub fn repay_stable_coin(
         ));
     }
 
-    let minter_raw = deps.api.addr_canonicalize(&sender.as_str())?;
-    let mut loan_info = read_minter_loan_info(deps.storage, &minter_raw)?;
+    let minter_raw = deps.api.addr_canonicalize(minter.as_str())?;
+    let mut loan_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
     loan_info.loans = loan_info.loans - Uint256::from(amount);
-    store_minter_loan_info(deps.storage, &minter_raw, &loan_info)?;
+    store_minter_loan_info(deps.storage, &minter_raw, vault_id, &loan_info)?;
 
-    Ok(Response::new().add_attributes(vec![
-        attr("contract_name", "central_control"),
-        attr("action", "repay_stable_coin"),
-        attr("sender", sender.to_string()),
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "repay_stable_coin"),
+            attr("minter", minter.to_string()),
+            attr("vault_id", vault_id.to_string()),
+            attr("payer", payer.to_string()),
+            attr("amount", amount.to_string()),
+        ])
+        .add_event(
+            CdpEvent::Repay {
+                minter: minter.to_string(),
+                vault_id,
+                payer: payer.to_string(),
+                amount,
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+        ))
+}
+
+/// Repay the whole loan of the vault and withdraw every collateral through
+/// its custody contract, in one transaction with the repayment on the stable pool
+pub fn close_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    minter: Addr,
+    vault_id: u64,
+    payer: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
+    let api = deps.api;
+    if api.addr_canonicalize(info.sender.as_str())? != config.pool_contract {
+        return Err(ContractError::Unauthorized(
+            "close_position".to_string(),
+            info.sender.to_string(),
+        ));
+    }
+
+    let minter_raw = api.addr_canonicalize(minter.as_str())?;
+    let mut loan_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
+    if Uint256::from(amount) != loan_info.loans {
+        return Err(ContractError::ClosePositionAmountMismatch(
+            loan_info.loans,
+            Uint256::from(amount),
+        ));
+    }
+    loan_info.loans = Uint256::zero();
+    store_minter_loan_info(deps.storage, &minter_raw, vault_id, &loan_info)?;
+
+    let collaterals = read_collaterals(deps.storage, &minter_raw, vault_id);
+    store_collaterals(deps.storage, &minter_raw, vault_id, &vec![])?;
+
+    let mut res = Response::new().add_attributes(vec![
+        attr("action", "close_position"),
+        attr("minter", minter.to_string()),
+        attr("vault_id", vault_id.to_string()),
+        attr("payer", payer.to_string()),
         attr("amount", amount.to_string()),
-    ]))
+    ]);
+    if !amount.is_zero() {
+        res = res.add_event(
+            CdpEvent::Repay {
+                minter: minter.to_string(),
+                vault_id,
+                payer: payer.to_string(),
+                amount,
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+        );
+    }
+
+    for (collateral_contract_raw, collateral_amount) in collaterals {
+        let whitelist_elem = read_whitelist_elem(deps.storage, &collateral_contract_raw)?;
+        let collateral_contract = api.addr_humanize(&collateral_contract_raw)?;
+        let collateral_amount = Uint128::from(collateral_amount);
+        res = res
+            .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: api
+                    .addr_humanize(&whitelist_elem.custody_contract)?
+                    .to_string(),
+                msg: to_binary(&CustodyExecuteMsg::WithdrawCollateral {
+                    minter: minter.to_string(),
+                    collateral_contract: collateral_contract.to_string(),
+                    collateral_amount,
+                })?,
+                funds: vec![],
+            }))
+            .add_message(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: api
+                    .addr_humanize(&whitelist_elem.reward_book_contract)?
+                    .to_string(),
+                msg: to_binary(&RewardBookExecuteMsg::DecreaseBalance {
+                    address: minter.to_string(),
+                    amount: collateral_amount,
+                })?,
+                funds: vec![],
+            }))
+            .add_event(
+                CdpEvent::Withdraw {
+                    minter: minter.to_string(),
+                    vault_id,
+                    collateral_contract: collateral_contract.to_string(),
+                    amount: collateral_amount,
+                }
+                .into_event(MODULE_CENTRAL_CONTROL),
+            );
+    }
+
+    Ok(res)
 }
 
 pub fn redeem_stable_coin(
@@ -714,6 +1811,7 @@ pub fn redeem_stable_coin(
     redeemer: Addr,
     amount: Uint128,
     minter: Addr,
+    vault_id: u64,
 ) -> Result<Response, ContractError> {
     let config = read_config(deps.storage)?;
     let api = deps.api;
@@ -725,7 +1823,7 @@ pub fn redeem_stable_coin(
     }
 
     let minter_raw = api.addr_canonicalize(minter.as_str())?;
-    let minter_loan_info = read_minter_loan_info(deps.storage, &minter_raw)?;
+    let minter_loan_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
     if !minter_loan_info.is_redemption_provider {
         return Err(ContractError::CannotRedeemToNonRedemption {});
     }
@@ -736,7 +1834,7 @@ pub fn redeem_stable_coin(
     //need to deduct the redeem fee to the redemption provider, initially 0.5%, it can be modified later by Kryptonite DAO
     let redeem_amount = Uint256::from(amount) * (Decimal256::one() - config.redeem_fee);
 
-    let mut collaterals = read_collaterals(deps.storage, &minter_raw);
+    let mut collaterals = read_collaterals(deps.storage, &minter_raw, vault_id);
     let mut redeem_collaterals = Tokens::default();
     let mut collaterals_values = Uint256::zero();
     let mut prev_collaterals_value;
@@ -771,13 +1869,13 @@ pub fn redeem_stable_coin(
     }
 
     collaterals.sub(redeem_collaterals.clone())?;
-    store_collaterals(deps.storage, &minter_raw, &collaterals)?;
+    store_collaterals(deps.storage, &minter_raw, vault_id, &collaterals)?;
 
-    let mut minter_loans_info = read_minter_loan_info(deps.storage, &minter_raw)?;
+    let mut minter_loans_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
 
     //redeemer repay loans for minter, this should not deduct the redeem fee
     minter_loans_info.loans = minter_loans_info.loans - Uint256::from(amount);
-    store_minter_loan_info(deps.storage, &minter_raw, &minter_loans_info)?;
+    store_minter_loan_info(deps.storage, &minter_raw, vault_id, &minter_loans_info)?;
 
     let mut messages: Vec<CosmosMsg> = vec![];
     for redeem_elem in redeem_collaterals {
@@ -807,21 +1905,43 @@ pub fn redeem_stable_coin(
         }));
     }
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "redeem_stable_coin"),
-        attr("redeemer", redeemer.to_string()),
-        attr("amount", amount.to_string()),
-        attr("redemption", minter.to_string()),
-    ]))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "redeem_stable_coin"),
+            attr("redeemer", redeemer.to_string()),
+            attr("amount", amount.to_string()),
+            attr("redemption", minter.to_string()),
+            attr("vault_id", vault_id.to_string()),
+        ])
+        .add_event(
+            CdpEvent::Redeem {
+                redeemer: redeemer.to_string(),
+                minter: minter.to_string(),
+                vault_id,
+                amount,
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+        ))
 }
 
 pub fn withdraw_collateral(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     collateral_contract: Addr,
     collateral_amount: Uint128,
+    minter: Option<Addr>,
+    vault_id: u64,
 ) -> Result<Response, ContractError> {
-    // let storage = deps.storage;
+    let minter = assert_position_access(
+        deps.as_ref(),
+        &env,
+        &info,
+        minter,
+        OperatorPermission::Withdraw,
+        "withdraw_collateral",
+    )?;
     let api = deps.api;
 
     let mut collaterals_vec: Tokens = vec![];
@@ -832,10 +1952,10 @@ pub fn withdraw_collateral(
         Uint256::from(collateral_amount),
     ));
 
-    let sender_raw = api.addr_canonicalize(info.sender.clone().as_str())?;
-    let minter_loans_info = read_minter_loan_info(deps.storage, &sender_raw)?;
+    let minter_raw = api.addr_canonicalize(minter.as_str())?;
+    let minter_loans_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
 
-    let mut minter_collaterals = read_collaterals(deps.storage, &sender_raw);
+    let mut minter_collaterals = read_collaterals(deps.storage, &minter_raw, vault_id);
     minter_collaterals.sub(collaterals_vec)?;
 
     let mint_max_value = compute_mint_max_value(deps.as_ref(), &minter_collaterals)?;
@@ -846,7 +1966,7 @@ pub fn withdraw_collateral(
             mint_max_value,
         ));
     }
-    store_collaterals(deps.storage, &sender_raw, &minter_collaterals)?;
+    store_collaterals(deps.storage, &minter_raw, vault_id, &minter_collaterals)?;
 
     let whitelist_elem = read_whitelist_elem(deps.storage, &collateral_contract_raw)?;
 
@@ -856,7 +1976,7 @@ pub fn withdraw_collateral(
                 .addr_humanize(&whitelist_elem.custody_contract)?
                 .to_string(),
             msg: to_binary(&CustodyExecuteMsg::WithdrawCollateral {
-                minter: info.sender.clone().to_string(),
+                minter: minter.to_string(),
                 collateral_contract: collateral_contract.to_string(),
                 collateral_amount,
             })?,
@@ -867,17 +1987,28 @@ pub fn withdraw_collateral(
                 .addr_humanize(&whitelist_elem.reward_book_contract)?
                 .to_string(),
             msg: to_binary(&RewardBookExecuteMsg::DecreaseBalance {
-                address: info.sender.to_string(),
+                address: minter.to_string(),
                 amount: collateral_amount,
             })?,
             funds: vec![],
         }))
         .add_attributes(vec![
             attr("action", "withdraw_collateral"),
-            attr("sender", info.sender),
+            attr("sender", info.sender.to_string()),
+            attr("minter", minter.to_string()),
+            attr("vault_id", vault_id.to_string()),
             attr("collateral_contract", collateral_contract.to_string()),
             attr("collateral_amount", collateral_amount.to_string()),
-        ]))
+        ])
+        .add_event(
+            CdpEvent::Withdraw {
+                minter: minter.to_string(),
+                vault_id,
+                collateral_contract: collateral_contract.to_string(),
+                amount: collateral_amount,
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+        ))
 }
 
 pub fn compute_mint_max_value(deps: Deps, collaterals: &Tokens) -> StdResult<Uint256> {
@@ -909,9 +2040,8 @@ pub fn whitelist_collateral(
     collateral_contract: CanonicalAddr,
     reward_book_contract: CanonicalAddr,
 ) -> Result<Response, ContractError> {
-    let config = read_config(deps.storage)?;
-
-    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner_addr {
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !has_any_role(deps.storage, &[Role::Admin, Role::RiskManager], &sender_raw)? {
         return Err(ContractError::Unauthorized(
             "whitelist_collateral".to_string(),
             info.sender.to_string(),
@@ -931,7 +2061,21 @@ pub fn whitelist_collateral(
         reward_book_contract,
     };
     store_whitelist_elem(deps.storage, &collateral_contract, &data)?;
-    Ok(Response::default())
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "whitelist_collateral"),
+            attr(
+                "collateral_contract",
+                deps.api.addr_humanize(&collateral_contract)?.to_string(),
+            ),
+            attr("max_ltv", max_ltv.to_string()),
+        ])
+        .add_event(config_change_event(
+            MODULE_CENTRAL_CONTROL,
+            "whitelist_collateral",
+            info.sender,
+        )))
 }
 
 pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
@@ -951,19 +2095,24 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         stable_denom: config.stable_denom,
         epoch_period: config.epoch_period,
         redeem_fee: config.redeem_fee,
+        max_liquidation_batch_size: config.max_liquidation_batch_size,
+        keeper_reward: config.keeper_reward,
+        liquidation_cooldown: config.liquidation_cooldown,
+        liquidation_record_retention: config.liquidation_record_retention,
     })
 }
 
-pub fn query_loan_info(deps: Deps, minter: Addr) -> StdResult<LoanInfoResponse> {
+pub fn query_loan_info(deps: Deps, minter: Addr, vault_id: u64) -> StdResult<LoanInfoResponse> {
     let minter_raw = deps.api.addr_canonicalize(&minter.as_str())?;
 
-    let loan_info = read_minter_loan_info(deps.storage, &minter_raw)?;
+    let loan_info = read_minter_loan_info(deps.storage, &minter_raw, vault_id)?;
 
     let minter_collaterals: Vec<(CanonicalAddr, Uint256)> =
-        read_collaterals(deps.storage, &minter_raw);
+        read_collaterals(deps.storage, &minter_raw, vault_id);
     let max_value = compute_mint_max_value(deps, &minter_collaterals)?;
     Ok(LoanInfoResponse {
         minter: minter.to_string(),
+        vault_id,
         loans: loan_info.loans,
         max_mint_value: max_value,
     })
@@ -1042,22 +2191,27 @@ pub fn query_whitelist(
 pub fn query_redemption_provider_list(
     deps: Deps,
     minter: Option<Addr>,
+    vault_id: u64,
     start_after: Option<Addr>,
     limit: Option<u32>,
 ) -> StdResult<RedemptionProviderListRespone> {
     if let Some(minter) = minter {
-        let minter_loan =
-            read_minter_loan_info(deps.storage, &deps.api.addr_canonicalize(minter.as_str())?)?;
+        let minter_loan = read_minter_loan_info(
+            deps.storage,
+            &deps.api.addr_canonicalize(minter.as_str())?,
+            vault_id,
+        )?;
         Ok(RedemptionProviderListRespone {
             provider_list: vec![MinterLoanResponse {
                 minter: deps.api.addr_humanize(&minter_loan.minter)?.to_string(),
+                vault_id,
                 loans: minter_loan.loans,
                 is_redemption_provider: minter_loan.is_redemption_provider,
             }],
         })
     } else {
         let start_after = if let Some(start_after) = start_after {
-            Some(deps.api.addr_canonicalize(start_after.as_str())?)
+            Some((deps.api.addr_canonicalize(start_after.as_str())?, vault_id))
         } else {
             None
         };
@@ -1067,3 +2221,118 @@ pub fn query_redemption_provider_list(
         Ok(RedemptionProviderListRespone { provider_list })
     }
 }
+
+pub fn query_operator(deps: Deps, minter: Addr, operator: Addr) -> StdResult<OperatorResponse> {
+    let approval = read_operator(
+        deps.storage,
+        &deps.api.addr_canonicalize(minter.as_str())?,
+        &deps.api.addr_canonicalize(operator.as_str())?,
+    )?
+    .ok_or_else(|| StdError::not_found("OperatorApproval"))?;
+
+    Ok(OperatorResponse {
+        minter: minter.to_string(),
+        operator: operator.to_string(),
+        permissions: approval.permissions,
+        expires_at: approval.expires_at,
+    })
+}
+
+pub fn query_operators(
+    deps: Deps,
+    minter: Addr,
+    start_after: Option<Addr>,
+    limit: Option<u32>,
+) -> StdResult<OperatorsResponse> {
+    let start_after = if let Some(start_after) = start_after {
+        Some(deps.api.addr_canonicalize(start_after.as_str())?)
+    } else {
+        None
+    };
+
+    let operators = read_operators(
+        deps,
+        &deps.api.addr_canonicalize(minter.as_str())?,
+        start_after,
+        limit,
+    )?;
+    Ok(OperatorsResponse { operators })
+}
+
+pub fn query_vaults(
+    deps: Deps,
+    minter: Addr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<VaultsResponse> {
+    let vaults = read_vaults(
+        deps,
+        &deps.api.addr_canonicalize(minter.as_str())?,
+        start_after,
+        limit,
+    )?;
+    Ok(VaultsResponse { vaults })
+}
+
+pub fn query_liquidation_record(deps: Deps, id: u64) -> StdResult<LiquidationRecordResponse> {
+    let record = read_liquidation_record(deps.storage, id)?;
+    liquidation_record_response(deps, id, record)
+}
+
+pub fn query_liquidation_history(
+    deps: Deps,
+    minter: Option<Addr>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<LiquidationHistoryResponse> {
+    let minter = if let Some(minter) = minter {
+        Some(deps.api.addr_canonicalize(minter.as_str())?)
+    } else {
+        None
+    };
+
+    let records = read_liquidation_records(
+        deps.storage,
+        minter.as_ref(),
+        start_time,
+        end_time,
+        start_after,
+        limit,
+    )?
+    .into_iter()
+    .map(|(id, record)| liquidation_record_response(deps, id, record))
+    .collect::<StdResult<Vec<LiquidationRecordResponse>>>()?;
+    Ok(LiquidationHistoryResponse { records })
+}
+
+fn liquidation_record_response(
+    deps: Deps,
+    id: u64,
+    record: LiquidationRecord,
+) -> StdResult<LiquidationRecordResponse> {
+    let mut collaterals: Vec<LiquidatedCollateral> = vec![];
+    for elem in record.collaterals {
+        collaterals.push(LiquidatedCollateral {
+            collateral_contract: deps.api.addr_humanize(&elem.collateral_contract)?.to_string(),
+            amount: elem.amount,
+            price: elem.price,
+        });
+    }
+
+    Ok(LiquidationRecordResponse {
+        id,
+        minter: deps.api.addr_humanize(&record.minter)?.to_string(),
+        vault_id: record.vault_id,
+        liquidator: deps.api.addr_humanize(&record.liquidator)?.to_string(),
+        collaterals,
+        loans: record.loans,
+        repaid: record.repaid,
+        estimated_bid_fee: record.estimated_bid_fee,
+        estimated_liquidator_fee: record.estimated_liquidator_fee,
+        keeper_reward: record.keeper_reward,
+        height: record.height,
+        time: record.time,
+    })
+}
@@ -15,6 +15,7 @@
 
 use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{OverflowError, StdError};
+use cdp::rbac::AccessError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -60,4 +61,47 @@ pub enum ContractError {
 
     #[error("Redeem fee exceeds limit")]
     RedeemFeeExceedsLimit {}, 
+
+    #[error("Vault {0} has not been opened")]
+    VaultNotFound(u64),
+
+    #[error("Closing a position must repay its loans: {0}, repay amount: {1}")]
+    ClosePositionAmountMismatch(Uint256, Uint256),
+
+    #[error("Keeper reward rate exceeds limit")]
+    KeeperRewardRateExceedsLimit {},
+
+    #[error("Liquidation batch size must be greater than zero")]
+    InvalidLiquidationBatchSize {},
+
+    #[error("Too many positions in a liquidation batch, maximum: {0}")]
+    LiquidationBatchTooLarge(u32),
+
+    #[error("Invalid reply ID")]
+    InvalidReplyId {},
+
+    #[error("A migration is in progress, migrate the remaining positions first")]
+    MigrationPending {},
+
+    #[error("Position was liquidated recently, next liquidation allowed at: {0}")]
+    LiquidationCooldown(u64),
+
+    #[error("Approval has already expired")]
+    Expired {},
+
+    #[error("Position token can not be transferred to its owner")]
+    SelfTransfer {},
+
+    #[error("Contract is paused")]
+    Paused {},
+}
+
+impl AccessError for ContractError {
+    fn unauthorized(function: &str, sender: &str) -> Self {
+        ContractError::Unauthorized(function.to_string(), sender.to_string())
+    }
+
+    fn paused() -> Self {
+        ContractError::Paused {}
+    }
 }
@@ -1,6 +1,8 @@
 
 pub mod contract;
 pub mod error;
+pub mod migration;
+pub mod position;
 pub mod state;
 
 #[cfg(test)]
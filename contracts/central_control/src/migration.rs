@@ -0,0 +1,237 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::state::{
+    debt_key, mint_default_vault_token, read_collaterals, store_collaterals,
+    store_minter_loan_info, store_whitelist_elem, CollateralElem, MinterLoanInfo, WhitelistElem,
+};
+use cdp::central_control::DEFAULT_VAULT_ID;
+use cdp::tokens::{Tokens, TokensMath};
+use cosmwasm_std::{CanonicalAddr, Order, StdError, StdResult, Storage};
+use cosmwasm_storage::{Bucket, ReadonlyBucket};
+use cw_storage_plus::{Index, IndexList, IndexedMap, Item, MultiIndex};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+// prefixes of the legacy `cosmwasm_storage` buckets
+pub const LEGACY_PREFIX_WHITELISTELEM: &[u8] = b"whitelistelem";
+pub const LEGACY_PREFIX_COLLATERALS: &[u8] = b"collateral";
+pub const LEGACY_PREFIX_LOANINFO: &[u8] = b"loan";
+
+// step of a migration in progress, entries are removed from the former layout once moved
+// so that each page resumes with the first entry left
+const MIGRATION_CURSOR: Item<MigrationStep> = Item::new("migration_cursor");
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, JsonSchema)]
+pub enum MigrationStep {
+    LegacyWhitelist,
+    LegacyCollaterals,
+    LegacyLoans,
+    VaultLoans,
+    VaultCollaterals,
+}
+
+impl MigrationStep {
+    fn next(self) -> Option<MigrationStep> {
+        match self {
+            MigrationStep::LegacyWhitelist => Some(MigrationStep::LegacyCollaterals),
+            MigrationStep::LegacyCollaterals => Some(MigrationStep::LegacyLoans),
+            MigrationStep::VaultLoans => Some(MigrationStep::VaultCollaterals),
+            MigrationStep::LegacyLoans | MigrationStep::VaultCollaterals => None,
+        }
+    }
+}
+
+/// Start moving every entry of the legacy buckets into the `cw_storage_plus` maps,
+/// the default vaults of the positions moved get their position tokens
+pub fn start_legacy_migration(storage: &mut dyn Storage) -> StdResult<()> {
+    MIGRATION_CURSOR.save(storage, &MigrationStep::LegacyWhitelist)
+}
+
+/// Start moving the positions stored per minter into the minter's default vault,
+/// the default vaults get their position tokens
+pub fn start_default_vaults_migration(storage: &mut dyn Storage) -> StdResult<()> {
+    MIGRATION_CURSOR.save(storage, &MigrationStep::VaultLoans)
+}
+
+pub fn is_migrating(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(MIGRATION_CURSOR.may_load(storage)?.is_some())
+}
+
+/// Move the next `limit` entries of the migration in progress, returns whether
+/// all of them are moved
+pub fn migrate_positions(storage: &mut dyn Storage, limit: Option<u32>) -> StdResult<bool> {
+    let mut step = MIGRATION_CURSOR
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err("All positions are migrated"))?;
+
+    let mut remaining = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    loop {
+        let moved = migrate_page(storage, step, remaining)?;
+        if moved == remaining {
+            MIGRATION_CURSOR.save(storage, &step)?;
+            return Ok(false);
+        }
+        remaining -= moved;
+
+        match step.next() {
+            Some(next) => step = next,
+            None => {
+                MIGRATION_CURSOR.remove(storage);
+                return Ok(true);
+            }
+        }
+    }
+}
+
+// move up to `limit` entries of the step, returns the number of entries moved
+fn migrate_page(storage: &mut dyn Storage, step: MigrationStep, limit: usize) -> StdResult<usize> {
+    match step {
+        MigrationStep::LegacyWhitelist => {
+            let prefix = LEGACY_PREFIX_WHITELISTELEM;
+            let entries = drain_bucket::<WhitelistElem>(storage, prefix, limit)?;
+            for (k, v) in entries.iter() {
+                store_whitelist_elem(storage, &CanonicalAddr::from(k.clone()), v)?;
+            }
+            Ok(entries.len())
+        }
+        MigrationStep::LegacyCollaterals => {
+            let entries = drain_bucket::<Tokens>(storage, LEGACY_PREFIX_COLLATERALS, limit)?;
+            for (k, v) in entries.iter() {
+                let minter = CanonicalAddr::from(k.clone());
+                store_collaterals(storage, &minter, DEFAULT_VAULT_ID, v)?;
+                mint_default_vault_token(storage, &minter)?;
+            }
+            Ok(entries.len())
+        }
+        MigrationStep::LegacyLoans => {
+            let entries = drain_bucket::<MinterLoanInfo>(storage, LEGACY_PREFIX_LOANINFO, limit)?;
+            for (k, v) in entries.iter() {
+                let minter = CanonicalAddr::from(k.clone());
+                store_minter_loan_info(storage, &minter, DEFAULT_VAULT_ID, v)?;
+                mint_default_vault_token(storage, &minter)?;
+            }
+            Ok(entries.len())
+        }
+        MigrationStep::VaultLoans => {
+            let loans = legacy_minter_loans();
+            let entries = loans
+                .range(storage, None, None, Order::Ascending)
+                .take(limit)
+                .collect::<StdResult<Vec<(Vec<u8>, MinterLoanInfo)>>>()?;
+            for (k, v) in entries.iter() {
+                loans.remove(storage, k)?;
+                let minter = CanonicalAddr::from(k.clone());
+                store_minter_loan_info(storage, &minter, DEFAULT_VAULT_ID, v)?;
+                mint_default_vault_token(storage, &minter)?;
+            }
+            Ok(entries.len())
+        }
+        MigrationStep::VaultCollaterals => {
+            let collaterals = legacy_minter_collaterals();
+            let entries = collaterals
+                .range(storage, None, None, Order::Ascending)
+                .take(limit)
+                .collect::<StdResult<Vec<((Vec<u8>, Vec<u8>), CollateralElem)>>>()?;
+            for ((minter, collateral_contract), v) in entries.iter() {
+                collaterals.remove(storage, (minter, collateral_contract))?;
+                let minter = CanonicalAddr::from(minter.clone());
+                let mut vault_collaterals = read_collaterals(storage, &minter, DEFAULT_VAULT_ID);
+                vault_collaterals.add(vec![(v.collateral_contract.clone(), v.amount)]);
+                store_collaterals(storage, &minter, DEFAULT_VAULT_ID, &vault_collaterals)?;
+                mint_default_vault_token(storage, &minter)?;
+            }
+            Ok(entries.len())
+        }
+    }
+}
+
+pub struct LegacyLoanIndexes<'a> {
+    pub redemption_provider: MultiIndex<'a, u8, MinterLoanInfo, Vec<u8>>,
+    pub debt: MultiIndex<'a, Vec<u8>, MinterLoanInfo, Vec<u8>>,
+}
+
+impl<'a> IndexList<MinterLoanInfo> for LegacyLoanIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<MinterLoanInfo>> + '_> {
+        let v: Vec<&dyn Index<MinterLoanInfo>> = vec![&self.redemption_provider, &self.debt];
+        Box::new(v.into_iter())
+    }
+}
+
+// loans keyed by minter, before vaults were introduced
+pub fn legacy_minter_loans<'a>() -> IndexedMap<'a, &'a [u8], MinterLoanInfo, LegacyLoanIndexes<'a>> {
+    let indexes = LegacyLoanIndexes {
+        redemption_provider: MultiIndex::new(
+            |d: &MinterLoanInfo| u8::from(d.is_redemption_provider),
+            "loan_info",
+            "loan_info__redemption_provider",
+        ),
+        debt: MultiIndex::new(
+            |d: &MinterLoanInfo| debt_key(d.loans),
+            "loan_info",
+            "loan_info__debt",
+        ),
+    };
+    IndexedMap::new("loan_info", indexes)
+}
+
+pub struct LegacyCollateralIndexes<'a> {
+    pub collateral_contract: MultiIndex<'a, Vec<u8>, CollateralElem, (Vec<u8>, Vec<u8>)>,
+}
+
+impl<'a> IndexList<CollateralElem> for LegacyCollateralIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<CollateralElem>> + '_> {
+        let v: Vec<&dyn Index<CollateralElem>> = vec![&self.collateral_contract];
+        Box::new(v.into_iter())
+    }
+}
+
+// collaterals keyed by (minter, collateral contract), before vaults were introduced
+pub fn legacy_minter_collaterals<'a>(
+) -> IndexedMap<'a, (&'a [u8], &'a [u8]), CollateralElem, LegacyCollateralIndexes<'a>> {
+    let indexes = LegacyCollateralIndexes {
+        collateral_contract: MultiIndex::new(
+            |d: &CollateralElem| d.collateral_contract.to_vec(),
+            "collaterals",
+            "collaterals__collateral_contract",
+        ),
+    };
+    IndexedMap::new("collaterals", indexes)
+}
+
+// load the first `limit` entries of a legacy bucket and remove them from storage
+fn drain_bucket<T>(
+    storage: &mut dyn Storage,
+    prefix: &[u8],
+    limit: usize,
+) -> StdResult<Vec<(Vec<u8>, T)>>
+where
+    T: Serialize + DeserializeOwned,
+{
+    let entries = ReadonlyBucket::<T>::new(storage, prefix)
+        .range(None, None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<(Vec<u8>, T)>>>()?;
+
+    let mut bucket = Bucket::<T>::new(storage, prefix);
+    for (k, _) in entries.iter() {
+        bucket.remove(k);
+    }
+
+    Ok(entries)
+}
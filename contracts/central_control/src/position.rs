@@ -0,0 +1,292 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Position tokens, the cw721 interface of the opened vaults. The holder of a
+//! token is the minter of its vault, so a transfer moves the vault to the
+//! recipient together with its reward book balances.
+
+use crate::error::ContractError;
+use crate::state::{
+    move_vault, read_collaterals, read_minter_loan_info, read_position_token,
+    read_position_token_count, read_position_token_ids, read_whitelist_elem,
+    store_position_token, TokenApproval,
+};
+use cdp::central_control::VaultResponse;
+use cdp::cw721::{
+    Approval, ContractInfoResponse, Cw721ReceiveMsg, NftInfoResponse, NumTokensResponse,
+    OwnerOfResponse, TokensResponse,
+};
+use cdp::events::{CdpEvent, MODULE_CENTRAL_CONTROL};
+use cdp::reward_book::ExecuteMsg as RewardBookExecuteMsg;
+use cdp::tokens::TokensToHuman;
+
+use cosmwasm_std::{
+    attr, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo, Response,
+    StdResult, Uint128, WasmMsg,
+};
+use cw_utils::Expiration;
+
+pub const POSITION_TOKEN_NAME: &str = "Kryptonite CDP Position";
+pub const POSITION_TOKEN_SYMBOL: &str = "kCDP";
+
+pub fn transfer_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: Addr,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let res = move_position(deps, &env, &info, &recipient, &token_id, "transfer_nft")?;
+
+    Ok(res.add_attributes(vec![
+        attr("action", "transfer_nft"),
+        attr("sender", info.sender.to_string()),
+        attr("recipient", recipient.to_string()),
+        attr("token_id", token_id),
+    ]))
+}
+
+pub fn send_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    contract: Addr,
+    token_id: String,
+    msg: Binary,
+) -> Result<Response, ContractError> {
+    let res = move_position(deps, &env, &info, &contract, &token_id, "send_nft")?;
+
+    let receive_msg = Cw721ReceiveMsg {
+        sender: info.sender.to_string(),
+        token_id: token_id.clone(),
+        msg,
+    };
+
+    Ok(res
+        .add_message(receive_msg.into_cosmos_msg(contract.to_string())?)
+        .add_attributes(vec![
+            attr("action", "send_nft"),
+            attr("sender", info.sender.to_string()),
+            attr("recipient", contract.to_string()),
+            attr("token_id", token_id),
+        ]))
+}
+
+/// Move the vault of the token to a new vault of `recipient`. Callable by the
+/// owner or an approved spender, the owner can not be the recipient. The reward book balances of the collaterals
+/// follow the vault, rewards accrued so far stay with the previous owner
+fn move_position(
+    deps: DepsMut,
+    env: &Env,
+    info: &MessageInfo,
+    recipient: &Addr,
+    token_id: &str,
+    fn_name: &str,
+) -> Result<Response, ContractError> {
+    let mut token = read_position_token(deps.storage, token_id)?;
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    let is_approved = token
+        .approvals
+        .iter()
+        .any(|approval| approval.spender == sender_raw && !approval.expires.is_expired(&env.block));
+    if token.owner != sender_raw && !is_approved {
+        return Err(ContractError::Unauthorized(
+            fn_name.to_string(),
+            info.sender.to_string(),
+        ));
+    }
+
+    let owner = deps.api.addr_humanize(&token.owner)?;
+    let recipient_raw = deps.api.addr_canonicalize(recipient.as_str())?;
+    if recipient_raw == token.owner {
+        return Err(ContractError::SelfTransfer {});
+    }
+    let (vault_id, collaterals) =
+        move_vault(deps.storage, &token.owner, token.vault_id, &recipient_raw)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for (collateral_contract, amount) in collaterals.iter() {
+        let collateral_elem = read_whitelist_elem(deps.storage, collateral_contract)?;
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps
+                .api
+                .addr_humanize(&collateral_elem.reward_book_contract)?
+                .to_string(),
+            msg: to_binary(&RewardBookExecuteMsg::TransferBalance {
+                from: owner.to_string(),
+                to: recipient.to_string(),
+                amount: Uint128::from(*amount),
+            })?,
+            funds: vec![],
+        }));
+    }
+
+    let event = CdpEvent::PositionTransfer {
+        token_id: token_id.to_string(),
+        from: owner.to_string(),
+        from_vault_id: token.vault_id,
+        to: recipient.to_string(),
+        to_vault_id: vault_id,
+    };
+
+    token.owner = recipient_raw;
+    token.vault_id = vault_id;
+    token.approvals = vec![];
+    store_position_token(deps.storage, token_id, &token)?;
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_event(event.into_event(MODULE_CENTRAL_CONTROL)))
+}
+
+pub fn approve_position(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    spender: Addr,
+    token_id: String,
+    expires: Option<Expiration>,
+) -> Result<Response, ContractError> {
+    let mut token = read_position_token(deps.storage, &token_id)?;
+    if token.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized(
+            "approve".to_string(),
+            info.sender.to_string(),
+        ));
+    }
+
+    let expires = expires.unwrap_or_default();
+    if expires.is_expired(&env.block) {
+        return Err(ContractError::Expired {});
+    }
+
+    let spender_raw = deps.api.addr_canonicalize(spender.as_str())?;
+    token.approvals.retain(|approval| approval.spender != spender_raw);
+    token.approvals.push(TokenApproval {
+        spender: spender_raw,
+        expires,
+    });
+    store_position_token(deps.storage, &token_id, &token)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "approve"),
+        attr("sender", info.sender.to_string()),
+        attr("spender", spender.to_string()),
+        attr("token_id", token_id),
+    ]))
+}
+
+pub fn revoke_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    spender: Addr,
+    token_id: String,
+) -> Result<Response, ContractError> {
+    let mut token = read_position_token(deps.storage, &token_id)?;
+    if token.owner != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized(
+            "revoke".to_string(),
+            info.sender.to_string(),
+        ));
+    }
+
+    let spender_raw = deps.api.addr_canonicalize(spender.as_str())?;
+    token.approvals.retain(|approval| approval.spender != spender_raw);
+    store_position_token(deps.storage, &token_id, &token)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "revoke"),
+        attr("sender", info.sender.to_string()),
+        attr("spender", spender.to_string()),
+        attr("token_id", token_id),
+    ]))
+}
+
+pub fn query_owner_of(
+    deps: Deps,
+    env: Env,
+    token_id: String,
+    include_expired: bool,
+) -> StdResult<OwnerOfResponse> {
+    let token = read_position_token(deps.storage, &token_id)?;
+    let approvals = token
+        .approvals
+        .into_iter()
+        .filter(|approval| include_expired || !approval.expires.is_expired(&env.block))
+        .map(|approval| {
+            Ok(Approval {
+                spender: deps.api.addr_humanize(&approval.spender)?.to_string(),
+                expires: approval.expires,
+            })
+        })
+        .collect::<StdResult<Vec<Approval>>>()?;
+
+    Ok(OwnerOfResponse {
+        owner: deps.api.addr_humanize(&token.owner)?.to_string(),
+        approvals,
+    })
+}
+
+pub fn query_nft_info(deps: Deps, token_id: String) -> StdResult<NftInfoResponse<VaultResponse>> {
+    let token = read_position_token(deps.storage, &token_id)?;
+    let loan_info = read_minter_loan_info(deps.storage, &token.owner, token.vault_id)?;
+
+    Ok(NftInfoResponse {
+        token_uri: None,
+        extension: VaultResponse {
+            vault_id: token.vault_id,
+            loans: loan_info.loans,
+            is_redemption_provider: loan_info.is_redemption_provider,
+            collaterals: read_collaterals(deps.storage, &token.owner, token.vault_id)
+                .to_human(deps)?,
+        },
+    })
+}
+
+pub fn query_tokens(
+    deps: Deps,
+    owner: Addr,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let tokens = read_position_token_ids(
+        deps.storage,
+        Some(&deps.api.addr_canonicalize(owner.as_str())?),
+        start_after,
+        limit,
+    )?;
+    Ok(TokensResponse { tokens })
+}
+
+pub fn query_all_tokens(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<TokensResponse> {
+    let tokens = read_position_token_ids(deps.storage, None, start_after, limit)?;
+    Ok(TokensResponse { tokens })
+}
+
+pub fn query_num_tokens(deps: Deps) -> StdResult<NumTokensResponse> {
+    Ok(NumTokensResponse {
+        count: read_position_token_count(deps.storage)?,
+    })
+}
+
+pub fn query_contract_info() -> StdResult<ContractInfoResponse> {
+    Ok(ContractInfoResponse {
+        name: POSITION_TOKEN_NAME.to_string(),
+        symbol: POSITION_TOKEN_SYMBOL.to_string(),
+    })
+}
@@ -12,23 +12,165 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cdp::central_control::{CollateralsResponse, MinterLoanResponse, WhitelistElemResponse};
-use cdp::tokens::Tokens;
+use cdp::central_control::{
+    CollateralsResponse, KeeperReward, LiquidationResult, MinterLoanResponse, OperatorPermission,
+    OperatorResponse, VaultResponse,
+    WhitelistElemResponse, DEFAULT_LIQUIDATION_RECORD_RETENTION, DEFAULT_MAX_LIQUIDATION_BATCH_SIZE,
+    DEFAULT_VAULT_ID,
+};
+use cdp::tokens::{Tokens, TokensToHuman};
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{CanonicalAddr, Deps, Order, StdError, StdResult, Storage};
+use cosmwasm_std::{CanonicalAddr, Deps, Order, StdError, StdResult, Storage, Uint128};
+
+use cw_storage_plus::{Bound, Index, IndexList, IndexedMap, Item, Map, MultiIndex, PrefixBound};
+use cw_utils::Expiration;
+
+// singletons keep the keys of the former `cosmwasm_storage` layout
+const CONFIG: Item<Config> = Item::new("\u{0}\u{6}config");
+const STATE: Item<State> = Item::new("\u{0}\u{5}state");
+const NEWOWNER: Item<NewOwnerAddr> = Item::new("\u{0}\u{8}newowner");
+const WHITELIST: Map<&[u8], WhitelistElem> = Map::new("whitelist");
+// (minter, operator) => approval
+const OPERATORS: Map<(&[u8], &[u8]), OperatorApproval> = Map::new("operators");
+// minter => number of vaults opened besides the default vault
+const VAULT_COUNT: Map<&[u8], u64> = Map::new("vault_count");
+// (minter, vault id) => block time of the last liquidation
+const LAST_LIQUIDATION: Map<(&[u8], u64), u64> = Map::new("last_liquidation");
+// (minter, vault id) => id of the position token of the vault, one entry per open vault.
+// The default vault gets its token once used
+const VAULT_TOKENS: Map<(&[u8], u64), String> = Map::new("vault_tokens");
+// number of position tokens minted, the last one is the id of the latest token
+const POSITION_TOKEN_COUNT: Item<u64> = Item::new("position_token_count");
+// results of the last liquidation batch, the replies of its failed liquidations update them
+const LIQUIDATION_BATCH: Item<Vec<LiquidationResult>> = Item::new("liquidation_batch");
+// number of liquidation records stored, the last one is the id of the latest record
+const LIQUIDATION_RECORD_COUNT: Item<u64> = Item::new("liquidation_record_count");
+// records pruned by a single liquidation once the retention is exceeded. A liquidation stores
+// one record, so a lowered retention is caught up by nine records per liquidation while the
+// gas a liquidation spends on pruning stays bounded
+const MAX_PRUNED_RECORDS: usize = 10;
+
+pub struct LoanIndexes<'a> {
+    // redemption provider flag, 1 for providers and 0 otherwise
+    pub redemption_provider: MultiIndex<'a, u8, MinterLoanInfo, (Vec<u8>, u64)>,
+    // big endian encoded loan amount, ranges in debt order
+    pub debt: MultiIndex<'a, Vec<u8>, MinterLoanInfo, (Vec<u8>, u64)>,
+}
+
+impl<'a> IndexList<MinterLoanInfo> for LoanIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<MinterLoanInfo>> + '_> {
+        let v: Vec<&dyn Index<MinterLoanInfo>> = vec![&self.redemption_provider, &self.debt];
+        Box::new(v.into_iter())
+    }
+}
+
+// keyed by (minter, vault id), one entry per vault
+pub fn minter_loans<'a>() -> IndexedMap<'a, (&'a [u8], u64), MinterLoanInfo, LoanIndexes<'a>> {
+    let indexes = LoanIndexes {
+        redemption_provider: MultiIndex::new(
+            |d: &MinterLoanInfo| u8::from(d.is_redemption_provider),
+            "vault_loans",
+            "vault_loans__redemption_provider",
+        ),
+        debt: MultiIndex::new(
+            |d: &MinterLoanInfo| debt_key(d.loans),
+            "vault_loans",
+            "vault_loans__debt",
+        ),
+    };
+    IndexedMap::new("vault_loans", indexes)
+}
+
+// (minter, vault id, collateral contract)
+pub type CollateralPk = (Vec<u8>, u64, Vec<u8>);
+
+pub struct CollateralIndexes<'a> {
+    pub collateral_contract: MultiIndex<'a, Vec<u8>, CollateralElem, CollateralPk>,
+}
+
+impl<'a> IndexList<CollateralElem> for CollateralIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<CollateralElem>> + '_> {
+        let v: Vec<&dyn Index<CollateralElem>> = vec![&self.collateral_contract];
+        Box::new(v.into_iter())
+    }
+}
+
+// keyed by (minter, vault id, collateral contract), one entry per deposited collateral
+pub fn minter_collaterals<'a>(
+) -> IndexedMap<'a, (&'a [u8], u64, &'a [u8]), CollateralElem, CollateralIndexes<'a>> {
+    let indexes = CollateralIndexes {
+        collateral_contract: MultiIndex::new(
+            |d: &CollateralElem| d.collateral_contract.to_vec(),
+            "vault_collaterals",
+            "vault_collaterals__collateral_contract",
+        ),
+    };
+    IndexedMap::new("vault_collaterals", indexes)
+}
+
+pub struct PositionTokenIndexes<'a> {
+    pub owner: MultiIndex<'a, Vec<u8>, PositionToken, String>,
+}
+
+impl<'a> IndexList<PositionToken> for PositionTokenIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<PositionToken>> + '_> {
+        let v: Vec<&dyn Index<PositionToken>> = vec![&self.owner];
+        Box::new(v.into_iter())
+    }
+}
+
+// keyed by token id, one entry per opened vault
+pub fn position_tokens<'a>() -> IndexedMap<'a, &'a str, PositionToken, PositionTokenIndexes<'a>> {
+    let indexes = PositionTokenIndexes {
+        owner: MultiIndex::new(
+            |d: &PositionToken| d.owner.to_vec(),
+            "position_tokens",
+            "position_tokens__owner",
+        ),
+    };
+    IndexedMap::new("position_tokens", indexes)
+}
+
+pub struct LiquidationRecordIndexes<'a> {
+    pub minter: MultiIndex<'a, Vec<u8>, LiquidationRecord, u64>,
+    // block time of the liquidation, records are stored in time order
+    pub time: MultiIndex<'a, u64, LiquidationRecord, u64>,
+}
+
+impl<'a> IndexList<LiquidationRecord> for LiquidationRecordIndexes<'a> {
+    fn get_indexes(&'_ self) -> Box<dyn Iterator<Item = &'_ dyn Index<LiquidationRecord>> + '_> {
+        let v: Vec<&dyn Index<LiquidationRecord>> = vec![&self.minter, &self.time];
+        Box::new(v.into_iter())
+    }
+}
 
-use cosmwasm_storage::{bucket, Bucket, ReadonlyBucket, ReadonlySingleton, Singleton};
+// keyed by record id, one entry per liquidation
+pub fn liquidation_records<'a>(
+) -> IndexedMap<'a, u64, LiquidationRecord, LiquidationRecordIndexes<'a>> {
+    let indexes = LiquidationRecordIndexes {
+        minter: MultiIndex::new(
+            |d: &LiquidationRecord| d.minter.to_vec(),
+            "liquidation_records",
+            "liquidation_records__minter",
+        ),
+        time: MultiIndex::new(
+            |d: &LiquidationRecord| d.time,
+            "liquidation_records",
+            "liquidation_records__time",
+        ),
+    };
+    IndexedMap::new("liquidation_records", indexes)
+}
 
-const KEY_CONFIG: &[u8] = b"config";
-const KEY_STATE: &[u8] = b"state";
-const PREFIX_WHITELISTELEM: &[u8] = b"whitelistelem";
-const PREFIX_COLLATERALS: &[u8] = b"collateral";
-const PREFIX_LOANINFO: &[u8] = b"loan";
-const KEY_NEWOWNER: &[u8] = b"newowner";
+pub fn debt_key(loans: Uint256) -> Vec<u8> {
+    let mut key = vec![0u8; 32];
+    loans.0.to_big_endian(&mut key);
+    key
+}
 
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
@@ -61,6 +203,23 @@ pub struct Config {
     //The distribution period after the staking revenue from the chain,
     pub epoch_period: u64,
     pub redeem_fee: Decimal256,
+    // configs stored before batch liquidations use the default size
+    #[serde(default = "default_max_liquidation_batch_size")]
+    pub max_liquidation_batch_size: u32,
+    #[serde(default)]
+    pub keeper_reward: KeeperReward,
+    #[serde(default)]
+    pub liquidation_cooldown: u64,
+    #[serde(default = "default_liquidation_record_retention")]
+    pub liquidation_record_retention: u64,
+}
+
+fn default_max_liquidation_batch_size() -> u32 {
+    DEFAULT_MAX_LIQUIDATION_BATCH_SIZE
+}
+
+fn default_liquidation_record_retention() -> u64 {
+    DEFAULT_LIQUIDATION_RECORD_RETENTION
 }
 
 
@@ -69,105 +228,389 @@ pub struct NewOwnerAddr {
     pub new_owner_addr: CanonicalAddr, 
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralElem {
+    pub minter: CanonicalAddr,
+    #[serde(default)]
+    pub vault_id: u64,
+    pub collateral_contract: CanonicalAddr,
+    pub amount: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OperatorApproval {
+    pub permissions: Vec<OperatorPermission>,
+    /// block time in seconds, the approval never expires if not set
+    pub expires_at: Option<u64>,
+}
+
+impl OperatorApproval {
+    pub fn allows(&self, permission: OperatorPermission, block_time: u64) -> bool {
+        let expired = matches!(self.expires_at, Some(expires_at) if block_time >= expires_at);
+        !expired && self.permissions.contains(&permission)
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PositionToken {
+    /// the owner is the minter of the vault
+    pub owner: CanonicalAddr,
+    pub vault_id: u64,
+    pub approvals: Vec<TokenApproval>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TokenApproval {
+    pub spender: CanonicalAddr,
+    pub expires: Expiration,
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct MinterLoanInfo {
     pub minter: CanonicalAddr,
+    /// positions from before vault support are stored without an id and belong to the default vault
+    #[serde(default)]
+    pub vault_id: u64,
     pub loans: Uint256,
     pub is_redemption_provider: bool,
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidationRecord {
+    pub minter: CanonicalAddr,
+    pub vault_id: u64,
+    pub liquidator: CanonicalAddr,
+    pub collaterals: Vec<LiquidatedCollateralElem>,
+    /// loans of the vault before the liquidation
+    pub loans: Uint256,
+    /// set once the proceeds of the collaterals are repaid
+    pub repaid: Uint256,
+    pub keeper_reward: Uint128,
+    /// fees of the liquidation queue, as simulated at the liquidation prices
+    #[serde(default, alias = "bid_fee")]
+    pub estimated_bid_fee: Uint256,
+    #[serde(default, alias = "liquidator_fee")]
+    pub estimated_liquidator_fee: Uint256,
+    pub height: u64,
+    pub time: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LiquidatedCollateralElem {
+    pub collateral_contract: CanonicalAddr,
+    pub amount: Uint256,
+    pub price: Decimal256,
+}
+
 pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
-    Singleton::new(storage, KEY_CONFIG).save(data)
+    CONFIG.save(storage, data)
 }
 
 pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
-    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+    CONFIG.load(storage)
 }
 
 
 pub fn store_new_owner(storage: &mut dyn Storage, data: &NewOwnerAddr) -> StdResult<()> {
-    Singleton::new(storage, KEY_NEWOWNER).save(data)
+    NEWOWNER.save(storage, data)
 }
 
 pub fn read_new_owner(storage: &dyn Storage) -> StdResult<NewOwnerAddr> {
-    ReadonlySingleton::new(storage, KEY_NEWOWNER).load()
+    NEWOWNER.load(storage)
 }
 
 pub fn store_state(storage: &mut dyn Storage, data: &State) -> StdResult<()> {
-    Singleton::new(storage, KEY_STATE).save(data)
+    STATE.save(storage, data)
 }
 
 pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
-    ReadonlySingleton::new(storage, KEY_STATE).load()
+    STATE.load(storage)
 }
 
 pub fn store_minter_loan_info(
     storage: &mut dyn Storage,
     minter: &CanonicalAddr,
+    vault_id: u64,
     loan_info: &MinterLoanInfo,
 ) -> StdResult<()> {
-    bucket(storage, PREFIX_LOANINFO).save(minter.as_slice(), loan_info)
+    minter_loans().save(storage, (minter.as_slice(), vault_id), loan_info)
 }
 
 pub fn read_minter_loan_info(
     storage: &dyn Storage,
     minter: &CanonicalAddr,
+    vault_id: u64,
 ) -> StdResult<MinterLoanInfo> {
-    let loan_info_bucket: ReadonlyBucket<MinterLoanInfo> =
-        ReadonlyBucket::new(storage, PREFIX_LOANINFO);
-
-    match loan_info_bucket.load(minter.clone().as_slice()) {
-        Ok(v) => Ok(v),
-        _ => Ok(MinterLoanInfo {
+    match minter_loans().may_load(storage, (minter.as_slice(), vault_id))? {
+        Some(v) => Ok(v),
+        None => Ok(MinterLoanInfo {
             minter: minter.clone(),
+            vault_id,
             loans: Uint256::zero(),
             is_redemption_provider: false,
         }),
     }
 }
 
+pub fn store_last_liquidation(
+    storage: &mut dyn Storage,
+    minter: &CanonicalAddr,
+    vault_id: u64,
+    time: u64,
+) -> StdResult<()> {
+    LAST_LIQUIDATION.save(storage, (minter.as_slice(), vault_id), &time)
+}
+
+pub fn read_last_liquidation(
+    storage: &dyn Storage,
+    minter: &CanonicalAddr,
+    vault_id: u64,
+) -> StdResult<Option<u64>> {
+    LAST_LIQUIDATION.may_load(storage, (minter.as_slice(), vault_id))
+}
+
+pub fn store_liquidation_batch(
+    storage: &mut dyn Storage,
+    results: &Vec<LiquidationResult>,
+) -> StdResult<()> {
+    LIQUIDATION_BATCH.save(storage, results)
+}
+
+pub fn read_liquidation_batch(storage: &dyn Storage) -> StdResult<Vec<LiquidationResult>> {
+    LIQUIDATION_BATCH.load(storage)
+}
+
+// open the next vault of the minter with its position token, returns the vault id
+// and the token id
+pub fn open_vault(storage: &mut dyn Storage, minter: &CanonicalAddr) -> StdResult<(u64, String)> {
+    let vault_id = next_vault_id(storage, minter)?;
+    let token_id = mint_position_token(storage, minter, vault_id)?;
+    Ok((vault_id, token_id))
+}
+
+fn next_vault_id(storage: &mut dyn Storage, minter: &CanonicalAddr) -> StdResult<u64> {
+    let vault_id = read_vault_count(storage, minter)? + 1;
+    VAULT_COUNT.save(storage, minter.as_slice(), &vault_id)?;
+    Ok(vault_id)
+}
+
+pub fn read_vault_count(storage: &dyn Storage, minter: &CanonicalAddr) -> StdResult<u64> {
+    Ok(VAULT_COUNT
+        .may_load(storage, minter.as_slice())?
+        .unwrap_or_default())
+}
+
+// the default vault always exists, other vaults from their opening until transferred
+pub fn vault_exists(storage: &dyn Storage, minter: &CanonicalAddr, vault_id: u64) -> StdResult<bool> {
+    Ok(vault_id == DEFAULT_VAULT_ID || VAULT_TOKENS.has(storage, (minter.as_slice(), vault_id)))
+}
+
+// read the vaults opened by the minter, starting with the default vault
+pub fn read_vaults(
+    deps: Deps,
+    minter: &CanonicalAddr,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<VaultResponse>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let default_vault = start_after.is_none().then_some(DEFAULT_VAULT_ID);
+    let start = Bound::exclusive(start_after.unwrap_or(DEFAULT_VAULT_ID));
+    let vault_ids = VAULT_TOKENS
+        .prefix(minter.as_slice())
+        .keys(deps.storage, Some(start), None, Order::Ascending)
+        .take(limit)
+        .collect::<StdResult<Vec<u64>>>()?;
+
+    default_vault
+        .into_iter()
+        .chain(vault_ids)
+        .take(limit)
+        .map(|vault_id| {
+            let loan_info = read_minter_loan_info(deps.storage, minter, vault_id)?;
+            Ok(VaultResponse {
+                vault_id,
+                loans: loan_info.loans,
+                is_redemption_provider: loan_info.is_redemption_provider,
+                collaterals: read_collaterals(deps.storage, minter, vault_id).to_human(deps)?,
+            })
+        })
+        .collect()
+}
+
+// move the loan, collaterals and position token of the vault to a newly opened vault
+// of `to`, returns the id of the new vault and the moved collaterals. The vault of `from`
+// is closed, a default vault is left empty until its next use mints it a new token
+pub fn move_vault(
+    storage: &mut dyn Storage,
+    from: &CanonicalAddr,
+    from_vault_id: u64,
+    to: &CanonicalAddr,
+) -> StdResult<(u64, Tokens)> {
+    let to_vault_id = next_vault_id(storage, to)?;
+    let token_id = VAULT_TOKENS.load(storage, (from.as_slice(), from_vault_id))?;
+    VAULT_TOKENS.remove(storage, (from.as_slice(), from_vault_id));
+    VAULT_TOKENS.save(storage, (to.as_slice(), to_vault_id), &token_id)?;
+
+    if let Some(loan_info) = minter_loans().may_load(storage, (from.as_slice(), from_vault_id))? {
+        minter_loans().remove(storage, (from.as_slice(), from_vault_id))?;
+        store_minter_loan_info(
+            storage,
+            to,
+            to_vault_id,
+            &MinterLoanInfo {
+                minter: to.clone(),
+                vault_id: to_vault_id,
+                ..loan_info
+            },
+        )?;
+    }
+
+    // a transfer does not reset the liquidation cooldown
+    if let Some(time) = LAST_LIQUIDATION.may_load(storage, (from.as_slice(), from_vault_id))? {
+        LAST_LIQUIDATION.remove(storage, (from.as_slice(), from_vault_id));
+        store_last_liquidation(storage, to, to_vault_id, time)?;
+    }
+
+    let collaterals = read_collaterals(storage, from, from_vault_id);
+    store_collaterals(storage, from, from_vault_id, &vec![])?;
+    store_collaterals(storage, to, to_vault_id, &collaterals)?;
+
+    Ok((to_vault_id, collaterals))
+}
+
+// mint a position token for the vault and return its id
+pub fn mint_position_token(
+    storage: &mut dyn Storage,
+    owner: &CanonicalAddr,
+    vault_id: u64,
+) -> StdResult<String> {
+    let token_count = read_position_token_count(storage)? + 1;
+    POSITION_TOKEN_COUNT.save(storage, &token_count)?;
+
+    let token_id = token_count.to_string();
+    store_position_token(
+        storage,
+        &token_id,
+        &PositionToken {
+            owner: owner.clone(),
+            vault_id,
+            approvals: vec![],
+        },
+    )?;
+    VAULT_TOKENS.save(storage, (owner.as_slice(), vault_id), &token_id)?;
+    Ok(token_id)
+}
+
+// mint a position token for the default vault of the minter unless it has one,
+// returns the id of the minted token
+pub fn mint_default_vault_token(
+    storage: &mut dyn Storage,
+    minter: &CanonicalAddr,
+) -> StdResult<Option<String>> {
+    if VAULT_TOKENS.has(storage, (minter.as_slice(), DEFAULT_VAULT_ID)) {
+        return Ok(None);
+    }
+    mint_position_token(storage, minter, DEFAULT_VAULT_ID).map(Some)
+}
+
+pub fn read_position_token_count(storage: &dyn Storage) -> StdResult<u64> {
+    Ok(POSITION_TOKEN_COUNT.may_load(storage)?.unwrap_or_default())
+}
+
+pub fn store_position_token(
+    storage: &mut dyn Storage,
+    token_id: &str,
+    token: &PositionToken,
+) -> StdResult<()> {
+    position_tokens().save(storage, token_id, token)
+}
+
+pub fn read_position_token(storage: &dyn Storage, token_id: &str) -> StdResult<PositionToken> {
+    position_tokens().load(storage, token_id)
+}
+
+// read the ids of the position tokens, held by `owner` if set
+pub fn read_position_token_ids(
+    storage: &dyn Storage,
+    owner: Option<&CanonicalAddr>,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<Vec<String>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    match owner {
+        Some(owner) => position_tokens()
+            .idx
+            .owner
+            .prefix(owner.to_vec())
+            .keys(storage, start_after.map(Bound::exclusive), None, Order::Ascending)
+            .take(limit)
+            .collect(),
+        None => position_tokens()
+            .keys(
+                storage,
+                start_after.as_deref().map(Bound::exclusive),
+                None,
+                Order::Ascending,
+            )
+            .take(limit)
+            .collect(),
+    }
+}
+
 pub fn read_redemeption_list(
     deps: Deps,
-    start_after: Option<CanonicalAddr>,
+    start_after: Option<(CanonicalAddr, u64)>,
     limit: Option<u32>,
 ) -> StdResult<Vec<MinterLoanResponse>> {
-    let whitelist_bucket: ReadonlyBucket<MinterLoanInfo> =
-        ReadonlyBucket::new(deps.storage, PREFIX_LOANINFO);
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = calc_range_start(start_after);
-    let mut result = Vec::with_capacity(limit);
-    for elem in whitelist_bucket.range(start.as_deref(), None, Order::Ascending) {
-        let (_k, v) = elem?;
-        if v.is_redemption_provider {
-            result.push(MinterLoanResponse {
+    let start = start_after.map(|(addr, vault_id)| Bound::exclusive((addr.to_vec(), vault_id)));
+
+    minter_loans()
+        .idx
+        .redemption_provider
+        .prefix(1u8)
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (_k, v) = elem?;
+            Ok(MinterLoanResponse {
                 minter: deps.api.addr_humanize(&v.minter)?.to_string(),
+                vault_id: v.vault_id,
                 loans: v.loans,
                 is_redemption_provider: v.is_redemption_provider,
-            });
-        }
-        if result.len() == limit {
-            break;
-        }
-    }
-    Ok(result)
+            })
+        })
+        .collect()
+}
+
+// read the loans with the largest debt first
+pub fn read_loans_by_debt(storage: &dyn Storage, limit: Option<u32>) -> StdResult<Vec<MinterLoanInfo>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    minter_loans()
+        .idx
+        .debt
+        .range(storage, None, None, Order::Descending)
+        .take(limit)
+        .map(|elem| Ok(elem?.1))
+        .collect()
 }
+
 pub fn store_whitelist_elem(
     storage: &mut dyn Storage,
     collateral_contract: &CanonicalAddr,
     whitelist_elem: &WhitelistElem,
 ) -> StdResult<()> {
-    bucket(storage, PREFIX_WHITELISTELEM).save(collateral_contract.as_slice(), whitelist_elem)
+    WHITELIST.save(storage, collateral_contract.as_slice(), whitelist_elem)
 }
 
 pub fn read_whitelist_elem(
     storage: &dyn Storage,
     collateral_contract: &CanonicalAddr,
 ) -> StdResult<WhitelistElem> {
-    let whitelist_bucket: ReadonlyBucket<WhitelistElem> =
-        ReadonlyBucket::new(storage, PREFIX_WHITELISTELEM);
-    match whitelist_bucket.load(collateral_contract.as_slice()) {
-        Ok(v) => Ok(v),
-        _ => Err(StdError::generic_err(
+    match WHITELIST.may_load(storage, collateral_contract.as_slice())? {
+        Some(v) => Ok(v),
+        None => Err(StdError::generic_err(
             "Token is not registered as collateral",
         )),
     }
@@ -178,14 +621,11 @@ pub fn read_whitelist(
     start_after: Option<CanonicalAddr>,
     limit: Option<u32>,
 ) -> StdResult<Vec<WhitelistElemResponse>> {
-    let whitelist_bucket: ReadonlyBucket<WhitelistElem> =
-        ReadonlyBucket::new(deps.storage, PREFIX_WHITELISTELEM);
-
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = calc_range_start(start_after);
+    let start = start_after.as_ref().map(|addr| Bound::exclusive(addr.as_slice()));
 
-    whitelist_bucket
-        .range(start.as_deref(), None, Order::Ascending)
+    WHITELIST
+        .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|elem| {
             let (_k, v) = elem?;
@@ -201,69 +641,249 @@ pub fn read_whitelist(
         .collect()
 }
 
-// this will set the first key after the provided key, by appending a 1 byte
-fn calc_range_start(start_after: Option<CanonicalAddr>) -> Option<Vec<u8>> {
-    start_after.map(|addr| {
-        let mut v = addr.as_slice().to_vec();
-        v.push(1);
-        v
-    })
-}
-
-// record the list of collaterals deposited by the minter
+// record the list of collaterals deposited in the minter's vault
 #[allow(clippy::ptr_arg)]
 pub fn store_collaterals(
     storage: &mut dyn Storage,
     minter: &CanonicalAddr,
+    vault_id: u64,
     collaterals: &Tokens,
 ) -> StdResult<()> {
-    let mut collaterals_bucket: Bucket<Tokens> = Bucket::new(storage, PREFIX_COLLATERALS);
-    if collaterals.is_empty() {
-        collaterals_bucket.remove(minter.as_slice());
-    } else {
-        collaterals_bucket.save(minter.as_slice(), collaterals)?;
+    let map = minter_collaterals();
+    let prev_collaterals = map
+        .prefix((minter.as_slice(), vault_id))
+        .keys(storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<Vec<u8>>>>()?;
+    for collateral_contract in prev_collaterals {
+        map.remove(storage, (minter.as_slice(), vault_id, &collateral_contract))?;
+    }
+
+    for (collateral_contract, amount) in collaterals.iter() {
+        map.save(
+            storage,
+            (minter.as_slice(), vault_id, collateral_contract.as_slice()),
+            &CollateralElem {
+                minter: minter.clone(),
+                vault_id,
+                collateral_contract: collateral_contract.clone(),
+                amount: *amount,
+            },
+        )?;
     }
 
     Ok(())
 }
 
-// read the list of collaterals deposited by the minter
-pub fn read_collaterals(storage: &dyn Storage, minter: &CanonicalAddr) -> Tokens {
-    let collaterals_bucket: ReadonlyBucket<Tokens> =
-        ReadonlyBucket::new(storage, PREFIX_COLLATERALS);
-    match collaterals_bucket.load(minter.as_slice()) {
-        Ok(v) => v,
-        _ => vec![],
-    }
+// read the list of collaterals deposited in the minter's vault
+pub fn read_collaterals(storage: &dyn Storage, minter: &CanonicalAddr, vault_id: u64) -> Tokens {
+    minter_collaterals()
+        .prefix((minter.as_slice(), vault_id))
+        .range(storage, None, None, Order::Ascending)
+        .filter_map(|elem| elem.ok())
+        .map(|(_k, v)| (v.collateral_contract, v.amount))
+        .collect()
 }
 
-// read list of collaterals deposited by all minter defaulte pagesize 10
+// read the deposits of the collateral across all vaults
+pub fn read_collateral_deposits(
+    storage: &dyn Storage,
+    collateral_contract: &CanonicalAddr,
+    start_after: Option<(CanonicalAddr, u64)>,
+    limit: Option<u32>,
+) -> StdResult<Vec<CollateralElem>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|(minter, vault_id)| {
+        Bound::exclusive((minter.to_vec(), vault_id, collateral_contract.to_vec()))
+    });
+
+    minter_collaterals()
+        .idx
+        .collateral_contract
+        .prefix(collateral_contract.to_vec())
+        .range(storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|elem| Ok(elem?.1))
+        .collect()
+}
+
+// read list of collaterals deposited in all vaults defaulte pagesize 10
 pub fn read_all_collaterals(
     deps: Deps,
-    start_after: Option<CanonicalAddr>,
+    start_after: Option<(CanonicalAddr, u64)>,
     limit: Option<u32>,
 ) -> StdResult<Vec<CollateralsResponse>> {
-    let whitelist_bucket: ReadonlyBucket<Tokens> =
-        ReadonlyBucket::new(deps.storage, PREFIX_COLLATERALS);
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .as_ref()
+        .map(|(addr, vault_id)| PrefixBound::exclusive((addr.as_slice(), *vault_id)));
+
+    // entries of a vault are contiguous, group them until the limit is reached
+    let mut result: Vec<CollateralsResponse> = Vec::with_capacity(limit);
+    for elem in minter_collaterals().prefix_range(deps.storage, start, None, Order::Ascending) {
+        let (_k, v) = elem?;
+        let minter = deps.api.addr_humanize(&v.minter)?.to_string();
+        let collateral = (
+            deps.api.addr_humanize(&v.collateral_contract)?.to_string(),
+            v.amount,
+        );
+        match result.last_mut() {
+            Some(last) if last.minter == minter && last.vault_id == v.vault_id => {
+                last.collaterals.push(collateral)
+            }
+            _ => {
+                if result.len() == limit {
+                    break;
+                }
+                result.push(CollateralsResponse {
+                    minter,
+                    vault_id: v.vault_id,
+                    collaterals: vec![collateral],
+                });
+            }
+        }
+    }
+
+    Ok(result)
+}
 
+pub fn store_operator(
+    storage: &mut dyn Storage,
+    minter: &CanonicalAddr,
+    operator: &CanonicalAddr,
+    approval: &OperatorApproval,
+) -> StdResult<()> {
+    OPERATORS.save(storage, (minter.as_slice(), operator.as_slice()), approval)
+}
+
+pub fn remove_operator(storage: &mut dyn Storage, minter: &CanonicalAddr, operator: &CanonicalAddr) {
+    OPERATORS.remove(storage, (minter.as_slice(), operator.as_slice()))
+}
+
+pub fn read_operator(
+    storage: &dyn Storage,
+    minter: &CanonicalAddr,
+    operator: &CanonicalAddr,
+) -> StdResult<Option<OperatorApproval>> {
+    OPERATORS.may_load(storage, (minter.as_slice(), operator.as_slice()))
+}
+
+// read the operators approved by the minter, expired approvals included
+pub fn read_operators(
+    deps: Deps,
+    minter: &CanonicalAddr,
+    start_after: Option<CanonicalAddr>,
+    limit: Option<u32>,
+) -> StdResult<Vec<OperatorResponse>> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
-    let start = calc_range_start(start_after);
+    let start = start_after.as_ref().map(|addr| Bound::exclusive(addr.as_slice()));
+    let minter_addr = deps.api.addr_humanize(minter)?.to_string();
 
-    whitelist_bucket
-        .range(start.as_deref(), None, Order::Ascending)
+    OPERATORS
+        .prefix(minter.as_slice())
+        .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|elem| {
             let (k, v) = elem?;
-            let minter = deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string();
-            let collaterals: Vec<(String, Uint256)> = v
-                .iter()
-                .map(|c| Ok((deps.api.addr_humanize(&c.0)?.to_string(), c.1)))
-                .collect::<StdResult<Vec<(String, Uint256)>>>()?;
-
-            Ok(CollateralsResponse {
-                minter,
-                collaterals,
+            Ok(OperatorResponse {
+                minter: minter_addr.clone(),
+                operator: deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string(),
+                permissions: v.permissions,
+                expires_at: v.expires_at,
             })
         })
         .collect()
 }
+
+// record the liquidation and prune the records beyond the retention,
+// returns the id of the record or none when no record is kept
+pub fn store_liquidation_record(
+    storage: &mut dyn Storage,
+    record: &LiquidationRecord,
+    retention: u64,
+) -> StdResult<Option<u64>> {
+    let mut record_count = LIQUIDATION_RECORD_COUNT.may_load(storage)?.unwrap_or_default();
+    let id = if retention > 0 {
+        record_count += 1;
+        LIQUIDATION_RECORD_COUNT.save(storage, &record_count)?;
+        liquidation_records().save(storage, record_count, record)?;
+        Some(record_count)
+    } else {
+        None
+    };
+
+    // the oldest records come first, a lowered retention is caught up over several liquidations
+    let last_pruned = record_count.saturating_sub(retention);
+    let pruned = liquidation_records()
+        .keys(storage, None, Some(Bound::inclusive(last_pruned)), Order::Ascending)
+        .take(MAX_PRUNED_RECORDS)
+        .collect::<StdResult<Vec<u64>>>()?;
+    for id in pruned {
+        liquidation_records().remove(storage, id)?;
+    }
+
+    Ok(id)
+}
+
+pub fn update_liquidation_record(
+    storage: &mut dyn Storage,
+    id: u64,
+    record: &LiquidationRecord,
+) -> StdResult<()> {
+    liquidation_records().save(storage, id, record)
+}
+
+pub fn read_liquidation_record(storage: &dyn Storage, id: u64) -> StdResult<LiquidationRecord> {
+    liquidation_records().load(storage, id)
+}
+
+// read the liquidation records of `minter` if set, made between `start_time` and `end_time`
+pub fn read_liquidation_records(
+    storage: &dyn Storage,
+    minter: Option<&CanonicalAddr>,
+    start_time: Option<u64>,
+    end_time: Option<u64>,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<Vec<(u64, LiquidationRecord)>> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let mut start_id = start_after.map_or(0, |id| id.saturating_add(1));
+
+    // ids grow with time, the first record made at or after `start_time` bounds the ids
+    if let Some(start_time) = start_time {
+        let first = liquidation_records()
+            .idx
+            .time
+            .range(
+                storage,
+                Some(Bound::inclusive((start_time, 0u64))),
+                None,
+                Order::Ascending,
+            )
+            .next()
+            .transpose()?;
+        match first {
+            Some((id, _)) => start_id = start_id.max(id),
+            None => return Ok(vec![]),
+        }
+    }
+
+    let start = Some(Bound::inclusive(start_id));
+    let records: Box<dyn Iterator<Item = StdResult<(u64, LiquidationRecord)>>> = match minter {
+        Some(minter) => Box::new(
+            liquidation_records()
+                .idx
+                .minter
+                .prefix(minter.to_vec())
+                .range(storage, start, None, Order::Ascending),
+        ),
+        None => Box::new(liquidation_records().range(storage, start, None, Order::Ascending)),
+    };
+
+    records
+        .take_while(|elem| match (elem, end_time) {
+            (Ok((_, record)), Some(end_time)) => record.time <= end_time,
+            _ => true,
+        })
+        .take(limit)
+        .collect()
+}
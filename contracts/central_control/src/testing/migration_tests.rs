@@ -0,0 +1,262 @@
+use crate::contract::{execute, migrate};
+use crate::error::ContractError;
+use crate::migration::{
+    legacy_minter_collaterals, legacy_minter_loans, LEGACY_PREFIX_COLLATERALS,
+    LEGACY_PREFIX_LOANINFO, LEGACY_PREFIX_WHITELISTELEM,
+};
+use crate::state::{
+    read_collateral_deposits, read_collaterals, read_minter_loan_info, read_position_token_ids,
+    read_redemeption_list,
+    read_whitelist_elem, store_config, CollateralElem, Config, MinterLoanInfo, WhitelistElem,
+};
+
+use cdp::central_control::{ExecuteMsg, KeeperReward, MigrateMsg};
+use cdp::rbac::{has_role, Role};
+use cdp::timelock::read_timelock_config;
+use cdp::tokens::Tokens;
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{attr, Api, Order};
+use cosmwasm_storage::{bucket, bucket_read};
+use cw2::set_contract_version;
+
+#[test]
+fn proper_migrate_from_legacy() {
+    let mut deps = mock_dependencies();
+    let owner = deps.api.addr_canonicalize("owner").unwrap();
+    let config = Config {
+        owner_addr: owner.clone(),
+        oracle_contract: deps.api.addr_canonicalize("oracle_contract").unwrap(),
+        pool_contract: deps.api.addr_canonicalize("pool_contract").unwrap(),
+        liquidation_contract: deps.api.addr_canonicalize("liquidation_contract").unwrap(),
+        stable_denom: "kUSD".to_string(),
+        custody_contract: deps.api.addr_canonicalize("custody_contract").unwrap(),
+        epoch_period: 0u64,
+        redeem_fee: Decimal256::zero(),
+        max_liquidation_batch_size: 2u32,
+        keeper_reward: KeeperReward::default(),
+        liquidation_cooldown: 0u64,
+        liquidation_record_retention: 10u64,
+    };
+    store_config(deps.as_mut().storage, &config).unwrap();
+
+    // legacy buckets
+    let collateral_contract = deps.api.addr_canonicalize("collateral_contract").unwrap();
+    let minter = deps.api.addr_canonicalize("minter").unwrap();
+    let whitelist_elem = WhitelistElem {
+        name: "Collateral".to_string(),
+        symbol: "COLL".to_string(),
+        max_ltv: Decimal256::percent(50),
+        custody_contract: deps.api.addr_canonicalize("custody_contract").unwrap(),
+        collateral_contract: collateral_contract.clone(),
+        reward_book_contract: deps.api.addr_canonicalize("reward_book_contract").unwrap(),
+    };
+    let collaterals: Tokens = vec![(collateral_contract.clone(), Uint256::from(100u64))];
+    let loan_info = MinterLoanInfo {
+        minter: minter.clone(),
+        vault_id: 0u64,
+        loans: Uint256::from(40u64),
+        is_redemption_provider: true,
+    };
+    bucket(deps.as_mut().storage, LEGACY_PREFIX_WHITELISTELEM)
+        .save(collateral_contract.as_slice(), &whitelist_elem)
+        .unwrap();
+    bucket(deps.as_mut().storage, LEGACY_PREFIX_COLLATERALS)
+        .save(minter.as_slice(), &collaterals)
+        .unwrap();
+    bucket(deps.as_mut().storage, LEGACY_PREFIX_LOANINFO)
+        .save(minter.as_slice(), &loan_info)
+        .unwrap();
+
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::FromLegacy {
+            guardian_addr: "guardian".to_string(),
+            timelock_delay: 86400u64,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(
+        read_whitelist_elem(deps.as_ref().storage, &collateral_contract).unwrap(),
+        whitelist_elem
+    );
+    assert_eq!(read_collaterals(deps.as_ref().storage, &minter, 0u64), collaterals);
+    assert_eq!(
+        read_minter_loan_info(deps.as_ref().storage, &minter, 0u64).unwrap(),
+        loan_info
+    );
+
+    // the legacy entries are removed
+    assert!(bucket_read::<WhitelistElem>(deps.as_ref().storage, LEGACY_PREFIX_WHITELISTELEM)
+        .may_load(collateral_contract.as_slice())
+        .unwrap()
+        .is_none());
+    assert!(bucket_read::<Tokens>(deps.as_ref().storage, LEGACY_PREFIX_COLLATERALS)
+        .may_load(minter.as_slice())
+        .unwrap()
+        .is_none());
+    assert!(bucket_read::<MinterLoanInfo>(deps.as_ref().storage, LEGACY_PREFIX_LOANINFO)
+        .may_load(minter.as_slice())
+        .unwrap()
+        .is_none());
+
+    assert_eq!(
+        read_timelock_config(deps.as_ref().storage).unwrap().delay,
+        86400u64
+    );
+    assert!(has_role(deps.as_ref().storage, Role::Admin, &owner).unwrap());
+    let guardian = deps.api.addr_canonicalize("guardian").unwrap();
+    assert!(has_role(deps.as_ref().storage, Role::Guardian, &guardian).unwrap());
+
+    // a versioned deployment can not be migrated from legacy again
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::FromLegacy {
+            guardian_addr: "guardian".to_string(),
+            timelock_delay: 86400u64,
+        },
+    )
+    .unwrap_err();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+}
+
+#[test]
+fn upgrade_moves_positions_to_default_vault() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, "crates.io:cdp-central-control", "0.0.1").unwrap();
+
+    let collateral_contract = deps.api.addr_canonicalize("collateral_contract").unwrap();
+    let minter = deps.api.addr_canonicalize("minter").unwrap();
+    // positions stored per minter, before vaults were introduced
+    legacy_minter_loans()
+        .save(
+            deps.as_mut().storage,
+            minter.as_slice(),
+            &MinterLoanInfo {
+                minter: minter.clone(),
+                vault_id: 0u64,
+                loans: Uint256::from(40u64),
+                is_redemption_provider: true,
+            },
+        )
+        .unwrap();
+    legacy_minter_collaterals()
+        .save(
+            deps.as_mut().storage,
+            (minter.as_slice(), collateral_contract.as_slice()),
+            &CollateralElem {
+                minter: minter.clone(),
+                vault_id: 0u64,
+                collateral_contract: collateral_contract.clone(),
+                amount: Uint256::from(100u64),
+            },
+        )
+        .unwrap();
+
+    migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+
+    let loan_info = read_minter_loan_info(deps.as_ref().storage, &minter, 0u64).unwrap();
+    assert_eq!(loan_info.loans, Uint256::from(40u64));
+    assert_eq!(
+        read_collaterals(deps.as_ref().storage, &minter, 0u64),
+        vec![(collateral_contract.clone(), Uint256::from(100u64))]
+    );
+    // the default vault gets its position token
+    let res = read_position_token_ids(deps.as_ref().storage, Some(&minter), None, None).unwrap();
+    assert_eq!(res.len(), 1);
+    // the new indexes are populated
+    assert_eq!(read_redemeption_list(deps.as_ref(), None, None).unwrap().len(), 1);
+    let res = read_collateral_deposits(deps.as_ref().storage, &collateral_contract, None, None)
+        .unwrap();
+    assert_eq!(res.len(), 1);
+
+    // the legacy maps are emptied
+    assert!(legacy_minter_loans()
+        .range(deps.as_ref().storage, None, None, Order::Ascending)
+        .next()
+        .is_none());
+    assert!(legacy_minter_collaterals()
+        .range(deps.as_ref().storage, None, None, Order::Ascending)
+        .next()
+        .is_none());
+}
+
+#[test]
+fn positions_are_migrated_by_pages() {
+    let mut deps = mock_dependencies();
+    set_contract_version(deps.as_mut().storage, "crates.io:cdp-central-control", "0.0.1").unwrap();
+
+    let collateral_contract = deps.api.addr_canonicalize("collateral_contract").unwrap();
+    let minters: Vec<_> = (0..12)
+        .map(|i| deps.api.addr_canonicalize(&format!("minter{:02}", i)).unwrap())
+        .collect();
+    for minter in minters.iter() {
+        legacy_minter_loans()
+            .save(
+                deps.as_mut().storage,
+                minter.as_slice(),
+                &MinterLoanInfo {
+                    minter: minter.clone(),
+                    vault_id: 0u64,
+                    loans: Uint256::from(40u64),
+                    is_redemption_provider: false,
+                },
+            )
+            .unwrap();
+        legacy_minter_collaterals()
+            .save(
+                deps.as_mut().storage,
+                (minter.as_slice(), collateral_contract.as_slice()),
+                &CollateralElem {
+                    minter: minter.clone(),
+                    vault_id: 0u64,
+                    collateral_contract: collateral_contract.clone(),
+                    amount: Uint256::from(100u64),
+                },
+            )
+            .unwrap();
+    }
+
+    // the migration moves a first page only
+    let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+    assert!(res.attributes.contains(&attr("done", "false")));
+    let moved = minters
+        .iter()
+        .filter(|minter| {
+            let loan_info = read_minter_loan_info(deps.as_ref().storage, minter, 0u64).unwrap();
+            !loan_info.loans.is_zero()
+        })
+        .count();
+    assert_eq!(moved, 10);
+
+    // positions can not be used until all of them are moved
+    let msg = ExecuteMsg::LiquidateCollateral {
+        minter: "minter00".to_string(),
+        vault_id: None,
+    };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("keeper", &[]), msg);
+    assert_eq!(res.unwrap_err(), ContractError::MigrationPending {});
+
+    // anyone moves the remaining positions
+    let msg = ExecuteMsg::MigratePositions { limit: Some(10u32) };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("keeper", &[]), msg).unwrap();
+    assert!(res.attributes.contains(&attr("done", "false")));
+    let msg = ExecuteMsg::MigratePositions { limit: Some(10u32) };
+    let res = execute(deps.as_mut(), mock_env(), mock_info("keeper", &[]), msg).unwrap();
+    assert!(res.attributes.contains(&attr("done", "true")));
+
+    for minter in minters.iter() {
+        let loan_info = read_minter_loan_info(deps.as_ref().storage, minter, 0u64).unwrap();
+        assert_eq!(loan_info.loans, Uint256::from(40u64));
+        assert_eq!(
+            read_collaterals(deps.as_ref().storage, minter, 0u64),
+            vec![(collateral_contract.clone(), Uint256::from(100u64))]
+        );
+    }
+
+    let msg = ExecuteMsg::MigratePositions { limit: None };
+    execute(deps.as_mut(), mock_env(), mock_info("keeper", &[]), msg).unwrap_err();
+}
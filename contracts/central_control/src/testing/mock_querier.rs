@@ -0,0 +1,137 @@
+use cdp::liquidation_queue::{
+    LiquidationAmountResponse, QueryMsg as LiquidationQueryMsg, SimulateExecuteBidResponse,
+};
+use cdp::oracle_pyth::{PriceResponse, QueryMsg as OracleQueryMsg};
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{MockApi, MockQuerier, MockStorage, MOCK_CONTRACT_ADDR};
+use cosmwasm_std::{
+    from_binary, from_slice, to_binary, Binary, Coin, ContractResult, Empty, OwnedDeps, Querier,
+    QuerierResult, QueryRequest, SystemError, SystemResult, WasmQuery,
+};
+use std::collections::HashMap;
+
+/// mock_dependencies is a drop-in replacement for cosmwasm_std::testing::mock_dependencies
+/// this uses our CustomQuerier.
+pub fn mock_dependencies(
+    contract_balance: &[Coin],
+) -> OwnedDeps<MockStorage, MockApi, WasmMockQuerier> {
+    let custom_querier: WasmMockQuerier =
+        WasmMockQuerier::new(MockQuerier::new(&[(MOCK_CONTRACT_ADDR, contract_balance)]));
+
+    OwnedDeps {
+        storage: MockStorage::default(),
+        api: MockApi::default(),
+        querier: custom_querier,
+        custom_query_type: Default::default(),
+    }
+}
+
+pub struct WasmMockQuerier {
+    base: MockQuerier<Empty>,
+    oracle_price_querier: OraclePriceQuerier,
+}
+
+#[derive(Clone, Default)]
+pub struct OraclePriceQuerier {
+    // asset => emv price
+    oracle_price: HashMap<String, Decimal256>,
+}
+
+impl OraclePriceQuerier {
+    pub fn new(oracle_price: &[(&str, Decimal256)]) -> Self {
+        OraclePriceQuerier {
+            oracle_price: oracle_price
+                .iter()
+                .map(|(asset, price)| (asset.to_string(), *price))
+                .collect(),
+        }
+    }
+}
+
+impl Querier for WasmMockQuerier {
+    fn raw_query(&self, bin_request: &[u8]) -> QuerierResult {
+        let request: QueryRequest<Empty> = match from_slice(bin_request) {
+            Ok(v) => v,
+            Err(e) => {
+                return SystemResult::Err(SystemError::InvalidRequest {
+                    error: format!("Parsing query request: {}", e),
+                    request: bin_request.into(),
+                })
+            }
+        };
+        self.handle_query(&request)
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn handle_query(&self, request: &QueryRequest<Empty>) -> QuerierResult {
+        match &request {
+            QueryRequest::Wasm(WasmQuery::Smart {
+                contract_addr: _,
+                msg,
+            }) => {
+                // the liquidation queue liquidates every collateral of the position
+                if let Ok(LiquidationQueryMsg::LiquidationAmount { collaterals, .. }) =
+                    from_binary(msg)
+                {
+                    return SystemResult::Ok(ContractResult::from(to_binary(
+                        &LiquidationAmountResponse { collaterals },
+                    )));
+                }
+                // the bids buy at the price, charging a 1% bid fee and a 1% liquidator fee
+                if let Ok(LiquidationQueryMsg::SimulateExecuteBid { amount, price, .. }) =
+                    from_binary(msg)
+                {
+                    let required_stable = amount * price;
+                    let fee = required_stable * Decimal256::percent(1);
+                    return SystemResult::Ok(ContractResult::from(to_binary(
+                        &SimulateExecuteBidResponse {
+                            repay_amount: required_stable - fee - fee,
+                            bid_fee: fee,
+                            liquidator_fee: fee,
+                            slots: vec![],
+                            filled: true,
+                        },
+                    )));
+                }
+                self.handle_oracle_query(msg)
+            }
+            _ => self.base.handle_query(request),
+        }
+    }
+
+    fn handle_oracle_query(&self, msg: &Binary) -> QuerierResult {
+        match from_binary(msg).unwrap() {
+            OracleQueryMsg::QueryPrice { asset } => {
+                match self.oracle_price_querier.oracle_price.get(&asset) {
+                    Some(v) => SystemResult::Ok(ContractResult::from(to_binary(&PriceResponse {
+                        asset,
+                        emv_price: *v,
+                        emv_price_raw: 0,
+                        price: *v,
+                        price_raw: 0,
+                        last_updated_base: 0,
+                        last_updated_quote: 0,
+                    }))),
+                    None => SystemResult::Err(SystemError::InvalidRequest {
+                        error: "No oracle price exists".to_string(),
+                        request: msg.as_slice().into(),
+                    }),
+                }
+            }
+        }
+    }
+}
+
+impl WasmMockQuerier {
+    pub fn new(base: MockQuerier<Empty>) -> Self {
+        WasmMockQuerier {
+            base,
+            oracle_price_querier: OraclePriceQuerier::default(),
+        }
+    }
+
+    pub fn with_oracle_price(&mut self, oracle_price: &[(&str, Decimal256)]) {
+        self.oracle_price_querier = OraclePriceQuerier::new(oracle_price);
+    }
+}
@@ -1,2 +1,4 @@
-
-mod tests;
\ No newline at end of file
+mod migration_tests;
+mod mock_querier;
+mod state_tests;
+mod tests;
@@ -0,0 +1,167 @@
+use crate::state::{
+    open_vault, read_all_collaterals, read_collateral_deposits, read_collaterals,
+    read_loans_by_debt, read_minter_loan_info, read_redemeption_list, read_vaults,
+    store_collaterals, store_minter_loan_info, vault_exists, MinterLoanInfo,
+};
+
+use cdp::tokens::Tokens;
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::testing::mock_dependencies;
+use cosmwasm_std::Api;
+
+#[test]
+fn loan_indexes() {
+    let mut deps = mock_dependencies();
+    for (minter, vault_id, loans, is_redemption_provider) in [
+        ("minter0000", 0u64, 300u64, true),
+        ("minter0001", 0u64, 100u64, false),
+        ("minter0000", 1u64, 200u64, true),
+    ] {
+        let minter_raw = deps.api.addr_canonicalize(minter).unwrap();
+        store_minter_loan_info(
+            deps.as_mut().storage,
+            &minter_raw,
+            vault_id,
+            &MinterLoanInfo {
+                minter: minter_raw.clone(),
+                vault_id,
+                loans: Uint256::from(loans),
+                is_redemption_provider,
+            },
+        )
+        .unwrap();
+    }
+
+    let res = read_redemeption_list(deps.as_ref(), None, None).unwrap();
+    assert_eq!(
+        res.iter()
+            .map(|v| (v.minter.as_str(), v.vault_id))
+            .collect::<Vec<(&str, u64)>>(),
+        vec![("minter0000", 0u64), ("minter0000", 1u64)]
+    );
+    let minter_raw = deps.api.addr_canonicalize("minter0000").unwrap();
+    let res =
+        read_redemeption_list(deps.as_ref(), Some((minter_raw.clone(), 0u64)), None).unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].vault_id, 1u64);
+
+    // the indexes follow updates of the loan
+    store_minter_loan_info(
+        deps.as_mut().storage,
+        &minter_raw,
+        0u64,
+        &MinterLoanInfo {
+            minter: minter_raw.clone(),
+            vault_id: 0u64,
+            loans: Uint256::from(50u64),
+            is_redemption_provider: false,
+        },
+    )
+    .unwrap();
+    let res = read_redemeption_list(deps.as_ref(), None, None).unwrap();
+    assert_eq!(res.len(), 1);
+
+    let res = read_loans_by_debt(deps.as_ref().storage, Some(2u32)).unwrap();
+    assert_eq!(
+        res.iter().map(|v| v.loans).collect::<Vec<Uint256>>(),
+        vec![Uint256::from(200u64), Uint256::from(100u64)]
+    );
+}
+
+#[test]
+fn collateral_indexes() {
+    let mut deps = mock_dependencies();
+    let collateral0 = deps.api.addr_canonicalize("collateral0000").unwrap();
+    let collateral1 = deps.api.addr_canonicalize("collateral0001").unwrap();
+    let minter0 = deps.api.addr_canonicalize("minter0000").unwrap();
+    let minter1 = deps.api.addr_canonicalize("minter0001").unwrap();
+
+    let tokens0: Tokens = vec![
+        (collateral0.clone(), Uint256::from(100u64)),
+        (collateral1.clone(), Uint256::from(200u64)),
+    ];
+    let tokens1: Tokens = vec![(collateral1.clone(), Uint256::from(300u64))];
+    store_collaterals(deps.as_mut().storage, &minter0, 0u64, &tokens0).unwrap();
+    store_collaterals(deps.as_mut().storage, &minter1, 0u64, &tokens1).unwrap();
+    assert_eq!(read_collaterals(deps.as_ref().storage, &minter0, 0u64), tokens0);
+
+    let res = read_collateral_deposits(deps.as_ref().storage, &collateral1, None, None).unwrap();
+    assert_eq!(
+        res.iter().map(|v| v.amount).collect::<Vec<Uint256>>(),
+        vec![Uint256::from(200u64), Uint256::from(300u64)]
+    );
+    let res = read_collateral_deposits(
+        deps.as_ref().storage,
+        &collateral1,
+        Some((minter0.clone(), 0u64)),
+        None,
+    )
+    .unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].minter, minter1);
+
+    // a vault's entries are grouped by the paginated query
+    let res = read_all_collaterals(deps.as_ref(), None, Some(1u32)).unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].minter, "minter0000");
+    assert_eq!(res[0].collaterals.len(), 2);
+    let res = read_all_collaterals(deps.as_ref(), Some((minter0.clone(), 0u64)), None).unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].minter, "minter0001");
+
+    // withdrawn collaterals are removed from the index
+    let tokens0: Tokens = vec![(collateral0, Uint256::from(100u64))];
+    store_collaterals(deps.as_mut().storage, &minter0, 0u64, &tokens0).unwrap();
+    let res = read_collateral_deposits(deps.as_ref().storage, &collateral1, None, None).unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].minter, minter1);
+}
+
+#[test]
+fn isolated_vaults() {
+    let mut deps = mock_dependencies();
+    let collateral = deps.api.addr_canonicalize("collateral0000").unwrap();
+    let minter = deps.api.addr_canonicalize("minter0000").unwrap();
+
+    // only the default vault exists before opening one
+    assert!(vault_exists(deps.as_ref().storage, &minter, 0u64).unwrap());
+    assert!(!vault_exists(deps.as_ref().storage, &minter, 1u64).unwrap());
+    assert_eq!(open_vault(deps.as_mut().storage, &minter).unwrap().0, 1u64);
+    assert_eq!(open_vault(deps.as_mut().storage, &minter).unwrap().0, 2u64);
+    assert!(vault_exists(deps.as_ref().storage, &minter, 2u64).unwrap());
+
+    let tokens: Tokens = vec![(collateral.clone(), Uint256::from(100u64))];
+    store_collaterals(deps.as_mut().storage, &minter, 1u64, &tokens).unwrap();
+    store_minter_loan_info(
+        deps.as_mut().storage,
+        &minter,
+        1u64,
+        &MinterLoanInfo {
+            minter: minter.clone(),
+            vault_id: 1u64,
+            loans: Uint256::from(40u64),
+            is_redemption_provider: false,
+        },
+    )
+    .unwrap();
+
+    // the other vaults are untouched
+    assert!(read_collaterals(deps.as_ref().storage, &minter, 0u64).is_empty());
+    assert!(read_collaterals(deps.as_ref().storage, &minter, 2u64).is_empty());
+    let loan_info = read_minter_loan_info(deps.as_ref().storage, &minter, 0u64).unwrap();
+    assert_eq!(loan_info.loans, Uint256::zero());
+
+    let res = read_vaults(deps.as_ref(), &minter, None, None).unwrap();
+    assert_eq!(
+        res.iter().map(|v| v.vault_id).collect::<Vec<u64>>(),
+        vec![0u64, 1u64, 2u64]
+    );
+    assert_eq!(res[1].loans, Uint256::from(40u64));
+    assert_eq!(
+        res[1].collaterals,
+        vec![("collateral0000".to_string(), Uint256::from(100u64))]
+    );
+    let res = read_vaults(deps.as_ref(), &minter, Some(0u64), Some(1u32)).unwrap();
+    assert_eq!(res.len(), 1);
+    assert_eq!(res[0].vault_id, 1u64);
+}
@@ -1,156 +1,1369 @@
+use crate::contract::{
+    approve_operator, become_redemption_provider, close_position, deposit_collateral, execute, execute_open_vault,
+    mint_stable_coin, query, query_operator, query_vaults, reply, repay_stable_coin,
+    revoke_operator, withdraw_collateral, BATCH_LIQUIDATION,
+};
+use crate::error::ContractError;
+use crate::position::{
+    approve_position, query_nft_info, query_owner_of, query_tokens, transfer_position,
+};
+use crate::state::{
+    read_collaterals, read_config, read_minter_loan_info, store_collaterals, store_config,
+    store_minter_loan_info, store_whitelist_elem, Config, MinterLoanInfo, WhitelistElem,
+};
+use crate::testing::mock_querier::mock_dependencies;
 
-use crate::contract::mint_stable_coin;
-use crate::state::{WhitelistElem, Config, store_whitelist_elem, read_minter_loan_info, store_config, store_collaterals};
-
-use super::*;
-use cdp::tokens::{Tokens, TokensMath};
-use cosmwasm_bignumber::Uint256;
-use cosmwasm_std::testing::{mock_dependencies, mock_env};
-use cosmwasm_std::{coins, from_binary, Addr, CosmosMsg, Decimal, Uint128, WasmMsg, attr, Api, to_binary, CanonicalAddr};
+use cdp::central_control::{
+    ExecuteMsg, KeeperReward, LiquidateCollateralsResponse, LiquidatedCollateral,
+    LiquidationHistoryResponse, LiquidationRecordResponse, LiquidationResult, LiquidationStatus,
+    LiquidationTarget, OperatorPermission, QueryMsg,
+};
+use cdp::custody::ExecuteMsg as CustodyExecuteMsg;
+use cdp::events::{CdpEvent, MODULE_CENTRAL_CONTROL};
+use cdp::rbac::{grant_role, HasRoleResponse, Role};
+use cdp::reward_book::ExecuteMsg as RewardBookExecuteMsg;
 use cdp::stable_pool::ExecuteMsg as PoolExecuteMsg;
+use cdp::timelock::{store_timelock_config, TimelockConfig};
+use cdp::tokens::{Tokens, TokensMath};
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::{
+    attr, coins, from_binary, to_binary, Addr, Api, BankMsg, CosmosMsg, DepsMut, OwnedDeps, Reply,
+    SubMsg, SubMsgResult, Uint128, WasmMsg,
+};
 
-#[test]
-fn mint_stable_coin_positive() {
-    let mut deps = mock_dependencies();
-    let env = mock_env();
-    let minter = Addr::unchecked("minter");
-    let stable_amount = Uint128::new(100);
-    let collateral_amount = Some(Uint128::new(100));
-    let collateral_contract = Some(Addr::unchecked("collateral_contract").to_string());
-    let is_redemption_provider = Some(true);
-        // set up the whitelist element for the collateral
+fn setup_collateral(deps: DepsMut, max_ltv: Decimal256) {
+    let config = Config {
+        owner_addr: deps.api.addr_canonicalize("owner").unwrap(),
+        oracle_contract: deps.api.addr_canonicalize("oracle_contract").unwrap(),
+        pool_contract: deps.api.addr_canonicalize("pool_contract").unwrap(),
+        liquidation_contract: deps.api.addr_canonicalize("liquidation_contract").unwrap(),
+        stable_denom: "kUSD".to_string(),
+        custody_contract: deps.api.addr_canonicalize("custody_contract").unwrap(),
+        epoch_period: 0u64,
+        redeem_fee: Decimal256::zero(),
+        max_liquidation_batch_size: 2u32,
+        keeper_reward: KeeperReward::default(),
+        liquidation_cooldown: 0u64,
+        liquidation_record_retention: 10u64,
+    };
+    store_config(deps.storage, &config).unwrap();
+
+    // set up the whitelist element for the collateral
+    let collateral_contract = deps.api.addr_canonicalize("collateral_contract").unwrap();
     let collateral_info = WhitelistElem {
         name: "Collateral".to_string(),
         symbol: "COLL".to_string(),
-        max_ltv: Decimal::percent(50).into(),
-        custody_contract: CanonicalAddr::from("0x14d3cc818735723ab86eaf9502376e847a64ddad"),
-        collateral_contract: CanonicalAddr::from("collateral_contract"),
-        
-    };
-    store_whitelist_elem(
-        &mut deps.storage,
-        &Addr::unchecked("collateral_contract"),
-        &collateral_info,
-    )
-    .unwrap();
-        // set up the config
-    let config = Config {
-        oracle_contract: Addr::unchecked("oracle_contract").to_string(),
-        pool_contract: Addr::unchecked("pool_contract").to_string(),
-        ..Default::default()
+        max_ltv,
+        custody_contract: deps.api.addr_canonicalize("custody_contract").unwrap(),
+        collateral_contract: collateral_contract.clone(),
+        reward_book_contract: deps.api.addr_canonicalize("reward_book_contract").unwrap(),
     };
-    store_config(&mut deps.storage, &config).unwrap();
-        // set up the minter's collaterals
-    let mut cur_collaterals = Tokens::new();
-    cur_collaterals.add(vec![(
-        Addr::unchecked("collateral_contract").into(),
-        Uint256::from(100),
-    )]);
-    store_collaterals(&mut deps.storage, &minter, &cur_collaterals).unwrap();
-        // call the function
+    store_whitelist_elem(deps.storage, &collateral_contract, &collateral_info).unwrap();
+
+    // set up the minter's collaterals
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    let mut cur_collaterals: Tokens = vec![];
+    cur_collaterals.add(vec![(collateral_contract, Uint256::from(100u64))]);
+    store_collaterals(deps.storage, &minter_raw, 0u64, &cur_collaterals).unwrap();
+}
+
+#[test]
+fn mint_stable_coin_positive() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+
+    let stable_amount = Uint128::new(100);
     let res = mint_stable_coin(
         deps.as_mut(),
-        env.clone().into(),
-        minter.to_string(),
+        mock_env(),
+        mock_info("minter", &[]),
+        "minter".to_string(),
         stable_amount,
-        collateral_amount,
-        collateral_contract,
-        is_redemption_provider,
+        Some(Uint128::new(100)),
+        Some("collateral_contract".to_string()),
+        Some(true),
+        0u64,
     )
     .unwrap();
-        // check the response
-    assert_eq!(res.messages.len(), 1);
-    let msg = res.messages.get(0).expect("no message");
+
     assert_eq!(
-        msg,
-        &CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: Addr::unchecked("pool_contract").to_string(),
-            msg: to_binary(&PoolExecuteMsg::MintStableCoin {
-                minter: minter.to_string(),
-                stable_amount,
-            })
-            .unwrap(),
-            funds: vec![],
-        })
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "reward_book_contract".to_string(),
+                msg: to_binary(&RewardBookExecuteMsg::IncreaseBalance {
+                    address: "minter".to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "pool_contract".to_string(),
+                msg: to_binary(&PoolExecuteMsg::MintStableCoin {
+                    minter: "minter".to_string(),
+                    stable_amount,
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
     );
     assert_eq!(
         res.attributes,
         vec![
             attr("action", "mint_stable_coin"),
-            attr("minter", minter.to_string()),
+            attr("minter", "minter"),
+            attr("vault_id", "0"),
             attr("stable_amount", stable_amount.to_string()),
         ]
     );
-        // check the updated minter's loan info
-    let minter_raw = deps.api.addr_canonicalize(&minter.to_string()).unwrap();
-    let minter_loans_info = read_minter_loan_info(&deps.storage, &minter_raw).unwrap();
+    assert_eq!(
+        res.events,
+        vec![CdpEvent::Mint {
+            minter: "minter".to_string(),
+            vault_id: 0u64,
+            stable_amount,
+        }
+        .into_event(MODULE_CENTRAL_CONTROL)]
+    );
+
+    // check the updated minter's loan info
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    let minter_loans_info = read_minter_loan_info(&deps.storage, &minter_raw, 0u64).unwrap();
     assert_eq!(minter_loans_info.loans, Uint256::from(stable_amount));
-    assert_eq!(minter_loans_info.is_redemption_provider, true);
-        // check the updated minter's collaterals
-    let cur_collaterals = read_collaterals(&deps.storage, &minter_raw).unwrap();
+    assert!(minter_loans_info.is_redemption_provider);
+
+    // check the updated minter's collaterals
+    let cur_collaterals = read_collaterals(&deps.storage, &minter_raw, 0u64);
     assert_eq!(
         cur_collaterals,
-        Tokens::from(vec![(
-            Addr::unchecked("collateral_contract").into(),
-            Uint256::from(100)
-        )])
+        vec![(
+            deps.api.addr_canonicalize("collateral_contract").unwrap(),
+            Uint256::from(200u64)
+        )]
     );
 }
 
 #[test]
 fn mint_stable_coin_negative() {
     let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+
+    let res = mint_stable_coin(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        "minter".to_string(),
+        Uint128::new(1000),
+        Some(Uint128::new(100)),
+        Some("collateral_contract".to_string()),
+        Some(true),
+        0u64,
+    );
+
+    match res.unwrap_err() {
+        ContractError::MintkUSDTooLarge(max_loan_to_value) => {
+            // max_loan_to_value = 200 * 0.5
+            assert_eq!(max_loan_to_value, Uint256::from(100u64));
+        }
+        err => panic!("unexpected error: {:?}", err),
+    }
+}
+
+#[test]
+fn repay_stable_coin_on_behalf() {
+    let mut deps = mock_dependencies(&[]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    store_minter_loan_info(
+        deps.as_mut().storage,
+        &minter_raw,
+        0u64,
+        &MinterLoanInfo {
+            minter: minter_raw.clone(),
+            vault_id: 0u64,
+            loans: Uint256::from(100u64),
+            is_redemption_provider: false,
+        },
+    )
+    .unwrap();
+
+    // only the stable pool reports repayments
+    let res = repay_stable_coin(
+        deps.as_mut(),
+        mock_info("friend", &[]),
+        Addr::unchecked("minter"),
+        0u64,
+        Addr::unchecked("friend"),
+        Uint128::new(40),
+    );
+    assert!(matches!(res, Err(ContractError::Unauthorized(..))));
+
+    let res = repay_stable_coin(
+        deps.as_mut(),
+        mock_info("pool_contract", &[]),
+        Addr::unchecked("minter"),
+        0u64,
+        Addr::unchecked("friend"),
+        Uint128::new(40),
+    )
+    .unwrap();
+    assert_eq!(
+        res.events,
+        vec![CdpEvent::Repay {
+            minter: "minter".to_string(),
+            vault_id: 0u64,
+            payer: "friend".to_string(),
+            amount: Uint128::new(40),
+        }
+        .into_event(MODULE_CENTRAL_CONTROL)]
+    );
+
+    let minter_loans_info = read_minter_loan_info(&deps.storage, &minter_raw, 0u64).unwrap();
+    assert_eq!(minter_loans_info.loans, Uint256::from(60u64));
+}
+
+#[test]
+fn operator_manages_position() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
     let env = mock_env();
-    let minter = Addr::unchecked("minter");
-    let stable_amount = Uint128::new(1000);
-    let collateral_amount = Some(Uint128::new(100));
-    let collateral_contract = Some(Addr::unchecked("collateral_contract").to_string());
-    let is_redemption_provider = Some(true);
-        // set up the whitelist element for the collateral
-    let collateral_info = WhitelistElem {
-        name: "Collateral".to_string(),
-        symbol: "COLL".to_string(),
-        max_ltv: Decimal::percent(50),
-        ..Default::default()
+    let now = env.block.time.seconds();
+
+    // an operator needs an approval
+    let res = become_redemption_provider(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator", &[]),
+        true,
+        Some(Addr::unchecked("minter")),
+        0u64,
+    );
+    assert!(matches!(res, Err(ContractError::Unauthorized(..))));
+
+    // approvals that already expired are rejected
+    let res = approve_operator(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("minter", &[]),
+        Addr::unchecked("operator"),
+        None,
+        Some(now),
+    );
+    assert!(res.is_err());
+
+    approve_operator(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("minter", &[]),
+        Addr::unchecked("operator"),
+        Some(vec![OperatorPermission::Mint, OperatorPermission::Withdraw]),
+        Some(now + 100),
+    )
+    .unwrap();
+    let res = query_operator(
+        deps.as_ref(),
+        Addr::unchecked("minter"),
+        Addr::unchecked("operator"),
+    )
+    .unwrap();
+    assert_eq!(res.expires_at, Some(now + 100));
+
+    // the minted kUSD is sent to the minter
+    let res = mint_stable_coin(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator", &[]),
+        "minter".to_string(),
+        Uint128::new(20),
+        None,
+        None,
+        None,
+        0u64,
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "pool_contract".to_string(),
+            msg: to_binary(&PoolExecuteMsg::MintStableCoin {
+                minter: "minter".to_string(),
+                stable_amount: Uint128::new(20),
+            })
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+
+    // the withdrawn collateral is sent to the minter
+    let res = withdraw_collateral(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator", &[]),
+        Addr::unchecked("collateral_contract"),
+        Uint128::new(10),
+        Some(Addr::unchecked("minter")),
+        0u64,
+    )
+    .unwrap();
+    assert_eq!(
+        res.events,
+        vec![CdpEvent::Withdraw {
+            minter: "minter".to_string(),
+            vault_id: 0u64,
+            collateral_contract: "collateral_contract".to_string(),
+            amount: Uint128::new(10),
+        }
+        .into_event(MODULE_CENTRAL_CONTROL)]
+    );
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    assert_eq!(
+        read_collaterals(&deps.storage, &minter_raw, 0u64)[0].1,
+        Uint256::from(90u64)
+    );
+
+    // the toggle redemption permission was not granted
+    let res = become_redemption_provider(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("operator", &[]),
+        true,
+        Some(Addr::unchecked("minter")),
+        0u64,
+    );
+    assert!(matches!(res, Err(ContractError::Unauthorized(..))));
+
+    // the approval lapses at expires_at
+    let mut expired_env = env.clone();
+    expired_env.block.time = expired_env.block.time.plus_seconds(100);
+    let res = withdraw_collateral(
+        deps.as_mut(),
+        expired_env,
+        mock_info("operator", &[]),
+        Addr::unchecked("collateral_contract"),
+        Uint128::new(10),
+        Some(Addr::unchecked("minter")),
+        0u64,
+    );
+    assert!(matches!(res, Err(ContractError::Unauthorized(..))));
+
+    revoke_operator(
+        deps.as_mut(),
+        mock_info("minter", &[]),
+        Addr::unchecked("operator"),
+    )
+    .unwrap();
+    let res = mint_stable_coin(
+        deps.as_mut(),
+        env,
+        mock_info("operator", &[]),
+        "minter".to_string(),
+        Uint128::new(20),
+        None,
+        None,
+        None,
+        0u64,
+    );
+    assert!(matches!(res, Err(ContractError::Unauthorized(..))));
+}
+
+#[test]
+fn vaults_are_isolated() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+
+    // collateral can only be deposited to opened vaults
+    let res = deposit_collateral(
+        deps.as_mut(),
+        mock_info("custody_contract", &[]),
+        Addr::unchecked("minter"),
+        Addr::unchecked("collateral_contract"),
+        Uint128::new(100),
+        1u64,
+    );
+    assert_eq!(res.unwrap_err(), ContractError::VaultNotFound(1u64));
+
+    let res = execute_open_vault(deps.as_mut(), mock_info("minter", &[])).unwrap();
+    assert!(res.attributes.contains(&attr("vault_id", "1")));
+
+    // the collateral of the default vault does not back the new vault
+    let res = mint_stable_coin(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        "minter".to_string(),
+        Uint128::new(10),
+        None,
+        None,
+        None,
+        1u64,
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::MintkUSDTooLarge(Uint256::zero())
+    );
+
+    let res = deposit_collateral(
+        deps.as_mut(),
+        mock_info("custody_contract", &[]),
+        Addr::unchecked("minter"),
+        Addr::unchecked("collateral_contract"),
+        Uint128::new(100),
+        1u64,
+    )
+    .unwrap();
+    // the reward book balance of the minter covers all of its vaults
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "reward_book_contract".to_string(),
+            msg: to_binary(&RewardBookExecuteMsg::IncreaseBalance {
+                address: "minter".to_string(),
+                amount: Uint128::new(100),
+            })
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+    mint_stable_coin(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        "minter".to_string(),
+        Uint128::new(50),
+        None,
+        None,
+        None,
+        1u64,
+    )
+    .unwrap();
+
+    // the loan of the new vault does not lock the collateral of the default vault
+    withdraw_collateral(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        Addr::unchecked("collateral_contract"),
+        Uint128::new(100),
+        None,
+        0u64,
+    )
+    .unwrap();
+    let res = withdraw_collateral(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        Addr::unchecked("collateral_contract"),
+        Uint128::new(10),
+        None,
+        1u64,
+    );
+    assert!(matches!(
+        res,
+        Err(ContractError::WithdrawCollateralTooLarge(..))
+    ));
+
+    let res = query_vaults(deps.as_ref(), Addr::unchecked("minter"), None, None).unwrap();
+    assert_eq!(res.vaults.len(), 2);
+    assert_eq!(res.vaults[0].loans, Uint256::zero());
+    assert!(res.vaults[0].collaterals.is_empty());
+    assert_eq!(res.vaults[1].loans, Uint256::from(50u64));
+}
+
+#[test]
+fn position_token_transfer() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+
+    // opening a vault mints its position token
+    let res = execute_open_vault(deps.as_mut(), mock_info("minter", &[])).unwrap();
+    assert!(res.attributes.contains(&attr("token_id", "1")));
+    deposit_collateral(
+        deps.as_mut(),
+        mock_info("custody_contract", &[]),
+        Addr::unchecked("minter"),
+        Addr::unchecked("collateral_contract"),
+        Uint128::new(100),
+        1u64,
+    )
+    .unwrap();
+    mint_stable_coin(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        "minter".to_string(),
+        Uint128::new(40),
+        None,
+        None,
+        Some(true),
+        1u64,
+    )
+    .unwrap();
+
+    let res = transfer_position(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("spender", &[]),
+        Addr::unchecked("buyer"),
+        "1".to_string(),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Unauthorized("transfer_nft".to_string(), "spender".to_string())
+    );
+
+    // moving the vault to its own owner is rejected
+    let res = transfer_position(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        Addr::unchecked("minter"),
+        "1".to_string(),
+    );
+    assert_eq!(res.unwrap_err(), ContractError::SelfTransfer {});
+
+    // an approved spender moves the vault to a new vault of the recipient
+    approve_position(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        Addr::unchecked("spender"),
+        "1".to_string(),
+        None,
+    )
+    .unwrap();
+    let res = transfer_position(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("spender", &[]),
+        Addr::unchecked("buyer"),
+        "1".to_string(),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "reward_book_contract".to_string(),
+            msg: to_binary(&RewardBookExecuteMsg::TransferBalance {
+                from: "minter".to_string(),
+                to: "buyer".to_string(),
+                amount: Uint128::new(100),
+            })
+            .unwrap(),
+            funds: vec![],
+        }))]
+    );
+    assert_eq!(
+        res.events,
+        vec![CdpEvent::PositionTransfer {
+            token_id: "1".to_string(),
+            from: "minter".to_string(),
+            from_vault_id: 1u64,
+            to: "buyer".to_string(),
+            to_vault_id: 1u64,
+        }
+        .into_event(MODULE_CENTRAL_CONTROL)]
+    );
+
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    let buyer_raw = deps.api.addr_canonicalize("buyer").unwrap();
+    assert!(read_collaterals(deps.as_ref().storage, &minter_raw, 1u64).is_empty());
+    let loan_info = read_minter_loan_info(deps.as_ref().storage, &minter_raw, 1u64).unwrap();
+    assert_eq!(loan_info.loans, Uint256::zero());
+    let loan_info = read_minter_loan_info(deps.as_ref().storage, &buyer_raw, 1u64).unwrap();
+    assert_eq!(loan_info.loans, Uint256::from(40u64));
+    assert!(loan_info.is_redemption_provider);
+
+    // approvals are cleared and the token reports the vault of the new owner
+    let res = query_owner_of(deps.as_ref(), mock_env(), "1".to_string(), true).unwrap();
+    assert_eq!(res.owner, "buyer");
+    assert!(res.approvals.is_empty());
+    let res = query_nft_info(deps.as_ref(), "1".to_string()).unwrap();
+    assert_eq!(res.extension.loans, Uint256::from(40u64));
+    assert_eq!(
+        res.extension.collaterals,
+        vec![("collateral_contract".to_string(), Uint256::from(100u64))]
+    );
+    let res = query_tokens(deps.as_ref(), Addr::unchecked("minter"), None, None).unwrap();
+    assert!(res.tokens.is_empty());
+    let res = query_tokens(deps.as_ref(), Addr::unchecked("buyer"), None, None).unwrap();
+    assert_eq!(res.tokens, vec!["1".to_string()]);
+
+    // the vault of the previous owner is closed
+    let res = query_vaults(deps.as_ref(), Addr::unchecked("minter"), None, None).unwrap();
+    assert_eq!(res.vaults.len(), 1);
+    assert_eq!(res.vaults[0].vault_id, 0u64);
+    let res = deposit_collateral(
+        deps.as_mut(),
+        mock_info("custody_contract", &[]),
+        Addr::unchecked("minter"),
+        Addr::unchecked("collateral_contract"),
+        Uint128::new(100),
+        1u64,
+    );
+    assert_eq!(res.unwrap_err(), ContractError::VaultNotFound(1u64));
+
+    // the holder controls the position
+    withdraw_collateral(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("buyer", &[]),
+        Addr::unchecked("collateral_contract"),
+        Uint128::new(10),
+        None,
+        1u64,
+    )
+    .unwrap();
+}
+
+#[test]
+fn default_vault_position_token() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+
+    // the position set up in the default vault gets its token on the next deposit
+    let deposit = |deps: DepsMut| {
+        deposit_collateral(
+            deps,
+            mock_info("custody_contract", &[]),
+            Addr::unchecked("minter"),
+            Addr::unchecked("collateral_contract"),
+            Uint128::new(100),
+            0u64,
+        )
+        .unwrap()
     };
-    store_whitelist_elem(
-        &mut deps.storage,
-        &Addr::unchecked("collateral_contract"),
-        &collateral_info,
+    let res = deposit(deps.as_mut());
+    assert!(res.attributes.contains(&attr("token_id", "1")));
+    let res = deposit(deps.as_mut());
+    assert!(!res.attributes.iter().any(|attr| attr.key == "token_id"));
+    let res = query_owner_of(deps.as_ref(), mock_env(), "1".to_string(), false).unwrap();
+    assert_eq!(res.owner, "minter");
+
+    // the default vault moves to a new vault of the recipient and is left empty
+    let res = transfer_position(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        Addr::unchecked("buyer"),
+        "1".to_string(),
     )
     .unwrap();
-        // set up the config
-    let config = Config {
-        oracle_contract: Addr::unchecked("oracle_contract").to_string(),
-        pool_contract: Addr::unchecked("pool_contract").to_string(),
-        ..Default::default()
+    assert!(res.attributes.contains(&attr("token_id", "1")));
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    let buyer_raw = deps.api.addr_canonicalize("buyer").unwrap();
+    assert!(read_collaterals(deps.as_ref().storage, &minter_raw, 0u64).is_empty());
+    assert_eq!(
+        read_collaterals(deps.as_ref().storage, &buyer_raw, 1u64),
+        vec![(
+            deps.api.addr_canonicalize("collateral_contract").unwrap(),
+            Uint256::from(300u64)
+        )]
+    );
+
+    // the next deposit into the default vault mints a new token
+    let res = deposit(deps.as_mut());
+    assert!(res.attributes.contains(&attr("token_id", "2")));
+    let res = query_tokens(deps.as_ref(), Addr::unchecked("minter"), None, None).unwrap();
+    assert_eq!(res.tokens, vec!["2".to_string()]);
+}
+
+#[test]
+fn close_position_withdraws_all_collaterals() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+    mint_stable_coin(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &[]),
+        "minter".to_string(),
+        Uint128::new(30),
+        None,
+        None,
+        None,
+        0u64,
+    )
+    .unwrap();
+
+    let res = close_position(
+        deps.as_mut(),
+        mock_info("minter", &[]),
+        Addr::unchecked("minter"),
+        0u64,
+        Addr::unchecked("minter"),
+        Uint128::new(30),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Unauthorized("close_position".to_string(), "minter".to_string())
+    );
+
+    // the repay amount is computed by the stable pool in the same transaction
+    let res = close_position(
+        deps.as_mut(),
+        mock_info("pool_contract", &[]),
+        Addr::unchecked("minter"),
+        0u64,
+        Addr::unchecked("minter"),
+        Uint128::new(20),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::ClosePositionAmountMismatch(Uint256::from(30u64), Uint256::from(20u64))
+    );
+
+    let res = close_position(
+        deps.as_mut(),
+        mock_info("pool_contract", &[]),
+        Addr::unchecked("minter"),
+        0u64,
+        Addr::unchecked("minter"),
+        Uint128::new(30),
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "custody_contract".to_string(),
+                msg: to_binary(&CustodyExecuteMsg::WithdrawCollateral {
+                    minter: "minter".to_string(),
+                    collateral_contract: "collateral_contract".to_string(),
+                    collateral_amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "reward_book_contract".to_string(),
+                msg: to_binary(&RewardBookExecuteMsg::DecreaseBalance {
+                    address: "minter".to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+    assert_eq!(
+        res.events,
+        vec![
+            CdpEvent::Repay {
+                minter: "minter".to_string(),
+                vault_id: 0u64,
+                payer: "minter".to_string(),
+                amount: Uint128::new(30),
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+            CdpEvent::Withdraw {
+                minter: "minter".to_string(),
+                vault_id: 0u64,
+                collateral_contract: "collateral_contract".to_string(),
+                amount: Uint128::new(100),
+            }
+            .into_event(MODULE_CENTRAL_CONTROL),
+        ]
+    );
+
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    assert!(read_collaterals(deps.as_ref().storage, &minter_raw, 0u64).is_empty());
+    let loan_info = read_minter_loan_info(deps.as_ref().storage, &minter_raw, 0u64).unwrap();
+    assert_eq!(loan_info.loans, Uint256::zero());
+}
+
+#[test]
+fn liquidate_collaterals_skips_safe_positions() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+
+    // the position of the minter is unsafe, the one of the safe minter is not
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    store_minter_loan_info(
+        deps.as_mut().storage,
+        &minter_raw,
+        0u64,
+        &MinterLoanInfo {
+            minter: minter_raw.clone(),
+            vault_id: 0u64,
+            loans: Uint256::from(80u64),
+            is_redemption_provider: false,
+        },
+    )
+    .unwrap();
+    let target = |minter: &str| LiquidationTarget {
+        minter: minter.to_string(),
+        vault_id: None,
     };
-    store_config(&mut deps.storage, &config).unwrap();
-        // set up the minter's collaterals
-    let mut cur_collaterals = Tokens::new();
-    cur_collaterals.add(vec![(
-        Addr::unchecked("collateral_contract").into(),
-        Uint256::from(100),
-    )]);
-    store_collaterals(&mut deps.storage, &minter, &cur_collaterals).unwrap();
-        // call the function
-    let res = mint_stable_coin(
+
+    let res = execute(
         deps.as_mut(),
-        env.clone().into(),
-        minter.to_string(),
-        stable_amount,
-        collateral_amount,
-        collateral_contract,
-        is_redemption_provider,
+        mock_env(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::LiquidateCollaterals {
+            minters: vec![target("minter"), target("safe_minter"), target("minter")],
+        },
     );
-        // check the error message
-    match res.unwrap_err() {
-        ContractError::MintStableTooLarge(max_loan_to_value) => {
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::LiquidationBatchTooLarge(2u32)
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::LiquidateCollaterals {
+            minters: vec![target("minter"), target("safe_minter")],
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::reply_on_error(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: mock_env().contract.address.to_string(),
+                msg: to_binary(&ExecuteMsg::BatchLiquidateCollateral {
+                    minter: "minter".to_string(),
+                    vault_id: 0u64,
+                    liquidator: "keeper".to_string(),
+                })
+                .unwrap(),
+                funds: vec![],
+            }),
+            BATCH_LIQUIDATION,
+        )]
+    );
+    let data: LiquidateCollateralsResponse = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(
+        data.results,
+        vec![
+            LiquidationResult {
+                minter: "minter".to_string(),
+                vault_id: 0u64,
+                status: LiquidationStatus::Liquidated,
+            },
+            LiquidationResult {
+                minter: "safe_minter".to_string(),
+                vault_id: 0u64,
+                status: LiquidationStatus::Safe,
+            },
+        ]
+    );
+
+    // the liquidations of a batch are only accepted from the contract itself
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::BatchLiquidateCollateral {
+            minter: "minter".to_string(),
+            vault_id: 0u64,
+            liquidator: "keeper".to_string(),
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Unauthorized("batch_liquidate_collateral".to_string(), "keeper".to_string())
+    );
+}
+
+#[test]
+fn liquidate_collaterals_reports_failed_positions() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+
+    for minter in ["minter", "minter1"] {
+        let minter_raw = deps.api.addr_canonicalize(minter).unwrap();
+        store_minter_loan_info(
+            deps.as_mut().storage,
+            &minter_raw,
+            0u64,
+            &MinterLoanInfo {
+                minter: minter_raw.clone(),
+                vault_id: 0u64,
+                loans: Uint256::from(80u64),
+                is_redemption_provider: false,
+            },
+        )
+        .unwrap();
+    }
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::LiquidateCollaterals {
+            minters: vec![
+                LiquidationTarget {
+                    minter: "minter".to_string(),
+                    vault_id: None,
+                },
+                LiquidationTarget {
+                    minter: "minter1".to_string(),
+                    vault_id: None,
+                },
+            ],
+        },
+    )
+    .unwrap();
+    let ids: Vec<u64> = res.messages.iter().map(|msg| msg.id).collect();
+    assert_eq!(ids, vec![BATCH_LIQUIDATION, BATCH_LIQUIDATION + 1]);
+
+    // the liquidation of the second position fails, the first one went through
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: BATCH_LIQUIDATION + 1,
+            result: SubMsgResult::Err("Insufficient bids".to_string()),
+        },
+    )
+    .unwrap();
+    let data: LiquidateCollateralsResponse = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(
+        data.results,
+        vec![
+            LiquidationResult {
+                minter: "minter".to_string(),
+                vault_id: 0u64,
+                status: LiquidationStatus::Liquidated,
+            },
+            LiquidationResult {
+                minter: "minter1".to_string(),
+                vault_id: 0u64,
+                status: LiquidationStatus::Failed {
+                    error: "Insufficient bids".to_string(),
+                },
+            },
+        ]
+    );
+
+    // replies only come for the positions of the batch
+    let res = reply(
+        deps.as_mut(),
+        mock_env(),
+        Reply {
+            id: BATCH_LIQUIDATION + 2,
+            result: SubMsgResult::Err("Insufficient bids".to_string()),
+        },
+    );
+    assert_eq!(res.unwrap_err(), ContractError::InvalidReplyId {});
+}
+
+#[test]
+fn liquidation_pays_keeper_reward() {
+    let mut deps = mock_dependencies(&coins(1000u128, "kUSD"));
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+    let mut config = read_config(deps.as_ref().storage).unwrap();
+    config.keeper_reward = KeeperReward {
+        flat_amount: Uint128::new(10),
+        rate: Decimal256::percent(10),
+        cap: Uint128::new(15),
+    };
+    store_config(deps.as_mut().storage, &config).unwrap();
+
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    store_minter_loan_info(
+        deps.as_mut().storage,
+        &minter_raw,
+        0u64,
+        &MinterLoanInfo {
+            minter: minter_raw.clone(),
+            vault_id: 0u64,
+            loans: Uint256::from(80u64),
+            is_redemption_provider: false,
+        },
+    )
+    .unwrap();
+
+    // 10 flat and 10% of the liquidated value of 100, capped at 15
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::LiquidateCollateral {
+            minter: "minter".to_string(),
+            vault_id: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::new(BankMsg::Send {
+            to_address: "keeper".to_string(),
+            amount: coins(15u128, "kUSD"),
+        })
+    );
+    assert!(res.attributes.contains(&attr("keeper_reward", "15")));
+    assert!(!res.attributes.iter().any(|a| a.key == "keeper_reward_unfunded"));
+}
+
+#[test]
+fn liquidation_reports_unfunded_keeper_reward() {
+    let mut deps = mock_dependencies(&coins(4u128, "kUSD"));
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+    let mut config = read_config(deps.as_ref().storage).unwrap();
+    config.keeper_reward = KeeperReward {
+        flat_amount: Uint128::new(10),
+        rate: Decimal256::zero(),
+        cap: Uint128::new(10),
+    };
+    store_config(deps.as_mut().storage, &config).unwrap();
+
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    store_minter_loan_info(
+        deps.as_mut().storage,
+        &minter_raw,
+        0u64,
+        &MinterLoanInfo {
+            minter: minter_raw.clone(),
+            vault_id: 0u64,
+            loans: Uint256::from(80u64),
+            is_redemption_provider: false,
+        },
+    )
+    .unwrap();
+
+    // only the balance of 4 is paid, the liquidation reports the 6 left unpaid
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::LiquidateCollateral {
+            minter: "minter".to_string(),
+            vault_id: None,
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages.last().unwrap(),
+        &SubMsg::new(BankMsg::Send {
+            to_address: "keeper".to_string(),
+            amount: coins(4u128, "kUSD"),
+        })
+    );
+    assert!(res.attributes.contains(&attr("keeper_reward", "4")));
+    assert!(res.attributes.contains(&attr("keeper_reward_unfunded", "6")));
+}
+
+#[test]
+fn liquidation_respects_cooldown() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+    let mut config = read_config(deps.as_ref().storage).unwrap();
+    config.liquidation_cooldown = 600u64;
+    store_config(deps.as_mut().storage, &config).unwrap();
+
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    store_minter_loan_info(
+        deps.as_mut().storage,
+        &minter_raw,
+        0u64,
+        &MinterLoanInfo {
+            minter: minter_raw.clone(),
+            vault_id: 0u64,
+            loans: Uint256::from(80u64),
+            is_redemption_provider: false,
+        },
+    )
+    .unwrap();
+    let msg = ExecuteMsg::LiquidateCollateral {
+        minter: "minter".to_string(),
+        vault_id: None,
+    };
+
+    let env = mock_env();
+    execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg.clone()).unwrap();
+
+    // the position is still unsafe but can not be liquidated again before the cooldown ends
+    let mut env = env;
+    env.block.time = env.block.time.plus_seconds(599u64);
+    let res = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), msg.clone());
+    let cooldown_end = mock_env().block.time.seconds() + 600u64;
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::LiquidationCooldown(cooldown_end)
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("keeper", &[]),
+        ExecuteMsg::LiquidateCollaterals {
+            minters: vec![LiquidationTarget {
+                minter: "minter".to_string(),
+                vault_id: None,
+            }],
+        },
+    )
+    .unwrap();
+    assert!(res.messages.is_empty());
+    let data: LiquidateCollateralsResponse = from_binary(&res.data.unwrap()).unwrap();
+    assert_eq!(data.results[0].status, LiquidationStatus::Cooldown);
+
+    env.block.time = env.block.time.plus_seconds(1u64);
+    execute(deps.as_mut(), env, mock_info("keeper", &[]), msg).unwrap();
+}
+
+#[test]
+fn liquidations_are_recorded() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_oracle_price(&[("collateral_contract", Decimal256::one())]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+    let mut config = read_config(deps.as_ref().storage).unwrap();
+    config.liquidation_record_retention = 2u64;
+    store_config(deps.as_mut().storage, &config).unwrap();
+
+    let minter_raw = deps.api.addr_canonicalize("minter").unwrap();
+    let collateral_raw = deps.api.addr_canonicalize("collateral_contract").unwrap();
+    let store_loans = |deps: DepsMut, loans: u64| {
+        store_minter_loan_info(
+            deps.storage,
+            &minter_raw,
+            0u64,
+            &MinterLoanInfo {
+                minter: minter_raw.clone(),
+                vault_id: 0u64,
+                loans: Uint256::from(loans),
+                is_redemption_provider: false,
+            },
+        )
+        .unwrap();
+    };
+
+    let mut env = mock_env();
+    let start_time = env.block.time.seconds();
+    for id in 1u64..=3u64 {
+        // the position is reopened before each liquidation
+        store_collaterals(
+            deps.as_mut().storage,
+            &minter_raw,
+            0u64,
+            &vec![(collateral_raw.clone(), Uint256::from(100u64))],
+        )
+        .unwrap();
+        store_loans(deps.as_mut(), 80u64);
+
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            ExecuteMsg::LiquidateCollateral {
+                minter: "minter".to_string(),
+                vault_id: None,
+            },
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .contains(&attr("liquidation_record_id", id.to_string())));
+        assert_eq!(
+            res.messages.last().unwrap(),
+            &SubMsg::new(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_binary(&ExecuteMsg::SettleLiquidationRecord { id }).unwrap(),
+                funds: vec![],
+            })
+        );
+
+        // the pool repays 50 of the 80 loans
+        store_loans(deps.as_mut(), 30u64);
+        let settle_msg = ExecuteMsg::SettleLiquidationRecord { id };
+        let res = execute(deps.as_mut(), env.clone(), mock_info("keeper", &[]), settle_msg.clone());
+        assert_eq!(
+            res.unwrap_err(),
+            ContractError::Unauthorized(
+                "settle_liquidation_record".to_string(),
+                "keeper".to_string()
+            )
+        );
+        let contract_info = mock_info(env.contract.address.as_str(), &[]);
+        execute(deps.as_mut(), env.clone(), contract_info, settle_msg).unwrap();
+
+        env.block.height += 1;
+        env.block.time = env.block.time.plus_seconds(100u64);
+    }
+
+    let record: LiquidationRecordResponse = from_binary(
+        &query(deps.as_ref(), mock_env(), QueryMsg::LiquidationRecord { id: 3u64 }).unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        record,
+        LiquidationRecordResponse {
+            id: 3u64,
+            minter: "minter".to_string(),
+            vault_id: 0u64,
+            liquidator: "keeper".to_string(),
+            collaterals: vec![LiquidatedCollateral {
+                collateral_contract: "collateral_contract".to_string(),
+                amount: Uint256::from(100u64),
+                price: Decimal256::one(),
+            }],
+            loans: Uint256::from(80u64),
+            repaid: Uint256::from(50u64),
+            estimated_bid_fee: Uint256::from(1u64),
+            estimated_liquidator_fee: Uint256::from(1u64),
+            keeper_reward: Uint128::zero(),
+            height: mock_env().block.height + 2,
+            time: start_time + 200,
+        }
+    );
+
+    // the oldest record is pruned beyond the retention
+    query(deps.as_ref(), mock_env(), QueryMsg::LiquidationRecord { id: 1u64 }).unwrap_err();
+
+    let history_ids = |minter: Option<&str>,
+                       start_time: Option<u64>,
+                       end_time: Option<u64>,
+                       start_after: Option<u64>,
+                       limit: Option<u32>| {
+        let res: LiquidationHistoryResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::LiquidationHistory {
+                    minter: minter.map(|minter| minter.to_string()),
+                    start_time,
+                    end_time,
+                    start_after,
+                    limit,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        res.records.iter().map(|record| record.id).collect::<Vec<u64>>()
+    };
+    assert_eq!(history_ids(None, None, None, None, None), vec![2u64, 3u64]);
+    assert_eq!(history_ids(Some("minter"), None, None, None, Some(1u32)), vec![2u64]);
+    assert_eq!(history_ids(Some("minter"), None, None, Some(2u64), None), vec![3u64]);
+    assert_eq!(history_ids(Some("other"), None, None, None, None), Vec::<u64>::new());
+    assert_eq!(
+        history_ids(None, Some(start_time + 150), None, None, None),
+        vec![3u64]
+    );
+    assert_eq!(
+        history_ids(None, Some(start_time), Some(start_time + 150), None, None),
+        vec![2u64]
+    );
+    assert_eq!(
+        history_ids(None, Some(start_time + 300), None, None, None),
+        Vec::<u64>::new()
+    );
+}
+
+#[test]
+fn config_changes_follow_roles() {
+    let mut deps = mock_dependencies(&[]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+    store_timelock_config(deps.as_mut().storage, &TimelockConfig { delay: 0u64 }).unwrap();
+    for (role, addr) in [
+        (Role::Admin, "owner"),
+        (Role::FeeManager, "fee_manager"),
+        (Role::RiskManager, "risk_manager"),
+    ] {
+        let addr_raw = deps.api.addr_canonicalize(addr).unwrap();
+        grant_role(deps.as_mut().storage, role, &addr_raw).unwrap();
+    }
+
+    let update_config = |redeem_fee, max_liquidation_batch_size| ExecuteMsg::UpdateConfig {
+        oracle_contract: None,
+        pool_contract: None,
+        liquidation_contract: None,
+        custody_contract: None,
+        stable_denom: None,
+        epoch_period: None,
+        redeem_fee,
+        max_liquidation_batch_size,
+        keeper_reward: None,
+        liquidation_cooldown: None,
+        liquidation_record_retention: None,
+    };
+
+    // fee managers change the redeem fee only, risk managers the liquidation parameters
+    for (sender, msg, allowed) in [
+        ("fee_manager", update_config(Some(Decimal256::percent(1)), None), true),
+        ("fee_manager", update_config(None, Some(5u32)), false),
+        ("risk_manager", update_config(None, Some(5u32)), true),
+        ("risk_manager", update_config(Some(Decimal256::percent(1)), None), false),
+        ("owner", update_config(Some(Decimal256::percent(1)), Some(5u32)), true),
+    ] {
+        let res = execute(deps.as_mut(), mock_env(), mock_info(sender, &[]), msg);
+        if allowed {
+            res.unwrap();
+        } else {
             assert_eq!(
-                max_loan_to_value,
-                Uint256::from(50) // max_loan_to_value = 100 * 0.5
+                res.unwrap_err(),
+                ContractError::Unauthorized(
+                    "propose_timelocked_change".to_string(),
+                    sender.to_string()
+                )
             );
         }
-        err => panic!("unexpected error: {:?}", err),
     }
+
+    // parameters are validated when the change is proposed
+    let msg = update_config(Some(Decimal256::one()), None);
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg);
+    assert_eq!(res.unwrap_err(), ContractError::RedeemFeeExceedsLimit {});
+    let msg = update_config(None, Some(0u32));
+    let res = execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg);
+    assert_eq!(res.unwrap_err(), ContractError::InvalidLiquidationBatchSize {});
+}
+
+#[test]
+fn admin_grants_are_timelocked() {
+    let mut deps = mock_dependencies(&[]);
+    setup_collateral(deps.as_mut(), Decimal256::percent(50));
+    store_timelock_config(deps.as_mut().storage, &TimelockConfig { delay: 0u64 }).unwrap();
+    let owner_raw = deps.api.addr_canonicalize("owner").unwrap();
+    grant_role(deps.as_mut().storage, Role::Admin, &owner_raw).unwrap();
+
+    let has_admin = |deps: &OwnedDeps<_, _, _>| -> bool {
+        let msg = QueryMsg::HasRole {
+            role: Role::Admin,
+            address: "admin0001".to_string(),
+        };
+        let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+        from_binary::<HasRoleResponse>(&res).unwrap().has_role
+    };
+
+    let msg = ExecuteMsg::GrantRole {
+        role: Role::Admin,
+        address: "admin0001".to_string(),
+    };
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    assert!(!has_admin(&deps));
+
+    let msg = ExecuteMsg::ExecuteChange { id: 1 };
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    assert!(has_admin(&deps));
 }
@@ -13,9 +13,10 @@
 // limitations under the License.
 use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::entry_point;
+use cw2::set_contract_version;
 use cosmwasm_std::{
-    attr, from_binary, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    Response, StdResult, Uint128, WasmMsg,
+    attr, from_binary, to_binary, Addr, Api, Binary, CanonicalAddr, CosmosMsg, Deps, DepsMut,
+    Env, MessageInfo, Response, StdError, StdResult, Storage, Uint128, WasmMsg,
 };
 use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
 
@@ -29,7 +30,23 @@ use cdp::custody::{
     ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, StateResponse,
 };
 use cdp::liquidation_queue::Cw20HookMsg as LiquidationCw20HookMsg;
+use cdp::rbac::assert_not_paused;
 use cdp::rewards::ExecuteMsg as RewardsExecuteMsg;
+use cdp::events::{config_change_event, MODULE_CUSTODY};
+use cdp::migration::{assert_legacy_deployment, assert_upgrade};
+use cdp::rbac::{
+    execute_grant_role, execute_pause, execute_revoke_role, execute_unpause, grant_role,
+    query_has_role, query_paused, query_role_members, revoke_role, Role,
+};
+use cdp::timelock::{
+    execute_cancel_change, execute_matured_change, execute_propose_change, query_pending_change,
+    query_pending_changes, query_timelock_config, store_timelock_config, update_timelock_config,
+    TimelockConfig,
+};
+
+// version info for migration
+const CONTRACT_NAME: &str = "crates.io:cdp-custody";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -61,38 +78,54 @@ pub fn instantiate(
         }
     })?;
 
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    store_timelock_config(
+        deps.storage,
+        &TimelockConfig {
+            delay: msg.timelock_delay,
+        },
+    )?;
+
+    grant_role(deps.storage, Role::Admin, &config.owner_addr)?;
+    grant_role(
+        deps.storage,
+        Role::Guardian,
+        &deps.api.addr_canonicalize(&msg.guardian_addr)?,
+    )?;
+
     Ok(Response::default())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, info, msg),
-        ExecuteMsg::UpdateConfig {
-            control_contract,
-            pool_contract,
-            collateral_contract,
-            liquidation_contract,
-            reward_book_contract,
-        } => update_config(
-            deps,
-            info,
-            control_contract,
-            pool_contract,
-            collateral_contract,
-            liquidation_contract,
-            reward_book_contract,
-        ),
-        ExecuteMsg::SetOwner { new_owner_addr } => {
+        ExecuteMsg::UpdateConfig { .. }
+        | ExecuteMsg::UpdateTimelockConfig { .. }
+        | ExecuteMsg::SetOwner { .. }
+        | ExecuteMsg::GrantRole {
+            role: Role::Admin | Role::Guardian,
+            ..
+        } => propose_timelocked_change(deps, env, info, msg),
+        ExecuteMsg::ExecuteChange { id } => execute_timelocked_change(deps, env, info, id),
+        ExecuteMsg::CancelChange { id } => execute_cancel_change(deps, info, MODULE_CUSTODY, id),
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => {
             let api = deps.api;
-            set_new_owner(deps, info, api.addr_validate(&new_owner_addr)?)
+            execute_grant_role(deps, info, MODULE_CUSTODY, role, api.addr_validate(&address)?)
         }
-        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::RevokeRole { role, address } => {
+            let api = deps.api;
+            execute_revoke_role(deps, info, MODULE_CUSTODY, role, api.addr_validate(&address)?)
+        }
+        ExecuteMsg::Pause {} => execute_pause(deps, info, MODULE_CUSTODY),
+        ExecuteMsg::Unpause {} => execute_unpause(deps, info, MODULE_CUSTODY),
 
         ExecuteMsg::RedeemStableCoin {
             redeemer,
@@ -134,6 +167,7 @@ pub fn receive_cw20(
         Cw20HookMsg::MintStableCoin {
             stable_amount,
             is_redemption_provider,
+            vault_id,
         } => mint_stable_coin(
             deps,
             info,
@@ -141,34 +175,24 @@ pub fn receive_cw20(
             cw20_msg.amount,
             stable_amount,
             is_redemption_provider,
+            vault_id,
         ),
 
-        Cw20HookMsg::DepositCollateral {} => {
-            deposit_collateral(deps, info, cw20_msg.sender, cw20_msg.amount)
+        Cw20HookMsg::DepositCollateral { vault_id } => {
+            deposit_collateral(deps, info, cw20_msg.sender, cw20_msg.amount, vault_id)
         }
     }
 }
 
-pub fn set_new_owner(
-    deps: DepsMut,
-    info: MessageInfo,
-    new_owner_addr: Addr,
-) -> Result<Response, ContractError> {
-    let config = read_config(deps.as_ref().storage)?;
-    let mut new_owner = read_new_owner(deps.as_ref().storage)?;
-    let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
-    if sender_raw != config.owner_addr {
-        return Err(ContractError::Unauthorized(
-            "set_new_owner".to_string(),
-            info.sender.to_string(),
-        ));
-    }
-    new_owner.new_owner_addr = deps.api.addr_canonicalize(&new_owner_addr.to_string())?;
-    store_new_owner(deps.storage, &new_owner)?;
-
-    Ok(Response::default())
+/// Applied by a matured `SetOwner` change, the new owner still has to accept
+pub fn set_new_owner(storage: &mut dyn Storage, new_owner_addr: CanonicalAddr) -> StdResult<()> {
+    let mut new_owner = read_new_owner(storage)?;
+    new_owner.new_owner_addr = new_owner_addr;
+    store_new_owner(storage, &new_owner)
 }
 
+/// Only the admin role of the previous owner is revoked, admins granted apart from the
+/// ownership keep their role until revoked
 pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
     let new_owner = read_new_owner(deps.as_ref().storage)?;
     let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
@@ -180,30 +204,125 @@ pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, Co
         ));
     }
 
+    revoke_role(deps.storage, Role::Admin, &config.owner_addr);
+    grant_role(deps.storage, Role::Admin, &new_owner.new_owner_addr)?;
     config.owner_addr = new_owner.new_owner_addr;
     store_config(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "accept_ownership"),
+            attr("owner", info.sender.to_string()),
+        ])
+        .add_event(config_change_event(MODULE_CUSTODY, "accept_ownership", info.sender)))
 }
 
-pub fn update_config(
+fn change_roles(_msg: &ExecuteMsg) -> &'static [Role] {
+    &[Role::Admin]
+}
+
+/// Admin queues a config change, it can be executed once the timelock delay has passed
+pub fn propose_timelocked_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    validate_change(deps.api, &msg)?;
+    let roles = change_roles(&msg);
+    execute_propose_change(deps, &env, info, MODULE_CUSTODY, roles, to_binary(&msg)?)
+}
+
+pub fn execute_timelocked_change(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    execute_matured_change(deps, &env, info, MODULE_CUSTODY, id, change_roles, apply_change)
+}
+
+// addresses are validated when a change is proposed rather than once its delay has passed
+fn validate_change(api: &dyn Api, msg: &ExecuteMsg) -> StdResult<()> {
+    if let ExecuteMsg::UpdateConfig {
+        control_contract,
+        pool_contract,
+        collateral_contract,
+        liquidation_contract,
+        reward_book_contract,
+        ..
+    } = msg
+    {
+        let addrs = [
+            control_contract,
+            pool_contract,
+            collateral_contract,
+            liquidation_contract,
+            reward_book_contract,
+        ];
+        for addr in addrs.iter().copied().flatten() {
+            api.addr_validate(addr)?;
+        }
+    }
+    if let ExecuteMsg::SetOwner {
+        new_owner_addr: address,
+    }
+    | ExecuteMsg::GrantRole { address, .. } = msg
+    {
+        api.addr_validate(address)?;
+    }
+    Ok(())
+}
+
+fn apply_change(deps: DepsMut, msg: ExecuteMsg) -> Result<&'static str, ContractError> {
+    let action = match msg {
+        ExecuteMsg::UpdateConfig {
+            control_contract,
+            pool_contract,
+            collateral_contract,
+            liquidation_contract,
+            reward_book_contract,
+        } => {
+            update_config(
+                deps,
+                control_contract,
+                pool_contract,
+                collateral_contract,
+                liquidation_contract,
+                reward_book_contract,
+            )?;
+            "update_config"
+        }
+        ExecuteMsg::UpdateTimelockConfig { delay } => {
+            update_timelock_config(deps.storage, delay)?;
+            "update_timelock_config"
+        }
+        ExecuteMsg::SetOwner { new_owner_addr } => {
+            set_new_owner(deps.storage, deps.api.addr_canonicalize(&new_owner_addr)?)?;
+            "set_new_owner"
+        }
+        ExecuteMsg::GrantRole { role, address } => {
+            grant_role(deps.storage, role, &deps.api.addr_canonicalize(&address)?)?;
+            "grant_role"
+        }
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Unsupported timelocked change",
+            )))
+        }
+    };
+    Ok(action)
+}
+
+pub fn update_config(
+    deps: DepsMut,
     control_contract: Option<String>,
     pool_contract: Option<String>,
     collateral_contract: Option<String>,
     liquidation_contract: Option<String>,
     reward_book_contract: Option<String>,
-) -> Result<Response, ContractError> {
+) -> Result<(), ContractError> {
     let mut config = read_config(deps.as_ref().storage)?;
-    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
-
-    if sender_raw != config.owner_addr {
-        return Err(ContractError::Unauthorized(
-            "update_config".to_string(),
-            info.sender.to_string(),
-        ));
-    }
 
     if let Some(control_contract) = control_contract {
         config.control_contract = deps.api.addr_canonicalize(&control_contract)?;
@@ -226,7 +345,7 @@ pub fn update_config(
     }
 
     store_config(deps.storage, &config)?;
-    Ok(Response::default())
+    Ok(())
 }
 
 pub fn deposit_collateral(
@@ -234,7 +353,10 @@ pub fn deposit_collateral(
     info: MessageInfo,
     minter: String,
     collateral_amount: Uint128,
+    vault_id: Option<u64>,
 ) -> Result<Response, ContractError> {
+    assert_not_paused::<ContractError>(deps.storage)?;
+
     let config = read_config(deps.storage)?;
     let api = deps.api;
     if api.addr_canonicalize(&info.sender.as_str())? != config.collateral_contract {
@@ -254,6 +376,7 @@ pub fn deposit_collateral(
                 minter: minter.clone(),
                 collateral_contract: collateral_contract.clone(),
                 collateral_amount,
+                vault_id,
             })?,
             funds: vec![],
         }))
@@ -306,7 +429,6 @@ pub fn withdraw_collateral(
         }))
         .add_attributes(vec![
             attr("action", "withdraw_collateral"),
-            attr("contract_name", "custody"),
             attr("minter", minter.clone()),
             attr("collateral_contract", collateral_contract.clone()),
             attr("collateral_amount", collateral_amount.to_string()),
@@ -320,6 +442,7 @@ pub fn mint_stable_coin(
     amount: Uint128,
     stable_amount: Uint128,
     is_redemption_provider: Option<bool>,
+    vault_id: Option<u64>,
 ) -> Result<Response, ContractError> {
     let config = read_config(deps.as_ref().storage)?;
     let api = deps.api;
@@ -350,6 +473,7 @@ pub fn mint_stable_coin(
                         .to_string(),
                 ),
                 is_redemption_provider,
+                vault_id,
             })?,
             funds: vec![],
         }))
@@ -392,7 +516,6 @@ pub fn redeem_stable_coin(
     });
 
     Ok(Response::new().add_message(send_msg).add_attributes(vec![
-        attr("contract_module", "custody"),
         attr("action", "redeem_stable_coin"),
         attr("redeemer", redeemer),
         attr("amount", redeem_amount),
@@ -485,6 +608,18 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
+        QueryMsg::TimelockConfig {} => to_binary(&query_timelock_config(deps)?),
+        QueryMsg::PendingChange { id } => to_binary(&query_pending_change(deps, id)?),
+        QueryMsg::PendingChanges { start_after, limit } => {
+            to_binary(&query_pending_changes(deps, start_after, limit)?)
+        }
+        QueryMsg::HasRole { role, address } => to_binary(&query_has_role(deps, role, address)?),
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&query_role_members(deps, role, start_after, limit)?),
+        QueryMsg::Paused {} => to_binary(&query_paused(deps)?),
     }
 }
 
@@ -511,6 +646,36 @@ fn query_state(deps: Deps) -> StdResult<StateResponse> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    match msg {
+        MigrateMsg::FromLegacy {
+            guardian_addr,
+            timelock_delay,
+        } => {
+            assert_legacy_deployment(deps.storage)?;
+            let config = read_config(deps.storage)?;
+            store_timelock_config(
+                deps.storage,
+                &TimelockConfig {
+                    delay: timelock_delay,
+                },
+            )?;
+            grant_role(deps.storage, Role::Admin, &config.owner_addr)?;
+            grant_role(
+                deps.storage,
+                Role::Guardian,
+                &deps.api.addr_canonicalize(&guardian_addr)?,
+            )?;
+        }
+        MigrateMsg::Upgrade {} => {
+            assert_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        }
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("version", CONTRACT_VERSION),
+    ]))
 }
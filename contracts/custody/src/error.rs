@@ -1,4 +1,5 @@
 use cosmwasm_std::{OverflowError, StdError};
+use cdp::rbac::AccessError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -18,4 +19,17 @@ pub enum ContractError {
     CollateralTypeError{},
     #[error("Functionality deprecated")]
     Deprecated {},
+
+    #[error("Contract is paused")]
+    Paused {},
+}
+
+impl AccessError for ContractError {
+    fn unauthorized(function: &str, sender: &str) -> Self {
+        ContractError::Unauthorized(function.to_string(), sender.to_string())
+    }
+
+    fn paused() -> Self {
+        ContractError::Paused {}
+    }
 }
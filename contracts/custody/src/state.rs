@@ -14,18 +14,19 @@
 // limitations under the License.
 
 
-use cosmwasm_storage::{Singleton, ReadonlySingleton};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 use cosmwasm_bignumber::{Uint256};
 use cosmwasm_std::{CanonicalAddr, StdResult, Storage};
+use cw_storage_plus::Item;
 
 
 
-static KEY_CONFIG: &[u8] = b"config";
-static KEY_STATE: &[u8] = b"state";
-static KEY_NEWOWNER: &[u8] = b"newowner";
+// singletons keep the keys of the former `cosmwasm_storage` layout
+const CONFIG: Item<Config> = Item::new("\u{0}\u{6}config");
+const STATE: Item<State> = Item::new("\u{0}\u{5}state");
+const NEWOWNER: Item<NewOwnerAddr> = Item::new("\u{0}\u{8}newowner");
 
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -50,25 +51,25 @@ pub struct State {
 }
 
 pub fn store_new_owner(storage: &mut dyn Storage, data: &NewOwnerAddr) -> StdResult<()> {
-    Singleton::new(storage, KEY_NEWOWNER).save(data)
+    NEWOWNER.save(storage, data)
 }
 
 pub fn read_new_owner(storage: &dyn Storage) -> StdResult<NewOwnerAddr> {
-    ReadonlySingleton::new(storage, KEY_NEWOWNER).load()
+    NEWOWNER.load(storage)
 }
 
 pub fn store_config(storage: &mut dyn Storage, data: &Config) -> StdResult<()> {
-    Singleton::new(storage, KEY_CONFIG).save(data)
+    CONFIG.save(storage, data)
 }
 
 pub fn read_config(storage: &dyn Storage) -> StdResult<Config> {
-    ReadonlySingleton::new(storage, KEY_CONFIG).load()
+    CONFIG.load(storage)
 }
 
 pub fn store_state(storage: &mut dyn Storage, data: &State) -> StdResult<()> {
-    Singleton::new(storage, KEY_STATE).save(data)
+    STATE.save(storage, data)
 }
 
 pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
-    ReadonlySingleton::new(storage, KEY_STATE).load()
+    STATE.load(storage)
 }
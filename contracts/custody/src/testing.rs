@@ -0,0 +1,66 @@
+use crate::contract::migrate;
+use crate::state::{read_config, read_state, store_config, store_state, Config, State};
+
+use cdp::custody::MigrateMsg;
+use cdp::rbac::{has_role, Role};
+use cdp::timelock::read_timelock_config;
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::testing::{mock_dependencies, mock_env};
+use cosmwasm_std::Api;
+use cw2::get_contract_version;
+
+#[test]
+fn proper_migrate_from_legacy() {
+    let mut deps = mock_dependencies();
+    let owner = deps.api.addr_canonicalize("owner").unwrap();
+    // the legacy singletons share the keys of the current ones
+    let config = Config {
+        owner_addr: owner.clone(),
+        control_contract: deps.api.addr_canonicalize("control").unwrap(),
+        pool_contract: deps.api.addr_canonicalize("pool").unwrap(),
+        collateral_contract: deps.api.addr_canonicalize("collateral").unwrap(),
+        liquidation_contract: deps.api.addr_canonicalize("liquidation").unwrap(),
+        reward_book_contract: deps.api.addr_canonicalize("reward_book").unwrap(),
+    };
+    store_config(deps.as_mut().storage, &config).unwrap();
+    let state = State {
+        total_amount: Uint256::from(1000u64),
+    };
+    store_state(deps.as_mut().storage, &state).unwrap();
+
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::FromLegacy {
+            guardian_addr: "guardian".to_string(),
+            timelock_delay: 86400u64,
+        },
+    )
+    .unwrap();
+
+    assert_eq!(read_config(deps.as_ref().storage).unwrap(), config);
+    assert_eq!(read_state(deps.as_ref().storage).unwrap(), state);
+    assert_eq!(
+        get_contract_version(deps.as_ref().storage).unwrap().contract,
+        "crates.io:cdp-custody"
+    );
+    assert_eq!(
+        read_timelock_config(deps.as_ref().storage).unwrap().delay,
+        86400u64
+    );
+    assert!(has_role(deps.as_ref().storage, Role::Admin, &owner).unwrap());
+    let guardian = deps.api.addr_canonicalize("guardian").unwrap();
+    assert!(has_role(deps.as_ref().storage, Role::Guardian, &guardian).unwrap());
+
+    // a versioned deployment can not be migrated from legacy again
+    migrate(
+        deps.as_mut(),
+        mock_env(),
+        MigrateMsg::FromLegacy {
+            guardian_addr: "guardian".to_string(),
+            timelock_delay: 86400u64,
+        },
+    )
+    .unwrap_err();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+}
@@ -1,4 +1,5 @@
 use crate::state::Bid;
+use cdp::liquidation_queue::PremiumSchedule;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{Env, StdError, StdResult};
 
@@ -10,6 +11,14 @@ pub fn assert_activate_status(
     available_bids: Uint256,
     bid_threshold: Uint256,
 ) -> StdResult<()> {
+    if let Some(expires_at) = bid.expires_at {
+        if expires_at <= env.block.time.seconds() {
+            return Err(StdError::generic_err(format!(
+                "Bid expired at {}",
+                expires_at
+            )));
+        }
+    }
     match bid.wait_end {
         Some(wait_end) => {
             if available_bids < bid_threshold {
@@ -62,16 +71,54 @@ pub fn assert_fees(fees: Decimal256) -> StdResult<()> {
     Ok(())
 }
 
-pub fn assert_max_slot_premium(max_slot: u8, premium_rate_per_slot: Decimal256) -> StdResult<()> {
-    let max_slot_premium =
-        premium_rate_per_slot * Decimal256::from_uint256(Uint256::from(max_slot as u128));
-    if max_slot_premium >= Decimal256::one() {
+pub fn assert_premium_schedule(max_slot: u8, premium_schedule: &PremiumSchedule) -> StdResult<()> {
+    match premium_schedule {
+        PremiumSchedule::Explicit { rates } => {
+            if rates.len() <= max_slot as usize {
+                return Err(StdError::generic_err(
+                    "Premium schedule must have a rate for every slot",
+                ));
+            }
+        }
+        PremiumSchedule::Piecewise { points } => {
+            if points.first().map(|(slot, _)| *slot) != Some(0u8) {
+                return Err(StdError::generic_err(
+                    "Premium curve must start at slot 0",
+                ));
+            }
+            if points.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+                return Err(StdError::generic_err(
+                    "Premium curve slots must be strictly increasing",
+                ));
+            }
+        }
+        PremiumSchedule::Linear { .. } => {}
+    }
+
+    // premium rates can not decrease with the slot, and the max slot premium
+    // rate can not reach 1
+    let rates: Vec<Decimal256> = (0..max_slot + 1)
+        .map(|slot| premium_schedule.premium_rate(slot))
+        .collect();
+    if rates.windows(2).any(|pair| pair[0] > pair[1]) {
+        return Err(StdError::generic_err(
+            "Premium rates can not decrease with the slot",
+        ));
+    }
+    if rates[max_slot as usize] >= Decimal256::one() {
         return Err(StdError::generic_err("Max slot premium rate exceeds limit"));
     }
     Ok(())
 }
 
-
+pub fn assert_max_slippage(max_slippage: Decimal256) -> StdResult<()> {
+    if max_slippage > Decimal256::one() {
+        return Err(StdError::generic_err(
+            "The max slippage can not be greater than one",
+        ));
+    }
+    Ok(())
+}
 
 pub fn assert_safe_ratio(safe_ratio: Decimal256) -> StdResult<()> {
     if safe_ratio > Decimal256::one() {
@@ -80,4 +127,22 @@ pub fn assert_safe_ratio(safe_ratio: Decimal256) -> StdResult<()> {
         ));
     }
     Ok(())
+}
+
+pub fn assert_close_factor(close_factor: Decimal256) -> StdResult<()> {
+    if close_factor.is_zero() || close_factor > Decimal256::one() {
+        return Err(StdError::generic_err(
+            "The close factor must be greater than zero and not greater than one",
+        ));
+    }
+    Ok(())
+}
+
+pub fn assert_prune_tip_rate(prune_tip_rate: Decimal256) -> StdResult<()> {
+    if prune_tip_rate > Decimal256::one() {
+        return Err(StdError::generic_err(
+            "The prune tip rate can not be greater than one",
+        ));
+    }
+    Ok(())
 }
\ No newline at end of file
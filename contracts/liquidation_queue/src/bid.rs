@@ -1,13 +1,20 @@
-use crate::asserts::{assert_activate_status, assert_withdraw_amount};
+use crate::asserts::{assert_activate_status, assert_max_slippage, assert_withdraw_amount};
 use crate::error::ContractError;
 use crate::state::{
-    pop_bid_idx, read_bid, read_bid_pool, read_bids_by_user, read_collateral_info, read_config,
-    read_epoch_scale_sum, read_or_create_bid_pool, read_total_bids, remove_bid, store_bid,
-    store_bid_pool, store_epoch_scale_sum, store_total_bids, Bid, BidPool, CollateralInfo, Config,
+    pop_bid_idx, read_bid, read_bid_pool, read_bids_by_user, read_bids_by_wait_end,
+    read_collateral_info, read_config, read_epoch_scale_sum, read_expired_bids,
+    read_next_active_limit_bid_pool, read_or_create_bid_pool, read_total_bids,
+    read_waiting_bids, remove_active_limit_bid_pool, remove_bid, remove_bid_from_wait_end_index,
+    store_active_limit_bid_pool, store_bid, store_bid_pool, store_epoch_scale_sum,
+    store_total_bids, store_waiting_bids, Bid, BidPool, CollateralInfo, Config, PoolKey,
 };
 use bigint::U256;
+use cdp::events::{CdpEvent, MODULE_LIQUIDATION_QUEUE};
+use cdp::liquidation_queue::{AutoRebid, ExecuteMsg, RebidShare};
 use cdp::oracle_pyth::PriceResponse;
-use cdp::querier::{query_price, query_collateral_whitelist_info};
+use cdp::querier::{query_balance, query_price, query_collateral_whitelist_info};
+use cdp::rbac::assert_not_paused;
+use cdp::swap_adapter::Cw20HookMsg as SwapCw20HookMsg;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
     attr, to_binary, BankMsg, CanonicalAddr, Coin, CosmosMsg, DepsMut, Env, MessageInfo, Response,
@@ -17,14 +24,35 @@ use cw20::Cw20ExecuteMsg;
 
 /// Stable asset is submitted to create a bid record. If available bids for the collateral is under
 /// the threshold, the bid is activated. Bids are not used for liquidations until activated
+#[allow(clippy::too_many_arguments)]
 pub fn submit_bid(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
     collateral_token: String,
     premium_slot: u8,
+    expires_at: Option<u64>,
+    max_price: Option<Decimal256>,
+    auto_rebid: Option<AutoRebid>,
 ) -> Result<Response, ContractError> {
+    assert_not_paused::<ContractError>(deps.storage)?;
+
     let config: Config = read_config(deps.storage)?;
+    if let Some(auto_rebid) = &auto_rebid {
+        if config.swap_adapter.is_none() {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Swap adapter is not configured",
+            )));
+        }
+        assert_max_slippage(auto_rebid.max_slippage)?;
+    }
+    if let Some(expires_at) = expires_at {
+        if expires_at <= env.block.time.seconds() {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Bid expiration must be in the future",
+            )));
+        }
+    }
     let collateral_token_raw: CanonicalAddr = deps.api.addr_canonicalize(&collateral_token)?;
     let collateral_info: CollateralInfo =
         read_collateral_info(deps.storage, &collateral_token_raw)?;
@@ -51,52 +79,95 @@ pub fn submit_bid(
             ))
         })??
         .into();
+    // limit bids are pooled by max price and expiration, small ones would only crowd
+    // the pools liquidations go through
+    if (max_price.is_some() || expires_at.is_some()) && amount < config.min_limit_bid_amount {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Bids with a max price or an expiration must be at least {} {}",
+            config.min_limit_bid_amount, config.stable_denom
+        ))));
+    }
 
-    // read or create bid_pool, make sure slot is valid
-    let mut bid_pool: BidPool =
-        read_or_create_bid_pool(deps.storage, &collateral_info, premium_slot)?;
-
-    // create bid object
     let bid_idx: Uint128 = pop_bid_idx(deps.storage)?;
-    let mut bid = Bid {
-        idx: bid_idx,
-        bidder: bidder_raw,
-        collateral_token: collateral_token_raw.clone(),
-        product_snapshot: Decimal256::one(),
-        amount,
-        premium_slot,
-        sum_snapshot: Decimal256::zero(),
-        pending_liquidated_collateral: Uint256::zero(),
-        wait_end: None,
-        epoch_snapshot: Uint128::zero(),
-        scale_snapshot: Uint128::zero(),
-    };
+    place_bid(
+        deps.storage,
+        &env,
+        &config,
+        &collateral_info,
+        Bid {
+            idx: bid_idx,
+            bidder: bidder_raw,
+            collateral_token: collateral_token_raw,
+            product_snapshot: Decimal256::one(),
+            amount,
+            premium_slot,
+            sum_snapshot: Decimal256::zero(),
+            pending_liquidated_collateral: Uint256::zero(),
+            wait_end: None,
+            epoch_snapshot: Uint128::zero(),
+            scale_snapshot: Uint128::zero(),
+            expires_at,
+            max_price,
+            auto_rebid,
+        },
+    )?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "submit_bid"),
+            attr("bid_idx", bid_idx),
+            attr("amount", amount),
+        ])
+        .add_event(
+            CdpEvent::BidSubmit {
+                bid_idx,
+                bidder: info.sender.to_string(),
+                collateral_token,
+                premium_slot,
+                amount,
+            }
+            .into_event(MODULE_LIQUIDATION_QUEUE),
+        ))
+}
+
+/// Store the new bid, activated directly if available bids for the collateral is under
+/// the threshold, waiting otherwise
+fn place_bid(
+    storage: &mut dyn Storage,
+    env: &Env,
+    config: &Config,
+    collateral_info: &CollateralInfo,
+    mut bid: Bid,
+) -> StdResult<()> {
+    let collateral_token_raw = &collateral_info.collateral_token;
+    let pool_key = PoolKey::of(&bid);
+
+    // read or create bid_pool, make sure slot is valid
+    let mut bid_pool: BidPool = read_or_create_bid_pool(storage, collateral_info, &pool_key)?;
 
     // if available bids is lower than bid_threshold, directly activate bid
     let available_bids: Uint256 =
-        read_total_bids(deps.storage, &collateral_token_raw).unwrap_or_default();
+        read_total_bids(storage, collateral_token_raw).unwrap_or_default();
     if available_bids < collateral_info.bid_threshold {
         // update bid and bid pool, add new share and pool indexes to bid
+        let amount = bid.amount;
         process_bid_activation(&mut bid, &mut bid_pool, amount);
 
         // store bid_pool
-        store_bid_pool(deps.storage, &collateral_token_raw, premium_slot, &bid_pool)?;
+        store_bid_pool(storage, collateral_token_raw, &pool_key, &bid_pool)?;
+        store_active_limit_bid_pool(storage, collateral_token_raw, &pool_key)?;
 
         // increase total bid amount
-        store_total_bids(deps.storage, &collateral_token_raw, available_bids + amount)?;
+        store_total_bids(storage, collateral_token_raw, available_bids + amount)?;
     } else {
         // calculate wait_end from current time
-        bid.wait_end = Some(env.block.time.plus_seconds(config.waiting_period).seconds());
+        let waiting_period = collateral_info.waiting_period(config);
+        bid.wait_end = Some(env.block.time.plus_seconds(waiting_period).seconds());
+        increase_waiting_bids(storage, collateral_token_raw, bid.premium_slot, bid.amount)?;
     };
 
     // save to storage
-    store_bid(deps.storage, bid_idx, &bid)?;
-
-    Ok(Response::new().add_attributes(vec![
-        attr("action", "submit_bid"),
-        attr("bid_idx", bid_idx),
-        attr("amount", amount),
-    ]))
+    store_bid(storage, bid.idx, &bid)
 }
 
 /// After bids are submitted, need to execute the activation after wait_period expires
@@ -137,8 +208,8 @@ pub fn activate_bids(
                 "Bid collateral token doesn't match",
             )));
         }
-        let mut bid_pool: BidPool =
-            read_bid_pool(deps.storage, &bid.collateral_token, bid.premium_slot)?;
+        let pool_key = PoolKey::of(&bid);
+        let mut bid_pool: BidPool = read_bid_pool(deps.storage, &bid.collateral_token, &pool_key)?;
 
         let amount_to_activate = bid.amount;
 
@@ -157,32 +228,118 @@ pub fn activate_bids(
 
         // update bid and bid pool, add new share and pool indexes to bid
         process_bid_activation(&mut bid, &mut bid_pool, amount_to_activate);
+        decrease_waiting_bids(
+            deps.storage,
+            &collateral_token_raw,
+            bid.premium_slot,
+            amount_to_activate,
+        )?;
 
         // save to storage
         store_bid(deps.storage, bid.idx, &bid)?;
-        store_bid_pool(
+        store_bid_pool(deps.storage, &bid.collateral_token, &pool_key, &bid_pool)?;
+        store_active_limit_bid_pool(deps.storage, &bid.collateral_token, &pool_key)?;
+
+        total_activated_amount += amount_to_activate;
+        available_bids += amount_to_activate;
+    }
+
+    store_total_bids(deps.storage, &collateral_token_raw, available_bids)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "activate_bids"),
+            attr("amount", total_activated_amount),
+        ])
+        .add_event(
+            CdpEvent::BidActivate {
+                bidder: info.sender.to_string(),
+                collateral_token,
+                amount: total_activated_amount,
+            }
+            .into_event(MODULE_LIQUIDATION_QUEUE),
+        ))
+}
+
+/// Anyone can activate the waiting bids of the collateral in first in first out
+/// order of their wait end. Bids are activated once their waiting period is over,
+/// or regardless of it while the available bids are under the threshold
+pub fn activate_bids_for(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collateral_token: String,
+    limit: Option<u8>,
+) -> Result<Response, ContractError> {
+    let collateral_token_raw: CanonicalAddr = deps.api.addr_canonicalize(&collateral_token)?;
+    let collateral_info: CollateralInfo =
+        read_collateral_info(deps.storage, &collateral_token_raw)?;
+    let mut available_bids: Uint256 =
+        read_total_bids(deps.storage, &collateral_token_raw).unwrap_or_default();
+
+    // expired bids count toward the limit, they are dropped from the queue
+    let bids: Vec<Bid> = read_bids_by_wait_end(deps.storage, &collateral_token_raw, limit)?;
+
+    let mut total_activated_amount = Uint256::zero();
+    let mut events = vec![];
+    for mut bid in bids.into_iter() {
+        if matches!(bid.expires_at, Some(expires_at) if expires_at <= env.block.time.seconds()) {
+            remove_bid_from_wait_end_index(deps.storage, &bid);
+            continue;
+        }
+
+        // the following bids are still in their waiting period
+        if assert_activate_status(&bid, &env, available_bids, collateral_info.bid_threshold)
+            .is_err()
+        {
+            break;
+        }
+
+        let pool_key = PoolKey::of(&bid);
+        let mut bid_pool: BidPool = read_bid_pool(deps.storage, &bid.collateral_token, &pool_key)?;
+        let amount_to_activate = bid.amount;
+
+        // update bid and bid pool, add new share and pool indexes to bid
+        process_bid_activation(&mut bid, &mut bid_pool, amount_to_activate);
+        decrease_waiting_bids(
             deps.storage,
-            &bid.collateral_token,
+            &collateral_token_raw,
             bid.premium_slot,
-            &bid_pool,
+            amount_to_activate,
         )?;
 
+        // save to storage
+        store_bid(deps.storage, bid.idx, &bid)?;
+        store_bid_pool(deps.storage, &bid.collateral_token, &pool_key, &bid_pool)?;
+        store_active_limit_bid_pool(deps.storage, &bid.collateral_token, &pool_key)?;
+
         total_activated_amount += amount_to_activate;
         available_bids += amount_to_activate;
+        events.push(
+            CdpEvent::BidActivate {
+                bidder: deps.api.addr_humanize(&bid.bidder)?.to_string(),
+                collateral_token: collateral_token.clone(),
+                amount: amount_to_activate,
+            }
+            .into_event(MODULE_LIQUIDATION_QUEUE),
+        );
     }
 
     store_total_bids(deps.storage, &collateral_token_raw, available_bids)?;
 
-    Ok(Response::new().add_attributes(vec![
-        attr("action", "activate_bids"),
-        attr("amount", total_activated_amount),
-    ]))
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "activate_bids_for"),
+            attr("keeper", info.sender.to_string()),
+            attr("amount", total_activated_amount),
+        ])
+        .add_events(events))
 }
 
 /// Bid owners can withdraw the ramaning bid amount at any time
 pub fn retract_bid(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     bid_idx: Uint128,
     amount: Option<Uint256>,
@@ -206,12 +363,24 @@ pub fn retract_bid(
             bid.amount = bid.amount - waiting_withdraw_amount;
             store_bid(deps.storage, bid.idx, &bid)?;
         }
+        decrease_waiting_bids(
+            deps.storage,
+            &collateral_token_raw,
+            bid.premium_slot,
+            waiting_withdraw_amount,
+        )?;
 
         waiting_withdraw_amount
     } else {
-        let available_bids: Uint256 = read_total_bids(deps.storage, &collateral_token_raw)?;
-        let mut bid_pool: BidPool =
-            read_bid_pool(deps.storage, &collateral_token_raw, bid.premium_slot)?;
+        let pool_key = PoolKey::of(&bid);
+        let mut bid_pool: BidPool = read_bid_pool(deps.storage, &collateral_token_raw, &pool_key)?;
+        let expired = deactivate_expired_bid_pool(
+            deps.storage,
+            &env,
+            &collateral_token_raw,
+            &pool_key,
+            &bid_pool,
+        )?;
 
         // calculate spent and reward until this moment
         let (withdrawable_amount, residue_bid) = calculate_remaining_bid(&bid, &bid_pool)?;
@@ -251,17 +420,16 @@ pub fn retract_bid(
         // claim residue bids if it is bigger than 1.0
         let refund_amount = withdraw_amount + claim_bid_residue(&mut bid_pool);
 
-        store_bid_pool(
-            deps.storage,
-            &collateral_token_raw,
-            bid.premium_slot,
-            &bid_pool,
-        )?;
-        store_total_bids(
-            deps.storage,
-            &collateral_token_raw,
-            available_bids - withdraw_amount,
-        )?;
+        store_bid_pool(deps.storage, &collateral_token_raw, &pool_key, &bid_pool)?;
+        // the bids of an expired pool are no longer available
+        if !expired {
+            let available_bids: Uint256 = read_total_bids(deps.storage, &collateral_token_raw)?;
+            store_total_bids(
+                deps.storage,
+                &collateral_token_raw,
+                available_bids - withdraw_amount,
+            )?;
+        }
 
         refund_amount
     };
@@ -279,11 +447,21 @@ pub fn retract_bid(
         }));
     }
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "retract_bid"),
-        attr("bid_idx", bid_idx),
-        attr("amount", withdraw_amount),
-    ]))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "retract_bid"),
+            attr("bid_idx", bid_idx),
+            attr("amount", withdraw_amount),
+        ])
+        .add_event(
+            CdpEvent::BidRetract {
+                bid_idx,
+                bidder: info.sender.to_string(),
+                amount: withdraw_amount,
+            }
+            .into_event(MODULE_LIQUIDATION_QUEUE),
+        ))
 }
 
 /// Control executes the liquidation providing a whitelisted collateral.
@@ -292,7 +470,7 @@ pub fn retract_bid(
 #[allow(clippy::too_many_arguments)]
 pub fn execute_liquidation(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     sender: String,
     liquidator: String,
     repay_address: String,
@@ -304,7 +482,7 @@ pub fn execute_liquidation(
     let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
     let collateral_info: CollateralInfo =
         read_collateral_info(deps.storage, &collateral_token_raw)?;
-    let available_bids: Uint256 = read_total_bids(deps.storage, &collateral_token_raw)?;
+
 
     // only collateral token custody can execute liquidations
     let control_contract = deps.api.addr_humanize(&config.control_contract)?;
@@ -332,35 +510,62 @@ pub fn execute_liquidation(
     let mut remaining_collateral_to_liquidate = amount;
     let mut repay_amount = Uint256::zero();
     let mut filled: bool = false;
-    for slot in 0..collateral_info.max_slot + 1 {
-        let mut bid_pool: BidPool = match read_bid_pool(deps.storage, &collateral_token_raw, slot) {
-            Ok(bid_pool) => bid_pool,
+    'slots: for slot in 0..collateral_info.max_slot + 1 {
+        let slot_key = PoolKey::slot(slot);
+        let premium_rate = match read_bid_pool(deps.storage, &collateral_token_raw, &slot_key) {
+            Ok(bid_pool) => bid_pool.premium_rate,
             Err(_) => continue,
         };
-        if bid_pool.total_bid_amount.is_zero() {
-            continue;
-        };
-
-        let (pool_repay_amount, pool_liquidated_collateral) = execute_pool_liquidation(
-            deps.storage,
-            &mut bid_pool,
-            &collateral_token_raw,
-            slot,
-            remaining_collateral_to_liquidate,
-            price.emv_price,
-            &mut filled,
-        )?;
-
-        store_bid_pool(deps.storage, &collateral_token_raw, slot, &bid_pool)?;
 
-        repay_amount += pool_repay_amount;
+        // the bids of the slot without a limit go first, then the limit bid pools whose
+        // max price is not below the price, the others sit out this liquidation
+        let mut next_pool_key = Some(slot_key);
+        while let Some(pool_key) = next_pool_key {
+            let mut bid_pool: BidPool =
+                read_bid_pool(deps.storage, &collateral_token_raw, &pool_key)?;
+            if deactivate_expired_bid_pool(
+                deps.storage,
+                &env,
+                &collateral_token_raw,
+                &pool_key,
+                &bid_pool,
+            )? {
+                // the expired bids wait to be retracted or pruned
+            } else if bid_pool.total_bid_amount.is_zero() {
+                // activating a bid makes the limit bid pool active again
+                remove_active_limit_bid_pool(deps.storage, &collateral_token_raw, &pool_key)?;
+            } else {
+                // limit bid pools follow the premium rate of their slot
+                bid_pool.premium_rate = premium_rate;
+                let (pool_repay_amount, pool_liquidated_collateral) = execute_pool_liquidation(
+                    deps.storage,
+                    &mut bid_pool,
+                    &collateral_token_raw,
+                    &pool_key,
+                    remaining_collateral_to_liquidate,
+                    price.emv_price,
+                    &mut filled,
+                )?;
+
+                store_bid_pool(deps.storage, &collateral_token_raw, &pool_key, &bid_pool)?;
+
+                repay_amount += pool_repay_amount;
+
+                if filled {
+                    remaining_collateral_to_liquidate = Uint256::zero();
+                    break 'slots;
+                } else {
+                    remaining_collateral_to_liquidate =
+                        remaining_collateral_to_liquidate - pool_liquidated_collateral;
+                }
+            }
 
-        if filled {
-            remaining_collateral_to_liquidate = Uint256::zero();
-            break;
-        } else {
-            remaining_collateral_to_liquidate =
-                remaining_collateral_to_liquidate - pool_liquidated_collateral;
+            next_pool_key = read_next_active_limit_bid_pool(
+                deps.storage,
+                &collateral_token_raw,
+                &pool_key,
+                price.emv_price,
+            )?;
         }
     }
 
@@ -370,14 +575,15 @@ pub fn execute_liquidation(
         )));
     }
 
+    let available_bids: Uint256 = read_total_bids(deps.storage, &collateral_token_raw)?;
     store_total_bids(
         deps.storage,
         &collateral_token_raw,
         available_bids - repay_amount,
     )?;
 
-    let bid_fee = repay_amount * config.bid_fee;
-    let liquidator_fee = repay_amount * config.liquidator_fee;
+    let bid_fee = repay_amount * collateral_info.bid_fee(&config);
+    let liquidator_fee = repay_amount * collateral_info.liquidator_fee(&config);
     let repay_amount = repay_amount - bid_fee - liquidator_fee;
 
     let mut messages: Vec<CosmosMsg> = vec![CosmosMsg::Bank(BankMsg::Send {
@@ -403,7 +609,7 @@ pub fn execute_liquidation(
     }
     if !liquidator_fee.is_zero() {
         messages.push(CosmosMsg::Bank(BankMsg::Send {
-            to_address: liquidator,
+            to_address: liquidator.clone(),
             amount: vec![
                 (Coin {
                     denom: config.stable_denom.clone(),
@@ -413,15 +619,28 @@ pub fn execute_liquidation(
         }));
     }
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "execute_bid"),
-        attr("stable_denom", config.stable_denom),
-        attr("repay_amount", repay_amount),
-        attr("bid_fee", bid_fee),
-        attr("liquidator_fee", liquidator_fee),
-        attr("collateral_token", collateral_token),
-        attr("collateral_amount", amount),
-    ]))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "execute_bid"),
+            attr("stable_denom", config.stable_denom),
+            attr("repay_amount", repay_amount),
+            attr("bid_fee", bid_fee),
+            attr("liquidator_fee", liquidator_fee),
+            attr("collateral_token", collateral_token.clone()),
+            attr("collateral_amount", amount),
+        ])
+        .add_event(
+            CdpEvent::BidExecute {
+                liquidator,
+                collateral_token,
+                collateral_amount: amount,
+                repay_amount,
+                bid_fee,
+                liquidator_fee,
+            }
+            .into_event(MODULE_LIQUIDATION_QUEUE),
+        ))
 }
 
 /// Bid owner can claim their share of the liquidated collateral until the
@@ -455,74 +674,481 @@ pub fn claim_liquidations(
                 "Bid collateral token doesn't match",
             )));
         }
-        if bid.wait_end.is_some() {
-            // bid not activated
+        claim_amount += claim_bid(deps.storage, bid)?;
+    }
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !claim_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: collateral_token.clone(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: info.sender.to_string(),
+                amount: claim_amount.into(),
+            })?,
+        }));
+    }
+
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "claim_liquidations"),
+            attr("collateral_token", collateral_token.clone()),
+            attr("collateral_amount", claim_amount),
+        ])
+        .add_event(
+            CdpEvent::BidClaim {
+                bidder: info.sender.to_string(),
+                collateral_token,
+                amount: claim_amount,
+            }
+            .into_event(MODULE_LIQUIDATION_QUEUE),
+        ))
+}
+
+/// Settle the liquidated collateral of the bid, returns the amount to pay out
+fn claim_bid(storage: &mut dyn Storage, bid: Bid) -> StdResult<Uint256> {
+    if bid.wait_end.is_some() {
+        // bid not activated
+        return Ok(Uint256::zero());
+    }
+
+    let pool_key = PoolKey::of(&bid);
+    let mut bid_pool: BidPool = read_bid_pool(storage, &bid.collateral_token, &pool_key)?;
+
+    // calculate remaining bid amount
+    let (remaining_bid, residue_bid) = calculate_remaining_bid(&bid, &bid_pool)?;
+
+    // calculate liquidated collateral
+    let (liquidated_collateral, residue_collateral) =
+        calculate_liquidated_collateral(storage, &bid)?;
+
+    // keep residues
+    bid_pool.residue_collateral += residue_collateral;
+    bid_pool.residue_bid += residue_bid;
+
+    // get claimable amount
+    let claim_amount = bid.pending_liquidated_collateral
+        + liquidated_collateral
+        + claim_col_residue(&mut bid_pool);
+
+    // store bid_pool to update residue
+    store_bid_pool(storage, &bid.collateral_token, &pool_key, &bid_pool)?;
+
+    // check if bid has been consumed, include 1 for rounding
+    if remaining_bid <= Uint256::one() {
+        remove_bid(storage, bid.idx)?;
+    } else {
+        store_bid(
+            storage,
+            bid.idx,
+            &Bid {
+                amount: remaining_bid,
+                product_snapshot: bid_pool.product_snapshot,
+                sum_snapshot: bid_pool.sum_snapshot,
+                scale_snapshot: bid_pool.current_scale,
+                pending_liquidated_collateral: Uint256::zero(),
+                ..bid
+            },
+        )?;
+    }
+
+    Ok(claim_amount)
+}
+
+/// Liquidated collateral of auto rebidding bids is sold through the swap adapter,
+/// the return is bid again in the premium slots of the bids by `RebidSwapped`
+pub fn rebid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collateral_token: String,
+    bids_idx: Vec<Uint128>,
+) -> Result<Response, ContractError> {
+    assert_not_paused::<ContractError>(deps.storage)?;
+
+    let config: Config = read_config(deps.storage)?;
+    let swap_adapter = match &config.swap_adapter {
+        Some(swap_adapter) => deps.api.addr_humanize(swap_adapter)?,
+        None => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Swap adapter is not configured",
+            )))
+        }
+    };
+    let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
+
+    let oracle_contract = deps.api.addr_humanize(&config.oracle_contract)?;
+    let price: PriceResponse = query_price(
+        deps.as_ref(),
+        oracle_contract,
+        collateral_token.clone(),
+        config.stable_denom.clone(),
+        None,
+    )?;
+
+    let mut rebids: Vec<RebidShare> = vec![];
+    let mut total_collateral = Uint256::zero();
+    let mut max_slippage = Decimal256::one();
+    for bid_idx in bids_idx {
+        let bid: Bid = read_bid(deps.storage, bid_idx)?;
+        if bid.collateral_token != collateral_token_raw {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Bid collateral token doesn't match",
+            )));
+        }
+        let auto_rebid = match &bid.auto_rebid {
+            Some(auto_rebid) => auto_rebid.clone(),
+            None => {
+                return Err(ContractError::Std(StdError::generic_err(format!(
+                    "Bid {} is not auto rebidding",
+                    bid_idx
+                ))))
+            }
+        };
+        if let Some(expires_at) = bid.expires_at {
+            if expires_at <= env.block.time.seconds() {
+                return Err(ContractError::Std(StdError::generic_err(format!(
+                    "Bid expired at {}",
+                    expires_at
+                ))));
+            }
+        }
+
+        let collateral_amount = claim_bid(deps.storage, bid.clone())?;
+        if collateral_amount.is_zero() {
             continue;
         }
 
-        let mut bid_pool: BidPool =
-            read_bid_pool(deps.storage, &bid.collateral_token, bid.premium_slot)?;
+        total_collateral += collateral_amount;
+        max_slippage = max_slippage.min(auto_rebid.max_slippage);
+        rebids.push(RebidShare {
+            bidder: deps.api.addr_humanize(&bid.bidder)?.to_string(),
+            premium_slot: bid.premium_slot,
+            collateral_amount,
+            expires_at: bid.expires_at,
+            max_price: bid.max_price,
+            auto_rebid,
+        });
+    }
 
-        // calculate remaining bid amount
-        let (remaining_bid, residue_bid) = calculate_remaining_bid(&bid, &bid_pool)?;
+    if total_collateral.is_zero() {
+        return Err(ContractError::Std(StdError::generic_err(
+            "No liquidated collateral to rebid",
+        )));
+    }
 
-        // calculate liquidated collateral
-        let (liquidated_collateral, residue_collateral) =
-            calculate_liquidated_collateral(deps.storage, &bid)?;
+    // the return is shared in proportion to the collateral, the strictest slippage
+    // of the bids holds for all of them
+    let min_receive = total_collateral * price.emv_price * (Decimal256::one() - max_slippage);
+    let prev_balance = query_balance(
+        deps.as_ref(),
+        env.contract.address.clone(),
+        config.stable_denom.clone(),
+    )?;
 
-        // keep residues
-        bid_pool.residue_collateral += residue_collateral;
-        bid_pool.residue_bid += residue_bid;
+    Ok(Response::new()
+        .add_messages(vec![
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: collateral_token.clone(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract: swap_adapter.to_string(),
+                    amount: total_collateral.into(),
+                    msg: to_binary(&SwapCw20HookMsg::Swap {
+                        ask_denom: config.stable_denom,
+                        min_receive: min_receive.into(),
+                        recipient: None,
+                    })?,
+                })?,
+            }),
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                funds: vec![],
+                msg: to_binary(&ExecuteMsg::RebidSwapped {
+                    collateral_token: collateral_token.clone(),
+                    prev_balance,
+                    min_receive,
+                    rebids,
+                })?,
+            }),
+        ])
+        .add_attributes(vec![
+            attr("action", "rebid"),
+            attr("keeper", info.sender.to_string()),
+            attr("collateral_token", collateral_token),
+            attr("collateral_amount", total_collateral),
+            attr("min_receive", min_receive),
+        ]))
+}
 
-        // get claimable amount
-        claim_amount += bid.pending_liquidated_collateral
-            + liquidated_collateral
-            + claim_col_residue(&mut bid_pool);
+/// The stable amount received from the swap of `Rebid` is shared between the
+/// bids in proportion to their collateral and bid again as new bids
+pub fn rebid_swapped(
+    deps: DepsMut,
+    env: Env,
+    collateral_token: String,
+    prev_balance: Uint256,
+    min_receive: Uint256,
+    rebids: Vec<RebidShare>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
+    let collateral_info: CollateralInfo =
+        read_collateral_info(deps.storage, &collateral_token_raw)?;
+
+    let balance = query_balance(
+        deps.as_ref(),
+        env.contract.address.clone(),
+        config.stable_denom.clone(),
+    )?;
+    let received = balance - prev_balance.min(balance);
+    if received < min_receive {
+        return Err(ContractError::Std(StdError::generic_err(format!(
+            "Swap return {} is below the minimum {}",
+            received, min_receive
+        ))));
+    }
+
+    let total_collateral = rebids
+        .iter()
+        .fold(Uint256::zero(), |total, share| total + share.collateral_amount);
+    let mut remaining = received;
+    let mut events = vec![];
+    for (i, share) in rebids.iter().enumerate() {
+        // the last share takes the rounding remainder
+        let amount = if i + 1 == rebids.len() {
+            remaining
+        } else {
+            received.multiply_ratio(share.collateral_amount, total_collateral)
+        };
+        remaining = remaining - amount.min(remaining);
+        if amount.is_zero() {
+            continue;
+        }
 
-        // store bid_pool to update residue
-        store_bid_pool(
+        let bid_idx: Uint128 = pop_bid_idx(deps.storage)?;
+        place_bid(
             deps.storage,
-            &collateral_token_raw,
-            bid.premium_slot,
-            &bid_pool,
+            &env,
+            &config,
+            &collateral_info,
+            Bid {
+                idx: bid_idx,
+                bidder: deps.api.addr_canonicalize(&share.bidder)?,
+                collateral_token: collateral_token_raw.clone(),
+                product_snapshot: Decimal256::one(),
+                amount,
+                premium_slot: share.premium_slot,
+                sum_snapshot: Decimal256::zero(),
+                pending_liquidated_collateral: Uint256::zero(),
+                wait_end: None,
+                epoch_snapshot: Uint128::zero(),
+                scale_snapshot: Uint128::zero(),
+                expires_at: share.expires_at,
+                max_price: share.max_price,
+                auto_rebid: Some(share.auto_rebid.clone()),
+            },
         )?;
+        events.push(
+            CdpEvent::BidSubmit {
+                bid_idx,
+                bidder: share.bidder.clone(),
+                collateral_token: collateral_token.clone(),
+                premium_slot: share.premium_slot,
+                amount,
+            }
+            .into_event(MODULE_LIQUIDATION_QUEUE),
+        );
+    }
 
-        // check if bid has been consumed, include 1 for rounding
-        if remaining_bid <= Uint256::one() {
-            remove_bid(deps.storage, bid.idx)?;
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "rebid_swapped"),
+            attr("collateral_token", collateral_token),
+            attr("amount", received),
+        ])
+        .add_events(events))
+}
+
+/// Expired bids are refunded to their bidders, the caller receives a share of
+/// the refunded stable amount as a tip
+pub fn prune_expired_bids(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    collateral_token: String,
+    limit: Option<u8>,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
+
+    let bids = read_expired_bids(
+        deps.storage,
+        &collateral_token_raw,
+        env.block.time.seconds(),
+        None,
+        limit,
+    )?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    let mut events = vec![];
+    let mut total_tip = Uint256::zero();
+    for bid in bids.into_iter() {
+        let bid = if bid.wait_end.is_some() {
+            decrease_waiting_bids(
+                deps.storage,
+                &collateral_token_raw,
+                bid.premium_slot,
+                bid.amount,
+            )?;
+            bid
         } else {
-            store_bid(
+            let pool_key = PoolKey::of(&bid);
+            let bid_pool: BidPool = read_bid_pool(deps.storage, &collateral_token_raw, &pool_key)?;
+            deactivate_expired_bid_pool(
                 deps.storage,
-                bid.idx,
-                &Bid {
-                    amount: remaining_bid,
-                    product_snapshot: bid_pool.product_snapshot,
-                    sum_snapshot: bid_pool.sum_snapshot,
-                    scale_snapshot: bid_pool.current_scale,
-                    pending_liquidated_collateral: Uint256::zero(),
-                    ..bid
-                },
+                &env,
+                &collateral_token_raw,
+                &pool_key,
+                &bid_pool,
             )?;
+            withdraw_from_bid_pool(deps.storage, &collateral_token_raw, bid)?
+        };
+
+        let bidder = deps.api.addr_humanize(&bid.bidder)?;
+        let tip = bid.amount * config.prune_tip_rate;
+        let refund_amount = bid.amount - tip;
+
+        if !refund_amount.is_zero() {
+            messages.push(CosmosMsg::Bank(BankMsg::Send {
+                to_address: bidder.to_string(),
+                amount: vec![Coin {
+                    denom: config.stable_denom.clone(),
+                    amount: refund_amount.into(),
+                }],
+            }));
         }
+        if !bid.pending_liquidated_collateral.is_zero() {
+            messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: collateral_token.clone(),
+                funds: vec![],
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: bidder.to_string(),
+                    amount: bid.pending_liquidated_collateral.into(),
+                })?,
+            }));
+        }
+
+        remove_bid(deps.storage, bid.idx)?;
+        total_tip += tip;
+        events.push(
+            CdpEvent::BidPrune {
+                bid_idx: bid.idx,
+                bidder: bidder.to_string(),
+                keeper: info.sender.to_string(),
+                amount: refund_amount,
+                collateral_amount: bid.pending_liquidated_collateral,
+                tip,
+            }
+            .into_event(MODULE_LIQUIDATION_QUEUE),
+        );
     }
 
-    let mut messages: Vec<CosmosMsg> = vec![];
-    if !claim_amount.is_zero() {
-        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
-            contract_addr: collateral_token.clone(),
-            funds: vec![],
-            msg: to_binary(&Cw20ExecuteMsg::Transfer {
-                recipient: info.sender.to_string(),
-                amount: claim_amount.into(),
-            })?,
+    if !total_tip.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: info.sender.to_string(),
+            amount: vec![Coin {
+                denom: config.stable_denom,
+                amount: total_tip.into(),
+            }],
         }));
     }
 
-    Ok(Response::new().add_messages(messages).add_attributes(vec![
-        attr("action", "claim_liquidations"),
-        attr("collateral_token", collateral_token),
-        attr("collateral_amount", claim_amount),
-    ]))
+    Ok(Response::new()
+        .add_messages(messages)
+        .add_attributes(vec![
+            attr("action", "prune_expired_bids"),
+            attr("collateral_token", collateral_token),
+            attr("pruned", events.len().to_string()),
+            attr("tip", total_tip),
+        ])
+        .add_events(events))
+}
+
+/// Take an expired limit bid pool out of the active pools, the amount left in it is
+/// no longer available for liquidations. Returns whether the pool is expired
+fn deactivate_expired_bid_pool(
+    storage: &mut dyn Storage,
+    env: &Env,
+    collateral_token: &CanonicalAddr,
+    pool_key: &PoolKey,
+    bid_pool: &BidPool,
+) -> StdResult<bool> {
+    if !pool_key.is_expired(env.block.time.seconds()) {
+        return Ok(false);
+    }
+
+    if remove_active_limit_bid_pool(storage, collateral_token, pool_key)? {
+        let available_bids: Uint256 = read_total_bids(storage, collateral_token)?;
+        store_total_bids(
+            storage,
+            collateral_token,
+            available_bids - bid_pool.total_bid_amount.min(available_bids),
+        )?;
+    }
+    Ok(true)
+}
+
+/// Take the remaining amount of an active bid out of its bid pool, the bid is
+/// settled up to now and returned with its remaining amount and liquidated collateral
+fn withdraw_from_bid_pool(
+    storage: &mut dyn Storage,
+    collateral_token: &CanonicalAddr,
+    bid: Bid,
+) -> StdResult<Bid> {
+    let pool_key = PoolKey::of(&bid);
+    let mut bid_pool: BidPool = read_bid_pool(storage, collateral_token, &pool_key)?;
+    let (remaining_bid, residue_bid) = calculate_remaining_bid(&bid, &bid_pool)?;
+    let (liquidated_collateral, residue_collateral) =
+        calculate_liquidated_collateral(storage, &bid)?;
+
+    bid_pool.residue_collateral += residue_collateral;
+    bid_pool.residue_bid += residue_bid;
+    bid_pool.total_bid_amount = bid_pool.total_bid_amount - remaining_bid;
+    store_bid_pool(storage, collateral_token, &pool_key, &bid_pool)?;
+
+    Ok(Bid {
+        amount: remaining_bid,
+        pending_liquidated_collateral: bid.pending_liquidated_collateral + liquidated_collateral,
+        ..bid
+    })
+}
+
+fn increase_waiting_bids(
+    storage: &mut dyn Storage,
+    collateral_token: &CanonicalAddr,
+    premium_slot: u8,
+    amount: Uint256,
+) -> StdResult<()> {
+    let waiting_bids = read_waiting_bids(storage, collateral_token, premium_slot)?;
+    store_waiting_bids(storage, collateral_token, premium_slot, waiting_bids + amount)
+}
+
+// waiting bids submitted before the amounts were tracked are not counted
+fn decrease_waiting_bids(
+    storage: &mut dyn Storage,
+    collateral_token: &CanonicalAddr,
+    premium_slot: u8,
+    amount: Uint256,
+) -> StdResult<()> {
+    let waiting_bids = read_waiting_bids(storage, collateral_token, premium_slot)?;
+    store_waiting_bids(
+        storage,
+        collateral_token,
+        premium_slot,
+        waiting_bids - amount.min(waiting_bids),
+    )
 }
 
 fn process_bid_activation(bid: &mut Bid, bid_pool: &mut BidPool, amount: Uint256) {
@@ -544,21 +1170,18 @@ fn execute_pool_liquidation(
     storage: &mut dyn Storage,
     bid_pool: &mut BidPool,
     collateral_token: &CanonicalAddr,
-    premium_slot: u8,
+    pool_key: &PoolKey,
     collateral_to_liquidate: Uint256,
     price: Decimal256,
     filled: &mut bool,
 ) -> StdResult<(Uint256, Uint256)> {
-    let premium_price = price * (Decimal256::one() - bid_pool.premium_rate);
-    let mut pool_collateral_to_liquidate = collateral_to_liquidate;
-    let mut pool_required_stable = pool_collateral_to_liquidate * premium_price;
-
-    if pool_required_stable > bid_pool.total_bid_amount {
-        pool_required_stable = bid_pool.total_bid_amount;
-        pool_collateral_to_liquidate = pool_required_stable / premium_price;
-    } else {
-        *filled = true;
-    }
+    let (pool_required_stable, pool_collateral_to_liquidate, pool_filled) = compute_pool_fill(
+        bid_pool.total_bid_amount,
+        bid_pool.premium_rate,
+        collateral_to_liquidate,
+        price,
+    );
+    *filled = pool_filled;
 
     // E / D
     let col_per_bid: Decimal256 = Decimal256::from_uint256(pool_collateral_to_liquidate)
@@ -580,7 +1203,7 @@ fn execute_pool_liquidation(
     store_epoch_scale_sum(
         storage,
         collateral_token,
-        premium_slot,
+        pool_key,
         bid_pool.current_epoch,
         bid_pool.current_scale,
         bid_pool.sum_snapshot,
@@ -612,6 +1235,24 @@ fn execute_pool_liquidation(
     Ok((pool_required_stable, pool_collateral_to_liquidate))
 }
 
+/// Stable amount consumed and collateral bought by a bid pool holding `total_bid_amount`,
+/// and whether the pool fills the whole `collateral_to_liquidate`
+pub(crate) fn compute_pool_fill(
+    total_bid_amount: Uint256,
+    premium_rate: Decimal256,
+    collateral_to_liquidate: Uint256,
+    price: Decimal256,
+) -> (Uint256, Uint256, bool) {
+    let premium_price = price * (Decimal256::one() - premium_rate);
+    let required_stable = collateral_to_liquidate * premium_price;
+
+    if required_stable > total_bid_amount {
+        (total_bid_amount, total_bid_amount / premium_price, false)
+    } else {
+        (required_stable, collateral_to_liquidate, true)
+    }
+}
+
 pub(crate) fn calculate_remaining_bid(
     bid: &Bid,
     bid_pool: &BidPool,
@@ -645,10 +1286,11 @@ pub(crate) fn calculate_liquidated_collateral(
     storage: &dyn Storage,
     bid: &Bid,
 ) -> StdResult<(Uint256, Decimal256)> {
+    let pool_key = PoolKey::of(bid);
     let reference_sum_snapshot = read_epoch_scale_sum(
         storage,
         &bid.collateral_token,
-        bid.premium_slot,
+        &pool_key,
         bid.epoch_snapshot,
         bid.scale_snapshot,
     )
@@ -659,7 +1301,7 @@ pub(crate) fn calculate_liquidated_collateral(
     let second_portion = if let Ok(second_scale_sum_snapshot) = read_epoch_scale_sum(
         storage,
         &bid.collateral_token,
-        bid.premium_slot,
+        &pool_key,
         bid.epoch_snapshot,
         bid.scale_snapshot + Uint128::from(1u128),
     ) {
@@ -1,25 +1,53 @@
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 
-use crate::asserts::{assert_fees, assert_max_slot, assert_max_slot_premium, assert_safe_ratio};
-use crate::bid::{activate_bids, claim_liquidations, execute_liquidation, retract_bid, submit_bid};
+use crate::asserts::{
+    assert_close_factor, assert_fees, assert_max_slot, assert_premium_schedule,
+    assert_prune_tip_rate, assert_safe_ratio,
+};
+use crate::bid::{
+    activate_bids, activate_bids_for, claim_liquidations, execute_liquidation,
+    prune_expired_bids, rebid, rebid_swapped, retract_bid, submit_bid,
+};
 use crate::query::{
-    query_bid, query_bid_pool, query_bid_pools, query_bids_by_user, query_collateral_info,
-    query_config, query_liquidation_amount,
+    query_bid, query_bid_depth, query_bid_pool, query_bid_pools, query_bids_by_user,
+    query_claimable_liquidations, query_collateral_info, query_config, query_liquidation_amount,
+    query_simulate_execute_bid,
 };
 use crate::state::{
-    read_collateral_info, read_config, store_collateral_info, store_config, CollateralInfo, Config, read_new_owner, store_new_owner, NewOwnerAddr,
+    index_bids_by_wait_end, read_bid_pool, read_collateral_info, read_collateral_infos,
+    read_config, start_wait_end_indexing, store_bid_pool, store_collateral_info, store_config,
+    CollateralInfo, Config, PoolKey, read_new_owner, store_new_owner, NewOwnerAddr,
 };
 use cdp::querier::query_collateral_whitelist_info;
+use cdp::events::{config_change_event, MODULE_LIQUIDATION_QUEUE};
+use cdp::migration::{assert_legacy_deployment, assert_upgrade};
+use cdp::rbac::{
+    execute_grant_role, execute_pause, execute_revoke_role, execute_unpause, grant_role,
+    has_any_role, query_has_role, query_paused, query_role_members, revoke_role, Role,
+};
+use cdp::timelock::{
+    execute_cancel_change, execute_matured_change, execute_propose_change, query_pending_change,
+    query_pending_changes, query_timelock_config, store_timelock_config, update_timelock_config,
+    TimelockConfig,
+};
 
 use crate::error::ContractError;
-use cdp::liquidation_queue::{Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg, MigrateMsg};
+use cdp::liquidation_queue::{
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, PremiumSchedule, QueryMsg,
+};
 use cosmwasm_bignumber::{Decimal256, Uint256};
+use cw2::set_contract_version;
 use cosmwasm_std::{
-    from_binary, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError, StdResult, Addr,
+    attr, from_binary, to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError,
+    StdResult, Storage, Api, CanonicalAddr,
 };
 use cw20::Cw20ReceiveMsg;
 
+// version info for migration
+const CONTRACT_NAME: &str = "crates.io:cdp-liquidation-queue";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -29,6 +57,14 @@ pub fn instantiate(
 ) -> StdResult<Response> {
     assert_fees(msg.liquidator_fee + msg.bid_fee)?;
     assert_safe_ratio(msg.safe_ratio)?;
+    let close_factor = msg.close_factor.unwrap_or_else(Decimal256::one);
+    assert_close_factor(close_factor)?;
+    let prune_tip_rate = msg.prune_tip_rate.unwrap_or_default();
+    assert_prune_tip_rate(prune_tip_rate)?;
+    let swap_adapter = msg
+        .swap_adapter
+        .map(|swap_adapter| deps.api.addr_canonicalize(&swap_adapter))
+        .transpose()?;
     
     store_config(
         deps.storage,
@@ -43,6 +79,10 @@ pub fn instantiate(
             price_timeframe: msg.price_timeframe,
             waiting_period: msg.waiting_period,
             control_contract: deps.api.addr_canonicalize(&msg.control_contract)?,
+            close_factor,
+            prune_tip_rate,
+            min_limit_bid_amount: msg.min_limit_bid_amount.unwrap_or_default(),
+            swap_adapter,
         },
     )?;
     
@@ -52,6 +92,22 @@ pub fn instantiate(
         }
     })?;
 
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    store_timelock_config(
+        deps.storage,
+        &TimelockConfig {
+            delay: msg.timelock_delay,
+        },
+    )?;
+
+    grant_role(deps.storage, Role::Admin, &deps.api.addr_canonicalize(&msg.owner)?)?;
+    grant_role(
+        deps.storage,
+        Role::Guardian,
+        &deps.api.addr_canonicalize(&msg.guardian_addr)?,
+    )?;
+
     Ok(Response::new())
 }
 
@@ -64,39 +120,50 @@ pub fn execute(
 ) -> Result<Response, ContractError> {
     match msg {
         ExecuteMsg::Receive(msg) => receive_cw20(deps, env, info, msg),
-        ExecuteMsg::UpdateConfig {
-            oracle_contract,
-            safe_ratio,
-            bid_fee,
-            liquidator_fee,
-            liquidation_threshold,
-            price_timeframe,
-            waiting_period,
-            control_contract,
-            stable_denom,
-        } => update_config(
-            deps,
-            info,
-            oracle_contract,
-            safe_ratio,
-            bid_fee,
-            liquidator_fee,
-            liquidation_threshold,
-            price_timeframe,
-            waiting_period,
-            control_contract,
-            stable_denom,
-        ),
-        ExecuteMsg::SetOwner { new_owner_addr } => {
-            let api = deps.api;
-            set_new_owner(deps, info, api.addr_validate(&new_owner_addr)?)
+        ExecuteMsg::UpdateConfig { .. }
+        | ExecuteMsg::UpdateTimelockConfig { .. }
+        | ExecuteMsg::SetOwner { .. }
+        | ExecuteMsg::GrantRole {
+            role: Role::Admin | Role::Guardian,
+            ..
+        } => propose_timelocked_change(deps, env, info, msg),
+        ExecuteMsg::ExecuteChange { id } => execute_timelocked_change(deps, env, info, id),
+        ExecuteMsg::CancelChange { id } => {
+            execute_cancel_change(deps, info, MODULE_LIQUIDATION_QUEUE, id)
         }
         ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => {
+            let api = deps.api;
+            execute_grant_role(
+                deps,
+                info,
+                MODULE_LIQUIDATION_QUEUE,
+                role,
+                api.addr_validate(&address)?,
+            )
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
+            let api = deps.api;
+            execute_revoke_role(
+                deps,
+                info,
+                MODULE_LIQUIDATION_QUEUE,
+                role,
+                api.addr_validate(&address)?,
+            )
+        }
+        ExecuteMsg::Pause {} => execute_pause(deps, info, MODULE_LIQUIDATION_QUEUE),
+        ExecuteMsg::Unpause {} => execute_unpause(deps, info, MODULE_LIQUIDATION_QUEUE),
         ExecuteMsg::WhitelistCollateral {
             collateral_token,
             bid_threshold,
             max_slot,
             premium_rate_per_slot,
+            premium_schedule,
+            bid_fee,
+            liquidator_fee,
+            waiting_period,
+            liquidation_threshold,
         } => whitelist_collateral(
             deps,
             info,
@@ -104,25 +171,85 @@ pub fn execute(
             bid_threshold,
             max_slot,
             premium_rate_per_slot,
+            premium_schedule,
+            bid_fee,
+            liquidator_fee,
+            waiting_period,
+            liquidation_threshold,
         ),
         ExecuteMsg::UpdateCollateralInfo {
             collateral_token,
             bid_threshold,
             max_slot,
-        } => update_collateral_info(deps, info, collateral_token, bid_threshold, max_slot),
+            premium_schedule,
+            bid_fee,
+            liquidator_fee,
+            waiting_period,
+            liquidation_threshold,
+        } => update_collateral_info(
+            deps,
+            info,
+            collateral_token,
+            bid_threshold,
+            max_slot,
+            premium_schedule,
+            bid_fee,
+            liquidator_fee,
+            waiting_period,
+            liquidation_threshold,
+        ),
         ExecuteMsg::SubmitBid {
             collateral_token,
             premium_slot,
-        } => submit_bid(deps, env, info, collateral_token, premium_slot),
+            expires_at,
+            max_price,
+            auto_rebid,
+        } => submit_bid(
+            deps,
+            env,
+            info,
+            collateral_token,
+            premium_slot,
+            expires_at,
+            max_price,
+            auto_rebid,
+        ),
         ExecuteMsg::ActivateBids {
             collateral_token,
             bids_idx,
         } => activate_bids(deps, env, info, collateral_token, bids_idx),
+        ExecuteMsg::ActivateBidsFor {
+            collateral_token,
+            limit,
+        } => activate_bids_for(deps, env, info, collateral_token, limit),
         ExecuteMsg::RetractBid { bid_idx, amount } => retract_bid(deps, env, info, bid_idx, amount),
         ExecuteMsg::ClaimLiquidations {
             collateral_token,
             bids_idx,
         } => claim_liquidations(deps, env, info, collateral_token, bids_idx),
+        ExecuteMsg::PruneExpiredBids {
+            collateral_token,
+            limit,
+        } => prune_expired_bids(deps, env, info, collateral_token, limit),
+        ExecuteMsg::IndexWaitingBids { limit } => index_waiting_bids(deps, limit),
+        ExecuteMsg::Rebid {
+            collateral_token,
+            bids_idx,
+        } => rebid(deps, env, info, collateral_token, bids_idx),
+        ExecuteMsg::RebidSwapped {
+            collateral_token,
+            prev_balance,
+            min_receive,
+            rebids,
+        } => {
+            if info.sender != env.contract.address {
+                return Err(ContractError::Unauthorized(
+                    "rebid_swapped".to_string(),
+                    info.sender.to_string(),
+                ));
+            }
+            rebid_swapped(deps, env, collateral_token, prev_balance, min_receive, rebids)
+        }
     }
 }
 
@@ -160,28 +287,15 @@ pub fn receive_cw20(
     }
 }
 
-
-
-pub fn set_new_owner(
-    deps: DepsMut,
-    info: MessageInfo,
-    new_owner_addr: Addr,
-) -> Result<Response, ContractError> {
-    let config = read_config(deps.as_ref().storage)?;
-    let mut new_owner = read_new_owner(deps.as_ref().storage)?;
-    let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
-    if sender_raw != config.owner {
-        return Err(ContractError::Unauthorized(
-            "set_new_owner".to_string(),
-            info.sender.to_string(),
-        ));
-    }
-    new_owner.new_owner_addr = deps.api.addr_canonicalize(&new_owner_addr.to_string())?;
-    store_new_owner(deps.storage, &new_owner)?;
-
-    Ok(Response::default())
+/// Applied by a matured `SetOwner` change, the new owner still has to accept
+pub fn set_new_owner(storage: &mut dyn Storage, new_owner_addr: CanonicalAddr) -> StdResult<()> {
+    let mut new_owner = read_new_owner(storage)?;
+    new_owner.new_owner_addr = new_owner_addr;
+    store_new_owner(storage, &new_owner)
 }
 
+/// Only the admin role of the previous owner is revoked, admins granted apart from the
+/// ownership keep their role until revoked
 pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
     let new_owner = read_new_owner(deps.as_ref().storage)?;
     let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
@@ -193,16 +307,151 @@ pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, Co
         ));
     }
 
+    revoke_role(deps.storage, Role::Admin, &config.owner);
+    grant_role(deps.storage, Role::Admin, &new_owner.new_owner_addr)?;
     config.owner = new_owner.new_owner_addr;
     store_config(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attributes(vec![
+            ("action", "accept_ownership".to_string()),
+            ("owner", info.sender.to_string()),
+        ])
+        .add_event(config_change_event(MODULE_LIQUIDATION_QUEUE, "accept_ownership", info.sender)))
+}
+
+/// Roles allowed to propose and execute a timelocked change,
+/// changes touching only fee parameters are open to fee managers
+fn change_roles(msg: &ExecuteMsg) -> &'static [Role] {
+    match msg {
+        ExecuteMsg::UpdateConfig {
+            oracle_contract: None,
+            safe_ratio: None,
+            liquidation_threshold: None,
+            price_timeframe: None,
+            waiting_period: None,
+            control_contract: None,
+            stable_denom: None,
+            close_factor: None,
+            swap_adapter: None,
+            ..
+        } => &[Role::Admin, Role::FeeManager],
+        _ => &[Role::Admin],
+    }
+}
+
+/// Admin queues a config change, it can be executed once the timelock delay has passed
+pub fn propose_timelocked_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    validate_change(deps.api, &msg)?;
+    let roles = change_roles(&msg);
+    execute_propose_change(deps, &env, info, MODULE_LIQUIDATION_QUEUE, roles, to_binary(&msg)?)
+}
+
+pub fn execute_timelocked_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    execute_matured_change(
+        deps,
+        &env,
+        info,
+        MODULE_LIQUIDATION_QUEUE,
+        id,
+        change_roles,
+        apply_change,
+    )
+}
+
+// addresses are validated when a change is proposed rather than once its delay has passed
+fn validate_change(api: &dyn Api, msg: &ExecuteMsg) -> StdResult<()> {
+    if let ExecuteMsg::UpdateConfig {
+        oracle_contract,
+        control_contract,
+        swap_adapter,
+        ..
+    } = msg
+    {
+        let addrs = [oracle_contract, control_contract, swap_adapter];
+        for addr in addrs.iter().copied().flatten() {
+            api.addr_validate(addr)?;
+        }
+    }
+    if let ExecuteMsg::SetOwner {
+        new_owner_addr: address,
+    }
+    | ExecuteMsg::GrantRole { address, .. } = msg
+    {
+        api.addr_validate(address)?;
+    }
+    Ok(())
+}
+
+fn apply_change(deps: DepsMut, msg: ExecuteMsg) -> Result<&'static str, ContractError> {
+    let action = match msg {
+        ExecuteMsg::UpdateConfig {
+            oracle_contract,
+            safe_ratio,
+            bid_fee,
+            liquidator_fee,
+            liquidation_threshold,
+            price_timeframe,
+            waiting_period,
+            control_contract,
+            stable_denom,
+            close_factor,
+            prune_tip_rate,
+            min_limit_bid_amount,
+            swap_adapter,
+        } => {
+            update_config(
+                deps,
+                oracle_contract,
+                safe_ratio,
+                bid_fee,
+                liquidator_fee,
+                liquidation_threshold,
+                price_timeframe,
+                waiting_period,
+                control_contract,
+                stable_denom,
+                close_factor,
+                prune_tip_rate,
+                min_limit_bid_amount,
+                swap_adapter,
+            )?;
+            "update_config"
+        }
+        ExecuteMsg::UpdateTimelockConfig { delay } => {
+            update_timelock_config(deps.storage, delay)?;
+            "update_timelock_config"
+        }
+        ExecuteMsg::SetOwner { new_owner_addr } => {
+            set_new_owner(deps.storage, deps.api.addr_canonicalize(&new_owner_addr)?)?;
+            "set_new_owner"
+        }
+        ExecuteMsg::GrantRole { role, address } => {
+            grant_role(deps.storage, role, &deps.api.addr_canonicalize(&address)?)?;
+            "grant_role"
+        }
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Unsupported timelocked change",
+            )))
+        }
+    };
+    Ok(action)
 }
 
 #[allow(clippy::too_many_arguments)]
 pub fn update_config(
     deps: DepsMut,
-    info: MessageInfo,
     oracle_contract: Option<String>,
     safe_ratio: Option<Decimal256>,
     bid_fee: Option<Decimal256>,
@@ -212,15 +461,12 @@ pub fn update_config(
     waiting_period: Option<u64>,
     control_contract: Option<String>,
     stable_denom: Option<String>,
-) -> Result<Response, ContractError> {
+    close_factor: Option<Decimal256>,
+    prune_tip_rate: Option<Decimal256>,
+    min_limit_bid_amount: Option<Uint256>,
+    swap_adapter: Option<String>,
+) -> Result<(), ContractError> {
     let mut config: Config = read_config(deps.storage)?;
-    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
-        return Err(ContractError::Unauthorized(
-            "update_config".to_string(),
-            info.sender.to_string(),
-        ));
-    }
-
 
     if let Some(oracle_contract) = oracle_contract {
         config.oracle_contract = deps.api.addr_canonicalize(&oracle_contract)?;
@@ -260,10 +506,29 @@ pub fn update_config(
         config.stable_denom = stable_denom;
     }
 
+    if let Some(close_factor) = close_factor {
+        assert_close_factor(close_factor)?;
+        config.close_factor = close_factor;
+    }
+
+    if let Some(prune_tip_rate) = prune_tip_rate {
+        assert_prune_tip_rate(prune_tip_rate)?;
+        config.prune_tip_rate = prune_tip_rate;
+    }
+
+    if let Some(min_limit_bid_amount) = min_limit_bid_amount {
+        config.min_limit_bid_amount = min_limit_bid_amount;
+    }
+
+    if let Some(swap_adapter) = swap_adapter {
+        config.swap_adapter = Some(deps.api.addr_canonicalize(&swap_adapter)?);
+    }
+
     store_config(deps.storage, &config)?;
-    Ok(Response::new())
+    Ok(())
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn whitelist_collateral(
     deps: DepsMut,
     info: MessageInfo,
@@ -271,11 +536,17 @@ pub fn whitelist_collateral(
     bid_threshold: Uint256,
     max_slot: u8,
     premium_rate_per_slot: Decimal256,
+    premium_schedule: Option<PremiumSchedule>,
+    bid_fee: Option<Decimal256>,
+    liquidator_fee: Option<Decimal256>,
+    waiting_period: Option<u64>,
+    liquidation_threshold: Option<Uint256>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
 
     let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
-    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !has_any_role(deps.storage, &[Role::Admin, Role::RiskManager], &sender_raw)? {
         return Err(ContractError::Unauthorized(
             "whitelist_collateral".to_string(),
             info.sender.to_string(),
@@ -302,35 +573,52 @@ pub fn whitelist_collateral(
         ))
     })?;
 
+    let collateral_info = CollateralInfo {
+        collateral_token: collateral_token_raw.clone(),
+        max_slot,
+        bid_threshold,
+        premium_rate_per_slot,
+        premium_schedule,
+        bid_fee,
+        liquidator_fee,
+        waiting_period,
+        liquidation_threshold,
+    };
+
     // assert max slot does not exceed cap and max premium rate does not exceed 1
     assert_max_slot(max_slot)?;
-    assert_max_slot_premium(max_slot, premium_rate_per_slot)?;
+    assert_premium_schedule(max_slot, &collateral_info.premium_schedule())?;
+    assert_fees(collateral_info.bid_fee(&config) + collateral_info.liquidator_fee(&config))?;
 
     // save collateral info
-    store_collateral_info(
-        deps.storage,
-        &collateral_token_raw,
-        &CollateralInfo {
-            collateral_token: collateral_token_raw.clone(),
-            max_slot,
-            bid_threshold,
-            premium_rate_per_slot,
-        },
-    )?;
+    store_collateral_info(deps.storage, &collateral_token_raw, &collateral_info)?;
 
-    Ok(Response::new().add_attribute("action", "whitelist_collateral"))
+    Ok(Response::new()
+        .add_attribute("action", "whitelist_collateral")
+        .add_event(config_change_event(
+            MODULE_LIQUIDATION_QUEUE,
+            "whitelist_collateral",
+            info.sender,
+        )))
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn update_collateral_info(
     deps: DepsMut,
     info: MessageInfo,
     collateral_token: String,
     bid_threshold: Option<Uint256>,
     max_slot: Option<u8>,
+    premium_schedule: Option<PremiumSchedule>,
+    bid_fee: Option<Decimal256>,
+    liquidator_fee: Option<Decimal256>,
+    waiting_period: Option<u64>,
+    liquidation_threshold: Option<Uint256>,
 ) -> Result<Response, ContractError> {
     let config: Config = read_config(deps.storage)?;
     let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
-    if deps.api.addr_canonicalize(info.sender.as_str())? != config.owner {
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !has_any_role(deps.storage, &[Role::Admin, Role::RiskManager], &sender_raw)? {
         return Err(ContractError::Unauthorized(
             "update_collateral_info".to_string(),
             info.sender.to_string(),
@@ -345,21 +633,127 @@ pub fn update_collateral_info(
         collateral_info.bid_threshold = bid_threshold;
     }
 
+    // pools above a lowered max slot keep their bids until retracted
+    let rerate_max_slot = collateral_info.max_slot.max(max_slot.unwrap_or_default());
     if let Some(max_slot) = max_slot {
-        // assert max slot does not exceed cap and max premium rate does not exceed 1
-        assert_max_slot(max_slot)?;
-        assert_max_slot_premium(max_slot, collateral_info.premium_rate_per_slot)?;
         collateral_info.max_slot = max_slot;
     }
 
+    let rerate = premium_schedule.is_some();
+    if premium_schedule.is_some() {
+        collateral_info.premium_schedule = premium_schedule;
+    }
+
+    // assert max slot does not exceed cap and max premium rate does not exceed 1
+    assert_max_slot(collateral_info.max_slot)?;
+    assert_premium_schedule(collateral_info.max_slot, &collateral_info.premium_schedule())?;
+
+    if bid_fee.is_some() {
+        collateral_info.bid_fee = bid_fee;
+    }
+
+    if liquidator_fee.is_some() {
+        collateral_info.liquidator_fee = liquidator_fee;
+    }
+    assert_fees(collateral_info.bid_fee(&config) + collateral_info.liquidator_fee(&config))?;
+
+    if waiting_period.is_some() {
+        collateral_info.waiting_period = waiting_period;
+    }
+
+    if liquidation_threshold.is_some() {
+        collateral_info.liquidation_threshold = liquidation_threshold;
+    }
+
     // save collateral info
     store_collateral_info(deps.storage, &collateral_token_raw, &collateral_info)?;
 
-    Ok(Response::new().add_attribute("action", "update_collateral_info"))
+    // existing pools move to the new premium rates, their bids are filled at
+    // the new rate from the next liquidation on
+    let mut attributes = vec![attr("action", "update_collateral_info")];
+    if rerate {
+        let rerated_slots = rerate_bid_pools(deps.storage, &collateral_info, rerate_max_slot)?;
+        attributes.push(attr(
+            "rerated_slots",
+            rerated_slots
+                .iter()
+                .map(|slot| slot.to_string())
+                .collect::<Vec<String>>()
+                .join(","),
+        ));
+    }
+
+    Ok(Response::new()
+        .add_attributes(attributes)
+        .add_event(config_change_event(
+            MODULE_LIQUIDATION_QUEUE,
+            "update_collateral_info",
+            info.sender,
+        )))
+}
+
+/// Set the premium rate of the existing bid pools from the premium schedule of
+/// the collateral, returns the slots of the pools whose rate changed. Limit bid
+/// pools take the rate of their slot when liquidated
+fn rerate_bid_pools(
+    storage: &mut dyn Storage,
+    collateral_info: &CollateralInfo,
+    max_slot: u8,
+) -> StdResult<Vec<u8>> {
+    let mut rerated_slots: Vec<u8> = vec![];
+    for premium_slot in 0..max_slot + 1 {
+        let collateral_token = &collateral_info.collateral_token;
+        let slot_key = PoolKey::slot(premium_slot);
+        let mut bid_pool = match read_bid_pool(storage, collateral_token, &slot_key) {
+            Ok(bid_pool) => bid_pool,
+            Err(_) => continue,
+        };
+
+        let premium_rate = collateral_info.premium_rate(premium_slot);
+        if bid_pool.premium_rate != premium_rate {
+            bid_pool.premium_rate = premium_rate;
+            store_bid_pool(storage, collateral_token, &slot_key, &bid_pool)?;
+            rerated_slots.push(premium_slot);
+        }
+    }
+
+    Ok(rerated_slots)
+}
+
+pub fn index_waiting_bids(deps: DepsMut, limit: Option<u8>) -> Result<Response, ContractError> {
+    let done = index_bids_by_wait_end(deps.storage, limit)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "index_waiting_bids"),
+        attr("done", done.to_string()),
+    ]))
+}
+
+/// Give collaterals without a premium schedule the explicit schedule of the
+/// premium rates of their bid pools, slots without a pool keep the linear rate
+fn migrate_premium_schedules(storage: &mut dyn Storage) -> StdResult<()> {
+    for mut collateral_info in read_collateral_infos(storage)? {
+        if collateral_info.premium_schedule.is_some() {
+            continue;
+        }
+
+        let collateral_token = collateral_info.collateral_token.clone();
+        let rates = (0..collateral_info.max_slot + 1)
+            .map(|premium_slot| {
+                read_bid_pool(storage, &collateral_token, &PoolKey::slot(premium_slot))
+                    .map(|bid_pool| bid_pool.premium_rate)
+                    .unwrap_or_else(|_| collateral_info.premium_rate(premium_slot))
+            })
+            .collect();
+        collateral_info.premium_schedule = Some(PremiumSchedule::Explicit { rates });
+        store_collateral_info(storage, &collateral_token, &collateral_info)?;
+    }
+
+    Ok(())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::LiquidationAmount {
@@ -369,6 +763,7 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             collateral_prices,
         } => to_binary(&query_liquidation_amount(
             deps,
+            env,
             borrow_amount,
             borrow_limit,
             collaterals,
@@ -390,6 +785,29 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             start_after,
             limit,
         )?),
+        QueryMsg::SimulateExecuteBid {
+            collateral_token,
+            amount,
+            price,
+        } => to_binary(&query_simulate_execute_bid(
+            deps,
+            env,
+            collateral_token,
+            amount,
+            price,
+        )?),
+        QueryMsg::BidDepth {
+            collateral_token,
+            price,
+        } => to_binary(&query_bid_depth(deps, env, collateral_token, price)?),
+        QueryMsg::ClaimableLiquidations {
+            bidder,
+            collateral_token,
+        } => to_binary(&query_claimable_liquidations(
+            deps,
+            bidder,
+            collateral_token,
+        )?),
         QueryMsg::BidPool {
             collateral_token,
             bid_slot,
@@ -404,11 +822,54 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
             start_after,
             limit,
         )?),
+        QueryMsg::TimelockConfig {} => to_binary(&query_timelock_config(deps)?),
+        QueryMsg::PendingChange { id } => to_binary(&query_pending_change(deps, id)?),
+        QueryMsg::PendingChanges { start_after, limit } => {
+            to_binary(&query_pending_changes(deps, start_after, limit)?)
+        }
+        QueryMsg::HasRole { role, address } => to_binary(&query_has_role(deps, role, address)?),
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&query_role_members(deps, role, start_after, limit)?),
+        QueryMsg::Paused {} => to_binary(&query_paused(deps)?),
     }
 }
 
-
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    match msg {
+        MigrateMsg::FromLegacy {
+            guardian_addr,
+            timelock_delay,
+        } => {
+            assert_legacy_deployment(deps.storage)?;
+            let config = read_config(deps.storage)?;
+            store_timelock_config(
+                deps.storage,
+                &TimelockConfig {
+                    delay: timelock_delay,
+                },
+            )?;
+            grant_role(deps.storage, Role::Admin, &config.owner)?;
+            grant_role(
+                deps.storage,
+                Role::Guardian,
+                &deps.api.addr_canonicalize(&guardian_addr)?,
+            )?;
+        }
+        MigrateMsg::Upgrade {} => {
+            assert_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        }
+    }
+
+    // the remaining pages are indexed through IndexWaitingBids
+    migrate_premium_schedules(deps.storage)?;
+    start_wait_end_indexing(deps.storage)?;
+    index_bids_by_wait_end(deps.storage, None)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![("action", "migrate"), ("version", CONTRACT_VERSION)]))
 }
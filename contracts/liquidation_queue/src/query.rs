@@ -1,15 +1,19 @@
 
-use crate::bid::{calculate_liquidated_collateral, calculate_remaining_bid};
+use crate::bid::{calculate_liquidated_collateral, calculate_remaining_bid, compute_pool_fill};
 use cdp::querier::query_collateral_whitelist_info;
 use crate::state::{
     read_bid, read_bid_pool, read_bid_pools, read_bids_by_user, read_collateral_info, read_config,
-    read_total_bids, Bid, BidPool, CollateralInfo, Config,
+    read_expired_bids, read_next_active_limit_bid_pool, read_waiting_bids, Bid, BidPool,
+    CollateralInfo, Config, PoolKey,
 };
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{CanonicalAddr, Deps, StdResult, Uint128};
+use cosmwasm_std::{CanonicalAddr, Deps, Env, StdResult, Uint128};
+use std::collections::HashMap;
 use cdp::liquidation_queue::{
-    BidPoolResponse, BidPoolsResponse, BidResponse, BidsResponse, CollateralInfoResponse,
-    ConfigResponse, LiquidationAmountResponse,
+    BidDepthResponse, BidDepthSlotResponse, BidPoolResponse, BidPoolsResponse, BidResponse,
+    BidsResponse, ClaimableBidResponse,
+    ClaimableLiquidationsResponse, CollateralInfoResponse, ConfigResponse,
+    LiquidationAmountResponse, SimulateExecuteBidResponse, SlotFillResponse, WaitingBidResponse,
 };
 
 use cdp::tokens::TokensHuman;
@@ -27,6 +31,14 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
         price_timeframe: config.price_timeframe,
         waiting_period: config.waiting_period,
         control_contract: deps.api.addr_humanize(&config.control_contract)?.to_string(),
+        close_factor: config.close_factor,
+        prune_tip_rate: config.prune_tip_rate,
+        min_limit_bid_amount: config.min_limit_bid_amount,
+        swap_adapter: config
+            .swap_adapter
+            .map(|swap_adapter| deps.api.addr_humanize(&swap_adapter))
+            .transpose()?
+            .map(|swap_adapter| swap_adapter.to_string()),
     };
 
     Ok(resp)
@@ -40,6 +52,7 @@ pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
 /// available bids at different premium rates
 pub fn query_liquidation_amount(
     deps: Deps,
+    env: Env,
     borrow_amount: Uint256,
     borrow_limit: Uint256,
     collaterals: TokensHuman,
@@ -61,20 +74,10 @@ pub fn query_liquidation_amount(
 
     // calculate value of all collaterals and weights
     let (collaterals_value, total_weight, collateral_weights, max_ltvs) =
-        compute_collateral_weights(deps, control_contract, &collaterals, &collateral_prices)?;
+        compute_collateral_weights(deps, &env, control_contract, &collaterals, &collateral_prices)?;
 
      
 
-    // check partial liquidation condition
-    let safe_ratio = if collaterals_value <= config.liquidation_threshold {
-        Decimal256::zero()
-    } else {
-        config.safe_ratio
-    };
-
-    let base_fee_deductor =
-        (Decimal256::one() - config.bid_fee) * (Decimal256::one() - config.liquidator_fee);
-
     let mut result: Vec<(String, Uint256)> = vec![];
     for (i, collateral) in collaterals.iter().enumerate() {
         let (price, weight, max_ltv) = (collateral_prices[i], collateral_weights[i], max_ltvs[i]);
@@ -82,20 +85,47 @@ pub fn query_liquidation_amount(
         let collateral_token_raw = deps.api.addr_canonicalize(&collateral.0)?;
         let collateral_info = read_collateral_info(deps.storage, &collateral_token_raw)?;
 
+        // check partial liquidation condition against the threshold of the collateral,
+        // the close factor only limits partial liquidations
+        let (safe_ratio, close_factor) =
+            if collaterals_value <= collateral_info.liquidation_threshold(&config) {
+                (Decimal256::zero(), Decimal256::one())
+            } else {
+                (config.safe_ratio, config.close_factor)
+            };
+
+        let base_fee_deductor = (Decimal256::one() - collateral_info.bid_fee(&config))
+            * (Decimal256::one() - collateral_info.liquidator_fee(&config));
+
         // calculate borrow amount and limit portion
         let position_portion =
             Decimal256::from_uint256(weight) / Decimal256::from_uint256(total_weight);
         let collateral_borrow_amount = borrow_amount * position_portion;
         let collateral_borrow_limit = borrow_limit * position_portion;
 
+        // collateral amount whose sale repays the close factor portion of the loan, found
+        // below at the discounted price of the slots the sale fills
+        let max_repay_amount = close_factor * collateral_borrow_amount;
+        let mut max_liquidation_amount = collateral.1;
+
         // iterate bid pools until safe ratio condition is met (intersection f(x) and g(x))
         let mut x = Uint256::zero();
         let mut g_x = Uint256::zero();
         let mut intersected = false;
         for slot in 0..collateral_info.max_slot + 1 {
             let (slot_available_bids, premium_rate) =
-                match read_bid_pool(deps.storage, &collateral_token_raw, slot) {
-                    Ok(bid_pool) => (bid_pool.total_bid_amount, bid_pool.premium_rate),
+                match read_bid_pool(deps.storage, &collateral_token_raw, &PoolKey::slot(slot)) {
+                    Ok(bid_pool) => (
+                        read_eligible_bid_amount(
+                            deps,
+                            &env,
+                            &collateral_token_raw,
+                            slot,
+                            &bid_pool,
+                            price,
+                        )?,
+                        bid_pool.premium_rate,
+                    ),
                     Err(_) => continue,
                 };
             if slot_available_bids.is_zero() {
@@ -106,6 +136,12 @@ pub fn query_liquidation_amount(
             let prev_g_x = g_x;
 
             let discounted_price = price * (Decimal256::one() - premium_rate) * base_fee_deductor;
+            if close_factor < Decimal256::one()
+                && prev_g_x < max_repay_amount
+                && prev_g_x + slot_available_bids >= max_repay_amount
+            {
+                max_liquidation_amount = prev_x + (max_repay_amount - prev_g_x) / discounted_price;
+            }
             x += slot_available_bids / discounted_price;
 
             let safe_borrow = safe_ratio * collateral_borrow_limit;
@@ -124,7 +160,7 @@ pub fn query_liquidation_amount(
 
                 result.push((
                     collateral.0.to_string(),
-                    liquidation_amount.min(collateral.1),
+                    liquidation_amount.min(collateral.1).min(max_liquidation_amount),
                 ));
                 intersected = true;
                 break;
@@ -134,7 +170,8 @@ pub fn query_liquidation_amount(
         //      1. Not enough bids. Should try to liquidate as much as possible
         //      2. Not enouugh collateral. Also liquidate all collateral
         if !intersected {
-            result.push((collateral.0.to_string(), x)); // liquidate all collateral possible
+            // liquidate all collateral possible
+            result.push((collateral.0.to_string(), x.min(max_liquidation_amount)));
         }
     }
 
@@ -143,11 +180,168 @@ pub fn query_liquidation_amount(
     })
 }
 
+/// Runs the slot by slot fill of `execute_liquidation` on the eligible bids
+pub fn query_simulate_execute_bid(
+    deps: Deps,
+    env: Env,
+    collateral_token: String,
+    amount: Uint256,
+    price: Decimal256,
+) -> StdResult<SimulateExecuteBidResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
+    let collateral_info: CollateralInfo =
+        read_collateral_info(deps.storage, &collateral_token_raw)?;
+
+    let mut remaining_collateral_to_liquidate = amount;
+    let mut repay_amount = Uint256::zero();
+    let mut slots: Vec<SlotFillResponse> = vec![];
+    let mut filled = false;
+    for slot in 0..collateral_info.max_slot + 1 {
+        let slot_key = PoolKey::slot(slot);
+        let bid_pool: BidPool = match read_bid_pool(deps.storage, &collateral_token_raw, &slot_key)
+        {
+            Ok(bid_pool) => bid_pool,
+            Err(_) => continue,
+        };
+        let total_bid_amount =
+            read_eligible_bid_amount(deps, &env, &collateral_token_raw, slot, &bid_pool, price)?;
+        if total_bid_amount.is_zero() {
+            continue;
+        }
+
+        let (pool_repay_amount, pool_liquidated_collateral, pool_filled) = compute_pool_fill(
+            total_bid_amount,
+            bid_pool.premium_rate,
+            remaining_collateral_to_liquidate,
+            price,
+        );
+        repay_amount += pool_repay_amount;
+        slots.push(SlotFillResponse {
+            premium_slot: slot,
+            bid_amount: pool_repay_amount,
+            collateral_amount: pool_liquidated_collateral,
+        });
+
+        if pool_filled {
+            filled = true;
+            break;
+        }
+        remaining_collateral_to_liquidate =
+            remaining_collateral_to_liquidate - pool_liquidated_collateral;
+    }
+
+    let bid_fee = repay_amount * collateral_info.bid_fee(&config);
+    let liquidator_fee = repay_amount * collateral_info.liquidator_fee(&config);
+
+    Ok(SimulateExecuteBidResponse {
+        repay_amount: repay_amount - bid_fee - liquidator_fee,
+        bid_fee,
+        liquidator_fee,
+        slots,
+        filled,
+    })
+}
+
+pub fn query_bid_depth(
+    deps: Deps,
+    env: Env,
+    collateral_token: String,
+    price: Decimal256,
+) -> StdResult<BidDepthResponse> {
+    let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
+    let collateral_info: CollateralInfo =
+        read_collateral_info(deps.storage, &collateral_token_raw)?;
+
+    // expired bids in waiting state can not be activated anymore
+    let mut expired_waiting_bids: HashMap<u8, Uint256> = HashMap::new();
+    let mut start_after: Option<Bid> = None;
+    loop {
+        let page = read_expired_bids(
+            deps.storage,
+            &collateral_token_raw,
+            env.block.time.seconds(),
+            start_after.as_ref(),
+            None,
+        )?;
+        for bid in page.iter().filter(|bid| bid.wait_end.is_some()) {
+            *expired_waiting_bids.entry(bid.premium_slot).or_default() += bid.amount;
+        }
+        match page.last() {
+            Some(last) => start_after = Some(last.clone()),
+            None => break,
+        }
+    }
+
+    let mut cumulative_collateral = Uint256::zero();
+    let mut slots: Vec<BidDepthSlotResponse> = vec![];
+    for slot in 0..collateral_info.max_slot + 1 {
+        let slot_key = PoolKey::slot(slot);
+        let bid_pool: BidPool = match read_bid_pool(deps.storage, &collateral_token_raw, &slot_key)
+        {
+            Ok(bid_pool) => bid_pool,
+            Err(_) => continue,
+        };
+
+        let active_amount =
+            read_eligible_bid_amount(deps, &env, &collateral_token_raw, slot, &bid_pool, price)?;
+        let purchase_price = price * (Decimal256::one() - bid_pool.premium_rate);
+        if !purchase_price.is_zero() {
+            cumulative_collateral += active_amount / purchase_price;
+        }
+
+        let waiting_amount = read_waiting_bids(deps.storage, &collateral_token_raw, slot)?;
+        let expired_waiting_amount = expired_waiting_bids.get(&slot).copied().unwrap_or_default();
+
+        slots.push(BidDepthSlotResponse {
+            premium_slot: slot,
+            premium_rate: bid_pool.premium_rate,
+            purchase_price,
+            active_amount,
+            cumulative_collateral,
+            waiting_amount: waiting_amount - expired_waiting_amount.min(waiting_amount),
+        });
+    }
+
+    Ok(BidDepthResponse {
+        collateral_token,
+        price,
+        slots,
+    })
+}
+
+/// Amount of the bids of the premium slot an execution at `price` would fill, the
+/// bids of `slot_pool` and of the active limit bid pools whose max price is not below
+/// `price`, expired pools left out
+fn read_eligible_bid_amount(
+    deps: Deps,
+    env: &Env,
+    collateral_token: &CanonicalAddr,
+    premium_slot: u8,
+    slot_pool: &BidPool,
+    price: Decimal256,
+) -> StdResult<Uint256> {
+    let mut eligible_amount = slot_pool.total_bid_amount;
+    let mut pool_key = PoolKey::slot(premium_slot);
+    while let Some(next_pool_key) =
+        read_next_active_limit_bid_pool(deps.storage, collateral_token, &pool_key, price)?
+    {
+        if !next_pool_key.is_expired(env.block.time.seconds()) {
+            let bid_pool: BidPool =
+                read_bid_pool(deps.storage, collateral_token, &next_pool_key)?;
+            eligible_amount += bid_pool.total_bid_amount;
+        }
+        pool_key = next_pool_key;
+    }
+    Ok(eligible_amount)
+}
+
 /// The portion of collateral that liquidated from the available set is calculated
 /// based on weight = min(collateral_value, available_bids) / max_ltv
 #[allow(clippy::ptr_arg)]
 fn compute_collateral_weights(
     deps: Deps,
+    env: &Env,
     control_contract: String,
     collaterals: &TokensHuman,
     collateral_prices: &Vec<Decimal256>,
@@ -158,9 +352,22 @@ fn compute_collateral_weights(
     let mut max_ltvs: Vec<Decimal256> = vec![];
 
     for (collateral, price) in collaterals.iter().zip(collateral_prices.iter()) {
-        let collateral_available_bids =
-            read_total_bids(deps.storage, &deps.api.addr_canonicalize(&collateral.0)?)
-                .unwrap_or_default();
+        let collateral_token_raw = deps.api.addr_canonicalize(&collateral.0)?;
+        let collateral_info = read_collateral_info(deps.storage, &collateral_token_raw)?;
+        let mut collateral_available_bids = Uint256::zero();
+        for slot in 0..collateral_info.max_slot + 1 {
+            let slot_key = PoolKey::slot(slot);
+            if let Ok(bid_pool) = read_bid_pool(deps.storage, &collateral_token_raw, &slot_key) {
+                collateral_available_bids += read_eligible_bid_amount(
+                    deps,
+                    env,
+                    &collateral_token_raw,
+                    slot,
+                    &bid_pool,
+                    *price,
+                )?;
+            }
+        }
         let max_ltv = query_collateral_whitelist_info(
             deps,
             control_contract.to_string(),
@@ -187,7 +394,7 @@ fn compute_collateral_weights(
 
 pub fn query_bid(deps: Deps, bid_idx: Uint128) -> StdResult<BidResponse> {
     let bid: Bid = read_bid(deps.storage, bid_idx)?;
-    let bid_pool: BidPool = read_bid_pool(deps.storage, &bid.collateral_token, bid.premium_slot)?;
+    let bid_pool: BidPool = read_bid_pool(deps.storage, &bid.collateral_token, &PoolKey::of(&bid))?;
 
     let (bid_amount, bid_pending_liquidated_collateral) = if bid.wait_end.is_some() {
         (bid.amount, bid.pending_liquidated_collateral)
@@ -214,6 +421,9 @@ pub fn query_bid(deps: Deps, bid_idx: Uint128) -> StdResult<BidResponse> {
         product_snapshot: bid.product_snapshot,
         sum_snapshot: bid.sum_snapshot,
         wait_end: bid.wait_end,
+        expires_at: bid.expires_at,
+        max_price: bid.max_price,
+        auto_rebid: bid.auto_rebid,
         epoch_snapshot: bid.epoch_snapshot,
         scale_snapshot: bid.scale_snapshot,
     })
@@ -239,7 +449,7 @@ pub fn query_bids_by_user(
     .iter()
     .map(|bid| {
         let bid_pool: BidPool =
-            read_bid_pool(deps.storage, &bid.collateral_token, bid.premium_slot)?;
+            read_bid_pool(deps.storage, &bid.collateral_token, &PoolKey::of(bid))?;
         let (bid_amount, bid_pending_liquidated_collateral) = if bid.wait_end.is_some() {
             (bid.amount, bid.pending_liquidated_collateral)
         } else {
@@ -264,6 +474,9 @@ pub fn query_bids_by_user(
             product_snapshot: bid.product_snapshot,
             sum_snapshot: bid.sum_snapshot,
             wait_end: bid.wait_end,
+            expires_at: bid.expires_at,
+            max_price: bid.max_price,
+            auto_rebid: bid.auto_rebid.clone(),
             epoch_snapshot: bid.epoch_snapshot,
             scale_snapshot: bid.scale_snapshot,
         };
@@ -274,13 +487,77 @@ pub fn query_bids_by_user(
     Ok(BidsResponse { bids })
 }
 
+/// Same amounts as `claim_liquidations` would pay out, without the pool residues
+pub fn query_claimable_liquidations(
+    deps: Deps,
+    bidder: String,
+    collateral_token: String,
+) -> StdResult<ClaimableLiquidationsResponse> {
+    let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
+    let bidder_raw = deps.api.addr_canonicalize(&bidder)?;
+
+    let mut claimable_collateral = Uint256::zero();
+    let mut bids: Vec<ClaimableBidResponse> = vec![];
+    let mut waiting_bids: Vec<WaitingBidResponse> = vec![];
+    let mut start_after: Option<Uint128> = None;
+    loop {
+        let page = read_bids_by_user(
+            deps.storage,
+            &collateral_token_raw,
+            &bidder_raw,
+            start_after,
+            None,
+        )?;
+        match page.last() {
+            Some(last) => start_after = Some(last.idx),
+            None => break,
+        }
+
+        for bid in page.iter() {
+            if let Some(wait_end) = bid.wait_end {
+                waiting_bids.push(WaitingBidResponse {
+                    idx: bid.idx,
+                    premium_slot: bid.premium_slot,
+                    amount: bid.amount,
+                    wait_end,
+                });
+                continue;
+            }
+
+            let bid_pool: BidPool =
+                read_bid_pool(deps.storage, &bid.collateral_token, &PoolKey::of(bid))?;
+            let (remaining_bid, _) = calculate_remaining_bid(bid, &bid_pool)?;
+            let (liquidated_collateral, _) = calculate_liquidated_collateral(deps.storage, bid)?;
+            let bid_claimable_collateral =
+                bid.pending_liquidated_collateral + liquidated_collateral;
+
+            claimable_collateral += bid_claimable_collateral;
+            bids.push(ClaimableBidResponse {
+                idx: bid.idx,
+                premium_slot: bid.premium_slot,
+                remaining_amount: remaining_bid,
+                claimable_collateral: bid_claimable_collateral,
+            });
+        }
+    }
+
+    Ok(ClaimableLiquidationsResponse {
+        bidder,
+        collateral_token,
+        claimable_collateral,
+        bids,
+        waiting_bids,
+    })
+}
+
 pub fn query_bid_pool(
     deps: Deps,
     collateral_token: String,
     bid_slot: u8,
 ) -> StdResult<BidPoolResponse> {
     let collateral_token_raw: CanonicalAddr = deps.api.addr_canonicalize(&collateral_token)?;
-    let bid_pool: BidPool = read_bid_pool(deps.storage, &collateral_token_raw, bid_slot)?;
+    let bid_pool: BidPool =
+        read_bid_pool(deps.storage, &collateral_token_raw, &PoolKey::slot(bid_slot))?;
 
     Ok(BidPoolResponse {
         sum_snapshot: bid_pool.sum_snapshot,
@@ -320,6 +597,7 @@ pub fn query_collateral_info(
     deps: Deps,
     collateral_token: String,
 ) -> StdResult<CollateralInfoResponse> {
+    let config: Config = read_config(deps.storage)?;
     let collateral_token_raw = deps.api.addr_canonicalize(&collateral_token)?;
     let collateral_info: CollateralInfo =
         read_collateral_info(deps.storage, &collateral_token_raw)?;
@@ -329,5 +607,10 @@ pub fn query_collateral_info(
         bid_threshold: collateral_info.bid_threshold,
         max_slot: collateral_info.max_slot,
         premium_rate_per_slot: collateral_info.premium_rate_per_slot,
+        premium_schedule: collateral_info.premium_schedule(),
+        bid_fee: collateral_info.bid_fee(&config),
+        liquidator_fee: collateral_info.liquidator_fee(&config),
+        waiting_period: collateral_info.waiting_period(&config),
+        liquidation_threshold: collateral_info.liquidation_threshold(&config),
     })
 }
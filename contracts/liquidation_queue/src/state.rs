@@ -1,3 +1,5 @@
+use bigint::U256;
+use cdp::liquidation_queue::{AutoRebid, PremiumSchedule};
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{CanonicalAddr, Order, StdError, StdResult, Storage, Uint128};
 use cosmwasm_storage::{singleton, singleton_read, Bucket, ReadonlyBucket};
@@ -10,11 +12,17 @@ static KEY_BID_IDX: &[u8] = b"bid_idx";
 
 static PREFIX_BID: &[u8] = b"bid";
 static PREFIX_BID_BY_USER: &[u8] = b"bid_by_user";
+static PREFIX_BID_BY_EXPIRY: &[u8] = b"bid_by_expiry";
+static PREFIX_BID_BY_WAIT_END: &[u8] = b"bid_by_wait_end";
 static PREFIX_BID_POOL_BY_COLLATERAL: &[u8] = b"bid_pool_by_col";
+static PREFIX_LIMIT_BID_POOL_BY_COLLATERAL: &[u8] = b"limit_bid_pool_by_col";
+static PREFIX_ACTIVE_LIMIT_BID_POOL: &[u8] = b"active_limit_bid_pool";
 static PREFIX_TOTAL_BIDS_BY_COLLATERAL: &[u8] = b"total_bids_by_col";
+static PREFIX_WAITING_BIDS_BY_COLLATERAL: &[u8] = b"waiting_bids_by_col";
 static PREFIX_COLLATERAL_INFO: &[u8] = b"col_info";
 static PREFIX_EPOCH_SCALE_SUM: &[u8] = b"epoch_scale_sum";
 static KEY_NEWOWNER: &[u8] = b"newowner";
+static KEY_WAIT_END_INDEX_CURSOR: &[u8] = b"wait_end_index_cursor";
 
 const MAX_LIMIT: u8 = 31;
 const DEFAULT_LIMIT: u8 = 10;
@@ -31,6 +39,18 @@ pub struct Config {
     pub price_timeframe: u64,
     pub waiting_period: u64,
     pub control_contract: CanonicalAddr,
+    #[serde(default = "default_close_factor")]
+    pub close_factor: Decimal256,
+    #[serde(default)]
+    pub prune_tip_rate: Decimal256,
+    #[serde(default)]
+    pub min_limit_bid_amount: Uint256,
+    #[serde(default)]
+    pub swap_adapter: Option<CanonicalAddr>,
+}
+
+fn default_close_factor() -> Decimal256 {
+    Decimal256::one()
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -83,10 +103,38 @@ pub fn read_total_bids(
     total_bids_bucket.load(collateral_token.as_slice())
 }
 
-pub fn store_epoch_scale_sum(
+/// Amount of the bids in waiting state by premium slot, expired bids included
+pub fn store_waiting_bids(
     storage: &mut dyn Storage,
     collateral_token: &CanonicalAddr,
     premium_slot: u8,
+    waiting_bids: Uint256,
+) -> StdResult<()> {
+    let mut waiting_bids_bucket: Bucket<Uint256> = Bucket::multilevel(
+        storage,
+        &[PREFIX_WAITING_BIDS_BY_COLLATERAL, collateral_token.as_slice()],
+    );
+    waiting_bids_bucket.save(&premium_slot.to_be_bytes(), &waiting_bids)
+}
+
+pub fn read_waiting_bids(
+    storage: &dyn Storage,
+    collateral_token: &CanonicalAddr,
+    premium_slot: u8,
+) -> StdResult<Uint256> {
+    let waiting_bids_bucket: ReadonlyBucket<Uint256> = ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_WAITING_BIDS_BY_COLLATERAL, collateral_token.as_slice()],
+    );
+    Ok(waiting_bids_bucket
+        .may_load(&premium_slot.to_be_bytes())?
+        .unwrap_or_default())
+}
+
+pub fn store_epoch_scale_sum(
+    storage: &mut dyn Storage,
+    collateral_token: &CanonicalAddr,
+    pool_key: &PoolKey,
     epoch: Uint128,
     scale: Uint128,
     sum: Decimal256,
@@ -96,7 +144,7 @@ pub fn store_epoch_scale_sum(
         &[
             PREFIX_EPOCH_SCALE_SUM,
             collateral_token.as_slice(),
-            &premium_slot.to_be_bytes(),
+            &pool_key.to_bytes(),
             &epoch.u128().to_be_bytes(),
         ],
     );
@@ -106,7 +154,7 @@ pub fn store_epoch_scale_sum(
 pub fn read_epoch_scale_sum(
     storage: &dyn Storage,
     collateral_token: &CanonicalAddr,
-    premium_slot: u8,
+    pool_key: &PoolKey,
     epoch: Uint128,
     scale: Uint128,
 ) -> StdResult<Decimal256> {
@@ -115,7 +163,7 @@ pub fn read_epoch_scale_sum(
         &[
             PREFIX_EPOCH_SCALE_SUM,
             collateral_token.as_slice(),
-            &premium_slot.to_be_bytes(),
+            &pool_key.to_bytes(),
             &epoch.u128().to_be_bytes(),
         ],
     );
@@ -128,6 +176,48 @@ pub struct CollateralInfo {
     pub bid_threshold: Uint256,
     pub max_slot: u8,
     pub premium_rate_per_slot: Decimal256,
+    // none for the linear premium of premium_rate_per_slot
+    #[serde(default)]
+    pub premium_schedule: Option<PremiumSchedule>,
+    // overrides of the config values, none to use the config value
+    #[serde(default)]
+    pub bid_fee: Option<Decimal256>,
+    #[serde(default)]
+    pub liquidator_fee: Option<Decimal256>,
+    #[serde(default)]
+    pub waiting_period: Option<u64>,
+    #[serde(default)]
+    pub liquidation_threshold: Option<Uint256>,
+}
+
+impl CollateralInfo {
+    pub fn premium_schedule(&self) -> PremiumSchedule {
+        self.premium_schedule
+            .clone()
+            .unwrap_or(PremiumSchedule::Linear {
+                rate_per_slot: self.premium_rate_per_slot,
+            })
+    }
+
+    pub fn premium_rate(&self, premium_slot: u8) -> Decimal256 {
+        self.premium_schedule().premium_rate(premium_slot)
+    }
+
+    pub fn bid_fee(&self, config: &Config) -> Decimal256 {
+        self.bid_fee.unwrap_or(config.bid_fee)
+    }
+
+    pub fn liquidator_fee(&self, config: &Config) -> Decimal256 {
+        self.liquidator_fee.unwrap_or(config.liquidator_fee)
+    }
+
+    pub fn waiting_period(&self, config: &Config) -> u64 {
+        self.waiting_period.unwrap_or(config.waiting_period)
+    }
+
+    pub fn liquidation_threshold(&self, config: &Config) -> Uint256 {
+        self.liquidation_threshold.unwrap_or(config.liquidation_threshold)
+    }
 }
 
 pub fn store_collateral_info(
@@ -153,6 +243,15 @@ pub fn read_collateral_info(
         .map_err(|_| StdError::generic_err("Collateral is not whitelisted"))
 }
 
+pub fn read_collateral_infos(storage: &dyn Storage) -> StdResult<Vec<CollateralInfo>> {
+    let collateral_info_bucket: ReadonlyBucket<CollateralInfo> =
+        ReadonlyBucket::new(storage, PREFIX_COLLATERAL_INFO);
+    collateral_info_bucket
+        .range(None, None, Order::Ascending)
+        .map(|elem| elem.map(|(_, collateral_info)| collateral_info))
+        .collect()
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct BidPool {
     pub sum_snapshot: Decimal256,
@@ -165,72 +264,239 @@ pub struct BidPool {
     pub residue_bid: Decimal256,
 }
 
+/// Bid pool of a premium slot. Bids with a max price or an expiration are pooled
+/// apart from the other bids of their slot, in a limit bid pool per max price and
+/// expiration, so that liquidations can leave out every bid above its limit or past
+/// its expiration by leaving out its pool
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PoolKey {
+    pub premium_slot: u8,
+    pub max_price: Option<Decimal256>,
+    pub expires_at: Option<u64>,
+}
+
+impl PoolKey {
+    pub fn slot(premium_slot: u8) -> Self {
+        PoolKey {
+            premium_slot,
+            max_price: None,
+            expires_at: None,
+        }
+    }
+
+    pub fn of(bid: &Bid) -> Self {
+        PoolKey {
+            premium_slot: bid.premium_slot,
+            max_price: bid.max_price,
+            expires_at: bid.expires_at,
+        }
+    }
+
+    pub fn is_limited(&self) -> bool {
+        self.max_price.is_some() || self.expires_at.is_some()
+    }
+
+    /// A bid pool expires once the block time reaches its expires_at
+    pub fn is_expired(&self, time: u64) -> bool {
+        matches!(self.expires_at, Some(expires_at) if expires_at <= time)
+    }
+
+    // the bare premium slot for the pool of the bids without a limit, as stored
+    // before limit bid pools existed
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut key = self.premium_slot.to_be_bytes().to_vec();
+        if self.is_limited() {
+            key.extend_from_slice(&self.limit_key());
+        }
+        key
+    }
+
+    // max price first so that limit bid pools range in max price order, a missing
+    // max price or expiration is stored as the highest value
+    fn limit_key(&self) -> Vec<u8> {
+        let mut key = [0u8; 40];
+        self.max_price
+            .map_or(U256::max_value(), |max_price| max_price.0)
+            .to_big_endian(&mut key[..32]);
+        key[32..].copy_from_slice(&self.expires_at.unwrap_or(u64::MAX).to_be_bytes());
+        key.to_vec()
+    }
+
+    fn from_limit_key(premium_slot: u8, key: &[u8]) -> StdResult<Self> {
+        if key.len() != 40 {
+            return Err(StdError::generic_err(
+                "Corrupted data found. 40 byte expected.",
+            ));
+        }
+        let max_price = U256::from_big_endian(&key[..32]);
+        let mut expiry = [0u8; 8];
+        expiry.copy_from_slice(&key[32..]);
+        let expires_at = u64::from_be_bytes(expiry);
+        Ok(PoolKey {
+            premium_slot,
+            max_price: Some(Decimal256(max_price)).filter(|_| max_price != U256::max_value()),
+            expires_at: Some(expires_at).filter(|_| expires_at != u64::MAX),
+        })
+    }
+}
+
+fn bid_pool_prefix(pool_key: &PoolKey) -> &'static [u8] {
+    if pool_key.is_limited() {
+        PREFIX_LIMIT_BID_POOL_BY_COLLATERAL
+    } else {
+        PREFIX_BID_POOL_BY_COLLATERAL
+    }
+}
+
 pub fn store_bid_pool(
     storage: &mut dyn Storage,
     collateral_token: &CanonicalAddr,
-    premium_slot: u8,
+    pool_key: &PoolKey,
     bid_pool: &BidPool,
 ) -> StdResult<()> {
     let mut bid_pool_bucket: Bucket<BidPool> = Bucket::multilevel(
         storage,
-        &[PREFIX_BID_POOL_BY_COLLATERAL, collateral_token.as_slice()],
+        &[bid_pool_prefix(pool_key), collateral_token.as_slice()],
     );
-    bid_pool_bucket.save(&premium_slot.to_be_bytes(), bid_pool)
+    bid_pool_bucket.save(&pool_key.to_bytes(), bid_pool)
 }
 
 pub fn read_bid_pool(
     storage: &dyn Storage,
     collateral_token: &CanonicalAddr,
-    premium_slot: u8,
+    pool_key: &PoolKey,
 ) -> StdResult<BidPool> {
     let bid_pool_bucket: ReadonlyBucket<BidPool> = ReadonlyBucket::multilevel(
         storage,
-        &[PREFIX_BID_POOL_BY_COLLATERAL, collateral_token.as_slice()],
+        &[bid_pool_prefix(pool_key), collateral_token.as_slice()],
     );
     bid_pool_bucket
-        .load(&premium_slot.to_be_bytes())
+        .load(&pool_key.to_bytes())
         .map_err(|_| StdError::generic_err("Bid pool not found"))
 }
 
 pub fn read_or_create_bid_pool(
     storage: &mut dyn Storage,
     collateral_info: &CollateralInfo,
-    premium_slot: u8,
+    pool_key: &PoolKey,
 ) -> StdResult<BidPool> {
-    let bid_pool_bucket: ReadonlyBucket<BidPool> = ReadonlyBucket::multilevel(
+    let collateral_token = &collateral_info.collateral_token;
+    if let Ok(bid_pool) = read_bid_pool(storage, collateral_token, pool_key) {
+        return Ok(bid_pool);
+    }
+
+    // limit bid pools are liquidated along with the bid pool of their slot
+    let premium_slot = pool_key.premium_slot;
+    let premium_rate = if pool_key.is_limited() {
+        read_or_create_bid_pool(storage, collateral_info, &PoolKey::slot(premium_slot))?
+            .premium_rate
+    } else if (0..collateral_info.max_slot + 1).contains(&premium_slot) {
+        collateral_info.premium_rate(premium_slot)
+    } else {
+        return Err(StdError::generic_err("Invalid premium slot"));
+    };
+
+    let bid_pool = BidPool {
+        product_snapshot: Decimal256::one(),
+        sum_snapshot: Decimal256::zero(),
+        total_bid_amount: Uint256::zero(),
+        premium_rate,
+        current_epoch: Uint128::zero(),
+        current_scale: Uint128::zero(),
+        residue_collateral: Decimal256::zero(),
+        residue_bid: Decimal256::zero(),
+    };
+    store_bid_pool(storage, collateral_token, pool_key, &bid_pool)?;
+    Ok(bid_pool)
+}
+
+/// Limit bid pools holding activated bids are active until expired, liquidations go
+/// through the active pools only. Nothing is stored for the bid pools of the slots
+pub fn store_active_limit_bid_pool(
+    storage: &mut dyn Storage,
+    collateral_token: &CanonicalAddr,
+    pool_key: &PoolKey,
+) -> StdResult<()> {
+    if !pool_key.is_limited() {
+        return Ok(());
+    }
+    let mut active_pool_index: Bucket<bool> = Bucket::multilevel(
         storage,
         &[
-            PREFIX_BID_POOL_BY_COLLATERAL,
-            collateral_info.collateral_token.as_slice(),
+            PREFIX_ACTIVE_LIMIT_BID_POOL,
+            collateral_token.as_slice(),
+            &pool_key.premium_slot.to_be_bytes(),
         ],
     );
-    match bid_pool_bucket.load(&premium_slot.to_be_bytes()) {
-        Ok(bid_pool) => Ok(bid_pool),
-        Err(_) => {
-            if (0..collateral_info.max_slot + 1).contains(&premium_slot) {
-                let bid_pool = BidPool {
-                    product_snapshot: Decimal256::one(),
-                    sum_snapshot: Decimal256::zero(),
-                    total_bid_amount: Uint256::zero(),
-                    premium_rate: collateral_info.premium_rate_per_slot
-                        * Decimal256::from_uint256(Uint256::from(premium_slot as u128)),
-                    current_epoch: Uint128::zero(),
-                    current_scale: Uint128::zero(),
-                    residue_collateral: Decimal256::zero(),
-                    residue_bid: Decimal256::zero(),
-                };
-                store_bid_pool(
-                    storage,
-                    &collateral_info.collateral_token,
-                    premium_slot,
-                    &bid_pool,
-                )?;
-                Ok(bid_pool)
-            } else {
-                Err(StdError::generic_err("Invalid premium slot"))
-            }
-        }
+    active_pool_index.save(&pool_key.limit_key(), &true)
+}
+
+/// Returns whether the limit bid pool was active
+pub fn remove_active_limit_bid_pool(
+    storage: &mut dyn Storage,
+    collateral_token: &CanonicalAddr,
+    pool_key: &PoolKey,
+) -> StdResult<bool> {
+    if !pool_key.is_limited() {
+        return Ok(false);
     }
+    let mut active_pool_index: Bucket<bool> = Bucket::multilevel(
+        storage,
+        &[
+            PREFIX_ACTIVE_LIMIT_BID_POOL,
+            collateral_token.as_slice(),
+            &pool_key.premium_slot.to_be_bytes(),
+        ],
+    );
+    let key = pool_key.limit_key();
+    let active = active_pool_index.may_load(&key)?.is_some();
+    active_pool_index.remove(&key);
+    Ok(active)
+}
+
+/// Next active limit bid pool of the premium slot after `pool_key` whose max price is
+/// not below `price`, in max price order. Limit bid pools below the price are out of
+/// the range, they are never read. Expired pools stay active until deactivated
+pub fn read_next_active_limit_bid_pool(
+    storage: &dyn Storage,
+    collateral_token: &CanonicalAddr,
+    pool_key: &PoolKey,
+    price: Decimal256,
+) -> StdResult<Option<PoolKey>> {
+    let active_pool_index: ReadonlyBucket<bool> = ReadonlyBucket::multilevel(
+        storage,
+        &[
+            PREFIX_ACTIVE_LIMIT_BID_POOL,
+            collateral_token.as_slice(),
+            &pool_key.premium_slot.to_be_bytes(),
+        ],
+    );
+
+    let start = if pool_key.is_limited() {
+        // the first key after the limit key of the pool
+        let mut start = pool_key.limit_key();
+        start.push(1);
+        start
+    } else {
+        // the max price only, the first key of the pools at the price
+        PoolKey {
+            premium_slot: pool_key.premium_slot,
+            max_price: Some(price),
+            expires_at: None,
+        }
+        .limit_key()[..32]
+            .to_vec()
+    };
+
+    let next_pool_key = active_pool_index
+        .range(Some(&start), None, Order::Ascending)
+        .next()
+        .map(|elem| {
+            let (k, _) = elem?;
+            PoolKey::from_limit_key(pool_key.premium_slot, &k)
+        })
+        .transpose();
+    next_pool_key
 }
 
 pub fn read_bid_pools(
@@ -270,10 +536,19 @@ pub struct Bid {
     pub wait_end: Option<u64>,
     pub epoch_snapshot: Uint128,
     pub scale_snapshot: Uint128,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub max_price: Option<Decimal256>,
+    #[serde(default)]
+    pub auto_rebid: Option<AutoRebid>,
 }
 
 pub fn store_bid(storage: &mut dyn Storage, bid_idx: Uint128, bid: &Bid) -> StdResult<()> {
     let mut bid_bucket: Bucket<Bid> = Bucket::new(storage, PREFIX_BID);
+    let prev_wait_end = bid_bucket
+        .may_load(&bid_idx.u128().to_be_bytes())?
+        .and_then(|prev_bid| prev_bid.wait_end);
     bid_bucket.save(&bid_idx.u128().to_be_bytes(), bid)?;
 
     let mut bid_indexer_by_user: Bucket<bool> = Bucket::multilevel(
@@ -286,6 +561,29 @@ pub fn store_bid(storage: &mut dyn Storage, bid_idx: Uint128, bid: &Bid) -> StdR
     );
     bid_indexer_by_user.save(&bid_idx.u128().to_be_bytes(), &true)?;
 
+    if let Some(expires_at) = bid.expires_at {
+        let mut bid_indexer_by_expiry: Bucket<bool> = Bucket::multilevel(
+            storage,
+            &[PREFIX_BID_BY_EXPIRY, bid.collateral_token.as_slice()],
+        );
+        bid_indexer_by_expiry.save(&expiry_key(expires_at, bid_idx), &true)?;
+    }
+
+    // the wait end changes on activation, an expired bid dropped from the wait end
+    // index is not indexed again
+    if prev_wait_end != bid.wait_end {
+        let mut bid_indexer_by_wait_end: Bucket<bool> = Bucket::multilevel(
+            storage,
+            &[PREFIX_BID_BY_WAIT_END, bid.collateral_token.as_slice()],
+        );
+        if let Some(prev_wait_end) = prev_wait_end {
+            bid_indexer_by_wait_end.remove(&wait_end_key(prev_wait_end, bid_idx));
+        }
+        if let Some(wait_end) = bid.wait_end {
+            bid_indexer_by_wait_end.save(&wait_end_key(wait_end, bid_idx), &true)?;
+        }
+    }
+
     Ok(())
 }
 
@@ -305,6 +603,22 @@ pub fn remove_bid(storage: &mut dyn Storage, bid_idx: Uint128) -> StdResult<()>
     );
     bid_indexer_by_user.remove(&bid_idx.u128().to_be_bytes());
 
+    if let Some(expires_at) = bid.expires_at {
+        let mut bid_indexer_by_expiry: Bucket<bool> = Bucket::multilevel(
+            storage,
+            &[PREFIX_BID_BY_EXPIRY, bid.collateral_token.as_slice()],
+        );
+        bid_indexer_by_expiry.remove(&expiry_key(expires_at, bid_idx));
+    }
+
+    if let Some(wait_end) = bid.wait_end {
+        let mut bid_indexer_by_wait_end: Bucket<bool> = Bucket::multilevel(
+            storage,
+            &[PREFIX_BID_BY_WAIT_END, bid.collateral_token.as_slice()],
+        );
+        bid_indexer_by_wait_end.remove(&wait_end_key(wait_end, bid_idx));
+    }
+
     Ok(())
 }
 
@@ -344,6 +658,130 @@ pub fn read_bids_by_user(
         .collect()
 }
 
+/// Bids of the collateral expired at `time`, in expiration order
+pub fn read_expired_bids(
+    storage: &dyn Storage,
+    collateral_token: &CanonicalAddr,
+    time: u64,
+    start_after: Option<&Bid>,
+    limit: Option<u8>,
+) -> StdResult<Vec<Bid>> {
+    let bid_expiry_index: ReadonlyBucket<bool> = ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_BID_BY_EXPIRY, collateral_token.as_slice()],
+    );
+
+    // a bid expires once the block time reaches its expires_at
+    let end = (time + 1).to_be_bytes();
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|bid| {
+        let mut start = expiry_key(bid.expires_at.unwrap_or_default(), bid.idx);
+        start.push(1);
+        start
+    });
+
+    bid_expiry_index
+        .range(start.as_deref(), Some(&end), Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (k, _) = elem?;
+            read_bid(storage, Uint128::from(bytes_to_u128(&k[8..])?))
+        })
+        .collect()
+}
+
+/// Waiting bids of the collateral in wait end order
+pub fn read_bids_by_wait_end(
+    storage: &dyn Storage,
+    collateral_token: &CanonicalAddr,
+    limit: Option<u8>,
+) -> StdResult<Vec<Bid>> {
+    let bid_wait_end_index: ReadonlyBucket<bool> = ReadonlyBucket::multilevel(
+        storage,
+        &[PREFIX_BID_BY_WAIT_END, collateral_token.as_slice()],
+    );
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+
+    bid_wait_end_index
+        .range(None, None, Order::Ascending)
+        .map(|elem| {
+            let (k, _) = elem?;
+            read_bid(storage, Uint128::from(bytes_to_u128(&k[8..])?))
+        })
+        .take(limit)
+        .collect()
+}
+
+/// Expired waiting bids can not be activated anymore, they leave the wait end
+/// index and wait to be pruned
+pub fn remove_bid_from_wait_end_index(storage: &mut dyn Storage, bid: &Bid) {
+    if let Some(wait_end) = bid.wait_end {
+        let mut bid_indexer_by_wait_end: Bucket<bool> = Bucket::multilevel(
+            storage,
+            &[PREFIX_BID_BY_WAIT_END, bid.collateral_token.as_slice()],
+        );
+        bid_indexer_by_wait_end.remove(&wait_end_key(wait_end, bid.idx));
+    }
+}
+
+/// Start indexing by wait end the bids stored before the wait end index existed
+pub fn start_wait_end_indexing(storage: &mut dyn Storage) -> StdResult<()> {
+    singleton(storage, KEY_WAIT_END_INDEX_CURSOR).save(&Uint128::zero())
+}
+
+/// Index the waiting bids among the next `limit` bids stored before the wait end
+/// index existed, returns whether all of them are indexed
+pub fn index_bids_by_wait_end(storage: &mut dyn Storage, limit: Option<u8>) -> StdResult<bool> {
+    let last_bid_idx: Uint128 = singleton_read(storage, KEY_WAIT_END_INDEX_CURSOR)
+        .may_load()?
+        .ok_or_else(|| StdError::generic_err("All bids are indexed by wait end"))?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = calc_range_start_idx(Some(last_bid_idx));
+
+    let bid_bucket: ReadonlyBucket<Bid> = ReadonlyBucket::new(storage, PREFIX_BID);
+    let bids = bid_bucket
+        .range(start.as_deref(), None, Order::Ascending)
+        .take(limit)
+        .map(|elem| elem.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<Bid>>>()?;
+
+    let done = bids.len() < limit;
+    if let Some(last_bid) = bids.last() {
+        singleton(storage, KEY_WAIT_END_INDEX_CURSOR).save(&last_bid.idx)?;
+    }
+    if done {
+        singleton::<Uint128>(storage, KEY_WAIT_END_INDEX_CURSOR).remove();
+    }
+
+    for bid in bids {
+        let mut bid_indexer_by_wait_end: Bucket<bool> = Bucket::multilevel(
+            storage,
+            &[PREFIX_BID_BY_WAIT_END, bid.collateral_token.as_slice()],
+        );
+        if let Some(wait_end) = bid.wait_end {
+            bid_indexer_by_wait_end.save(&wait_end_key(wait_end, bid.idx), &true)?;
+        }
+    }
+
+    Ok(done)
+}
+
+// expiration time first so that expired bids range in expiration order
+fn expiry_key(expires_at: u64, bid_idx: Uint128) -> Vec<u8> {
+    let mut key = expires_at.to_be_bytes().to_vec();
+    key.extend_from_slice(&bid_idx.u128().to_be_bytes());
+    key
+}
+
+// wait end first so that waiting bids range in first in first out order
+fn wait_end_key(wait_end: u64, bid_idx: Uint128) -> Vec<u8> {
+    let mut key = wait_end.to_be_bytes().to_vec();
+    key.extend_from_slice(&bid_idx.u128().to_be_bytes());
+    key
+}
+
 fn bytes_to_u128(data: &[u8]) -> StdResult<u128> {
     match data[0..16].try_into() {
         Ok(bytes) => Ok(u128::from_be_bytes(bytes)),
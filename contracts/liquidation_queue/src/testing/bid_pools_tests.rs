@@ -6,7 +6,7 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info};
 use cosmwasm_std::{attr, from_binary, to_binary, Coin, Decimal, StdError, Uint128};
 use cw20::Cw20ReceiveMsg;
-use moneymarket::liquidation_queue::{
+use cdp::liquidation_queue::{
     BidPoolResponse, BidResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
 };
 
@@ -29,7 +29,13 @@ fn one_bidder_distribution() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -50,6 +56,11 @@ fn one_bidder_distribution() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -58,6 +69,9 @@ fn one_bidder_distribution() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -144,7 +158,13 @@ fn two_bidder_distribution() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -165,6 +185,11 @@ fn two_bidder_distribution() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -173,6 +198,9 @@ fn two_bidder_distribution() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -212,6 +240,9 @@ fn two_bidder_distribution() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "bob0000",
@@ -334,7 +365,13 @@ fn two_bidder_distribution_big_numbers() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -355,6 +392,11 @@ fn two_bidder_distribution_big_numbers() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -363,6 +405,9 @@ fn two_bidder_distribution_big_numbers() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -402,6 +447,9 @@ fn two_bidder_distribution_big_numbers() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "bob0000",
@@ -524,7 +572,13 @@ fn one_user_two_bid_slots() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 10u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -545,6 +599,11 @@ fn one_user_two_bid_slots() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -553,6 +612,9 @@ fn one_user_two_bid_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -569,6 +631,9 @@ fn one_user_two_bid_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -690,7 +755,13 @@ fn partial_withdraw_after_execution() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -711,6 +782,11 @@ fn partial_withdraw_after_execution() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -719,6 +795,9 @@ fn partial_withdraw_after_execution() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -766,6 +845,9 @@ fn partial_withdraw_after_execution() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "bob0000",
@@ -895,7 +977,13 @@ fn completely_empty_pool() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -916,6 +1004,11 @@ fn completely_empty_pool() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), env, info, msg).unwrap();
@@ -924,6 +1017,9 @@ fn completely_empty_pool() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -952,6 +1048,9 @@ fn completely_empty_pool() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "bob0000",
@@ -1073,7 +1172,13 @@ fn product_truncated_to_zero() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1094,6 +1199,11 @@ fn product_truncated_to_zero() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -1104,6 +1214,9 @@ fn product_truncated_to_zero() {
         let msg = ExecuteMsg::SubmitBid {
             collateral_token: "col0000".to_string(),
             premium_slot: 0u8,
+            expires_at: None,
+            max_price: None,
+            auto_rebid: None,
         };
         let info = mock_info(
             "alice0000",
@@ -1195,7 +1308,13 @@ fn two_bidder_reward_distribution_common_slot() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     // 10 ust/col
@@ -1217,6 +1336,11 @@ fn two_bidder_reward_distribution_common_slot() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), env, info, msg).unwrap();
@@ -1225,6 +1349,9 @@ fn two_bidder_reward_distribution_common_slot() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -1250,6 +1377,9 @@ fn two_bidder_reward_distribution_common_slot() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "bob0000",
@@ -1372,7 +1502,13 @@ fn two_bidder_distribution_multiple_common_slots() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1393,6 +1529,11 @@ fn two_bidder_distribution_multiple_common_slots() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
 
     let info = mock_info("owner0000", &[]);
@@ -1402,6 +1543,9 @@ fn two_bidder_distribution_multiple_common_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
 
     let info = mock_info(
@@ -1428,6 +1572,9 @@ fn two_bidder_distribution_multiple_common_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
 
     let info = mock_info(
@@ -1453,6 +1600,9 @@ fn two_bidder_distribution_multiple_common_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
 
     let info = mock_info(
@@ -1480,6 +1630,9 @@ fn two_bidder_distribution_multiple_common_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
 
     let info = mock_info(
@@ -1687,7 +1840,13 @@ fn two_bidder_unequal_deposit_reward_distribution() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     // 2 ust/col
@@ -1709,6 +1868,11 @@ fn two_bidder_unequal_deposit_reward_distribution() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), env, info, msg).unwrap();
@@ -1717,6 +1881,9 @@ fn two_bidder_unequal_deposit_reward_distribution() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 2u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -1742,6 +1909,9 @@ fn two_bidder_unequal_deposit_reward_distribution() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 2u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "bob0000",
@@ -1871,7 +2041,13 @@ fn scalable_reward_distribution_after_multiple_liquidations() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1892,6 +2068,11 @@ fn scalable_reward_distribution_after_multiple_liquidations() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
 
     let info = mock_info("owner0000", &[]);
@@ -1901,6 +2082,9 @@ fn scalable_reward_distribution_after_multiple_liquidations() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
 
     let info = mock_info(
@@ -1927,6 +2111,9 @@ fn scalable_reward_distribution_after_multiple_liquidations() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
 
     let info = mock_info(
@@ -1952,6 +2139,9 @@ fn scalable_reward_distribution_after_multiple_liquidations() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
 
     let info = mock_info(
@@ -2002,6 +2192,9 @@ fn scalable_reward_distribution_after_multiple_liquidations() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
 
     let info = mock_info(
@@ -2028,6 +2221,9 @@ fn scalable_reward_distribution_after_multiple_liquidations() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
 
     let info = mock_info(
@@ -2126,7 +2322,13 @@ fn not_enough_bid_for_collateral() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -2147,6 +2349,11 @@ fn not_enough_bid_for_collateral() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), env, info, msg).unwrap();
@@ -2155,6 +2362,9 @@ fn not_enough_bid_for_collateral() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 6u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -2180,6 +2390,9 @@ fn not_enough_bid_for_collateral() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 6u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "bob0000",
@@ -2244,7 +2457,13 @@ fn two_bidder_reward_distribution_common_slot_large_numbers() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     // 1000 ust/col
@@ -2266,6 +2485,11 @@ fn two_bidder_reward_distribution_common_slot_large_numbers() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -2274,6 +2498,9 @@ fn two_bidder_reward_distribution_common_slot_large_numbers() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "alice0000",
@@ -2298,6 +2525,9 @@ fn two_bidder_reward_distribution_common_slot_large_numbers() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "col0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "bob0000",
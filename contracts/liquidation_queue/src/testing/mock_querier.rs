@@ -1,4 +1,4 @@
-use moneymarket::overseer::{WhitelistResponse, WhitelistResponseElem};
+use cdp::central_control::{WhitelistElemResponse, WhitelistResponse};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -10,16 +10,16 @@ use cosmwasm_std::{
 };
 use std::collections::HashMap;
 
-use moneymarket::oracle::PriceResponse;
-use sei_cosmwasm::{SeiQuery, SeiQueryWrapper, SeiRoute};
+use cdp::oracle_pyth::PriceResponse;
+use sei_cosmwasm::SeiQueryWrapper;
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     /// Query oracle price to oracle contract
-    Price { base: String, quote: String },
+    QueryPrice { asset: String },
     Whitelist {
-        collateral_token: Option<String>,
+        collateral_contract: Option<String>,
         start_after: Option<String>,
         limit: Option<u32>,
     },
@@ -71,6 +71,8 @@ pub(crate) fn collaterals_to_map(
     collateral_map
 }
 
+// the stable denom is not taxed, the configured tax is not queried
+#[allow(dead_code)]
 #[derive(Clone, Default)]
 pub struct TaxQuerier {
     rate: Decimal,
@@ -170,11 +172,20 @@ impl WasmMockQuerier {
                 contract_addr: _,
                 msg,
             }) => match from_binary(msg).unwrap() {
-                QueryMsg::Price { base, quote } => {
-                    match self.oracle_price_querier.oracle_price.get(&(base, quote)) {
-                        Some(v) => {
+                QueryMsg::QueryPrice { asset } => {
+                    let price = self
+                        .oracle_price_querier
+                        .oracle_price
+                        .iter()
+                        .find(|((base, _), _)| *base == asset);
+                    match price {
+                        Some((_, v)) => {
                             SystemResult::Ok(ContractResult::from(to_binary(&PriceResponse {
-                                rate: v.0,
+                                asset,
+                                emv_price: v.0,
+                                emv_price_raw: 0,
+                                price: v.0,
+                                price_raw: 0,
                                 last_updated_base: v.1,
                                 last_updated_quote: v.2,
                             })))
@@ -186,23 +197,24 @@ impl WasmMockQuerier {
                     }
                 }
                 QueryMsg::Whitelist {
-                    collateral_token,
+                    collateral_contract,
                     start_after: _,
                     limit: _,
                 } => {
                     match self
                         .collateral_querier
                         .collaterals
-                        .get(&collateral_token.unwrap())
+                        .get(&collateral_contract.unwrap())
                     {
                         Some(v) => {
                             SystemResult::Ok(ContractResult::from(to_binary(&WhitelistResponse {
-                                elems: vec![WhitelistResponseElem {
+                                elems: vec![WhitelistElemResponse {
                                     name: "name".to_string(),
                                     symbol: "symbol".to_string(),
                                     max_ltv: *v,
                                     custody_contract: "custody0000".to_string(),
-                                    collateral_token: "token0000".to_string(),
+                                    collateral_contract: "token0000".to_string(),
+                                    reward_book_contract: "reward_book0000".to_string(),
                                 }],
                             })))
                         }
@@ -244,4 +256,8 @@ impl WasmMockQuerier {
     pub fn with_collateral_max_ltv(&mut self, collaterals: &[(&String, &Decimal256)]) {
         self.collateral_querier = CollateralQuerier::new(collaterals);
     }
+
+    pub fn update_balance(&mut self, addr: &str, balance: Vec<Coin>) {
+        self.base.update_balance(addr, balance);
+    }
 }
@@ -8,7 +8,7 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info, MockApi};
 use cosmwasm_std::{from_binary, to_binary, Coin, Decimal, MemoryStorage, OwnedDeps, Uint128};
 use cw20::Cw20ReceiveMsg;
-use moneymarket::liquidation_queue::{
+use cdp::liquidation_queue::{
     BidsResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg,
 };
 
@@ -112,7 +112,13 @@ fn instantiate_and_whitelist(deps: &mut OwnedDeps<MemoryStorage, MockApi, WasmMo
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 101u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -125,6 +131,11 @@ fn instantiate_and_whitelist(deps: &mut OwnedDeps<MemoryStorage, MockApi, WasmMo
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000000000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -155,6 +166,9 @@ fn simulate_bids_with_2_liq_amounts(
         let msg = ExecuteMsg::SubmitBid {
             collateral_token: "col0000".to_string(),
             premium_slot: 0u8,
+            expires_at: None,
+            max_price: None,
+            auto_rebid: None,
         };
         let info = mock_info(
             "alice0000",
@@ -4,7 +4,7 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info};
 use cosmwasm_std::{from_binary, to_binary, BankMsg, Coin, CosmosMsg, Decimal, SubMsg, Uint128};
 use cw20::Cw20ReceiveMsg;
-use moneymarket::liquidation_queue::{
+use cdp::liquidation_queue::{
     Cw20HookMsg, ExecuteMsg, InstantiateMsg, LiquidationAmountResponse, QueryMsg,
 };
 
@@ -28,7 +28,13 @@ fn partial_one_collateral_one_slot_high_ltv() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -39,6 +45,11 @@ fn partial_one_collateral_one_slot_high_ltv() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -46,6 +57,9 @@ fn partial_one_collateral_one_slot_high_ltv() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -68,16 +82,16 @@ fn partial_one_collateral_one_slot_high_ltv() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(16433u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(16429u64))],
         }
     );
 
-    // 16433 col liq
-    // remaining = 20000 - 16433 = 3,567
-    // new limit = 3,567 * 1 * 0.9 = 3,210
-    // safe = 3,210 * 0.8 = 2,568 **
+    // 16429 col liq
+    // remaining = 20000 - 16429 = 3,571
+    // new limit = 3,571 * 1 * 0.9 = 3,213
+    // safe = 3,213 * 0.8 = 2,570 **
 
-    // new borrow amount = 19000 - 16433 = 2,567 **
+    // new borrow amount = 19000 - 16429 = 2,571 **
 
     let info = mock_info("token0000", &[]);
     let env = mock_env();
@@ -92,7 +106,7 @@ fn partial_one_collateral_one_slot_high_ltv() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(16433u64),
+        amount: Uint128::from(16429u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -108,7 +122,120 @@ fn partial_one_collateral_one_slot_high_ltv() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(16433u128),
+                amount: Uint128::from(16429u128),
+            }]
+        }))]
+    );
+}
+
+#[test]
+fn partial_liquidation_close_factor_at_premium() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"token0000".to_string(), &Decimal256::percent(50))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(80),
+        bid_fee: Decimal256::percent(0),
+        liquidator_fee: Decimal256::percent(0),
+        liquidation_threshold: Uint256::zero(),
+        price_timeframe: 60u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: Some(Decimal256::percent(50)),
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let _res = instantiate(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "token0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::from(10000u128), // to get instant activation
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::SubmitBid {
+        collateral_token: "token0000".to_string(),
+        premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(10000u128),
+        }],
+    );
+    execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+
+    // the safe ratio alone would liquidate all the collateral, the close factor
+    // limits the repay amount to 1000 * 0.5 = 500
+    // collateral sold at the 10% premium = 500 / 0.9 = 555
+    let msg = QueryMsg::LiquidationAmount {
+        borrow_amount: Uint256::from(1000u64),
+        borrow_limit: Uint256::from(500u64),
+        collaterals: vec![("token0000".to_string(), Uint256::from(1000u64))],
+        collateral_prices: vec![Decimal256::one()],
+    };
+
+    let res = query(deps.as_ref(), mock_env(), msg).unwrap();
+    let res: LiquidationAmountResponse = from_binary(&res).unwrap();
+    assert_eq!(
+        res,
+        LiquidationAmountResponse {
+            collaterals: vec![("token0000".to_string(), Uint256::from(555u64))],
+        }
+    );
+
+    let info = mock_info("token0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("token0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::one(),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(555u64),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator00000".to_string(),
+            fee_address: Some("fee0000".to_string()),
+            repay_address: Some("repay0000".to_string()),
+        })
+        .unwrap(),
+    });
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // repays the close factor portion of the loan, rounded down
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "repay0000".to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(499u128),
             }]
         }))]
     );
@@ -134,7 +261,13 @@ fn partial_one_collateral_one_slot() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -145,6 +278,11 @@ fn partial_one_collateral_one_slot() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -152,6 +290,9 @@ fn partial_one_collateral_one_slot() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -174,16 +315,16 @@ fn partial_one_collateral_one_slot() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(7291u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(7273u64))],
         }
     );
 
-    // 7291 col liq
-    // remaining = 20000 - 7291 = 12709
-    // new limit = 12709 * 0.1 * 0.5 = 635
-    // safe = 635 * 0.8 = 508
+    // 7273 col liq
+    // remaining = 20000 - 7273 = 12,727
+    // new limit = 12,727 * 0.1 * 0.5 = 636
+    // safe = 636 * 0.8 = 508
 
-    // new borrow amount = 1200 - 692 = 508
+    // new borrow amount = 1200 - 690 = 510
 
     let info = mock_info("token0000", &[]);
     let env = mock_env();
@@ -198,7 +339,7 @@ fn partial_one_collateral_one_slot() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(7291u64),
+        amount: Uint128::from(7273u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -214,7 +355,7 @@ fn partial_one_collateral_one_slot() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(692u128),
+                amount: Uint128::from(690u128),
             }]
         }))]
     );
@@ -240,7 +381,13 @@ fn partial_one_collateral_one_slot_with_fees() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -251,6 +398,11 @@ fn partial_one_collateral_one_slot_with_fees() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -258,6 +410,9 @@ fn partial_one_collateral_one_slot_with_fees() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -280,16 +435,16 @@ fn partial_one_collateral_one_slot_with_fees() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(7551u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(7401u64))],
         }
     );
 
-    // 7551 col liq
-    // remaining = 20000 - 7551 = 12,449
-    // new limit = 12,449 * 0.1 * 0.5 = 622
-    // safe = 622 * 0.8 = 497
+    // 7401 col liq
+    // remaining = 20000 - 7401 = 12,599
+    // new limit = 12,599 * 0.1 * 0.5 = 629
+    // safe = 629 * 0.8 = 503
 
-    // new borrow amount = 1200 - 702 = 498
+    // new borrow amount = 1200 - 696 = 504
 
     let info = mock_info("token0000", &[]);
     let env = mock_env();
@@ -304,7 +459,7 @@ fn partial_one_collateral_one_slot_with_fees() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(7551u64),
+        amount: Uint128::from(7401u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -321,14 +476,14 @@ fn partial_one_collateral_one_slot_with_fees() {
                 to_address: "repay0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(702u128),
+                    amount: Uint128::from(696u128),
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "fee0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(6u128),
+                    amount: Uint128::from(7u128),
                 }]
             }))
         ]
@@ -355,7 +510,13 @@ fn partial_one_collateral_one_slot_with_fees_all() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -366,6 +527,11 @@ fn partial_one_collateral_one_slot_with_fees_all() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -373,6 +539,9 @@ fn partial_one_collateral_one_slot_with_fees_all() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -395,16 +564,16 @@ fn partial_one_collateral_one_slot_with_fees_all() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(7686u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(7532u64))],
         }
     );
 
-    // 7686 col liq
-    // remaining = 20000 - 7686 = 12,314
-    // new limit = 12,314 * 0.1 * 0.5 = 615.7
-    // safe = 615.7 * 0.8 = 492.56
+    // 7532 col liq
+    // remaining = 20000 - 7532 = 12,468
+    // new limit = 12,468 * 0.1 * 0.5 = 623.4
+    // safe = 623.4 * 0.8 = 498.72
 
-    // new borrow amount = 1200 - 708 = 492
+    // new borrow amount = 1200 - 701 = 499
 
     let info = mock_info("token0000", &[]);
     let env = mock_env();
@@ -419,7 +588,7 @@ fn partial_one_collateral_one_slot_with_fees_all() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(7686u64),
+        amount: Uint128::from(7532u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -436,21 +605,21 @@ fn partial_one_collateral_one_slot_with_fees_all() {
                 to_address: "repay0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(708u128),
+                    amount: Uint128::from(701u128),
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "fee0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(6u128),
+                    amount: Uint128::from(7u128),
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "liquidator00000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(6u128),
+                    amount: Uint128::from(7u128),
                 }]
             }))
         ]
@@ -477,7 +646,13 @@ fn partial_one_collateral_two_slots() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -488,6 +663,11 @@ fn partial_one_collateral_two_slots() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -495,6 +675,9 @@ fn partial_one_collateral_two_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -507,6 +690,9 @@ fn partial_one_collateral_two_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -522,18 +708,18 @@ fn partial_one_collateral_two_slots() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(42861u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(42841u64))],
         }
     );
 
-    // 42861 col liq
-    // remaining = 200000 - 42861 = 157,139
-    // new limit = 157140 * 0.1 * 0.5 = 7,856
-    // safe = 7,856 * 0.8 = 6,284 ****
+    // 42841 col liq
+    // remaining = 200000 - 42841 = 157,159
+    // new limit = 157,159 * 0.1 * 0.5 = 7,857
+    // safe = 7,857 * 0.8 = 6,285 ****
 
     // repay amount = 4015
 
-    // new borrow amount = 10300 - 4015 = 6,285 ****
+    // new borrow amount = 10300 - 4,015 = 6,285 ****
 
     let info = mock_info("token0000", &[]);
     let env = mock_env();
@@ -590,7 +776,13 @@ fn partial_one_collateral_two_slots_with_fees() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -601,6 +793,11 @@ fn partial_one_collateral_two_slots_with_fees() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -608,6 +805,9 @@ fn partial_one_collateral_two_slots_with_fees() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -620,6 +820,9 @@ fn partial_one_collateral_two_slots_with_fees() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -635,18 +838,18 @@ fn partial_one_collateral_two_slots_with_fees() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(44453u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(43626u64))],
         }
     );
 
-    // 44453 col liq
-    // remaining = 200000 - 44453 = 155,547
-    // new limit = 155,547 * 0.1 * 0.5 = 7,777
-    // safe = 7,777 * 0.8 = 6,221 ****
+    // 43626 col liq
+    // remaining = 200000 - 43626 = 156,374
+    // new limit = 156,374 * 0.1 * 0.5 = 7,818
+    // safe = 7,818 * 0.8 = 6,254 ****
 
-    // repay amount = 4076
+    // repay amount = 4044
 
-    // new borrow amount = 10300 - 4076 = 6,224 ****
+    // new borrow amount = 10300 - 4,044 = 6,256 ****
 
     let info = mock_info("token0000", &[]);
     let env = mock_env();
@@ -661,7 +864,7 @@ fn partial_one_collateral_two_slots_with_fees() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(44453u64),
+        amount: Uint128::from(43626u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -678,7 +881,7 @@ fn partial_one_collateral_two_slots_with_fees() {
                 to_address: "repay0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(4076u128), // repay amount = 4076
+                    amount: Uint128::from(4044u128), // repay amount = 4044
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
@@ -712,7 +915,13 @@ fn non_partial_liquidation() {
         liquidation_threshold: Uint256::from(1000000u128),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -723,6 +932,11 @@ fn non_partial_liquidation() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -730,6 +944,9 @@ fn non_partial_liquidation() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -752,7 +969,7 @@ fn non_partial_liquidation() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(12643u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(12632u64))],
         }
     );
 
@@ -769,7 +986,7 @@ fn non_partial_liquidation() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(12643u64),
+        amount: Uint128::from(12632u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -785,7 +1002,7 @@ fn non_partial_liquidation() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(1201u128), // repay all borrowed amount, overpay
+                amount: Uint128::from(1200u128), // repay all borrowed amount, overpay
             }]
         }))]
     );
@@ -811,7 +1028,13 @@ fn non_partial_liquidation_two_slots() {
         liquidation_threshold: Uint256::from(1000000u128),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -822,6 +1045,11 @@ fn non_partial_liquidation_two_slots() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -829,6 +1057,9 @@ fn non_partial_liquidation_two_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -841,6 +1072,9 @@ fn non_partial_liquidation_two_slots() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -856,7 +1090,7 @@ fn non_partial_liquidation_two_slots() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(12756u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(12745u64))],
         }
     );
 
@@ -873,7 +1107,7 @@ fn non_partial_liquidation_two_slots() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(12756u64),
+        amount: Uint128::from(12745u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -889,7 +1123,7 @@ fn non_partial_liquidation_two_slots() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(1200u128), // repay all borrowed amount
+                amount: Uint128::from(1199u128), // repay all borrowed amount
             }]
         }))]
     );
@@ -915,7 +1149,13 @@ fn non_partial_liquidation_with_fees() {
         liquidation_threshold: Uint256::from(1000000u128),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -926,6 +1166,11 @@ fn non_partial_liquidation_with_fees() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -933,6 +1178,9 @@ fn non_partial_liquidation_with_fees() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -955,7 +1203,7 @@ fn non_partial_liquidation_with_fees() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(12899u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(12760u64))],
         }
     );
 
@@ -972,7 +1220,7 @@ fn non_partial_liquidation_with_fees() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(12899u64),
+        amount: Uint128::from(12760u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -996,7 +1244,7 @@ fn non_partial_liquidation_with_fees() {
                 to_address: "fee0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(11u128),
+                    amount: Uint128::from(12u128),
                 }]
             }))
         ]
@@ -1023,7 +1271,13 @@ fn non_partial_liquidation_two_slots_with_fees() {
         liquidation_threshold: Uint256::from(1000000u128),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1034,6 +1288,11 @@ fn non_partial_liquidation_two_slots_with_fees() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -1041,6 +1300,9 @@ fn non_partial_liquidation_two_slots_with_fees() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -1053,6 +1315,9 @@ fn non_partial_liquidation_two_slots_with_fees() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -1068,7 +1333,7 @@ fn non_partial_liquidation_two_slots_with_fees() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(13015u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(12874u64))],
         }
     );
 
@@ -1085,7 +1350,7 @@ fn non_partial_liquidation_two_slots_with_fees() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(13015u64),
+        amount: Uint128::from(12874u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1102,14 +1367,14 @@ fn non_partial_liquidation_two_slots_with_fees() {
                 to_address: "repay0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(1200u128), // repay all borrowed amount
+                    amount: Uint128::from(1199u128), // repay all borrowed amount
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "fee0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(11u128),
+                    amount: Uint128::from(12u128),
                 }]
             }))
         ]
@@ -1136,7 +1401,13 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
         liquidation_threshold: Uint256::from(2000000000u128),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1147,6 +1418,11 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000000000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -1154,6 +1430,9 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -1166,6 +1445,9 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -1181,7 +1463,7 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(13833067518u64))],
+            collaterals: vec![("token0000".to_string(), Uint256::from(13822434876u64))],
         }
     );
 
@@ -1198,7 +1480,7 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(13833067518u64),
+        amount: Uint128::from(13822434876u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1222,7 +1504,7 @@ fn non_partial_liquidation_two_slots_with_fees_big_nums() {
                 to_address: "fee0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(13011300u128),
+                    amount: Uint128::from(13131313u128),
                 }]
             }))
         ]
@@ -1251,7 +1533,13 @@ fn partial_two_collaterals_ltv_diff() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1262,6 +1550,11 @@ fn partial_two_collaterals_ltv_diff() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -1270,12 +1563,20 @@ fn partial_two_collaterals_ltv_diff() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -1288,6 +1589,9 @@ fn partial_two_collaterals_ltv_diff() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0001".to_string(),
         premium_slot: 0u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -1307,23 +1611,23 @@ fn partial_two_collaterals_ltv_diff() {
         res,
         LiquidationAmountResponse {
             collaterals: vec![
-                ("token0000".to_string(), Uint256::from(19230775u64)),
-                ("token0001".to_string(), Uint256::from(399193550u64))
+                ("token0000".to_string(), Uint256::from(19230770u64)),
+                ("token0001".to_string(), Uint256::from(399193549u64))
             ],
         }
     );
 
-    // 19230775 col1 liq
-    // remaining = 1000000000 - 19230775 = 980,769,225
+    // 19230770 col1 liq
+    // remaining = 1000000000 - 19230770 = 980,769,230
 
-    // 399193550 col2 liq
-    // remaining = 1000000000 - 399193550 = 600,806,450
+    // 399193549 col2 liq
+    // remaining = 1000000000 - 399193549 = 600,806,451
 
-    // new limit = (980,769,225 * 1) * 0.99 + (600,806,450 * 1) * 0.01 = 976,969,597
-    // safe = 976,969,597 * 0.8 = 781,575,677 **
+    // new limit = (980,769,230 * 1) * 0.99 + (600,806,451 * 1) * 0.01 = 976,969,602
+    // safe = 976,969,602 * 0.8 = 781,575,681 **
 
-    // repayed = 19230775 +  399193550 = 418,424,325
-    // new borrow amount = 1200000000 - 418,424,325 = 781,575,675 **
+    // repayed = 19230770 + 399193549 = 418,424,319
+    // new borrow amount = 1200000000 - 418,424,319 = 781,575,681 **
 
     let env = mock_env();
     deps.querier.with_oracle_price(&[
@@ -1347,7 +1651,7 @@ fn partial_two_collaterals_ltv_diff() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(19230775u64),
+        amount: Uint128::from(19230770u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1364,14 +1668,14 @@ fn partial_two_collaterals_ltv_diff() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(19230775u128),
+                amount: Uint128::from(19230770u128),
             }]
         }))]
     );
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(399193550u64),
+        amount: Uint128::from(399193549u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1388,7 +1692,7 @@ fn partial_two_collaterals_ltv_diff() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(399193550u128),
+                amount: Uint128::from(399193549u128),
             }]
         }))]
     );
@@ -1416,7 +1720,13 @@ fn partial_two_collaterals_multi_slots_per_col() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1427,6 +1737,11 @@ fn partial_two_collaterals_multi_slots_per_col() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -1435,12 +1750,20 @@ fn partial_two_collaterals_multi_slots_per_col() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -1453,16 +1776,25 @@ fn partial_two_collaterals_multi_slots_per_col() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 11u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0001".to_string(),
         premium_slot: 3u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0001".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -1482,23 +1814,23 @@ fn partial_two_collaterals_multi_slots_per_col() {
         res,
         LiquidationAmountResponse {
             collaterals: vec![
-                ("token0000".to_string(), Uint256::from(3796u64)),
-                ("token0001".to_string(), Uint256::from(9637u64))
+                ("token0000".to_string(), Uint256::from(3776u64)),
+                ("token0001".to_string(), Uint256::from(9607u64))
             ],
         }
     );
 
-    // 3796 col1 liq
-    // remaining = 20000 - 3796 = 16,204
+    // 3776 col1 liq
+    // remaining = 20000 - 3776 = 16,224
 
-    // 9637 col2 liq
-    // remaining = 30000 - 9637 = 20,363
+    // 9607 col2 liq
+    // remaining = 30000 - 9607 = 20,393
 
-    // new limit = (16,204 * 0.1) * 0.5 + (20,363 * 0.05) * 0.3 = 1,115
-    // safe = 1,115 * 0.8 = 892 **
+    // new limit = (16,224 * 0.1) * 0.5 + (20,393 * 0.05) * 0.3 = 1,117
+    // safe = 1,117 * 0.8 = 893 **
 
-    // repayed = 355 + 453 = 808
-    // new borrow amount = 1700 - 808 = 892 **
+    // repayed = 353 + 452 = 805
+    // new borrow amount = 1700 - 805 = 895 **
 
     let env = mock_env();
     deps.querier.with_oracle_price(&[
@@ -1522,7 +1854,7 @@ fn partial_two_collaterals_multi_slots_per_col() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(3796u64),
+        amount: Uint128::from(3776u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1539,14 +1871,14 @@ fn partial_two_collaterals_multi_slots_per_col() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(355u128),
+                amount: Uint128::from(353u128),
             }]
         }))]
     );
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(9637u64),
+        amount: Uint128::from(9607u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1563,7 +1895,7 @@ fn partial_two_collaterals_multi_slots_per_col() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(453u128),
+                amount: Uint128::from(452u128),
             }]
         }))]
     );
@@ -1591,7 +1923,13 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1602,6 +1940,11 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -1610,12 +1953,20 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -1628,6 +1979,9 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0001".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -1647,23 +2001,23 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
         res,
         LiquidationAmountResponse {
             collaterals: vec![
-                ("token0000".to_string(), Uint256::from(3037u64)),
-                ("token0001".to_string(), Uint256::from(7775u64))
+                ("token0000".to_string(), Uint256::from(3019u64)),
+                ("token0001".to_string(), Uint256::from(7747u64))
             ],
         }
     );
 
-    // 3037 col1 liq
-    // remaining = 20000 - 3037 = 16,963
+    // 3019 col1 liq
+    // remaining = 20000 - 3019 = 16,981
 
-    // 7775 col2 liq
-    // remaining = 30000 - 7775 = 22,225
+    // 7747 col2 liq
+    // remaining = 30000 - 7747 = 22,253
 
-    // new limit = (16,963 * 0.1) * 0.5 + (22,225 * 0.05) * 0.3 = 1,181
-    // safe = 1,181 * 0.8 = 944 **
+    // new limit = (16,981 * 0.1) * 0.5 + (22,253 * 0.05) * 0.3 = 1,182
+    // safe = 1,182 * 0.8 = 945 **
 
-    // repayed = 288 + 369 = 657
-    // new borrow amount = 1600 - 657 = 943 **
+    // repayed = 286 + 367 = 653
+    // new borrow amount = 1600 - 653 = 947 **
 
     let env = mock_env();
     deps.querier.with_oracle_price(&[
@@ -1687,7 +2041,7 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(3037u64),
+        amount: Uint128::from(3019u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1704,14 +2058,14 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(288u128), // col1 repay = 288
+                amount: Uint128::from(286u128), // col1 repay = 286
             }]
         }))]
     );
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(7775u64),
+        amount: Uint128::from(7747u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1728,7 +2082,7 @@ fn partial_two_collaterals_one_slot_diff_ltv() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(369u128), // col2 repay = 369
+                amount: Uint128::from(367u128), // col2 repay = 367
             }]
         }))]
     );
@@ -1757,7 +2111,13 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1768,6 +2128,11 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -1776,6 +2141,11 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::WhitelistCollateral {
@@ -1783,12 +2153,20 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(1000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -1801,11 +2179,17 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0001".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0002".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -1830,27 +2214,27 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
         res,
         LiquidationAmountResponse {
             collaterals: vec![
-                ("token0000".to_string(), Uint256::from(3328u64)),
-                ("token0001".to_string(), Uint256::from(5824u64)),
-                ("token0002".to_string(), Uint256::from(1210u64)),
+                ("token0000".to_string(), Uint256::from(3310u64)),
+                ("token0001".to_string(), Uint256::from(5765u64)),
+                ("token0002".to_string(), Uint256::from(1209u64)),
             ],
         }
     );
 
-    // 3328 col1 liq
-    // remaining = 20000 - 3328 = 16,672
+    // 3310 col1 liq
+    // remaining = 20000 - 3310 = 16,690
 
-    // 5824 col2 liq
-    // remaining = 30000 - 5824 = 24,176
+    // 5765 col2 liq
+    // remaining = 30000 - 5765 = 24,235
 
-    // 1210 col3 liq
-    // remaining = 6000 - 1210 = 4,790
+    // 1209 col3 liq
+    // remaining = 6000 - 1209 = 4,791
 
-    // new limit = (16,672 * 0.1) * 0.5 + (24,176 * 0.05) * 0.7 + (4,790 * 1.1) * 0.3 = 3,260
-    // safe = 3,260 * 0.8 = 2,608 **
+    // new limit = (16,690 * 0.1) * 0.5 + (24,235 * 0.05) * 0.7 + (4,791 * 1.1) * 0.3 = 3,263
+    // safe = 3,263 * 0.8 = 2,610 **
 
-    // repayed = 316 + 262 + 1317 = 1,895
-    // new borrow amount = 4500 - 1,895 = 2,605 **
+    // repayed = 314 + 259 + 1316 = 1,889
+    // new borrow amount = 4500 - 1,889 = 2,611 **
     let env = mock_env();
     deps.querier.with_oracle_price(&[
         (
@@ -1881,7 +2265,7 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(3328u64),
+        amount: Uint128::from(3310u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1898,14 +2282,14 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(316u128), // col1 repay = 316
+                amount: Uint128::from(314u128), // col1 repay = 314
             }]
         }))]
     );
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(5824u64),
+        amount: Uint128::from(5765u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1922,14 +2306,14 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(262u128), // col2 repay = 262
+                amount: Uint128::from(259u128), // col2 repay = 259
             }]
         }))]
     );
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(1210u64),
+        amount: Uint128::from(1209u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -1946,7 +2330,7 @@ fn partial_three_collaterals_one_slot_diff_ltv() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(1317u128), // col3 repay = 1317
+                amount: Uint128::from(1316u128), // col3 repay = 1316
             }]
         }))]
     );
@@ -1975,7 +2359,13 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -1986,6 +2376,11 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(100000000000000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -1994,6 +2389,11 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(100000000000000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::WhitelistCollateral {
@@ -2001,12 +2401,20 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(100000000000000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -2019,11 +2427,17 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0001".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0002".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -2054,7 +2468,7 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
         LiquidationAmountResponse {
             collaterals: vec![
                 ("token0000".to_string(), Uint256::from(69498951644u64)),
-                ("token0001".to_string(), Uint256::from(2471406687u64)),
+                ("token0001".to_string(), Uint256::from(2471406686u64)),
                 ("token0002".to_string(), Uint256::from(50965898u64)),
             ],
         }
@@ -2063,17 +2477,17 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
     // 69498951644 col1 liq
     // remaining = 200000000000 - 69498951644 = 130,501,048,356
 
-    // 2471406687 col2 liq
-    // remaining = 3000000000 - 2471406687 = 528,593,313
+    // 2471406686 col2 liq
+    // remaining = 3000000000 - 2471406686 = 528,593,314
 
     // 50965898 col3 liq
     // remaining = 60000000 - 50965898 = 9,034,102
 
-    // new limit = (130,501,048,356 * 10) * 0.5 + (528,593,313 * 5) * 0.4 + (9,034,102 * 110) * 0.3 = 653,860,553,772
-    // safe = 653,860,553,772 * 0.8 = 523,088,443,017 **
+    // new limit = (130,501,048,356 * 10) * 0.5 + (528,593,314 * 5) * 0.4 + (9,034,102 * 110) * 0.3 = 653,860,553,774
+    // safe = 653,860,553,774 * 0.8 = 523,088,443,019 **
 
-    // repayed = 660240040618 + 11121330091 + 5550186292 = 676,911,557,001
-    // new borrow amount = 1200000000000 - 676,911,557,001= 523,088,442,999 **
+    // repayed = 660240040618 + 11121330087 + 5550186292 = 676,911,556,997
+    // new borrow amount = 1200000000000 - 676,911,556,997 = 523,088,443,003 **
 
     let env = mock_env();
     deps.querier.with_oracle_price(&[
@@ -2129,7 +2543,7 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(2471406687u64),
+        amount: Uint128::from(2471406686u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -2146,7 +2560,7 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(11121330091u128), // col2 repay = 11121330091
+                amount: Uint128::from(11121330087u128), // col2 repay = 11121330087
             }]
         }))]
     );
@@ -2199,7 +2613,13 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -2210,6 +2630,11 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(100000000000000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -2218,6 +2643,11 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(100000000000000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::WhitelistCollateral {
@@ -2225,12 +2655,20 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(100000000000000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -2243,11 +2681,17 @@ fn partial_three_collaterals_one_slot_diff_ltv_big_amounts_2() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0001".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0002".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -2422,7 +2866,13 @@ fn not_enough_bids_for_one_of_two_col() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -2433,6 +2883,11 @@ fn not_enough_bids_for_one_of_two_col() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -2441,12 +2896,20 @@ fn not_enough_bids_for_one_of_two_col() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -2466,6 +2929,9 @@ fn not_enough_bids_for_one_of_two_col() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0001".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -2485,23 +2951,23 @@ fn not_enough_bids_for_one_of_two_col() {
         res,
         LiquidationAmountResponse {
             collaterals: vec![
-                ("token0000".to_string(), Uint256::from(11862304u64)),
-                ("token0001".to_string(), Uint256::from(6541171u64))
+                ("token0000".to_string(), Uint256::from(11655875u64)),
+                ("token0001".to_string(), Uint256::from(6451613u64))
             ],
         }
     );
 
-    // 11862304 col1 liq
-    // remaining = 20000000 - 11862304 = 8,137,696
+    // 11655875 col1 liq
+    // remaining = 20000000 - 11655875 = 8,344,125
 
-    // 6541171 col2 liq
-    // remaining = 30000000 - 6541171 = 23,458,829
+    // 6451613 col2 liq
+    // remaining = 30000000 - 6451613 = 23,548,387
 
-    // new limit = (8,137,696 * 0.1) * 0.5 + (23,458,829 * 0.05) * 0.3 = 758,767
-    // safe = 758,767 * 0.8 = 607,013 **
+    // new limit = (8,344,125 * 0.1) * 0.5 + (23,548,387 * 0.05) * 0.3 = 770,432
+    // safe = 770,432 * 0.8 = 616,345 **
 
-    // repayed = 1104602 + 288523 = 1,393,125
-    // new borrow amount = 2800000 - 1,393,125 = 1,406,875 // loan cant be brought back to safe
+    // repayed = 1096235 + 287419 = 1,383,654
+    // new borrow amount = 2800000 - 1,383,654 = 1,416,346 // loan cant be brought back to safe
 
     let env = mock_env();
     deps.querier.with_oracle_price(&[
@@ -2525,7 +2991,7 @@ fn not_enough_bids_for_one_of_two_col() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(11862304u64),
+        amount: Uint128::from(11655875u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -2543,14 +3009,14 @@ fn not_enough_bids_for_one_of_two_col() {
                 to_address: "repay0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(1104602u128),
+                    amount: Uint128::from(1096235u128),
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "fee0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(11157u128),
+                    amount: Uint128::from(11073u128),
                 }]
             }))
         ]
@@ -2558,7 +3024,7 @@ fn not_enough_bids_for_one_of_two_col() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(6541171u64),
+        amount: Uint128::from(6451613u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -2576,14 +3042,14 @@ fn not_enough_bids_for_one_of_two_col() {
                 to_address: "repay0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(288523u128),
+                    amount: Uint128::from(287419u128),
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "fee0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(2913u128),
+                    amount: Uint128::from(2903u128),
                 }]
             }))
         ]
@@ -2612,7 +3078,13 @@ fn integration_test_simul() {
         liquidation_threshold: Uint256::zero(),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -2623,6 +3095,11 @@ fn integration_test_simul() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(100000000000000000000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -2631,6 +3108,9 @@ fn integration_test_simul() {
         let msg = ExecuteMsg::SubmitBid {
             collateral_token: "token0000".to_string(),
             premium_slot: slot as u8,
+            expires_at: None,
+            max_price: None,
+            auto_rebid: None,
         };
         let info = mock_info(
             "addr0000",
@@ -2645,6 +3125,9 @@ fn integration_test_simul() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 30u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -2669,13 +3152,13 @@ fn integration_test_simul() {
     assert_eq!(
         res,
         LiquidationAmountResponse {
-            collaterals: vec![("token0000".to_string(), Uint256::from(8489891541u64)),],
+            collaterals: vec![("token0000".to_string(), Uint256::from(8484841036u64)),],
         }
     );
 
-    // 8489891541
+    // 8484841036
 
-    // 10000000000 - 8489891541 = 1,510,108,459
+    // 10000000000 - 8484841036 = 1,510,108,459
     // 1,510,108,459 * 0.9 * 0.6 * 0.8 = 652,366,854
 
     // 6000000000 - 5347633145 = 652,366,855
@@ -2692,7 +3175,7 @@ fn integration_test_simul() {
 
     let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
         sender: "custody0000".to_string(),
-        amount: Uint128::from(8489891541u64),
+        amount: Uint128::from(8484841036u64),
         msg: to_binary(&Cw20HookMsg::ExecuteBid {
             liquidator: "liquidator00000".to_string(),
             fee_address: Some("fee0000".to_string()),
@@ -2709,7 +3192,7 @@ fn integration_test_simul() {
             to_address: "repay0000".to_string(),
             amount: vec![Coin {
                 denom: "uusd".to_string(),
-                amount: Uint128::from(5347633145u128),
+                amount: Uint128::from(5345451327u128),
             }]
         }))]
     );
@@ -2,10 +2,15 @@ use crate::contract::{execute, instantiate, query};
 use crate::testing::mock_querier::mock_dependencies;
 use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::testing::{mock_env, mock_info};
-use cosmwasm_std::{from_binary, Coin, Decimal, Uint128};
-use moneymarket::liquidation_queue::{
-    BidPoolResponse, BidPoolsResponse, BidResponse, BidsResponse, CollateralInfoResponse,
-    ExecuteMsg, InstantiateMsg, LiquidationAmountResponse, QueryMsg,
+use cosmwasm_std::{
+    from_binary, to_binary, BankMsg, Coin, CosmosMsg, Decimal, SubMsg, Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cdp::liquidation_queue::{
+    BidDepthResponse, BidDepthSlotResponse, BidPoolResponse, BidPoolsResponse, BidResponse,
+    BidsResponse, ClaimableBidResponse, ClaimableLiquidationsResponse, CollateralInfoResponse,
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, LiquidationAmountResponse, PremiumSchedule, QueryMsg,
+    SimulateExecuteBidResponse, SlotFillResponse, WaitingBidResponse,
 };
 
 #[test]
@@ -31,7 +36,13 @@ fn query_liquidation_amount() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -42,6 +53,11 @@ fn query_liquidation_amount() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
@@ -50,6 +66,11 @@ fn query_liquidation_amount() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::WhitelistCollateral {
@@ -57,12 +78,20 @@ fn query_liquidation_amount() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -75,11 +104,17 @@ fn query_liquidation_amount() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0001".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0002".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -133,16 +168,16 @@ fn query_liquidation_amount() {
     };
 
     // fee_deductor = 0.931095
-    // liquidation_ratio = 0.3580014213
+    // liquidation_ratio = 0.35442
     let res = query(deps.as_ref(), mock_env(), query_msg).unwrap();
     let res: LiquidationAmountResponse = from_binary(&res).unwrap();
     assert_eq!(
         res,
         LiquidationAmountResponse {
             collaterals: vec![
-                ("token0000".to_string(), Uint256::from(358003u64)),
-                ("token0001".to_string(), Uint256::from(716005u64)),
-                ("token0002".to_string(), Uint256::from(1074007u64)),
+                ("token0000".to_string(), Uint256::from(354420u64)),
+                ("token0001".to_string(), Uint256::from(708843u64)),
+                ("token0002".to_string(), Uint256::from(1063265u64)),
             ],
         }
     );
@@ -168,7 +203,13 @@ fn query_bids() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -179,6 +220,11 @@ fn query_bids() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -186,6 +232,9 @@ fn query_bids() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -198,11 +247,17 @@ fn query_bids() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -231,6 +286,9 @@ fn query_bids() {
             sum_snapshot: Decimal256::zero(),
             epoch_snapshot: Uint128::zero(),
             scale_snapshot: Uint128::zero(),
+            expires_at: None,
+            max_price: None,
+            auto_rebid: None,
         }
     );
 
@@ -264,6 +322,9 @@ fn query_bids() {
                     sum_snapshot: Decimal256::zero(),
                     epoch_snapshot: Uint128::zero(),
                     scale_snapshot: Uint128::zero(),
+                    expires_at: None,
+                    max_price: None,
+                    auto_rebid: None,
                 },
                 BidResponse {
                     idx: Uint128::from(2u128),
@@ -277,6 +338,9 @@ fn query_bids() {
                     sum_snapshot: Decimal256::zero(),
                     epoch_snapshot: Uint128::zero(),
                     scale_snapshot: Uint128::zero(),
+                    expires_at: None,
+                    max_price: None,
+                    auto_rebid: None,
                 },
                 BidResponse {
                     idx: Uint128::from(3u128),
@@ -290,6 +354,9 @@ fn query_bids() {
                     sum_snapshot: Decimal256::zero(),
                     epoch_snapshot: Uint128::zero(),
                     scale_snapshot: Uint128::zero(),
+                    expires_at: None,
+                    max_price: None,
+                    auto_rebid: None,
                 }
             ]
         }
@@ -324,6 +391,9 @@ fn query_bids() {
                 sum_snapshot: Decimal256::zero(),
                 epoch_snapshot: Uint128::zero(),
                 scale_snapshot: Uint128::zero(),
+                expires_at: None,
+                max_price: None,
+                auto_rebid: None,
             }]
         }
     );
@@ -349,7 +419,13 @@ fn query_bid_pools() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -360,6 +436,11 @@ fn query_bid_pools() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128), // to get instant activation
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -367,6 +448,9 @@ fn query_bid_pools() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 5u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -379,11 +463,17 @@ fn query_bid_pools() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 6u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "token0000".to_string(),
         premium_slot: 10u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
 
@@ -504,7 +594,13 @@ fn query_collateral_info() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -515,6 +611,11 @@ fn query_collateral_info() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -537,6 +638,478 @@ fn query_collateral_info() {
             max_slot: 30u8,
             bid_threshold: Uint256::from(10000u128),
             premium_rate_per_slot: Decimal256::percent(1),
+            premium_schedule: PremiumSchedule::Linear {
+                rate_per_slot: Decimal256::percent(1),
+            },
+            bid_fee: Decimal256::percent(1),
+            liquidator_fee: Decimal256::percent(0),
+            waiting_period: 60u64,
+            liquidation_threshold: Uint256::from(100000000u64),
+        }
+    );
+}
+
+#[test]
+fn query_claimable_liquidations() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        liquidator_fee: Decimal256::zero(),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::SubmitBid {
+        collateral_token: "asset0000".to_string(),
+        premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::ActivateBids {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: None,
+    };
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60u64);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // second bid is still in its waiting period
+    let msg = ExecuteMsg::SubmitBid {
+        collateral_token: "asset0000".to_string(),
+        premium_slot: 2u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(500000u128),
+        }],
+    );
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // required_stable 495,000
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: None,
+            repay_address: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let claimable_response: ClaimableLiquidationsResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::ClaimableLiquidations {
+                bidder: "addr0000".to_string(),
+                collateral_token: "asset0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        claimable_response,
+        ClaimableLiquidationsResponse {
+            bidder: "addr0000".to_string(),
+            collateral_token: "asset0000".to_string(),
+            claimable_collateral: Uint256::from(1000000u64),
+            bids: vec![ClaimableBidResponse {
+                idx: Uint128::from(1u128),
+                premium_slot: 1u8,
+                remaining_amount: Uint256::from(505000u64),
+                claimable_collateral: Uint256::from(1000000u64),
+            }],
+            waiting_bids: vec![WaitingBidResponse {
+                idx: Uint128::from(2u128),
+                premium_slot: 2u8,
+                amount: Uint256::from(500000u64),
+                wait_end: env.block.time.plus_seconds(60u64).seconds(),
+            }],
+        }
+    );
+
+    // the claim transfers the reported amount
+    let msg = ExecuteMsg::ClaimLiquidations {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: None,
+    };
+    let info = mock_info("addr0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "asset0000".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: "addr0000".to_string(),
+                amount: Uint128::from(1000000u128),
+            })
+            .unwrap(),
+        }))]
+    );
+}
+
+#[test]
+fn query_simulate_execute_bid() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::percent(1),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    for premium_slot in [1u8, 2u8] {
+        let msg = ExecuteMsg::SubmitBid {
+            collateral_token: "asset0000".to_string(),
+            premium_slot,
+            expires_at: None,
+            max_price: None,
+            auto_rebid: None,
+        };
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    let msg = ExecuteMsg::ActivateBids {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: None,
+    };
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60u64);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // slot 1 is emptied by 2,020,202 collateral, slot 2 takes the rest
+    let simulate_response: SimulateExecuteBidResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateExecuteBid {
+                collateral_token: "asset0000".to_string(),
+                amount: Uint256::from(3000000u64),
+                price: Decimal256::percent(50),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        simulate_response,
+        SimulateExecuteBidResponse {
+            repay_amount: Uint256::from(1450499u64),
+            bid_fee: Uint256::from(14801u64),
+            liquidator_fee: Uint256::from(14801u64),
+            slots: vec![
+                SlotFillResponse {
+                    premium_slot: 1u8,
+                    bid_amount: Uint256::from(1000000u64),
+                    collateral_amount: Uint256::from(2020202u64),
+                },
+                SlotFillResponse {
+                    premium_slot: 2u8,
+                    bid_amount: Uint256::from(480101u64),
+                    collateral_amount: Uint256::from(979798u64),
+                },
+            ],
+            filled: true,
+        }
+    );
+
+    // more collateral than the bids can absorb
+    let simulate_response: SimulateExecuteBidResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateExecuteBid {
+                collateral_token: "asset0000".to_string(),
+                amount: Uint256::from(5000000u64),
+                price: Decimal256::percent(50),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert!(!simulate_response.filled);
+
+    // the execution matches the simulation
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(3000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: Some("fee0000".to_string()),
+            repay_address: Some("repay0000".to_string()),
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "repay0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(1450499u128),
+                }]
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "fee0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(14801u128),
+                }]
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "liquidator0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(14801u128),
+                }]
+            })),
+        ]
+    );
+}
+
+#[test]
+fn query_bid_depth() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        liquidator_fee: Decimal256::zero(),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // the bid with a max price of 0.4 is not usable at 0.5
+    let bids = [
+        (1u8, None, 1000000u128),
+        (1u8, Some(Decimal256::percent(40)), 1000000u128),
+        (2u8, None, 1000000u128),
+    ];
+    for (premium_slot, max_price, amount) in bids {
+        let msg = ExecuteMsg::SubmitBid {
+            collateral_token: "asset0000".to_string(),
+            premium_slot,
+            expires_at: None,
+            max_price,
+            auto_rebid: None,
+        };
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(amount),
+            }],
+        );
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    let msg = ExecuteMsg::ActivateBids {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: None,
+    };
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60u64);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::SubmitBid {
+        collateral_token: "asset0000".to_string(),
+        premium_slot: 2u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
+    };
+    let info = mock_info(
+        "addr0001",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(300000u128),
+        }],
+    );
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let bid_depth_response: BidDepthResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env,
+            QueryMsg::BidDepth {
+                collateral_token: "asset0000".to_string(),
+                price: Decimal256::percent(50),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(
+        bid_depth_response,
+        BidDepthResponse {
+            collateral_token: "asset0000".to_string(),
+            price: Decimal256::percent(50),
+            slots: vec![
+                BidDepthSlotResponse {
+                    premium_slot: 1u8,
+                    premium_rate: Decimal256::percent(1),
+                    purchase_price: Decimal256::from_ratio(495, 1000),
+                    active_amount: Uint256::from(1000000u64),
+                    cumulative_collateral: Uint256::from(2020202u64),
+                    waiting_amount: Uint256::zero(),
+                },
+                BidDepthSlotResponse {
+                    premium_slot: 2u8,
+                    premium_rate: Decimal256::percent(2),
+                    purchase_price: Decimal256::percent(49),
+                    active_amount: Uint256::from(1000000u64),
+                    cumulative_collateral: Uint256::from(4061018u64),
+                    waiting_amount: Uint256::from(300000u64),
+                },
+            ],
         }
     );
 }
@@ -2,16 +2,22 @@ use crate::contract::{execute, instantiate, query};
 use crate::testing::mock_querier::mock_dependencies;
 
 use crate::error::ContractError;
+use cdp_mock_swap::contract as mock_swap;
+use cdp_mock_swap::msg::{
+    ExecuteMsg as MockSwapExecuteMsg, InstantiateMsg as MockSwapInstantiateMsg,
+};
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::testing::{mock_env, mock_info};
+use cosmwasm_std::testing::{mock_env, mock_info, MOCK_CONTRACT_ADDR};
 use cosmwasm_std::{
     attr, from_binary, to_binary, BankMsg, Coin, CosmosMsg, Decimal, StdError, SubMsg, Uint128,
+    WasmMsg,
 };
-use cw20::Cw20ReceiveMsg;
-use moneymarket::liquidation_queue::{
-    BidResponse, CollateralInfoResponse, ConfigResponse, Cw20HookMsg, ExecuteMsg, InstantiateMsg,
-    QueryMsg,
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg};
+use cdp::liquidation_queue::{
+    AutoRebid, BidPoolResponse, BidResponse, CollateralInfoResponse, ConfigResponse, Cw20HookMsg,
+    ExecuteMsg, InstantiateMsg, PremiumSchedule, QueryMsg, SimulateExecuteBidResponse,
 };
+use cdp::swap_adapter::Cw20HookMsg as SwapCw20HookMsg;
 
 #[test]
 fn proper_initialization() {
@@ -27,7 +33,13 @@ fn proper_initialization() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -51,7 +63,11 @@ fn proper_initialization() {
             liquidation_threshold: Uint256::from(100000000u64),
             price_timeframe: 60u64,
             waiting_period: 60u64,
-            overseer: "overseer0000".to_string(),
+            control_contract: "control0000".to_string(),
+            close_factor: Decimal256::one(),
+            prune_tip_rate: Decimal256::zero(),
+            min_limit_bid_amount: Uint256::zero(),
+            swap_adapter: None,
         }
     );
 }
@@ -70,7 +86,13 @@ fn update_config() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -78,20 +100,14 @@ fn update_config() {
 
     // update owner
     let info = mock_info("owner0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner: Some("owner0001".to_string()),
-        oracle_contract: None,
-        safe_ratio: None,
-        bid_fee: None,
-        liquidator_fee: None,
-        liquidation_threshold: None,
-        price_timeframe: None,
-        waiting_period: None,
-        overseer: None,
+    let msg = ExecuteMsg::SetOwner {
+        new_owner_addr: "owner0001".to_string(),
     };
-
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg).unwrap();
     assert_eq!(0, res.messages.len());
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::ExecuteChange { id: 1 }).unwrap();
+    let info = mock_info("owner0001", &[]);
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::AcceptOwnership {}).unwrap();
 
     // it worked, let's query the state
     let value: ConfigResponse =
@@ -108,14 +124,17 @@ fn update_config() {
             liquidation_threshold: Uint256::from(100000000u64),
             price_timeframe: 60u64,
             waiting_period: 60u64,
-            overseer: "overseer0000".to_string(),
+            control_contract: "control0000".to_string(),
+            close_factor: Decimal256::one(),
+            prune_tip_rate: Decimal256::zero(),
+            min_limit_bid_amount: Uint256::zero(),
+            swap_adapter: None,
         }
     );
 
-    // Update left items
+    // Update left items, applied once the timelock has passed
     let info = mock_info("owner0001", &[]);
     let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
         oracle_contract: Some("oracle0001".to_string()),
         safe_ratio: Some(Decimal256::percent(15)),
         bid_fee: Some(Decimal256::percent(2)),
@@ -123,11 +142,17 @@ fn update_config() {
         liquidation_threshold: Some(Uint256::from(150000000u64)),
         price_timeframe: Some(120u64),
         waiting_period: Some(100u64),
-        overseer: Some("overseer0001".to_string()),
+        control_contract: Some("control0001".to_string()),
+        stable_denom: None,
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
     };
 
-    let res = execute(deps.as_mut(), mock_env(), info, msg).unwrap();
+    let res = execute(deps.as_mut(), mock_env(), info.clone(), msg.clone()).unwrap();
     assert_eq!(0, res.messages.len());
+    execute(deps.as_mut(), mock_env(), info, ExecuteMsg::ExecuteChange { id: 2 }).unwrap();
 
     // it worked, let's query the state
     let value: ConfigResponse =
@@ -144,28 +169,23 @@ fn update_config() {
             liquidation_threshold: Uint256::from(150000000u64),
             price_timeframe: 120u64,
             waiting_period: 100u64,
-            overseer: "overseer0001".to_string(),
+            control_contract: "control0001".to_string(),
+            close_factor: Decimal256::one(),
+            prune_tip_rate: Decimal256::zero(),
+            min_limit_bid_amount: Uint256::zero(),
+            swap_adapter: None,
         }
     );
 
     // Unauthorized err
     let info = mock_info("owner0000", &[]);
-    let msg = ExecuteMsg::UpdateConfig {
-        owner: None,
-        oracle_contract: Some("oracle0001".to_string()),
-        safe_ratio: Some(Decimal256::percent(1)),
-        bid_fee: Some(Decimal256::percent(2)),
-        liquidator_fee: Some(Decimal256::percent(1)),
-        liquidation_threshold: Some(Uint256::from(150000000u64)),
-        price_timeframe: Some(100u64),
-        waiting_period: Some(100u64),
-        overseer: Some("overseer0001".to_string()),
-    };
-
     let err = execute(deps.as_mut(), mock_env(), info, msg).unwrap_err();
     assert_eq!(
         err,
-        ContractError::Std(StdError::generic_err("unauthorized"))
+        ContractError::Unauthorized(
+            "propose_timelocked_change".to_string(),
+            "owner0000".to_string()
+        )
     );
 }
 
@@ -185,7 +205,13 @@ fn submit_bid() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -196,6 +222,11 @@ fn submit_bid() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -203,6 +234,9 @@ fn submit_bid() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "asset0000".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info("addr0000", &[]);
     let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
@@ -283,6 +317,9 @@ fn submit_bid() {
             wait_end: Some(wait_end.seconds()),
             epoch_snapshot: Uint128::zero(),
             scale_snapshot: Uint128::zero(),
+            expires_at: None,
+            max_price: None,
+            auto_rebid: None,
         }
     );
 }
@@ -303,7 +340,13 @@ fn activate_bid() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -314,6 +357,11 @@ fn activate_bid() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -321,6 +369,9 @@ fn activate_bid() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "asset0000".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -404,6 +455,9 @@ fn activate_bid() {
             wait_end: None,
             epoch_snapshot: Uint128::zero(),
             scale_snapshot: Uint128::zero(),
+            expires_at: None,
+            max_price: None,
+            auto_rebid: None,
         }
     );
 }
@@ -424,7 +478,13 @@ fn retract_bid() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -435,6 +495,11 @@ fn retract_bid() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -442,6 +507,9 @@ fn retract_bid() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "asset0000".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -497,7 +565,13 @@ fn retract_unactive_bid() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -508,6 +582,11 @@ fn retract_unactive_bid() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -515,6 +594,9 @@ fn retract_unactive_bid() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "asset0000".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -576,7 +658,13 @@ fn execute_bid() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 100000u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -597,6 +685,11 @@ fn execute_bid() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), env, info, msg).unwrap();
@@ -604,6 +697,9 @@ fn execute_bid() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "asset0000".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -670,21 +766,21 @@ fn execute_bid() {
                 to_address: "repay0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(480297u128), // 485100 / (1 + tax_rate)
+                    amount: Uint128::from(485100u128), // 485100, no tax is deducted
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "fee0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(4900u128), // 4950 / (1 + tax_rate)
+                    amount: Uint128::from(4950u128), // 4950, no tax is deducted
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "liquidator0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(4900u128), // 4950 / (1 + tax_rate)
+                    amount: Uint128::from(4950u128), // 4950, no tax is deducted
                 }]
             })),
         ]
@@ -708,21 +804,21 @@ fn execute_bid() {
                 to_address: "custody0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(480297u128), // 485100 / (1 + tax_rate)
+                    amount: Uint128::from(485100u128), // 485100, no tax is deducted
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "custody0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(4900u128), // 4950 / (1 + tax_rate)
+                    amount: Uint128::from(4950u128), // 4950, no tax is deducted
                 }]
             })),
             SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
                 to_address: "liquidator0000".to_string(),
                 amount: vec![Coin {
                     denom: "uusd".to_string(),
-                    amount: Uint128::from(4900u128), // 4950 / (1 + tax_rate)
+                    amount: Uint128::from(4950u128), // 4950, no tax is deducted
                 }]
             })),
         ]
@@ -766,7 +862,13 @@ fn claim_liquidations() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 1000000u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -787,6 +889,11 @@ fn claim_liquidations() {
         max_slot: 30u8,
         bid_threshold: Uint256::zero(),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -794,6 +901,9 @@ fn claim_liquidations() {
     let msg = ExecuteMsg::SubmitBid {
         collateral_token: "asset0000".to_string(),
         premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
     };
     let info = mock_info(
         "addr0000",
@@ -862,7 +972,13 @@ fn update_collateral_info() {
         liquidation_threshold: Uint256::from(100000000u64),
         price_timeframe: 60u64,
         waiting_period: 60u64,
-        overseer: "overseer0000".to_string(),
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
     };
 
     let info = mock_info("addr0000", &[]);
@@ -873,6 +989,11 @@ fn update_collateral_info() {
         max_slot: 30u8,
         bid_threshold: Uint256::from(10000u128),
         premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
     let info = mock_info("owner0000", &[]);
     execute(deps.as_mut(), mock_env(), info, msg).unwrap();
@@ -881,6 +1002,11 @@ fn update_collateral_info() {
         collateral_token: "token0000".to_string(),
         bid_threshold: Some(Uint256::from(20000u128)),
         max_slot: Some(20u8),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
     };
 
     // unauthorized attempt
@@ -888,7 +1014,7 @@ fn update_collateral_info() {
     let err = execute(deps.as_mut(), mock_env(), info, msg.clone()).unwrap_err();
     assert_eq!(
         err,
-        ContractError::Std(StdError::generic_err("unauthorized"))
+        ContractError::Unauthorized("update_collateral_info".to_string(), "addr0000".to_string())
     );
 
     // successfull attempt
@@ -914,6 +1040,1405 @@ fn update_collateral_info() {
             max_slot: 20u8,                          // updated max_slot
             bid_threshold: Uint256::from(20000u128), // updated bid threshold
             premium_rate_per_slot: Decimal256::percent(1),
+            premium_schedule: PremiumSchedule::Linear {
+                rate_per_slot: Decimal256::percent(1),
+            },
+            bid_fee: Decimal256::percent(1),
+            liquidator_fee: Decimal256::percent(0),
+            waiting_period: 60u64,
+            liquidation_threshold: Uint256::from(100000000u64),
         }
     );
 }
+
+#[test]
+fn prune_expired_bids() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        liquidator_fee: Decimal256::zero(),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: Some(Decimal256::percent(1)),
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // bid 1 expires after its waiting period, bid 2 never expires
+    let expires_at = env.block.time.plus_seconds(100u64).seconds();
+    for (bidder, expires_at) in [("addr0000", Some(expires_at)), ("addr0001", None)] {
+        let msg = ExecuteMsg::SubmitBid {
+            collateral_token: "asset0000".to_string(),
+            premium_slot: 1u8,
+            expires_at,
+            max_price: None,
+            auto_rebid: None,
+        };
+        let info = mock_info(
+            bidder,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    let msg = ExecuteMsg::ActivateBidsFor {
+        collateral_token: "asset0000".to_string(),
+        limit: None,
+    };
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60u64);
+    let info = mock_info("keeper0000", &[]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // the expired bid is not used, the whole liquidation is filled by bid 2
+    // required_stable 495,000
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(100u64);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: None,
+            repay_address: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    for (bid_idx, amount, pending_collateral) in [(1u128, 1000000u64, 0u64), (2, 505000, 1000000)]
+    {
+        let bid_response: BidResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Bid {
+                    bid_idx: Uint128::from(bid_idx),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(bid_response.amount, Uint256::from(amount));
+        assert_eq!(
+            bid_response.pending_liquidated_collateral,
+            Uint256::from(pending_collateral)
+        );
+    }
+
+    // the expired bid is refunded, the keeper receives the tip
+    let msg = ExecuteMsg::PruneExpiredBids {
+        collateral_token: "asset0000".to_string(),
+        limit: None,
+    };
+    let info = mock_info("keeper0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "addr0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(990000u128),
+                }]
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "keeper0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(10000u128),
+                }]
+            })),
+        ]
+    );
+
+    let err = query(
+        deps.as_ref(),
+        env,
+        QueryMsg::Bid {
+            bid_idx: Uint128::from(1u128),
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("No bids with the specified information exist")
+    );
+}
+
+#[test]
+fn execute_bid_past_expired_bids() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        liquidator_fee: Decimal256::zero(),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // bids 1 to 12 expire one after the other once active, bid 13 never expires
+    let start = env.block.time;
+    let mut bids = vec![];
+    for i in 0..12u64 {
+        bids.push(("addr0000", Some(start.plus_seconds(100u64 + i).seconds())));
+    }
+    bids.push(("addr0001", None));
+    for (bidder, expires_at) in bids {
+        let msg = ExecuteMsg::SubmitBid {
+            collateral_token: "asset0000".to_string(),
+            premium_slot: 1u8,
+            expires_at,
+            max_price: None,
+            auto_rebid: None,
+        };
+        let info = mock_info(
+            bidder,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    let mut env = mock_env();
+    env.block.time = start.plus_seconds(60u64);
+    let msg = ExecuteMsg::ActivateBidsFor {
+        collateral_token: "asset0000".to_string(),
+        limit: Some(31u8),
+    };
+    let info = mock_info("keeper0000", &[]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // once all of them expired only bid 13 is eligible
+    let mut env = mock_env();
+    env.block.time = start.plus_seconds(200u64);
+    let simulate_response: SimulateExecuteBidResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateExecuteBid {
+                collateral_token: "asset0000".to_string(),
+                amount: Uint256::from(3000000u64),
+                price: Decimal256::percent(50),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulate_response.repay_amount, Uint256::from(1000000u64));
+    assert!(!simulate_response.filled);
+
+    // bid 13 fills the liquidation
+    // required_stable 495,000
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: None,
+            repay_address: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+    for (bid_idx, amount, pending_collateral) in [
+        (1u128, 1000000u64, 0u64),
+        (12, 1000000, 0),
+        (13, 505000, 1000000),
+    ] {
+        let bid_response: BidResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Bid {
+                    bid_idx: Uint128::from(bid_idx),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(bid_response.amount, Uint256::from(amount));
+        assert_eq!(
+            bid_response.pending_liquidated_collateral,
+            Uint256::from(pending_collateral)
+        );
+    }
+
+    // the 505,000 left in bid 13 can not repay the 990,000 of another 2,000,000
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: "custody0000".to_string(),
+            amount: Uint128::from(2000000u128),
+            msg: to_binary(&Cw20HookMsg::ExecuteBid {
+                liquidator: "liquidator0000".to_string(),
+                fee_address: None,
+                repay_address: None,
+            })
+            .unwrap(),
+        }),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Not enough bids to execute this liquidation"
+        ))
+    );
+
+    // pruning only cleans up, ten bids at a time
+    for pruned in ["10", "2"] {
+        let msg = ExecuteMsg::PruneExpiredBids {
+            collateral_token: "asset0000".to_string(),
+            limit: None,
+        };
+        let info = mock_info("keeper0000", &[]);
+        let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(res.attributes[2], attr("pruned", pruned));
+    }
+}
+
+#[test]
+fn execute_bid_with_max_price() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        liquidator_fee: Decimal256::zero(),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: Some(Uint256::from(1000000u64)),
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // limit bids under the minimum amount are rejected
+    let msg = ExecuteMsg::SubmitBid {
+        collateral_token: "asset0000".to_string(),
+        premium_slot: 1u8,
+        expires_at: None,
+        max_price: Some(Decimal256::percent(40)),
+        auto_rebid: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(999999u128),
+        }],
+    );
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Bids with a max price or an expiration must be at least 1000000 uusd"
+        ))
+    );
+
+    // bid 1 can be filled up to a price of 0.4, bid 2 at any price, bids 3 to 34
+    // only at prices below 0.14
+    let mut bids = vec![
+        ("addr0000", Some(Decimal256::percent(40))),
+        ("addr0001", None),
+    ];
+    for i in 0..32u64 {
+        let max_price = Decimal256::permille(100 + i);
+        bids.push(("addr0002", Some(max_price)));
+    }
+    for (bidder, max_price) in bids {
+        let msg = ExecuteMsg::SubmitBid {
+            collateral_token: "asset0000".to_string(),
+            premium_slot: 1u8,
+            expires_at: None,
+            max_price,
+            auto_rebid: None,
+        };
+        let info = mock_info(
+            bidder,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60u64);
+    for _ in 0..2 {
+        let msg = ExecuteMsg::ActivateBidsFor {
+            collateral_token: "asset0000".to_string(),
+            limit: Some(31u8),
+        };
+        let info = mock_info("keeper0000", &[]);
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    // at a price of 0.5 only bid 2 is eligible, however many limit bids are below it
+    let simulate_response: SimulateExecuteBidResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::SimulateExecuteBid {
+                collateral_token: "asset0000".to_string(),
+                amount: Uint256::from(3000000u64),
+                price: Decimal256::percent(50),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(simulate_response.repay_amount, Uint256::from(1000000u64));
+    assert!(!simulate_response.filled);
+
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: None,
+            repay_address: None,
+        })
+        .unwrap(),
+    });
+
+    // at a price of 0.5 the limit bids sit out, bid 2 fills the liquidation
+    // required_stable 495,000
+    let info = mock_info("asset0000", &[]);
+    execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+    // at a price of 0.4 bid 2 is used up first, bid 1 takes the rest
+    // required_stable 396,000 + 396,000
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(40),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+    execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+    for (bid_idx, amount, pending_collateral) in [
+        (1u128, 713000u64, 724748u64),
+        (2, 0, 2275251),
+        (3, 1000000, 0),
+        (34, 1000000, 0),
+    ] {
+        let bid_response: BidResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Bid {
+                    bid_idx: Uint128::from(bid_idx),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(bid_response.amount, Uint256::from(amount));
+        assert_eq!(
+            bid_response.pending_liquidated_collateral,
+            Uint256::from(pending_collateral)
+        );
+    }
+}
+
+#[test]
+fn execute_bid_with_collateral_overrides() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::percent(1),
+        liquidator_fee: Decimal256::percent(1),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // thin market collateral, longer waiting period and higher fees
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: Some(Decimal256::percent(2)),
+        liquidator_fee: Some(Decimal256::percent(3)),
+        waiting_period: Some(120u64),
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let collateral_info_response: CollateralInfoResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            env.clone(),
+            QueryMsg::CollateralInfo {
+                collateral_token: "asset0000".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(collateral_info_response.bid_fee, Decimal256::percent(2));
+    assert_eq!(collateral_info_response.liquidator_fee, Decimal256::percent(3));
+    assert_eq!(collateral_info_response.waiting_period, 120u64);
+    assert_eq!(
+        collateral_info_response.liquidation_threshold,
+        Uint256::from(100000000u64)
+    );
+
+    let msg = ExecuteMsg::SubmitBid {
+        collateral_token: "asset0000".to_string(),
+        premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // the config waiting period is not enough
+    let msg = ExecuteMsg::ActivateBids {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: Some(vec![Uint128::from(1u128)]),
+    };
+    let wait_end = env.block.time.plus_seconds(120u64);
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60u64);
+    let info = mock_info("addr0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(format!(
+            "Wait period expires at {}",
+            wait_end.seconds()
+        )))
+    );
+
+    env.block.time = wait_end;
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // required_stable 495,000
+    // bid_fee         9,900
+    // liquidator_fee  14,850
+    // repay_amount    470,250
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: Some("fee0000".to_string()),
+            repay_address: Some("repay0000".to_string()),
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "repay0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(470250u128),
+                }]
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "fee0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(9900u128),
+                }]
+            })),
+            SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+                to_address: "liquidator0000".to_string(),
+                amount: vec![Coin {
+                    denom: "uusd".to_string(),
+                    amount: Uint128::from(14850u128),
+                }]
+            })),
+        ]
+    );
+}
+
+#[test]
+fn rerate_premium_schedule() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        liquidator_fee: Decimal256::zero(),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // 1% per slot up to slot 2, 4% per slot up to slot 4, 10% beyond
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 5u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: Some(PremiumSchedule::Piecewise {
+            points: vec![
+                (0u8, Decimal256::zero()),
+                (2u8, Decimal256::percent(2)),
+                (4u8, Decimal256::percent(10)),
+            ],
+        }),
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    for premium_slot in [1u8, 3u8, 5u8] {
+        let msg = ExecuteMsg::SubmitBid {
+            collateral_token: "asset0000".to_string(),
+            premium_slot,
+            expires_at: None,
+            max_price: None,
+            auto_rebid: None,
+        };
+        let info = mock_info(
+            "addr0000",
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    for (premium_slot, premium_rate) in [(1u8, 1u64), (3, 6), (5, 10)] {
+        let bid_pool_response: BidPoolResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::BidPool {
+                    collateral_token: "asset0000".to_string(),
+                    bid_slot: premium_slot,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(bid_pool_response.premium_rate, Decimal256::percent(premium_rate));
+    }
+
+    // the existing pools move to the new schedule, slot 5 keeps its rate
+    let msg = ExecuteMsg::UpdateCollateralInfo {
+        collateral_token: "asset0000".to_string(),
+        bid_threshold: None,
+        max_slot: None,
+        premium_schedule: Some(PremiumSchedule::Explicit {
+            rates: vec![
+                Decimal256::zero(),
+                Decimal256::percent(5),
+                Decimal256::percent(6),
+                Decimal256::percent(8),
+                Decimal256::percent(9),
+                Decimal256::percent(10),
+            ],
+        }),
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_collateral_info"),
+            attr("rerated_slots", "1,3"),
+        ]
+    );
+    for (premium_slot, premium_rate) in [(1u8, 5u64), (3, 8), (5, 10)] {
+        let bid_pool_response: BidPoolResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::BidPool {
+                    collateral_token: "asset0000".to_string(),
+                    bid_slot: premium_slot,
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(bid_pool_response.premium_rate, Decimal256::percent(premium_rate));
+    }
+
+    let msg = ExecuteMsg::ActivateBids {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: None,
+    };
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60u64);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // bid of slot 1 is filled at the new 5% premium
+    // required_stable 475,000
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: None,
+            repay_address: Some("repay0000".to_string()),
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: "repay0000".to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(475000u128),
+            }]
+        }))]
+    );
+}
+
+#[test]
+fn activate_bids_for_past_expired_bids() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        liquidator_fee: Decimal256::zero(),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: None,
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // bids 1 to 3 expire while waiting, bid 5 expires once active
+    let start = env.block.time;
+    let bids = [
+        ("addr0000", Some(start.plus_seconds(90u64).seconds())),
+        ("addr0000", Some(start.plus_seconds(90u64).seconds())),
+        ("addr0000", Some(start.plus_seconds(90u64).seconds())),
+        ("addr0001", None),
+        ("addr0002", Some(start.plus_seconds(150u64).seconds())),
+    ];
+    for (bidder, expires_at) in bids {
+        let msg = ExecuteMsg::SubmitBid {
+            collateral_token: "asset0000".to_string(),
+            premium_slot: 1u8,
+            expires_at,
+            max_price: None,
+            auto_rebid: None,
+        };
+        let info = mock_info(
+            bidder,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    let mut env = mock_env();
+    env.block.time = start.plus_seconds(60u64);
+    let msg = ExecuteMsg::ActivateBids {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: Some(vec![Uint128::from(5u128)]),
+    };
+    let info = mock_info("addr0002", &[]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // the expired bids at the head of the queue count toward the limit and leave it
+    let mut env = mock_env();
+    env.block.time = start.plus_seconds(100u64);
+    let msg = ExecuteMsg::ActivateBidsFor {
+        collateral_token: "asset0000".to_string(),
+        limit: Some(4u8),
+    };
+    let info = mock_info("keeper0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "activate_bids_for"),
+            attr("keeper", "keeper0000"),
+            attr("amount", "1000000"),
+        ]
+    );
+
+    let msg = ExecuteMsg::SubmitBid {
+        collateral_token: "asset0000".to_string(),
+        premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: None,
+    };
+    let info = mock_info(
+        "addr0003",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // the liquidation leaves out the expired bid 5, which stays active until pruned
+    let mut env = mock_env();
+    env.block.time = start.plus_seconds(150u64);
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(100000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: None,
+            repay_address: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    // bid 6 is next in line
+    let mut env = mock_env();
+    env.block.time = start.plus_seconds(160u64);
+    let msg = ExecuteMsg::ActivateBidsFor {
+        collateral_token: "asset0000".to_string(),
+        limit: Some(1u8),
+    };
+    let info = mock_info("keeper0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    for (bid_idx, wait_end) in [
+        (1u128, Some(start.plus_seconds(60u64).seconds())),
+        (4, None),
+        (5, None),
+        (6, None),
+    ] {
+        let bid_response: BidResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Bid {
+                    bid_idx: Uint128::from(bid_idx),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(bid_response.wait_end, wait_end);
+    }
+}
+
+#[test]
+fn rebid_through_swap_adapter() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        liquidator_fee: Decimal256::zero(),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: Some("swap0000".to_string()),
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::SubmitBid {
+        collateral_token: "asset0000".to_string(),
+        premium_slot: 1u8,
+        expires_at: None,
+        max_price: None,
+        auto_rebid: Some(AutoRebid {
+            max_slippage: Decimal256::percent(5),
+        }),
+    };
+    let info = mock_info(
+        "addr0000",
+        &[Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1000000u128),
+        }],
+    );
+    execute(deps.as_mut(), env, info, msg).unwrap();
+
+    let msg = ExecuteMsg::ActivateBids {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: None,
+    };
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60u64);
+    let info = mock_info("addr0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // required_stable 495,000
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: None,
+            repay_address: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(505000u128),
+        }],
+    );
+
+    // the swap adapter returns 0.49 uusd per collateral
+    let mut swap_deps = cosmwasm_std::testing::mock_dependencies();
+    let msg = MockSwapInstantiateMsg {
+        owner: "owner0000".to_string(),
+    };
+    let info = mock_info("owner0000", &[]);
+    mock_swap::instantiate(swap_deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = MockSwapExecuteMsg::SetRate {
+        offer_token: "asset0000".to_string(),
+        ask_denom: "uusd".to_string(),
+        rate: Decimal256::percent(49),
+    };
+    mock_swap::execute(swap_deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // the liquidated collateral is sent to the swap adapter, at most 5% below
+    // the oracle value
+    let msg = ExecuteMsg::Rebid {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: vec![Uint128::from(1u128)],
+    };
+    let info = mock_info("keeper0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let swap_msg = to_binary(&SwapCw20HookMsg::Swap {
+        ask_denom: "uusd".to_string(),
+        min_receive: Uint128::from(475000u128),
+        recipient: None,
+    })
+    .unwrap();
+    let rebid_swapped_msg = match &res.messages[..] {
+        [send, rebid_swapped] => {
+            assert_eq!(
+                send,
+                &SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "asset0000".to_string(),
+                    funds: vec![],
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: "swap0000".to_string(),
+                        amount: Uint128::from(1000000u128),
+                        msg: swap_msg.clone(),
+                    })
+                    .unwrap(),
+                }))
+            );
+            match &rebid_swapped.msg {
+                CosmosMsg::Wasm(WasmMsg::Execute { contract_addr, msg, .. }) => {
+                    assert_eq!(contract_addr, MOCK_CONTRACT_ADDR);
+                    from_binary::<ExecuteMsg>(msg).unwrap()
+                }
+                msg => panic!("unexpected message {:?}", msg),
+            }
+        }
+        messages => panic!("unexpected messages {:?}", messages),
+    };
+
+    // the swap adapter pays the return to the liquidation queue
+    let msg = MockSwapExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: MOCK_CONTRACT_ADDR.to_string(),
+        amount: Uint128::from(1000000u128),
+        msg: swap_msg,
+    });
+    let info = mock_info("asset0000", &[]);
+    let res = mock_swap::execute(swap_deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages,
+        vec![SubMsg::new(CosmosMsg::Bank(BankMsg::Send {
+            to_address: MOCK_CONTRACT_ADDR.to_string(),
+            amount: vec![Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(490000u128),
+            }],
+        }))]
+    );
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(995000u128),
+        }],
+    );
+
+    // only the contract itself can rebid the swap return
+    let info = mock_info("keeper0000", &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, rebid_swapped_msg.clone()).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Unauthorized("rebid_swapped".to_string(), "keeper0000".to_string())
+    );
+
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, rebid_swapped_msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "rebid_swapped"),
+            attr("collateral_token", "asset0000"),
+            attr("amount", "490000"),
+        ]
+    );
+
+    // the return is bid again in the same slot, the settled bid keeps its remainder
+    for (bid_idx, amount, pending_collateral) in [(1u128, 505000u64, 0u64), (2, 490000, 0)] {
+        let bid_response: BidResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                env.clone(),
+                QueryMsg::Bid {
+                    bid_idx: Uint128::from(bid_idx),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(bid_response.premium_slot, 1u8);
+        assert_eq!(bid_response.amount, Uint256::from(amount));
+        assert_eq!(
+            bid_response.pending_liquidated_collateral,
+            Uint256::from(pending_collateral)
+        );
+        assert!(bid_response.auto_rebid.is_some());
+    }
+}
+
+#[test]
+fn rebid_with_mixed_slippages() {
+    let mut deps = mock_dependencies(&[]);
+    deps.querier
+        .with_collateral_max_ltv(&[(&"asset0000".to_string(), &Decimal256::percent(90))]);
+
+    let msg = InstantiateMsg {
+        owner: "owner0000".to_string(),
+        oracle_contract: "oracle0000".to_string(),
+        stable_denom: "uusd".to_string(),
+        safe_ratio: Decimal256::percent(10),
+        bid_fee: Decimal256::zero(),
+        liquidator_fee: Decimal256::zero(),
+        liquidation_threshold: Uint256::from(100000000u64),
+        price_timeframe: 100000u64,
+        waiting_period: 60u64,
+        control_contract: "control0000".to_string(),
+        close_factor: None,
+        prune_tip_rate: None,
+        min_limit_bid_amount: None,
+        swap_adapter: Some("swap0000".to_string()),
+        guardian_addr: "guardian0000".to_string(),
+        timelock_delay: 0u64,
+    };
+
+    let info = mock_info("addr0000", &[]);
+    let env = mock_env();
+    deps.querier.with_oracle_price(&[(
+        &("asset0000".to_string(), "uusd".to_string()),
+        &(
+            Decimal256::percent(50),
+            env.block.time.seconds(),
+            env.block.time.seconds(),
+        ),
+    )]);
+
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::WhitelistCollateral {
+        collateral_token: "asset0000".to_string(),
+        max_slot: 30u8,
+        bid_threshold: Uint256::zero(),
+        premium_rate_per_slot: Decimal256::percent(1),
+        premium_schedule: None,
+        bid_fee: None,
+        liquidator_fee: None,
+        waiting_period: None,
+        liquidation_threshold: None,
+    };
+    let info = mock_info("owner0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // bid 1 accepts 1% of slippage, bid 2 10%
+    for (bidder, max_slippage) in [
+        ("addr0000", Decimal256::percent(1)),
+        ("addr0001", Decimal256::percent(10)),
+    ] {
+        let msg = ExecuteMsg::SubmitBid {
+            collateral_token: "asset0000".to_string(),
+            premium_slot: 1u8,
+            expires_at: None,
+            max_price: None,
+            auto_rebid: Some(AutoRebid { max_slippage }),
+        };
+        let info = mock_info(
+            bidder,
+            &[Coin {
+                denom: "uusd".to_string(),
+                amount: Uint128::from(1000000u128),
+            }],
+        );
+        execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(60u64);
+    let msg = ExecuteMsg::ActivateBidsFor {
+        collateral_token: "asset0000".to_string(),
+        limit: None,
+    };
+    let info = mock_info("keeper0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    // required_stable 990,000, each bid gets 1,000,000 collateral
+    let msg = ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "custody0000".to_string(),
+        amount: Uint128::from(2000000u128),
+        msg: to_binary(&Cw20HookMsg::ExecuteBid {
+            liquidator: "liquidator0000".to_string(),
+            fee_address: None,
+            repay_address: None,
+        })
+        .unwrap(),
+    });
+    let info = mock_info("asset0000", &[]);
+    execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1010000u128),
+        }],
+    );
+
+    // batched with bid 2, bid 1 still gets no less than 1% below the oracle value
+    let msg = ExecuteMsg::Rebid {
+        collateral_token: "asset0000".to_string(),
+        bids_idx: vec![Uint128::from(1u128), Uint128::from(2u128)],
+    };
+    let info = mock_info("keeper0000", &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[0],
+        SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: "asset0000".to_string(),
+            funds: vec![],
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: "swap0000".to_string(),
+                amount: Uint128::from(2000000u128),
+                msg: to_binary(&SwapCw20HookMsg::Swap {
+                    ask_denom: "uusd".to_string(),
+                    min_receive: Uint128::from(990000u128),
+                    recipient: None,
+                })
+                .unwrap(),
+            })
+            .unwrap(),
+        }))
+    );
+    let rebid_swapped_msg = match &res.messages[1].msg {
+        CosmosMsg::Wasm(WasmMsg::Execute { msg, .. }) => from_binary::<ExecuteMsg>(msg).unwrap(),
+        msg => panic!("unexpected message {:?}", msg),
+    };
+
+    // a return within the slippage of bid 2 only is rejected
+    deps.querier.update_balance(
+        MOCK_CONTRACT_ADDR,
+        vec![Coin {
+            denom: "uusd".to_string(),
+            amount: Uint128::from(1955000u128),
+        }],
+    );
+    let info = mock_info(MOCK_CONTRACT_ADDR, &[]);
+    let err = execute(deps.as_mut(), env, info, rebid_swapped_msg).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Std(StdError::generic_err(
+            "Swap return 945000 is below the minimum 990000"
+        ))
+    );
+}
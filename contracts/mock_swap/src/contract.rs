@@ -0,0 +1,164 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A swap adapter returning a fixed rate of the offered cw20 or native tokens,
+//! paid out of its own balance of the ask asset
+
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+
+use crate::msg::{ExecuteMsg, InstantiateMsg};
+use cdp::swap_adapter::{Cw20HookMsg, QueryMsg, SimulateSwapResponse};
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, coins, from_binary, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo,
+    Response, StdError, StdResult, Uint128,
+};
+use cw20::Cw20ReceiveMsg;
+use cw_storage_plus::{Item, Map};
+
+const OWNER: Item<Addr> = Item::new("owner");
+const RATES: Map<(&str, &str), Decimal256> = Map::new("rates");
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> StdResult<Response> {
+    OWNER.save(deps.storage, &deps.api.addr_validate(&msg.owner)?)?;
+    Ok(Response::new().add_attribute("action", "instantiate"))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> StdResult<Response> {
+    match msg {
+        ExecuteMsg::Receive(msg) => receive_cw20(deps, info, msg),
+        ExecuteMsg::Swap {
+            ask_denom,
+            min_receive,
+            recipient,
+        } => {
+            if info.funds.len() != 1 {
+                return Err(StdError::generic_err("Exactly one coin must be offered"));
+            }
+            let offer = &info.funds[0];
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => info.sender.clone(),
+            };
+            swap(
+                deps.as_ref(),
+                &offer.denom,
+                offer.amount,
+                ask_denom,
+                min_receive,
+                recipient,
+            )
+        }
+        ExecuteMsg::SetRate {
+            offer_token,
+            ask_denom,
+            rate,
+        } => {
+            if info.sender != OWNER.load(deps.storage)? {
+                return Err(StdError::generic_err("unauthorized"));
+            }
+            RATES.save(deps.storage, (&offer_token, &ask_denom), &rate)?;
+            Ok(Response::new().add_attribute("action", "set_rate"))
+        }
+    }
+}
+
+fn receive_cw20(deps: DepsMut, info: MessageInfo, msg: Cw20ReceiveMsg) -> StdResult<Response> {
+    match from_binary(&msg.msg)? {
+        Cw20HookMsg::Swap {
+            ask_denom,
+            min_receive,
+            recipient,
+        } => {
+            let recipient = match recipient {
+                Some(recipient) => deps.api.addr_validate(&recipient)?,
+                None => deps.api.addr_validate(&msg.sender)?,
+            };
+            swap(
+                deps.as_ref(),
+                info.sender.as_str(),
+                msg.amount,
+                ask_denom,
+                min_receive,
+                recipient,
+            )
+        }
+    }
+}
+
+fn swap(
+    deps: Deps,
+    offer_token: &str,
+    offer_amount: Uint128,
+    ask_denom: String,
+    min_receive: Uint128,
+    recipient: Addr,
+) -> StdResult<Response> {
+    let return_amount = compute_return(deps, offer_token, offer_amount, &ask_denom)?;
+    if return_amount < min_receive {
+        return Err(StdError::generic_err(format!(
+            "Return amount {} is below the minimum {}",
+            return_amount, min_receive
+        )));
+    }
+
+    Ok(Response::new()
+        .add_message(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: coins(return_amount.u128(), ask_denom),
+        })
+        .add_attributes(vec![
+            attr("action", "swap"),
+            attr("offer_amount", offer_amount),
+            attr("return_amount", return_amount),
+        ]))
+}
+
+fn compute_return(
+    deps: Deps,
+    offer_token: &str,
+    offer_amount: Uint128,
+    ask_denom: &str,
+) -> StdResult<Uint128> {
+    let rate = RATES
+        .may_load(deps.storage, (offer_token, ask_denom))?
+        .ok_or_else(|| StdError::generic_err("No rate for the pair"))?;
+    Ok((Uint256::from(offer_amount) * rate).into())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::SimulateSwap {
+            offer_token,
+            offer_amount,
+            ask_denom,
+        } => to_binary(&SimulateSwapResponse {
+            return_amount: compute_return(deps, &offer_token, offer_amount, &ask_denom)?,
+        }),
+    }
+}
@@ -0,0 +1,41 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Mock messages, the adapter interface is `cdp::swap_adapter`
+
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::Uint128;
+use cw20::Cw20ReceiveMsg;
+
+#[cw_serde]
+pub struct InstantiateMsg {
+    pub owner: String,
+}
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    Receive(Cw20ReceiveMsg),
+    Swap {
+        ask_denom: String,
+        min_receive: Uint128,
+        recipient: Option<String>,
+    },
+    /// Units of `ask_denom` returned per unit of `offer_token`
+    SetRate {
+        offer_token: String,
+        ask_denom: String,
+        rate: Decimal256,
+    },
+}
@@ -0,0 +1,115 @@
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{ExecuteMsg, InstantiateMsg};
+
+use cdp::swap_adapter::{Cw20HookMsg, QueryMsg, SimulateSwapResponse};
+use cosmwasm_bignumber::Decimal256;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{coins, from_binary, to_binary, BankMsg, CosmosMsg, StdError, Uint128};
+use cw20::Cw20ReceiveMsg;
+
+fn swap_msg(amount: u128, min_receive: u128) -> ExecuteMsg {
+    ExecuteMsg::Receive(Cw20ReceiveMsg {
+        sender: "seller".to_string(),
+        amount: Uint128::from(amount),
+        msg: to_binary(&Cw20HookMsg::Swap {
+            ask_denom: "ukusd".to_string(),
+            min_receive: Uint128::from(min_receive),
+            recipient: None,
+        })
+        .unwrap(),
+    })
+}
+
+#[test]
+fn swap_at_fixed_rate() {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        InstantiateMsg {
+            owner: "owner".to_string(),
+        },
+    )
+    .unwrap();
+
+    let set_rate = ExecuteMsg::SetRate {
+        offer_token: "col0000".to_string(),
+        ask_denom: "ukusd".to_string(),
+        rate: Decimal256::percent(250),
+    };
+    let err = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), set_rate.clone())
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("unauthorized"));
+    execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), set_rate).unwrap();
+
+    let res: SimulateSwapResponse = from_binary(
+        &query(
+            deps.as_ref(),
+            mock_env(),
+            QueryMsg::SimulateSwap {
+                offer_token: "col0000".to_string(),
+                offer_amount: Uint128::from(100u128),
+                ask_denom: "ukusd".to_string(),
+            },
+        )
+        .unwrap(),
+    )
+    .unwrap();
+    assert_eq!(res.return_amount, Uint128::from(250u128));
+
+    // the return is paid to the seller
+    let res = execute(deps.as_mut(), mock_env(), mock_info("col0000", &[]), swap_msg(100, 250))
+        .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "seller".to_string(),
+            amount: coins(250u128, "ukusd"),
+        })
+    );
+
+    // below the minimum return
+    let err = execute(deps.as_mut(), mock_env(), mock_info("col0000", &[]), swap_msg(100, 251))
+        .unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err("Return amount 250 is below the minimum 251")
+    );
+
+    // native tokens are offered as funds
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        ExecuteMsg::SetRate {
+            offer_token: "usei".to_string(),
+            ask_denom: "ukusd".to_string(),
+            rate: Decimal256::percent(50),
+        },
+    )
+    .unwrap();
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("seller", &coins(100u128, "usei")),
+        ExecuteMsg::Swap {
+            ask_denom: "ukusd".to_string(),
+            min_receive: Uint128::from(50u128),
+            recipient: Some("recipient".to_string()),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: "recipient".to_string(),
+            amount: coins(50u128, "ukusd"),
+        })
+    );
+
+    // unknown pair
+    let err = execute(deps.as_mut(), mock_env(), mock_info("col0001", &[]), swap_msg(100, 0))
+        .unwrap_err();
+    assert_eq!(err, StdError::generic_err("No rate for the pair"));
+}
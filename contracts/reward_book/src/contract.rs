@@ -15,48 +15,75 @@
 use cosmwasm_std::entry_point;
 
 use crate::error::ContractError;
-use crate::math::decimal_summation_in_256;
-use crate::state::{read_config, read_state, store_config, store_state, Config, State, read_new_owner, store_new_owner, NewOwnerAddr};
+use crate::migration::{
+    is_migrating, migrate_decimal_indexes, migrate_holders, migrate_reward_denoms,
+};
+use crate::state::{
+    next_auto_repay_holders, read_config, read_new_owner, read_reward_index, read_state,
+    store_config, store_new_owner, store_reward_index, store_state, Config, NewOwnerAddr, State,
+};
+use crate::repay::{execute_auto_repay, execute_repay_swapped, execute_set_auto_repay};
 use crate::user::{
     execute_claim_rewards, execute_decrease_balance, execute_increase_balance,
-    query_accrued_rewards, query_holder, query_holders,
+    execute_transfer_balance, query_accrued_rewards, query_holder, query_holders, rewards_str,
+};
+use cdp::querier::{query_balance, query_collaterals_accrued_rewards, query_token_balance};
+use cdp::events::{config_change_event, MODULE_REWARD_BOOK};
+use cdp::migration::{assert_legacy_deployment, assert_upgrade};
+use cdp::rbac::{
+    execute_grant_role, execute_pause, execute_revoke_role, execute_unpause, grant_role,
+    query_has_role, query_paused, query_role_members, revoke_role, Role,
 };
-use cdp::querier::query_collaterals_accrued_rewards;
-use cosmwasm_bignumber::Uint256;
+use cdp::timelock::{
+    execute_cancel_change, execute_matured_change, execute_propose_change, query_pending_change,
+    query_pending_changes, query_timelock_config, store_timelock_config, update_timelock_config,
+    TimelockConfig,
+};
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cw2::set_contract_version;
 use cosmwasm_std::{
-    attr, to_binary, Addr, Binary, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Reply,
-    Response, StdResult, SubMsg, Uint128, WasmMsg,
+    attr, to_binary, Addr, Api, Binary, CanonicalAddr, CosmosMsg, Deps, DepsMut,
+    Env, MessageInfo, Reply, Response, StdError, StdResult, Storage, SubMsg, Uint128, WasmMsg,
 };
+use cw20::Denom;
 
 use cdp::handle::optional_addr_validate;
 use cdp::reward_book::{
-    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, StateResponse,
+    ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, RewardAmount,
+    RewardIndexResponse, StateResponse,
 };
 use cdp::custody::ExecuteMsg as CustodyExecuteMsg;
 
+// version info for migration
+const CONTRACT_NAME: &str = "crates.io:cdp-reward-book";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
     msg: InstantiateMsg,
-) -> StdResult<Response> {
+) -> Result<Response, ContractError> {
     let conf = Config {
         owner: deps.api.addr_canonicalize(&info.sender.to_string())?,
         control_contract: deps.api.addr_canonicalize(&msg.control_contract)?,
         reward_contract: deps.api.addr_canonicalize(&msg.reward_contract)?,
         custody_contract: deps.api.addr_canonicalize(&msg.custody_contract)?,
-        reward_denom: msg.reward_denom,
+        reward_denoms: validate_reward_denoms(deps.api, msg.reward_denoms)?,
         threshold: msg.threshold,
+        pool_contract: optional_addr_canonicalize(deps.api, msg.pool_contract)?,
+        stable_denom: msg.stable_denom,
+        swap_adapter: optional_addr_canonicalize(deps.api, msg.swap_adapter)?,
+        oracle_contract: optional_addr_canonicalize(deps.api, msg.oracle_contract)?,
+        removed_reward_denoms: vec![],
     };
 
     store_config(deps.storage, &conf)?;
     store_state(
         deps.storage,
         &State {
-            global_index: Decimal::zero(),
             total_balance: Uint128::zero(),
-            prev_reward_balance: Uint128::zero(),
         },
     )?;
     
@@ -65,7 +92,22 @@ pub fn instantiate(
             new_owner_addr: deps.api.addr_canonicalize(&info.sender.to_string())?,
         }
     })?;
-    
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    store_timelock_config(
+        deps.storage,
+        &TimelockConfig {
+            delay: msg.timelock_delay,
+        },
+    )?;
+
+    grant_role(deps.storage, Role::Admin, &deps.api.addr_canonicalize(info.sender.as_str())?)?;
+    grant_role(
+        deps.storage,
+        Role::Guardian,
+        &deps.api.addr_canonicalize(&msg.guardian_addr)?,
+    )?;
 
     Ok(Response::default())
 }
@@ -77,39 +119,70 @@ pub fn execute(
     info: MessageInfo,
     msg: ExecuteMsg,
 ) -> Result<Response, ContractError> {
+    // holders left in a former layout can not be read
+    if !matches!(msg, ExecuteMsg::MigrateHolders { .. }) && is_migrating(deps.storage)? {
+        return Err(ContractError::MigrationPending {});
+    }
+
     match msg {
-        ExecuteMsg::UpdateConfig {
-            control_contract,
-            reward_contract,
-            custody_contract,
-            reward_denom,
-            threshold,
-        } => {
+        ExecuteMsg::UpdateConfig { .. }
+        | ExecuteMsg::UpdateTimelockConfig { .. }
+        | ExecuteMsg::SetOwner { .. }
+        | ExecuteMsg::GrantRole {
+            role: Role::Admin | Role::Guardian,
+            ..
+        } => propose_timelocked_change(deps, env, info, msg),
+        ExecuteMsg::ExecuteChange { id } => execute_timelocked_change(deps, env, info, id),
+        ExecuteMsg::CancelChange { id } => {
+            execute_cancel_change(deps, info, MODULE_REWARD_BOOK, id)
+        }
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => {
             let api = deps.api;
-            update_config(
-                deps,
-                info,
-                optional_addr_validate(api, control_contract)?,
-                optional_addr_validate(api, custody_contract)?,
-                optional_addr_validate(api, reward_contract)?,
-                reward_denom,
-                threshold,
-            )
+            execute_grant_role(deps, info, MODULE_REWARD_BOOK, role, api.addr_validate(&address)?)
         }
-        ExecuteMsg::SetOwner { new_owner_addr } => {
+        ExecuteMsg::RevokeRole { role, address } => {
             let api = deps.api;
-            set_new_owner(deps, info, api.addr_validate(&new_owner_addr)?)
+            execute_revoke_role(deps, info, MODULE_REWARD_BOOK, role, api.addr_validate(&address)?)
         }
-        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::Pause {} => execute_pause(deps, info, MODULE_REWARD_BOOK),
+        ExecuteMsg::Unpause {} => execute_unpause(deps, info, MODULE_REWARD_BOOK),
         ExecuteMsg::ClaimRewards { recipient } => execute_claim_rewards(deps, env, info, recipient),
         ExecuteMsg::UpdateGlobalIndex {} => update_global_index(deps, env, info),
+        ExecuteMsg::MigrateHolders { limit } => execute_migrate_holders(deps, limit),
         ExecuteMsg::IncreaseBalance { address, amount } => {
             execute_increase_balance(deps, env, info, address, amount)
         }
         ExecuteMsg::DecreaseBalance { address, amount } => {
             execute_decrease_balance(deps, env, info, address, amount)
         }
+        ExecuteMsg::TransferBalance { from, to, amount } => {
+            execute_transfer_balance(deps, env, info, from, to, amount)
+        }
         ExecuteMsg::ExecuteUpdateGlobalIndex {} => execute_update_global_index(deps, env),
+        ExecuteMsg::SetAutoRepay { auto_repay } => execute_set_auto_repay(deps, info, auto_repay),
+        ExecuteMsg::AutoRepay { holder } => {
+            assert_self_call(&env, &info, "auto_repay")?;
+            let api = deps.api;
+            execute_auto_repay(deps, env, api.addr_validate(&holder)?)
+        }
+        ExecuteMsg::RepaySwapped {
+            holder,
+            vault_id,
+            prev_balance,
+            direct_amount,
+        } => {
+            assert_self_call(&env, &info, "repay_swapped")?;
+            let api = deps.api;
+            execute_repay_swapped(
+                deps,
+                env,
+                api.addr_validate(&holder)?,
+                vault_id,
+                prev_balance,
+                direct_amount,
+            )
+        }
     }
 }
 
@@ -123,6 +196,18 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
         QueryMsg::Holders { start_after, limit } => {
             to_binary(&query_holders(deps, start_after, limit)?)
         }
+        QueryMsg::TimelockConfig {} => to_binary(&query_timelock_config(deps)?),
+        QueryMsg::PendingChange { id } => to_binary(&query_pending_change(deps, id)?),
+        QueryMsg::PendingChanges { start_after, limit } => {
+            to_binary(&query_pending_changes(deps, start_after, limit)?)
+        }
+        QueryMsg::HasRole { role, address } => to_binary(&query_has_role(deps, role, address)?),
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&query_role_members(deps, role, start_after, limit)?),
+        QueryMsg::Paused {} => to_binary(&query_paused(deps)?),
     }
 }
 
@@ -138,41 +223,92 @@ fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
             .api
             .addr_humanize(&config.custody_contract)?
             .to_string(),
-        reward_denom: config.reward_denom,
+        reward_denoms: config.reward_denoms,
         owner: deps.api.addr_humanize(&config.owner)?.to_string(),
+        pool_contract: optional_addr_humanize(deps.api, config.pool_contract)?,
+        stable_denom: config.stable_denom,
+        swap_adapter: optional_addr_humanize(deps.api, config.swap_adapter)?,
+        oracle_contract: optional_addr_humanize(deps.api, config.oracle_contract)?,
     })
 }
 
+fn optional_addr_canonicalize(
+    api: &dyn Api,
+    addr: Option<String>,
+) -> StdResult<Option<CanonicalAddr>> {
+    addr.map(|addr| api.addr_canonicalize(&addr)).transpose()
+}
+
+fn optional_addr_humanize(
+    api: &dyn Api,
+    addr: Option<CanonicalAddr>,
+) -> StdResult<Option<String>> {
+    addr.map(|addr| Ok(api.addr_humanize(&addr)?.to_string()))
+        .transpose()
+}
+
+fn assert_self_call(env: &Env, info: &MessageInfo, name: &str) -> Result<(), ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized(
+            name.to_string(),
+            info.sender.to_string(),
+        ));
+    }
+    Ok(())
+}
+
 fn query_state(deps: Deps) -> StdResult<StateResponse> {
     let state: State = read_state(deps.storage)?;
+    let reward_indexes = read_config(deps.storage)?
+        .reward_denoms
+        .into_iter()
+        .map(|denom| {
+            let reward_index = read_reward_index(deps.storage, &denom)?;
+            Ok(RewardIndexResponse {
+                denom,
+                global_index: reward_index.global_index,
+                prev_reward_balance: reward_index.prev_reward_balance,
+            })
+        })
+        .collect::<StdResult<Vec<RewardIndexResponse>>>()?;
     Ok(StateResponse {
-        global_index: state.global_index,
         total_balance: state.total_balance,
-        prev_reward_balance: state.prev_reward_balance,
+        reward_indexes,
     })
 }
 
+/// Reward denoms must be a non empty list without duplicates, CW20 addresses are validated
+fn validate_reward_denoms(
+    api: &dyn Api,
+    reward_denoms: Vec<Denom>,
+) -> Result<Vec<Denom>, ContractError> {
+    if reward_denoms.is_empty() {
+        return Err(ContractError::InvalidRewardDenoms {});
+    }
 
-pub fn set_new_owner(
-    deps: DepsMut,
-    info: MessageInfo,
-    new_owner_addr: Addr,
-) -> Result<Response, ContractError> {
-    let config = read_config(deps.as_ref().storage)?;
-    let mut new_owner = read_new_owner(deps.as_ref().storage)?;
-    let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
-    if sender_raw != config.owner {
-        return Err(ContractError::Unauthorized(
-            "set_new_owner".to_string(),
-            info.sender.to_string(),
-        ));
+    let mut validated: Vec<Denom> = vec![];
+    for denom in reward_denoms {
+        let denom = match denom {
+            Denom::Native(denom) => Denom::Native(denom),
+            Denom::Cw20(contract_addr) => Denom::Cw20(api.addr_validate(contract_addr.as_str())?),
+        };
+        if validated.contains(&denom) {
+            return Err(ContractError::InvalidRewardDenoms {});
+        }
+        validated.push(denom);
     }
-    new_owner.new_owner_addr = deps.api.addr_canonicalize(&new_owner_addr.to_string())?;
-    store_new_owner(deps.storage, &new_owner)?;
+    Ok(validated)
+}
 
-    Ok(Response::default())
+/// Applied by a matured `SetOwner` change, the new owner still has to accept
+pub fn set_new_owner(storage: &mut dyn Storage, new_owner_addr: CanonicalAddr) -> StdResult<()> {
+    let mut new_owner = read_new_owner(storage)?;
+    new_owner.new_owner_addr = new_owner_addr;
+    store_new_owner(storage, &new_owner)
 }
 
+/// Only the admin role of the previous owner is revoked, admins granted apart from the
+/// ownership keep their role until revoked
 pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, ContractError> {
     let new_owner = read_new_owner(deps.as_ref().storage)?;
     let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
@@ -184,30 +320,154 @@ pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response, Co
         ));
     }
 
+    revoke_role(deps.storage, Role::Admin, &config.owner);
+    grant_role(deps.storage, Role::Admin, &new_owner.new_owner_addr)?;
     config.owner = new_owner.new_owner_addr;
     store_config(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "accept_ownership"),
+            attr("owner", info.sender.to_string()),
+        ])
+        .add_event(config_change_event(MODULE_REWARD_BOOK, "accept_ownership", info.sender)))
 }
 
-fn update_config(
+fn change_roles(_msg: &ExecuteMsg) -> &'static [Role] {
+    &[Role::Admin]
+}
+
+/// Admin queues a config change, it can be executed once the timelock delay has passed
+fn propose_timelocked_change(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    validate_change(deps.api, &msg)?;
+    let roles = change_roles(&msg);
+    execute_propose_change(deps, &env, info, MODULE_REWARD_BOOK, roles, to_binary(&msg)?)
+}
+
+fn execute_timelocked_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    id: u64,
+) -> Result<Response, ContractError> {
+    execute_matured_change(deps, &env, info, MODULE_REWARD_BOOK, id, change_roles, |deps, msg| {
+        apply_change(deps, &env, msg)
+    })
+}
+
+// addresses and reward denoms are validated when a change is proposed rather than once
+// its delay has passed
+fn validate_change(api: &dyn Api, msg: &ExecuteMsg) -> Result<(), ContractError> {
+    if let ExecuteMsg::UpdateConfig {
+        control_contract,
+        reward_contract,
+        custody_contract,
+        reward_denoms,
+        pool_contract,
+        swap_adapter,
+        oracle_contract,
+        ..
+    } = msg
+    {
+        if let Some(reward_denoms) = reward_denoms {
+            validate_reward_denoms(api, reward_denoms.clone())?;
+        }
+        let addrs = [
+            control_contract,
+            reward_contract,
+            custody_contract,
+            pool_contract,
+            swap_adapter,
+            oracle_contract,
+        ];
+        for addr in addrs.iter().copied().flatten() {
+            api.addr_validate(addr)?;
+        }
+    }
+    if let ExecuteMsg::SetOwner {
+        new_owner_addr: address,
+    }
+    | ExecuteMsg::GrantRole { address, .. } = msg
+    {
+        api.addr_validate(address)?;
+    }
+    Ok(())
+}
+
+fn apply_change(
+    deps: DepsMut,
+    env: &Env,
+    msg: ExecuteMsg,
+) -> Result<&'static str, ContractError> {
+    let api = deps.api;
+    let action = match msg {
+        ExecuteMsg::UpdateConfig {
+            control_contract,
+            reward_contract,
+            custody_contract,
+            reward_denoms,
+            threshold,
+            pool_contract,
+            stable_denom,
+            swap_adapter,
+            oracle_contract,
+        } => {
+            update_config(
+                deps,
+                env,
+                optional_addr_validate(api, control_contract)?,
+                optional_addr_validate(api, custody_contract)?,
+                optional_addr_validate(api, reward_contract)?,
+                reward_denoms,
+                threshold,
+                optional_addr_validate(api, pool_contract)?,
+                stable_denom,
+                optional_addr_validate(api, swap_adapter)?,
+                optional_addr_validate(api, oracle_contract)?,
+            )?;
+            "update_config"
+        }
+        ExecuteMsg::UpdateTimelockConfig { delay } => {
+            update_timelock_config(deps.storage, delay)?;
+            "update_timelock_config"
+        }
+        ExecuteMsg::SetOwner { new_owner_addr } => {
+            set_new_owner(deps.storage, deps.api.addr_canonicalize(&new_owner_addr)?)?;
+            "set_new_owner"
+        }
+        ExecuteMsg::GrantRole { role, address } => {
+            grant_role(deps.storage, role, &deps.api.addr_canonicalize(&address)?)?;
+            "grant_role"
+        }
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Unsupported timelocked change",
+            )))
+        }
+    };
+    Ok(action)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn update_config(
+    deps: DepsMut,
+    env: &Env,
     control_contract: Option<Addr>,
     custody_contract: Option<Addr>,
     reward_contract: Option<Addr>,
-    reward_denom: Option<String>,
+    reward_denoms: Option<Vec<Denom>>,
     threshold: Option<Uint256>,
-) -> Result<Response, ContractError> {
+    pool_contract: Option<Addr>,
+    stable_denom: Option<String>,
+    swap_adapter: Option<Addr>,
+    oracle_contract: Option<Addr>,
+) -> Result<(), ContractError> {
     let mut config = read_config(deps.as_ref().storage)?;
-    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
-
-    if sender_raw != config.owner {
-        return Err(ContractError::Unauthorized(
-            "update_config".to_string(),
-            info.sender.to_string(),
-        ));
-    }
 
     if let Some(control_contract) = control_contract {
         config.control_contract = deps.api.addr_canonicalize(control_contract.as_str())?;
@@ -221,20 +481,57 @@ fn update_config(
         config.reward_contract = deps.api.addr_canonicalize(reward_contract.as_str())?;
     }
 
-    if let Some(reward_denom) = reward_denom {
-        config.reward_denom = reward_denom;
+    if let Some(reward_denoms) = reward_denoms {
+        let reward_denoms = validate_reward_denoms(deps.api, reward_denoms)?;
+        // removed denoms stop accruing, the rewards they accrued stay claimable
+        for denom in config.reward_denoms.iter() {
+            if !reward_denoms.contains(denom) && !config.removed_reward_denoms.contains(denom) {
+                config.removed_reward_denoms.push(denom.clone());
+            }
+        }
+        // added denoms accrue from the balance held when added, which is not a reward
+        for denom in reward_denoms.iter() {
+            if !config.reward_denoms.contains(denom) {
+                let mut reward_index = read_reward_index(deps.storage, denom)?;
+                reward_index.prev_reward_balance =
+                    query_reward_balance(deps.as_ref(), env, denom)?;
+                store_reward_index(deps.storage, denom, &reward_index)?;
+            }
+        }
+        config.removed_reward_denoms.retain(|denom| !reward_denoms.contains(denom));
+        config.reward_denoms = reward_denoms;
     }
 
     if let Some(threshold) = threshold {
         config.threshold = threshold;
     }
 
+    if let Some(pool_contract) = pool_contract {
+        config.pool_contract = Some(deps.api.addr_canonicalize(pool_contract.as_str())?);
+    }
+
+    if let Some(stable_denom) = stable_denom {
+        config.stable_denom = Some(stable_denom);
+    }
+
+    if let Some(swap_adapter) = swap_adapter {
+        config.swap_adapter = Some(deps.api.addr_canonicalize(swap_adapter.as_str())?);
+    }
+
+    if let Some(oracle_contract) = oracle_contract {
+        config.oracle_contract = Some(deps.api.addr_canonicalize(oracle_contract.as_str())?);
+    }
+
     store_config(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(())
 }
 
 pub const CLAIM_COLLATERAL_REWARD: u64 = 1u64;
+pub const AUTO_REPAY: u64 = 2u64;
+
+// auto repaying holders repaid on each index update
+const MAX_AUTO_REPAYS_PER_UPDATE: usize = 10;
 
 /// Increase global_index according to claimed rewards amount
 pub fn update_global_index(
@@ -274,52 +571,257 @@ pub fn update_global_index(
             }),
             CLAIM_COLLATERAL_REWARD,
         ))
-        .add_attributes(vec![attr("actoin", "update_global_index")]))
+        .add_attributes(vec![attr("action", "update_global_index")]))
+}
+
+fn query_reward_balance(deps: Deps, env: &Env, denom: &Denom) -> StdResult<Uint128> {
+    Ok(match denom {
+        Denom::Native(denom) => {
+            query_balance(deps, env.contract.address.clone(), denom.to_string())?
+        }
+        Denom::Cw20(contract_addr) => {
+            query_token_balance(deps, contract_addr.clone(), env.contract.address.clone())?
+        }
+    }
+    .into())
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
     match msg.id {
         CLAIM_COLLATERAL_REWARD => execute_update_global_index(deps, env),
+        // a failed auto repay leaves the rewards of the holder pending
+        AUTO_REPAY => Ok(Response::new().add_attribute("action", "auto_repay_failed")),
         _ => Err(ContractError::InvalidReplyId {}),
     }
 }
 
 pub fn execute_update_global_index(deps: DepsMut, env: Env) -> Result<Response, ContractError> {
-    let mut state: State = read_state(deps.as_ref().storage)?;
+    let state: State = read_state(deps.as_ref().storage)?;
 
     let config = read_config(deps.as_ref().storage)?;
 
-    // //Load the reward book contract balance
-    let balance = deps.querier.query_balance(
-        env.contract.address,
-        config.reward_denom.as_str(),
-    )?;
+    let mut claimed: Vec<RewardAmount> = vec![];
+    for denom in config.reward_denoms {
+        // Load the reward book contract balance
+        let balance = query_reward_balance(deps.as_ref(), &env, &denom)?;
+
+        let mut reward_index = read_reward_index(deps.storage, &denom)?;
 
-    let previous_balance = state.prev_reward_balance;
+        //claimed_rewards = current_balance - prev_balance;
+        let claimed_rewards = balance.checked_sub(reward_index.prev_reward_balance)?;
 
-    //claimed_rewards = current_balance - prev_balance;
-    let claimed_rewards = balance.amount.checked_sub(previous_balance)?;
+        reward_index.prev_reward_balance = balance;
 
-    state.prev_reward_balance = balance.amount;
+        // global_index += claimed_rewards / total_balance;
+        reward_index.global_index += Decimal256::from_ratio(
+            Uint256::from(claimed_rewards),
+            Uint256::from(state.total_balance),
+        );
+        store_reward_index(deps.storage, &denom, &reward_index)?;
 
-    // global_index += claimed_rewards / total_balance;
-    state.global_index = decimal_summation_in_256(
-        state.global_index,
-        Decimal::from_ratio(claimed_rewards, state.total_balance),
-    );
-    store_state(deps.storage, &state)?;
+        claimed.push(RewardAmount {
+            denom,
+            amount: claimed_rewards,
+        });
+    }
+
+    // a failing auto repay must not revert the index update
+    let mut auto_repays: Vec<SubMsg> = vec![];
+    for holder in next_auto_repay_holders(deps.storage, MAX_AUTO_REPAYS_PER_UPDATE)? {
+        auto_repays.push(SubMsg::reply_on_error(
+            CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: env.contract.address.to_string(),
+                msg: to_binary(&ExecuteMsg::AutoRepay {
+                    holder: deps.api.addr_humanize(&holder)?.to_string(),
+                })?,
+                funds: vec![],
+            }),
+            AUTO_REPAY,
+        ));
+    }
 
     let attributes = vec![
         attr("action", "execute_update_global_index"),
-        attr("claimed_rewards", claimed_rewards),
+        attr("claimed_rewards", rewards_str(&claimed)),
     ];
-    let res = Response::new().add_attributes(attributes);
+    let res = Response::new()
+        .add_submessages(auto_repays)
+        .add_attributes(attributes);
 
     Ok(res)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    match msg {
+        MigrateMsg::FromLegacy {
+            guardian_addr,
+            timelock_delay,
+        } => {
+            assert_legacy_deployment(deps.storage)?;
+            migrate_decimal_indexes(deps.storage)?;
+            migrate_reward_denoms(deps.storage, true)?;
+
+            let config = read_config(deps.storage)?;
+            store_timelock_config(
+                deps.storage,
+                &TimelockConfig {
+                    delay: timelock_delay,
+                },
+            )?;
+            grant_role(deps.storage, Role::Admin, &config.owner)?;
+            grant_role(
+                deps.storage,
+                Role::Guardian,
+                &deps.api.addr_canonicalize(&guardian_addr)?,
+            )?;
+        }
+        MigrateMsg::Upgrade {} => {
+            assert_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+            migrate_reward_denoms(deps.storage, false)?;
+        }
+    }
+
+    // the remaining pages are moved through MigrateHolders
+    let done = !is_migrating(deps.storage)? || migrate_holders(deps.storage, None)?;
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("version", CONTRACT_VERSION),
+        attr("done", done.to_string()),
+    ]))
+}
+
+pub fn execute_migrate_holders(
+    deps: DepsMut,
+    limit: Option<u32>,
+) -> Result<Response, ContractError> {
+    let done = migrate_holders(deps.storage, limit)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate_holders"),
+        attr("done", done.to_string()),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cdp::reward_book::AccruedRewardsResponse;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{coin, from_binary};
+
+    #[test]
+    pub fn proper_update_reward_denoms() {
+        let mut deps = mock_dependencies();
+        let usei = Denom::Native("usei".to_string());
+        let uatom = Denom::Native("uatom".to_string());
+        instantiate(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            InstantiateMsg {
+                control_contract: "control".to_string(),
+                reward_contract: "reward".to_string(),
+                custody_contract: "custody".to_string(),
+                reward_denoms: vec![usei.clone()],
+                threshold: Uint256::zero(),
+                pool_contract: None,
+                stable_denom: None,
+                swap_adapter: None,
+                oracle_contract: None,
+                guardian_addr: "guardian".to_string(),
+                timelock_delay: 0,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("control", &[]),
+            ExecuteMsg::IncreaseBalance {
+                address: "holder".to_string(),
+                amount: Uint128::new(1000),
+            },
+        )
+        .unwrap();
+
+        // uatom held before it becomes a reward denom is not a reward
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(100, "usei"), coin(500, "uatom")]);
+        execute_update_global_index(deps.as_mut(), mock_env()).unwrap();
+
+        let update_config = ExecuteMsg::UpdateConfig {
+            control_contract: None,
+            reward_contract: None,
+            custody_contract: None,
+            reward_denoms: Some(vec![uatom.clone()]),
+            threshold: None,
+            pool_contract: None,
+            stable_denom: None,
+            swap_adapter: None,
+            oracle_contract: None,
+        };
+        execute(deps.as_mut(), mock_env(), mock_info("owner", &[]), update_config).unwrap();
+        execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("owner", &[]),
+            ExecuteMsg::ExecuteChange { id: 1 },
+        )
+        .unwrap();
+
+        let config = read_config(deps.as_ref().storage).unwrap();
+        assert_eq!(config.reward_denoms, vec![uatom.clone()]);
+        assert_eq!(config.removed_reward_denoms, vec![usei.clone()]);
+        assert_eq!(
+            read_reward_index(deps.as_ref().storage, &uatom)
+                .unwrap()
+                .prev_reward_balance,
+            Uint128::new(500)
+        );
+
+        // the removed usei is no longer indexed, its accrued rewards stay claimable
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(150, "usei"), coin(700, "uatom")]);
+        let res = execute_update_global_index(deps.as_mut(), mock_env()).unwrap();
+        assert!(res.attributes.contains(&attr("claimed_rewards", "200uatom")));
+
+        let accrued: AccruedRewardsResponse = from_binary(
+            &query(
+                deps.as_ref(),
+                mock_env(),
+                QueryMsg::AccruedRewards {
+                    address: "holder".to_string(),
+                },
+            )
+            .unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            accrued.rewards,
+            vec![
+                RewardAmount {
+                    denom: uatom,
+                    amount: Uint128::new(200),
+                },
+                RewardAmount {
+                    denom: usei,
+                    amount: Uint128::new(100),
+                },
+            ]
+        );
+
+        let res = execute(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("holder", &[]),
+            ExecuteMsg::ClaimRewards { recipient: None },
+        )
+        .unwrap();
+        assert!(res.attributes.contains(&attr("rewards", "200uatom,100usei")));
+    }
 }
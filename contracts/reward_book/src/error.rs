@@ -15,6 +15,7 @@
 
 use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{OverflowError, StdError, Uint128};
+use cdp::rbac::AccessError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -37,9 +38,37 @@ pub enum ContractError {
     #[error("Invalid reply ID")]
     InvalidReplyId {},
 
+    #[error("A migration is in progress, migrate the remaining holders first")]
+    MigrationPending {},
+
     #[error("Functionality deprecated")]
     Deprecated {},
 
+    #[error("Reward denoms must be a non empty list without duplicates")]
+    InvalidRewardDenoms {},
+
+    #[error("Auto repay requires the stable pool and the stable denom to be configured")]
+    AutoRepayNotConfigured {},
+
+    #[error("Rewards of an auto repaying holder repay its loan and can not be sent to a recipient")]
+    AutoRepayRecipient {},
+
+    #[error("Max slippage can not be greater than one")]
+    InvalidMaxSlippage {},
+
     #[error("Decrease amount cannot exceed user balance: {0}")]
     DecreaseExcceedUserBalance (Uint128),
+
+    #[error("Contract is paused")]
+    Paused {},
+}
+
+impl AccessError for ContractError {
+    fn unauthorized(function: &str, sender: &str) -> Self {
+        ContractError::Unauthorized(function.to_string(), sender.to_string())
+    }
+
+    fn paused() -> Self {
+        ContractError::Paused {}
+    }
 }
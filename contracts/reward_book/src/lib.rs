@@ -1,5 +1,6 @@
 pub mod contract;
 pub mod state;
 pub mod error;
-mod math;
-mod user;
\ No newline at end of file
+pub mod migration;
+mod repay;
+mod user;
@@ -0,0 +1,387 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::state::{
+    read_config, store_config, store_holder, store_holder_reward, store_reward_index, store_state,
+    Holder, HolderReward, RewardIndex, State,
+};
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{CanonicalAddr, Decimal, Order, StdError, StdResult, Storage, Uint128};
+use cw20::Denom;
+use cw_storage_plus::{Bound, Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// the legacy layout shares the keys of the current one
+const LEGACY_STATE: Item<LegacyState> = Item::new("\u{0}\u{5}state");
+const LEGACY_HOLDERS: Map<&[u8], LegacyHolder> = Map::new("holders");
+
+// the single reward denom layout shares the keys of the current one
+const SINGLE_DENOM_CONFIG: Item<SingleDenomConfig> = Item::new("\u{0}\u{6}config");
+const SINGLE_DENOM_STATE: Item<SingleDenomState> = Item::new("\u{0}\u{5}state");
+const SINGLE_DENOM_HOLDERS: Map<&[u8], SingleDenomHolder> = Map::new("holders");
+
+// holders left to migrate. Holders are converted in place, each page resumes after the
+// last holder converted
+const MIGRATION_CURSOR: Item<HolderMigration> = Item::new("migration_cursor");
+
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct HolderMigration {
+    /// holders are stored with `Decimal` indexes
+    pub decimal_indexes: bool,
+    /// the former reward denom, the rewards of the holders are moved to its index
+    pub denom: Denom,
+    pub last_holder: Option<Vec<u8>>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyState {
+    pub global_index: Decimal,
+    pub total_balance: Uint128,
+    pub prev_reward_balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LegacyHolder {
+    pub balance: Uint128,
+    pub index: Decimal,
+    pub pending_rewards: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SingleDenomConfig {
+    pub owner: CanonicalAddr,
+    pub control_contract: CanonicalAddr,
+    pub custody_contract: CanonicalAddr,
+    pub reward_denom: String,
+    pub reward_contract: CanonicalAddr,
+    pub threshold: Uint256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SingleDenomState {
+    pub global_index: Decimal256,
+    pub total_balance: Uint128,
+    pub prev_reward_balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SingleDenomHolder {
+    pub balance: Uint128,
+    pub index: Decimal256,
+    pub pending_rewards: Decimal256,
+}
+
+/// Convert the `Decimal` global index to `Decimal256`, the holder indexes are converted
+/// when the holders are migrated
+pub fn migrate_decimal_indexes(storage: &mut dyn Storage) -> StdResult<()> {
+    let legacy_state = LEGACY_STATE.load(storage)?;
+    SINGLE_DENOM_STATE.save(
+        storage,
+        &SingleDenomState {
+            global_index: legacy_state.global_index.into(),
+            total_balance: legacy_state.total_balance,
+            prev_reward_balance: legacy_state.prev_reward_balance,
+        },
+    )
+}
+
+/// Move the reward index of the single reward denom to the per denom indexes, the former
+/// `reward_denom` becomes the only reward denom. The holders' rewards are moved by
+/// `migrate_holders`. Nothing is left to move once a deployment has been upgraded
+pub fn migrate_reward_denoms(storage: &mut dyn Storage, decimal_indexes: bool) -> StdResult<()> {
+    let mut config = read_config(storage)?;
+    if !config.reward_denoms.is_empty() {
+        return Ok(());
+    }
+
+    let denom = Denom::Native(SINGLE_DENOM_CONFIG.load(storage)?.reward_denom);
+    config.reward_denoms = vec![denom.clone()];
+    store_config(storage, &config)?;
+
+    let single_denom_state = SINGLE_DENOM_STATE.load(storage)?;
+    store_state(
+        storage,
+        &State {
+            total_balance: single_denom_state.total_balance,
+        },
+    )?;
+    store_reward_index(
+        storage,
+        &denom,
+        &RewardIndex {
+            global_index: single_denom_state.global_index,
+            prev_reward_balance: single_denom_state.prev_reward_balance,
+        },
+    )?;
+
+    MIGRATION_CURSOR.save(
+        storage,
+        &HolderMigration {
+            decimal_indexes,
+            denom,
+            last_holder: None,
+        },
+    )
+}
+
+pub fn is_migrating(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(MIGRATION_CURSOR.may_load(storage)?.is_some())
+}
+
+/// Move the rewards of the next `limit` holders to the index of the former reward denom,
+/// returns whether all holders are migrated
+pub fn migrate_holders(storage: &mut dyn Storage, limit: Option<u32>) -> StdResult<bool> {
+    let mut cursor = MIGRATION_CURSOR
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err("All holders are migrated"))?;
+
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = cursor.last_holder.as_deref().map(Bound::exclusive);
+    let holders: Vec<(Vec<u8>, SingleDenomHolder)> = if cursor.decimal_indexes {
+        LEGACY_HOLDERS
+            .range(storage, start, None, Order::Ascending)
+            .take(limit)
+            .map(|item| {
+                item.map(|(k, v)| {
+                    let holder = SingleDenomHolder {
+                        balance: v.balance,
+                        index: Decimal256::from(v.index),
+                        pending_rewards: Decimal256::from(v.pending_rewards),
+                    };
+                    (k, holder)
+                })
+            })
+            .collect::<StdResult<_>>()?
+    } else {
+        SINGLE_DENOM_HOLDERS
+            .range(storage, start, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<_>>()?
+    };
+
+    let done = holders.len() < limit;
+    cursor.last_holder = holders.last().map(|(k, _)| k.clone());
+    for (k, v) in holders {
+        let holder_address = CanonicalAddr::from(k);
+        store_holder(storage, &holder_address, &Holder { balance: v.balance })?;
+        store_holder_reward(
+            storage,
+            &holder_address,
+            &cursor.denom,
+            &HolderReward {
+                index: v.index,
+                pending_rewards: v.pending_rewards,
+            },
+        )?;
+    }
+
+    if done {
+        MIGRATION_CURSOR.remove(storage);
+    } else {
+        MIGRATION_CURSOR.save(storage, &cursor)?;
+    }
+    Ok(done)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::contract::{execute, migrate};
+    use crate::error::ContractError;
+    use crate::state::{read_holder, read_holder_reward, read_reward_index, read_state};
+    use cdp::rbac::{has_role, Role};
+    use cdp::reward_book::{ExecuteMsg, MigrateMsg};
+    use cdp::timelock::read_timelock_config;
+    use cosmwasm_bignumber::Uint256;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+    use cosmwasm_std::{attr, Api};
+    use cw2::set_contract_version;
+
+    #[test]
+    fn proper_migrate_from_legacy() {
+        let mut deps = mock_dependencies();
+        let owner = deps.api.addr_canonicalize("owner").unwrap();
+        let config = SingleDenomConfig {
+            owner: owner.clone(),
+            control_contract: deps.api.addr_canonicalize("control").unwrap(),
+            custody_contract: deps.api.addr_canonicalize("custody").unwrap(),
+            reward_denom: "ukrw".to_string(),
+            reward_contract: deps.api.addr_canonicalize("reward").unwrap(),
+            threshold: Uint256::zero(),
+        };
+        SINGLE_DENOM_CONFIG.save(deps.as_mut().storage, &config).unwrap();
+
+        // legacy layout
+        LEGACY_STATE
+            .save(
+                deps.as_mut().storage,
+                &LegacyState {
+                    global_index: Decimal::from_ratio(3u128, 7u128),
+                    total_balance: Uint128::new(1000),
+                    prev_reward_balance: Uint128::new(50),
+                },
+            )
+            .unwrap();
+        let holder = deps.api.addr_canonicalize("holder0000").unwrap();
+        LEGACY_HOLDERS
+            .save(
+                deps.as_mut().storage,
+                holder.as_slice(),
+                &LegacyHolder {
+                    balance: Uint128::new(1000),
+                    index: Decimal::from_ratio(1u128, 7u128),
+                    pending_rewards: Decimal::from_ratio(1u128, 3u128),
+                },
+            )
+            .unwrap();
+
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg::FromLegacy {
+                guardian_addr: "guardian".to_string(),
+                timelock_delay: 86400u64,
+            },
+        )
+        .unwrap();
+
+        // the former reward denom is the only reward denom
+        let denom = Denom::Native("ukrw".to_string());
+        assert_eq!(
+            read_config(deps.as_ref().storage).unwrap().reward_denoms,
+            vec![denom.clone()]
+        );
+        let state = read_state(deps.as_ref().storage).unwrap();
+        assert_eq!(state.total_balance, Uint128::new(1000));
+        assert_eq!(
+            read_reward_index(deps.as_ref().storage, &denom).unwrap(),
+            RewardIndex {
+                global_index: Decimal256::from(Decimal::from_ratio(3u128, 7u128)),
+                prev_reward_balance: Uint128::new(50),
+            }
+        );
+
+        let migrated = read_holder(deps.as_ref().storage, &holder).unwrap();
+        assert_eq!(
+            migrated,
+            Holder {
+                balance: Uint128::new(1000),
+            }
+        );
+        assert_eq!(
+            read_holder_reward(deps.as_ref().storage, &holder, &denom).unwrap(),
+            HolderReward {
+                index: Decimal256::from(Decimal::from_ratio(1u128, 7u128)),
+                pending_rewards: Decimal256::from(Decimal::from_ratio(1u128, 3u128)),
+            }
+        );
+
+        assert_eq!(
+            read_timelock_config(deps.as_ref().storage).unwrap().delay,
+            86400u64
+        );
+        assert!(has_role(deps.as_ref().storage, Role::Admin, &owner).unwrap());
+        let guardian = deps.api.addr_canonicalize("guardian").unwrap();
+        assert!(has_role(deps.as_ref().storage, Role::Guardian, &guardian).unwrap());
+
+        // a versioned deployment can not be migrated from legacy again
+        migrate(
+            deps.as_mut(),
+            mock_env(),
+            MigrateMsg::FromLegacy {
+                guardian_addr: "guardian".to_string(),
+                timelock_delay: 86400u64,
+            },
+        )
+        .unwrap_err();
+        migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+    }
+
+    #[test]
+    fn holders_are_migrated_by_pages() {
+        let mut deps = mock_dependencies();
+        set_contract_version(deps.as_mut().storage, "crates.io:cdp-reward-book", "0.0.1").unwrap();
+        let config = SingleDenomConfig {
+            owner: deps.api.addr_canonicalize("owner").unwrap(),
+            control_contract: deps.api.addr_canonicalize("control").unwrap(),
+            custody_contract: deps.api.addr_canonicalize("custody").unwrap(),
+            reward_denom: "ukrw".to_string(),
+            reward_contract: deps.api.addr_canonicalize("reward").unwrap(),
+            threshold: Uint256::zero(),
+        };
+        SINGLE_DENOM_CONFIG.save(deps.as_mut().storage, &config).unwrap();
+        SINGLE_DENOM_STATE
+            .save(
+                deps.as_mut().storage,
+                &SingleDenomState {
+                    global_index: Decimal256::percent(3),
+                    total_balance: Uint128::new(12000),
+                    prev_reward_balance: Uint128::new(50),
+                },
+            )
+            .unwrap();
+        let holders: Vec<_> = (0..12)
+            .map(|i| deps.api.addr_canonicalize(&format!("holder{:02}", i)).unwrap())
+            .collect();
+        for holder in holders.iter() {
+            SINGLE_DENOM_HOLDERS
+                .save(
+                    deps.as_mut().storage,
+                    holder.as_slice(),
+                    &SingleDenomHolder {
+                        balance: Uint128::new(1000),
+                        index: Decimal256::percent(1),
+                        pending_rewards: Decimal256::percent(2),
+                    },
+                )
+                .unwrap();
+        }
+
+        // the migration moves a first page only
+        let res = migrate(deps.as_mut(), mock_env(), MigrateMsg::Upgrade {}).unwrap();
+        assert!(res.attributes.contains(&attr("done", "false")));
+
+        // holders can not be used until all of them are moved
+        let msg = ExecuteMsg::ClaimRewards { recipient: None };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("holder00", &[]), msg);
+        assert_eq!(res.unwrap_err(), ContractError::MigrationPending {});
+
+        // anyone moves the remaining holders
+        let msg = ExecuteMsg::MigrateHolders { limit: None };
+        let res = execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap();
+        assert!(res.attributes.contains(&attr("done", "true")));
+
+        let denom = Denom::Native("ukrw".to_string());
+        for holder in holders.iter() {
+            assert_eq!(
+                read_holder(deps.as_ref().storage, holder).unwrap().balance,
+                Uint128::new(1000)
+            );
+            assert_eq!(
+                read_holder_reward(deps.as_ref().storage, holder, &denom).unwrap(),
+                HolderReward {
+                    index: Decimal256::percent(1),
+                    pending_rewards: Decimal256::percent(2),
+                }
+            );
+        }
+
+        let msg = ExecuteMsg::MigrateHolders { limit: None };
+        execute(deps.as_mut(), mock_env(), mock_info("anyone", &[]), msg).unwrap_err();
+    }
+}
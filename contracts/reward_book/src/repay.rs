@@ -0,0 +1,533 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+use crate::error::ContractError;
+use crate::state::{
+    denom_str, read_auto_repay, read_config, remove_auto_repay, store_auto_repay, Config,
+};
+use crate::user::{claim_holder_rewards, rewards_str};
+use cdp::oracle_pyth::PriceResponse;
+use cdp::querier::{query_balance, query_control_loan_info, query_price};
+use cdp::rbac::assert_not_paused;
+use cdp::reward_book::{AutoRepay, ExecuteMsg, RewardAmount};
+use cdp::stable_pool::ExecuteMsg as PoolExecuteMsg;
+use cdp::swap_adapter::{Cw20HookMsg as SwapCw20HookMsg, ExecuteMsg as SwapExecuteMsg};
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{
+    attr, coins, to_binary, Addr, BankMsg, CosmosMsg, DepsMut, Env, MessageInfo, Response,
+    Uint128, WasmMsg,
+};
+use cw20::{Cw20ExecuteMsg, Denom};
+
+pub fn execute_set_auto_repay(
+    deps: DepsMut,
+    info: MessageInfo,
+    auto_repay: Option<AutoRepay>,
+) -> Result<Response, ContractError> {
+    let holder_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+
+    let enabled = auto_repay.is_some();
+    match auto_repay {
+        Some(auto_repay) => {
+            assert_not_paused::<ContractError>(deps.storage)?;
+            let config: Config = read_config(deps.storage)?;
+            if config.pool_contract.is_none() || config.stable_denom.is_none() {
+                return Err(ContractError::AutoRepayNotConfigured {});
+            }
+            if auto_repay.max_slippage > Decimal256::one() {
+                return Err(ContractError::InvalidMaxSlippage {});
+            }
+            store_auto_repay(deps.storage, &holder_raw, &auto_repay)?;
+        }
+        None => remove_auto_repay(deps.storage, &holder_raw),
+    }
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "set_auto_repay"),
+        attr("holder_address", info.sender.to_string()),
+        attr("auto_repay", enabled.to_string()),
+    ]))
+}
+
+/// Repay the loan of an auto repaying holder, nothing is done once the holder
+/// turned auto repay off
+pub fn execute_auto_repay(
+    deps: DepsMut,
+    env: Env,
+    holder: Addr,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let holder_raw = deps.api.addr_canonicalize(holder.as_str())?;
+    let messages = match read_auto_repay(deps.storage, &holder_raw)? {
+        Some(auto_repay) => auto_repay_messages(deps, &env, &config, &holder, &auto_repay)?,
+        None => vec![],
+    };
+
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "auto_repay"),
+        attr("holder_address", holder.to_string()),
+    ]))
+}
+
+/// Claim the rewards of the holder, swap them to kUSD and repay its loan with the
+/// return by a message to the contract itself. Rewards already in kUSD are repaid
+/// as they are, rewards that can not be swapped without an adapter and an oracle stay
+/// pending. The minimum return of a swap is the oracle value of the rewards less the
+/// holder's max slippage. No message is returned when nothing was claimed
+pub fn auto_repay_messages(
+    deps: DepsMut,
+    env: &Env,
+    config: &Config,
+    holder: &Addr,
+    auto_repay: &AutoRepay,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    assert_not_paused::<ContractError>(deps.storage)?;
+
+    let stable_denom = config
+        .stable_denom
+        .clone()
+        .ok_or(ContractError::AutoRepayNotConfigured {})?;
+    let stable = Denom::Native(stable_denom.clone());
+    let swap = match (&config.swap_adapter, &config.oracle_contract) {
+        (Some(swap_adapter), Some(oracle_contract)) => Some((
+            deps.api.addr_humanize(swap_adapter)?,
+            deps.api.addr_humanize(oracle_contract)?,
+        )),
+        _ => None,
+    };
+
+    let holder_raw = deps.api.addr_canonicalize(holder.as_str())?;
+    let claimed = claim_holder_rewards(deps.storage, config, &holder_raw, |denom| {
+        *denom == stable || swap.is_some()
+    })?;
+    if claimed.is_empty() {
+        return Ok(vec![]);
+    }
+
+    let prev_balance: Uint128 = query_balance(
+        deps.as_ref(),
+        env.contract.address.clone(),
+        stable_denom.clone(),
+    )?
+    .into();
+
+    let mut direct_amount = Uint128::zero();
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for reward in claimed.iter() {
+        let (swap_adapter, oracle_contract) = match &swap {
+            Some(swap) if reward.denom != stable => swap,
+            _ => {
+                direct_amount += reward.amount;
+                continue;
+            }
+        };
+
+        let price: PriceResponse = query_price(
+            deps.as_ref(),
+            oracle_contract.clone(),
+            denom_str(&reward.denom).to_string(),
+            stable_denom.clone(),
+            None,
+        )?;
+        let min_receive: Uint128 = (Uint256::from(reward.amount)
+            * price.emv_price
+            * (Decimal256::one() - auto_repay.max_slippage))
+            .into();
+        messages.push(swap_message(swap_adapter, reward, &stable_denom, min_receive)?);
+    }
+
+    messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::RepaySwapped {
+            holder: holder.to_string(),
+            vault_id: auto_repay.vault_id,
+            prev_balance,
+            direct_amount,
+        })?,
+        funds: vec![],
+    }));
+
+    Ok(messages)
+}
+
+// swap return is sent back to the contract
+fn swap_message(
+    swap_adapter: &Addr,
+    reward: &RewardAmount,
+    stable_denom: &str,
+    min_receive: Uint128,
+) -> Result<CosmosMsg, ContractError> {
+    Ok(match &reward.denom {
+        Denom::Native(denom) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: swap_adapter.to_string(),
+            msg: to_binary(&SwapExecuteMsg::Swap {
+                ask_denom: stable_denom.to_string(),
+                min_receive,
+                recipient: None,
+            })?,
+            funds: coins(reward.amount.u128(), denom),
+        }),
+        Denom::Cw20(contract_addr) => CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: contract_addr.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Send {
+                contract: swap_adapter.to_string(),
+                amount: reward.amount,
+                msg: to_binary(&SwapCw20HookMsg::Swap {
+                    ask_denom: stable_denom.to_string(),
+                    min_receive,
+                    recipient: None,
+                })?,
+            })?,
+            funds: vec![],
+        }),
+    })
+}
+
+/// Repay the loan of the holder's vault with the kUSD received since `prev_balance`
+/// and `direct_amount`, any kUSD beyond the loan is sent to the holder
+pub fn execute_repay_swapped(
+    deps: DepsMut,
+    env: Env,
+    holder: Addr,
+    vault_id: Option<u64>,
+    prev_balance: Uint128,
+    direct_amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config: Config = read_config(deps.storage)?;
+    let (pool_contract, stable_denom) = match (config.pool_contract, config.stable_denom) {
+        (Some(pool_contract), Some(stable_denom)) => (pool_contract, stable_denom),
+        _ => return Err(ContractError::AutoRepayNotConfigured {}),
+    };
+
+    let balance: Uint128 = query_balance(
+        deps.as_ref(),
+        env.contract.address.clone(),
+        stable_denom.clone(),
+    )?
+    .into();
+    let received = balance.checked_sub(prev_balance)? + direct_amount;
+
+    let loan_info = query_control_loan_info(
+        deps.as_ref(),
+        deps.api.addr_humanize(&config.control_contract)?.to_string(),
+        holder.to_string(),
+        vault_id,
+    )?;
+    let repay_amount: Uint128 = Uint256::from(received).min(loan_info.loans).into();
+    let refund_amount = received.checked_sub(repay_amount)?;
+
+    let mut messages: Vec<CosmosMsg> = vec![];
+    if !repay_amount.is_zero() {
+        messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+            contract_addr: deps.api.addr_humanize(&pool_contract)?.to_string(),
+            msg: to_binary(&PoolExecuteMsg::RepayStableCoin {
+                minter: Some(holder.to_string()),
+                vault_id,
+            })?,
+            funds: coins(repay_amount.u128(), stable_denom.clone()),
+        }));
+    }
+    if !refund_amount.is_zero() {
+        messages.push(CosmosMsg::Bank(BankMsg::Send {
+            to_address: holder.to_string(),
+            amount: coins(refund_amount.u128(), stable_denom.clone()),
+        }));
+    }
+
+    let repaid = RewardAmount {
+        denom: Denom::Native(stable_denom),
+        amount: repay_amount,
+    };
+    Ok(Response::new().add_messages(messages).add_attributes(vec![
+        attr("action", "repay_swapped"),
+        attr("holder_address", holder.to_string()),
+        attr("repay_amount", rewards_str(&[repaid])),
+        attr("refund_amount", refund_amount),
+    ]))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::contract::{execute, instantiate, AUTO_REPAY};
+    use crate::error::ContractError;
+    use cdp::central_control::LoanInfoResponse;
+    use cdp::oracle_pyth::PriceResponse;
+    use cdp::reward_book::{AutoRepay, ExecuteMsg, InstantiateMsg};
+    use cdp::stable_pool::ExecuteMsg as PoolExecuteMsg;
+    use cdp::swap_adapter::{Cw20HookMsg as SwapCw20HookMsg, ExecuteMsg as SwapExecuteMsg};
+    use cdp_mock_swap::contract as mock_swap;
+    use cdp_mock_swap::msg::{
+        ExecuteMsg as MockSwapExecuteMsg, InstantiateMsg as MockSwapInstantiateMsg,
+    };
+    use cosmwasm_bignumber::{Decimal256, Uint256};
+    use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR};
+    use cosmwasm_std::{
+        attr, coin, coins, from_binary, to_binary, Addr, BankMsg, ContractResult, CosmosMsg,
+        QuerierResult, SubMsg, SystemResult, Uint128, WasmMsg, WasmQuery,
+    };
+    use cw20::{BalanceResponse, Cw20ExecuteMsg, Cw20ReceiveMsg, Denom};
+
+    // usei and the reward token are priced at 2 kUSD, the holder's loan is 150 kUSD
+    fn wasm_querier(reward_token_balance: u128) -> impl Fn(&WasmQuery) -> QuerierResult {
+        move |query| {
+            let res = match query {
+                WasmQuery::Smart { contract_addr, .. } if contract_addr == "oracle" => {
+                    to_binary(&PriceResponse {
+                        asset: "usei".to_string(),
+                        emv_price: Decimal256::from_uint256(2u64),
+                        emv_price_raw: 2,
+                        price: Decimal256::from_uint256(2u64),
+                        price_raw: 2,
+                        last_updated_base: 0,
+                        last_updated_quote: 0,
+                    })
+                }
+                WasmQuery::Smart { contract_addr, .. } if contract_addr == "reward_token" => {
+                    to_binary(&BalanceResponse {
+                        balance: Uint128::new(reward_token_balance),
+                    })
+                }
+                _ => to_binary(&LoanInfoResponse {
+                    minter: "holder".to_string(),
+                    vault_id: 1,
+                    loans: Uint256::from(150u64),
+                    max_mint_value: Uint256::from(1000u64),
+                }),
+            };
+            SystemResult::Ok(ContractResult::Ok(res.unwrap()))
+        }
+    }
+
+    #[test]
+    pub fn proper_auto_repay_through_swap_adapter() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        instantiate(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("owner", &[]),
+            InstantiateMsg {
+                control_contract: "control".to_string(),
+                reward_contract: "reward".to_string(),
+                custody_contract: "custody".to_string(),
+                reward_denoms: vec![
+                    Denom::Native("usei".to_string()),
+                    Denom::Cw20(Addr::unchecked("reward_token")),
+                    Denom::Native("kusd".to_string()),
+                ],
+                threshold: Uint256::zero(),
+                pool_contract: Some("pool".to_string()),
+                stable_denom: Some("kusd".to_string()),
+                swap_adapter: Some("swap".to_string()),
+                oracle_contract: Some("oracle".to_string()),
+                guardian_addr: "guardian".to_string(),
+                timelock_delay: 0,
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("control", &[]),
+            ExecuteMsg::IncreaseBalance {
+                address: "holder".to_string(),
+                amount: Uint128::new(1000),
+            },
+        )
+        .unwrap();
+        execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("holder", &[]),
+            ExecuteMsg::SetAutoRepay {
+                auto_repay: Some(AutoRepay {
+                    vault_id: Some(1),
+                    max_slippage: Decimal256::percent(1),
+                }),
+            },
+        )
+        .unwrap();
+
+        // the swap adapter returns 2 kUSD per usei and per reward token
+        let mut swap_deps = mock_dependencies();
+        let info = mock_info("owner", &[]);
+        let msg = MockSwapInstantiateMsg {
+            owner: "owner".to_string(),
+        };
+        mock_swap::instantiate(swap_deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        for offer_token in ["usei", "reward_token"] {
+            let msg = MockSwapExecuteMsg::SetRate {
+                offer_token: offer_token.to_string(),
+                ask_denom: "kusd".to_string(),
+                rate: Decimal256::from_uint256(2u64),
+            };
+            mock_swap::execute(swap_deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+        }
+
+        // an index update claiming 100 usei, 50 reward tokens and 20 kUSD triggers
+        // the auto repay of the holder
+        deps.querier
+            .update_balance(MOCK_CONTRACT_ADDR, vec![coin(100, "usei"), coin(20, "kusd")]);
+        deps.querier.update_wasm(wasm_querier(50));
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            ExecuteMsg::ExecuteUpdateGlobalIndex {},
+        )
+        .unwrap();
+        assert!(res
+            .attributes
+            .contains(&attr("claimed_rewards", "100usei,50reward_token,20kusd")));
+        let auto_repay_msg = ExecuteMsg::AutoRepay {
+            holder: "holder".to_string(),
+        };
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::reply_on_error(
+                WasmMsg::Execute {
+                    contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                    msg: to_binary(&auto_repay_msg).unwrap(),
+                    funds: vec![],
+                },
+                AUTO_REPAY,
+            )]
+        );
+
+        // the rewards are swapped with at most 1% below their oracle value, the kUSD
+        // rewards are repaid as they are
+        let res = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            auto_repay_msg,
+        )
+        .unwrap();
+        let native_swap_msg = SwapExecuteMsg::Swap {
+            ask_denom: "kusd".to_string(),
+            min_receive: Uint128::new(198),
+            recipient: None,
+        };
+        let cw20_swap_msg = to_binary(&SwapCw20HookMsg::Swap {
+            ask_denom: "kusd".to_string(),
+            min_receive: Uint128::new(99),
+            recipient: None,
+        })
+        .unwrap();
+        let repay_swapped_msg = ExecuteMsg::RepaySwapped {
+            holder: "holder".to_string(),
+            vault_id: Some(1),
+            prev_balance: Uint128::new(20),
+            direct_amount: Uint128::new(20),
+        };
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "swap".to_string(),
+                    msg: to_binary(&native_swap_msg).unwrap(),
+                    funds: coins(100, "usei"),
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "reward_token".to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Send {
+                        contract: "swap".to_string(),
+                        amount: Uint128::new(50),
+                        msg: cw20_swap_msg.clone(),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                    msg: to_binary(&repay_swapped_msg).unwrap(),
+                    funds: vec![],
+                }),
+            ]
+        );
+
+        // the swap adapter pays both returns to the reward book, the adapter messages are
+        // read as the mock's
+        let native_swap_msg = from_binary(&to_binary(&native_swap_msg).unwrap()).unwrap();
+        let info = mock_info(MOCK_CONTRACT_ADDR, &coins(100, "usei"));
+        let res =
+            mock_swap::execute(swap_deps.as_mut(), env.clone(), info, native_swap_msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: MOCK_CONTRACT_ADDR.to_string(),
+                amount: coins(200, "kusd"),
+            })]
+        );
+        let msg = MockSwapExecuteMsg::Receive(Cw20ReceiveMsg {
+            sender: MOCK_CONTRACT_ADDR.to_string(),
+            amount: Uint128::new(50),
+            msg: cw20_swap_msg,
+        });
+        let info = mock_info("reward_token", &[]);
+        let res = mock_swap::execute(swap_deps.as_mut(), env.clone(), info, msg).unwrap();
+        assert_eq!(
+            res.messages,
+            vec![SubMsg::new(BankMsg::Send {
+                to_address: MOCK_CONTRACT_ADDR.to_string(),
+                amount: coins(100, "kusd"),
+            })]
+        );
+        deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(320, "kusd"));
+        deps.querier.update_wasm(wasm_querier(0));
+
+        // only the contract itself can repay the swap returns
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            mock_info("keeper", &[]),
+            repay_swapped_msg.clone(),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Unauthorized("repay_swapped".to_string(), "keeper".to_string())
+        );
+
+        // 320 kUSD are received, the loan takes 150 and the rest is refunded to the holder
+        let res = execute(
+            deps.as_mut(),
+            env,
+            mock_info(MOCK_CONTRACT_ADDR, &[]),
+            repay_swapped_msg,
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                    contract_addr: "pool".to_string(),
+                    msg: to_binary(&PoolExecuteMsg::RepayStableCoin {
+                        minter: Some("holder".to_string()),
+                        vault_id: Some(1),
+                    })
+                    .unwrap(),
+                    funds: coins(150, "kusd"),
+                })),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "holder".to_string(),
+                    amount: coins(170, "kusd"),
+                }),
+            ]
+        );
+        assert!(res.attributes.contains(&attr("repay_amount", "150kusd")));
+        assert!(res.attributes.contains(&attr("refund_amount", "170")));
+    }
+}
@@ -12,28 +12,59 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cosmwasm_bignumber::Uint256;
-use cosmwasm_std::{Addr, Api, CanonicalAddr, Decimal, Deps, Order, StdResult, Storage, Uint128};
+use cosmwasm_bignumber::{Decimal256, Uint256};
+use cosmwasm_std::{Addr, Api, CanonicalAddr, Deps, Order, StdResult, Storage, Uint128};
 
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cdp::reward_book::HolderResponse;
+use cdp::reward_book::{AutoRepay, HolderResponse, HolderRewardResponse};
+use cw20::Denom;
 use cw_storage_plus::{Bound, Item, Map};
 
 pub const STATE: Item<State> = Item::new("\u{0}\u{5}state");
 pub const CONFIG: Item<Config> = Item::new("\u{0}\u{6}config");
 pub const HOLDERS: Map<&[u8], Holder> = Map::new("holders");
 pub const NEWOWNER:Item<NewOwnerAddr> = Item::new("\u{0}\u{8}newowner");
+// denom key => reward index of the denom
+pub const REWARD_INDEXES: Map<&str, RewardIndex> = Map::new("reward_indexes");
+// (holder, denom key) => rewards of the holder in the denom
+pub const HOLDER_REWARDS: Map<(&[u8], &str), HolderReward> = Map::new("holder_rewards");
+// holder => auto repay setting, only auto repaying holders are stored
+pub const AUTO_REPAYS: Map<&[u8], AutoRepay> = Map::new("auto_repays");
+// last holder auto repaid on an index update, the next update resumes after it
+pub const AUTO_REPAY_CURSOR: Item<Vec<u8>> = Item::new("auto_repay_cursor");
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Config {
     pub owner: CanonicalAddr,
     pub control_contract: CanonicalAddr,
     pub custody_contract: CanonicalAddr,
-    pub reward_denom: String,
+    // configs stored before multiple reward denoms are empty until migrated
+    #[serde(default)]
+    pub reward_denoms: Vec<Denom>,
     pub reward_contract: CanonicalAddr,    // krp-Staking module reward contract address
     pub threshold: Uint256,
+    #[serde(default)]
+    pub pool_contract: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub stable_denom: Option<String>,
+    #[serde(default)]
+    pub swap_adapter: Option<CanonicalAddr>,
+    #[serde(default)]
+    pub oracle_contract: Option<CanonicalAddr>,
+    // denoms removed from the reward denoms whose accrued rewards are still claimable
+    #[serde(default)]
+    pub removed_reward_denoms: Vec<Denom>,
+}
+
+impl Config {
+    // denoms whose rewards are settled and claimed by the holders
+    pub fn claimable_denoms(&self) -> Vec<Denom> {
+        let mut denoms = self.reward_denoms.clone();
+        denoms.extend(self.removed_reward_denoms.iter().cloned());
+        denoms
+    }
 }
 
 pub fn store_config(storage: &mut dyn Storage, config: &Config) -> StdResult<()> {
@@ -61,9 +92,7 @@ pub fn read_new_owner(storage: &dyn Storage) -> StdResult<NewOwnerAddr> {
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct State {
-    pub global_index: Decimal,
     pub total_balance: Uint128,
-    pub prev_reward_balance: Uint128,
 }
 
 pub fn store_state(storage: &mut dyn Storage, state: &State) -> StdResult<()> {
@@ -77,8 +106,18 @@ pub fn read_state(storage: &dyn Storage) -> StdResult<State> {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub struct Holder {
     pub balance: Uint128,
-    pub index: Decimal,
-    pub pending_rewards: Decimal,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct RewardIndex {
+    pub global_index: Decimal256,
+    pub prev_reward_balance: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema, Default)]
+pub struct HolderReward {
+    pub index: Decimal256,
+    pub pending_rewards: Decimal256,
 }
 
 // This is similar to HashMap<holder's address, Hodler>
@@ -96,12 +135,130 @@ pub fn read_holder(storage: &dyn Storage, holder_address: &CanonicalAddr) -> Std
         Some(holder) => Ok(holder),
         None => Ok(Holder {
             balance: Uint128::zero(),
-            index: Decimal::zero(),
-            pending_rewards: Decimal::zero(),
         }),
     }
 }
 
+// native denom or CW20 contract address
+pub fn denom_str(denom: &Denom) -> &str {
+    match denom {
+        Denom::Native(denom) => denom.as_str(),
+        Denom::Cw20(contract_addr) => contract_addr.as_str(),
+    }
+}
+
+// native denoms and CW20 addresses are kept apart in storage keys
+fn denom_key(denom: &Denom) -> String {
+    match denom {
+        Denom::Native(denom) => format!("native:{}", denom),
+        Denom::Cw20(contract_addr) => format!("cw20:{}", contract_addr),
+    }
+}
+
+pub fn store_reward_index(
+    storage: &mut dyn Storage,
+    denom: &Denom,
+    reward_index: &RewardIndex,
+) -> StdResult<()> {
+    REWARD_INDEXES.save(storage, &denom_key(denom), reward_index)
+}
+
+// a denom starts with a zero index once added
+pub fn read_reward_index(storage: &dyn Storage, denom: &Denom) -> StdResult<RewardIndex> {
+    Ok(REWARD_INDEXES
+        .may_load(storage, &denom_key(denom))?
+        .unwrap_or_default())
+}
+
+pub fn store_holder_reward(
+    storage: &mut dyn Storage,
+    holder_address: &CanonicalAddr,
+    denom: &Denom,
+    holder_reward: &HolderReward,
+) -> StdResult<()> {
+    HOLDER_REWARDS.save(
+        storage,
+        (holder_address.as_slice(), &denom_key(denom)),
+        holder_reward,
+    )
+}
+
+pub fn read_holder_reward(
+    storage: &dyn Storage,
+    holder_address: &CanonicalAddr,
+    denom: &Denom,
+) -> StdResult<HolderReward> {
+    Ok(HOLDER_REWARDS
+        .may_load(storage, (holder_address.as_slice(), &denom_key(denom)))?
+        .unwrap_or_default())
+}
+
+pub fn read_holder_rewards(
+    storage: &dyn Storage,
+    holder_address: &CanonicalAddr,
+    reward_denoms: &[Denom],
+) -> StdResult<Vec<HolderRewardResponse>> {
+    reward_denoms
+        .iter()
+        .map(|denom| {
+            let holder_reward = read_holder_reward(storage, holder_address, denom)?;
+            Ok(HolderRewardResponse {
+                denom: denom.clone(),
+                index: holder_reward.index,
+                pending_rewards: holder_reward.pending_rewards,
+            })
+        })
+        .collect()
+}
+
+pub fn store_auto_repay(
+    storage: &mut dyn Storage,
+    holder_address: &CanonicalAddr,
+    auto_repay: &AutoRepay,
+) -> StdResult<()> {
+    AUTO_REPAYS.save(storage, holder_address.as_slice(), auto_repay)
+}
+
+pub fn remove_auto_repay(storage: &mut dyn Storage, holder_address: &CanonicalAddr) {
+    AUTO_REPAYS.remove(storage, holder_address.as_slice())
+}
+
+pub fn read_auto_repay(
+    storage: &dyn Storage,
+    holder_address: &CanonicalAddr,
+) -> StdResult<Option<AutoRepay>> {
+    AUTO_REPAYS.may_load(storage, holder_address.as_slice())
+}
+
+// read the next auto repaying holders after the cursor, starting over once the end
+// is reached, and move the cursor to the last one read
+pub fn next_auto_repay_holders(
+    storage: &mut dyn Storage,
+    limit: usize,
+) -> StdResult<Vec<CanonicalAddr>> {
+    let cursor = AUTO_REPAY_CURSOR.may_load(storage)?;
+    let mut holders = AUTO_REPAYS
+        .keys(
+            storage,
+            cursor.as_deref().map(Bound::exclusive),
+            None,
+            Order::Ascending,
+        )
+        .take(limit)
+        .collect::<StdResult<Vec<Vec<u8>>>>()?;
+    if holders.is_empty() && cursor.is_some() {
+        holders = AUTO_REPAYS
+            .keys(storage, None, None, Order::Ascending)
+            .take(limit)
+            .collect::<StdResult<Vec<Vec<u8>>>>()?;
+    }
+
+    if let Some(last) = holders.last() {
+        AUTO_REPAY_CURSOR.save(storage, last)?;
+    }
+    Ok(holders.into_iter().map(CanonicalAddr::from).collect())
+}
+
 // settings for pagination
 const MAX_LIMIT: u32 = 30;
 const DEFAULT_LIMIT: u32 = 10;
@@ -113,18 +270,19 @@ pub fn read_holders(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start =
         calc_range_start(deps.api, start_after.map(Addr::unchecked))?.map(Bound::ExclusiveRaw);
+    let reward_denoms = read_config(deps.storage)?.claimable_denoms();
 
     HOLDERS
         .range(deps.storage, start, None, Order::Ascending)
         .take(limit)
         .map(|elem| {
             let (k, v) = elem?;
-            let address: String = deps.api.addr_humanize(&CanonicalAddr::from(k))?.to_string();
+            let holder_address = CanonicalAddr::from(k);
             Ok(HolderResponse {
-                address,
+                address: deps.api.addr_humanize(&holder_address)?.to_string(),
                 balance: v.balance,
-                index: v.index,
-                pending_rewards: v.pending_rewards,
+                rewards: read_holder_rewards(deps.storage, &holder_address, &reward_denoms)?,
+                auto_repay: read_auto_repay(deps.storage, &holder_address)?,
             })
         })
         .collect()
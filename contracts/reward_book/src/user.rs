@@ -13,23 +13,25 @@
 // limitations under the License.
 
 use crate::error::ContractError;
+use crate::repay::auto_repay_messages;
 use crate::state::{
-    read_config, read_holder, read_holders, read_state, store_holder, store_state, Config, Holder,
-    State,
+    denom_str, read_auto_repay, read_config, read_holder, read_holder_reward,
+    read_holder_rewards, read_holders, read_reward_index, read_state, store_holder,
+    store_holder_reward, store_reward_index, store_state, Config, Holder, State,
 };
-use cdp::reward_book::{AccruedRewardsResponse, HolderResponse, HoldersResponse};
+use cdp::events::{CdpEvent, MODULE_REWARD_BOOK};
+use cdp::reward_book::{AccruedRewardsResponse, HolderResponse, HoldersResponse, RewardAmount};
 
+use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::{
-    attr, BankMsg, Coin, CosmosMsg, Decimal, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Uint128,
-};
-
-use crate::math::{
-    decimal_multiplication_in_256, decimal_subtraction_in_256, decimal_summation_in_256,
+    attr, to_binary, BankMsg, CanonicalAddr, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
+    Response, StdResult, Storage, Uint128, WasmMsg,
 };
+use cw20::{Cw20ExecuteMsg, Denom};
 
 pub fn execute_claim_rewards(
     deps: DepsMut,
-    _env: Env,
+    env: Env,
     info: MessageInfo,
     recipient: Option<String>,
 ) -> Result<Response, ContractError> {
@@ -40,48 +42,146 @@ pub fn execute_claim_rewards(
         None => holder_addr.clone(),
     };
 
-    let mut holder: Holder = read_holder(deps.storage, &holder_addr_raw)?;
-    let mut state: State = read_state(deps.storage)?;
     let config: Config = read_config(deps.storage)?;
+    if let Some(auto_repay) = read_auto_repay(deps.storage, &holder_addr_raw)? {
+        if recipient != holder_addr {
+            return Err(ContractError::AutoRepayRecipient {});
+        }
+        let messages = auto_repay_messages(deps, &env, &config, &holder_addr, &auto_repay)?;
+        if messages.is_empty() {
+            return Err(ContractError::NoRewardsHaveAccrued { });
+        }
+        return Ok(Response::new().add_messages(messages).add_attributes(vec![
+            attr("action", "claim_reward"),
+            attr("holder_address", holder_addr.to_string()),
+            attr("auto_repay", "true"),
+        ]));
+    }
 
-    let reward_with_decimals =
-        calculate_decimal_rewards(state.global_index, holder.index, holder.balance);
+    let claimed = claim_holder_rewards(deps.storage, &config, &holder_addr_raw, |_| true)?;
+    if claimed.is_empty() {
+        return Err(ContractError::NoRewardsHaveAccrued { });
+    }
+
+    let mut res = Response::new()
+        .add_messages(reward_messages(&claimed, recipient.as_str())?)
+        .add_attributes(vec![
+            attr("action", "claim_reward"),
+            attr("holder_address", holder_addr.to_string()),
+            attr("rewards", rewards_str(&claimed)),
+        ]);
+    for reward in claimed {
+        res = res.add_event(
+            CdpEvent::RewardClaim {
+                holder: holder_addr.to_string(),
+                recipient: recipient.to_string(),
+                denom: denom_str(&reward.denom).to_string(),
+                amount: reward.amount,
+            }
+            .into_event(MODULE_REWARD_BOOK),
+        );
+    }
 
-    let all_reward_with_decimals =
-        decimal_summation_in_256(reward_with_decimals, holder.pending_rewards);
-    let rewards = all_reward_with_decimals * Uint128::new(1);
-    let decimals = all_reward_with_decimals - Decimal::from_ratio(rewards, Uint128::new(1));
+    Ok(res)
+}
 
-    if rewards.is_zero() {
-        return Err(ContractError::NoRewardsHaveAccrued { });
+// native rewards are sent at once, CW20 rewards by a transfer per token
+fn reward_messages(rewards: &[RewardAmount], recipient: &str) -> StdResult<Vec<CosmosMsg>> {
+    let mut coins: Vec<Coin> = vec![];
+    let mut messages: Vec<CosmosMsg> = vec![];
+    for reward in rewards {
+        match &reward.denom {
+            Denom::Native(denom) => coins.push(Coin {
+                denom: denom.to_string(),
+                amount: reward.amount,
+            }),
+            Denom::Cw20(contract_addr) => messages.push(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                    recipient: recipient.to_string(),
+                    amount: reward.amount,
+                })?,
+                funds: vec![],
+            })),
+        }
     }
 
-    let new_balance = (state.prev_reward_balance.checked_sub(rewards))?;
-    state.prev_reward_balance = new_balance;
-    store_state(deps.storage, &state)?;
+    if !coins.is_empty() {
+        // the bank module only accepts coins sorted by denom
+        coins.sort_by(|a, b| a.denom.cmp(&b.denom));
+        messages.insert(
+            0,
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: recipient.to_string(),
+                amount: coins,
+            }),
+        );
+    }
 
-    holder.pending_rewards = decimals;
-    holder.index = state.global_index;
-    store_holder(deps.storage, &holder_addr_raw, &holder)?;
+    Ok(messages)
+}
 
-    let bank_msg = CosmosMsg::Bank(BankMsg::Send {
-        to_address: recipient.to_string(),
-        amount: vec![ Coin {
-                denom: config.reward_denom,
-                amount: rewards,
-            },
-        ],
-    });
+// rewards formatted as a list of coins, CW20 rewards by their contract address
+pub fn rewards_str(rewards: &[RewardAmount]) -> String {
+    rewards
+        .iter()
+        .map(|reward| format!("{}{}", reward.amount, denom_str(&reward.denom)))
+        .collect::<Vec<String>>()
+        .join(",")
+}
 
-    let res = Response::new()
-        .add_attributes(vec![
-            attr("action", "claim_reward"),
-            attr("holder_address", holder_addr),
-            attr("rewards", rewards),
-        ])
-        .add_message(bank_msg);
+/// Take the whole rewards of the holder in the denoms accepted by `claimable`,
+/// the decimals are left pending
+pub fn claim_holder_rewards(
+    storage: &mut dyn Storage,
+    config: &Config,
+    holder_address: &CanonicalAddr,
+    claimable: impl Fn(&Denom) -> bool,
+) -> StdResult<Vec<RewardAmount>> {
+    let holder: Holder = read_holder(storage, holder_address)?;
+    settle_holder_rewards(storage, config, holder_address, holder.balance)?;
+
+    let mut claimed: Vec<RewardAmount> = vec![];
+    for denom in config.claimable_denoms().iter().filter(|denom| claimable(denom)) {
+        let mut holder_reward = read_holder_reward(storage, holder_address, denom)?;
+        let rewards: Uint128 = (holder_reward.pending_rewards * Uint256::one()).into();
+        if rewards.is_zero() {
+            continue;
+        }
+
+        let mut reward_index = read_reward_index(storage, denom)?;
+        reward_index.prev_reward_balance = reward_index.prev_reward_balance.checked_sub(rewards)?;
+        store_reward_index(storage, denom, &reward_index)?;
+
+        holder_reward.pending_rewards =
+            holder_reward.pending_rewards - Decimal256::from_uint256(rewards);
+        store_holder_reward(storage, holder_address, denom, &holder_reward)?;
+
+        claimed.push(RewardAmount {
+            denom: denom.clone(),
+            amount: rewards,
+        });
+    }
+    Ok(claimed)
+}
 
-    Ok(res)
+/// Withdraw the rewards accrued on `balance` to pending rewards and
+/// set the reward index of every denom to its global index
+fn settle_holder_rewards(
+    storage: &mut dyn Storage,
+    config: &Config,
+    holder_address: &CanonicalAddr,
+    balance: Uint128,
+) -> StdResult<()> {
+    for denom in config.claimable_denoms().iter() {
+        let global_index = read_reward_index(storage, denom)?.global_index;
+        let mut holder_reward = read_holder_reward(storage, holder_address, denom)?;
+        let rewards = calculate_decimal_rewards(global_index, holder_reward.index, balance);
+        holder_reward.index = global_index;
+        holder_reward.pending_rewards = rewards + holder_reward.pending_rewards;
+        store_holder_reward(storage, holder_address, denom, &holder_reward)?;
+    }
+    Ok(())
 }
 
 pub fn execute_increase_balance(
@@ -108,11 +208,7 @@ pub fn execute_increase_balance(
     let mut state: State = read_state(deps.storage)?;
     let mut holder: Holder = read_holder(deps.storage, &address_raw)?;
 
-    // get decimals
-    let rewards = calculate_decimal_rewards(state.global_index, holder.index, holder.balance);
-
-    holder.index = state.global_index;
-    holder.pending_rewards = decimal_summation_in_256(rewards, holder.pending_rewards);
+    settle_holder_rewards(deps.storage, &config, &address_raw, holder.balance)?;
     holder.balance += amount;
     state.total_balance += amount;
 
@@ -154,10 +250,7 @@ pub fn execute_decrease_balance(
         return Err(ContractError::DecreaseExcceedUserBalance(holder.balance));
     }
 
-    let rewards = calculate_decimal_rewards(state.global_index, holder.index, holder.balance);
-
-    holder.index = state.global_index;
-    holder.pending_rewards = decimal_summation_in_256(rewards, holder.pending_rewards);
+    settle_holder_rewards(deps.storage, &config, &address_raw, holder.balance)?;
     holder.balance = (holder.balance.checked_sub(amount))?;
     state.total_balance = (state.total_balance.checked_sub(amount))?;
 
@@ -175,27 +268,87 @@ pub fn execute_decrease_balance(
     Ok(res)
 }
 
-pub fn query_accrued_rewards(deps: Deps, address: String) -> StdResult<AccruedRewardsResponse> {
-    let global_index = read_state(deps.storage)?.global_index;
+pub fn execute_transfer_balance(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    from: String,
+    to: String,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = read_config(deps.storage)?;
 
-    let holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
-    let reward_with_decimals =
-        calculate_decimal_rewards(global_index, holder.index, holder.balance);
-    let all_reward_with_decimals =
-        decimal_summation_in_256(reward_with_decimals, holder.pending_rewards);
+    let from_raw = deps.api.addr_canonicalize(&from)?;
+    let to_raw = deps.api.addr_canonicalize(&to)?;
 
-    let rewards = all_reward_with_decimals * Uint128::new(1);
+    // Check sender is control contract
+    if config.control_contract != deps.api.addr_canonicalize(info.sender.as_str())? {
+        return Err(ContractError::Unauthorized(
+            "execute_transfer_balance".to_string(),
+            info.sender.to_string(),
+        ));
+    }
+
+    let mut from_holder: Holder = read_holder(deps.storage, &from_raw)?;
+    if from_holder.balance < amount {
+        return Err(ContractError::DecreaseExcceedUserBalance(from_holder.balance));
+    }
+
+    // rewards accrued so far stay with the sender, the recipient accrues from now on
+    settle_holder_rewards(deps.storage, &config, &from_raw, from_holder.balance)?;
+    from_holder.balance = (from_holder.balance.checked_sub(amount))?;
+    store_holder(deps.storage, &from_raw, &from_holder)?;
+
+    let mut to_holder: Holder = read_holder(deps.storage, &to_raw)?;
+    settle_holder_rewards(deps.storage, &config, &to_raw, to_holder.balance)?;
+    to_holder.balance += amount;
+    store_holder(deps.storage, &to_raw, &to_holder)?;
+
+    let attributes = vec![
+        attr("action", "transfer_balance"),
+        attr("from", from),
+        attr("to", to),
+        attr("amount", amount),
+    ];
+
+    let res = Response::new().add_attributes(attributes);
+
+    Ok(res)
+}
+
+pub fn query_accrued_rewards(deps: Deps, address: String) -> StdResult<AccruedRewardsResponse> {
+    let config: Config = read_config(deps.storage)?;
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+    let holder: Holder = read_holder(deps.storage, &address_raw)?;
+
+    let rewards = config
+        .claimable_denoms()
+        .into_iter()
+        .map(|denom| {
+            let global_index = read_reward_index(deps.storage, &denom)?.global_index;
+            let holder_reward = read_holder_reward(deps.storage, &address_raw, &denom)?;
+            let reward_with_decimals =
+                calculate_decimal_rewards(global_index, holder_reward.index, holder.balance);
+            let all_reward_with_decimals = reward_with_decimals + holder_reward.pending_rewards;
+            Ok(RewardAmount {
+                denom,
+                amount: (all_reward_with_decimals * Uint256::one()).into(),
+            })
+        })
+        .collect::<StdResult<Vec<RewardAmount>>>()?;
 
     Ok(AccruedRewardsResponse { rewards })
 }
 
 pub fn query_holder(deps: Deps, address: String) -> StdResult<HolderResponse> {
-    let holder: Holder = read_holder(deps.storage, &deps.api.addr_canonicalize(&address)?)?;
+    let config: Config = read_config(deps.storage)?;
+    let address_raw = deps.api.addr_canonicalize(&address)?;
+    let holder: Holder = read_holder(deps.storage, &address_raw)?;
     Ok(HolderResponse {
         address,
         balance: holder.balance,
-        index: holder.index,
-        pending_rewards: holder.pending_rewards,
+        rewards: read_holder_rewards(deps.storage, &address_raw, &config.claimable_denoms())?,
+        auto_repay: read_auto_repay(deps.storage, &address_raw)?,
     })
 }
 
@@ -217,15 +370,12 @@ pub fn query_holders(
 
 // calculate the reward based on the sender's index and the global index.
 fn calculate_decimal_rewards(
-    global_index: Decimal,
-    user_index: Decimal,
+    global_index: Decimal256,
+    user_index: Decimal256,
     user_balance: Uint128,
-) -> Decimal {
-    let decimal_balance = Decimal::from_ratio(user_balance, Uint128::new(1));
-    decimal_multiplication_in_256(
-        decimal_subtraction_in_256(global_index, user_index),
-        decimal_balance,
-    )
+) -> Decimal256 {
+    let decimal_balance = Decimal256::from_uint256(user_balance);
+    (global_index - user_index) * decimal_balance
 }
 
 #[cfg(test)]
@@ -234,8 +384,8 @@ mod tests {
 
     #[test]
     pub fn proper_calculate_rewards() {
-        let global_index = Decimal::from_ratio(Uint128::new(9), Uint128::new(100));
-        let user_index = Decimal::zero();
+        let global_index = Decimal256::from_ratio(9u64, 100u64);
+        let user_index = Decimal256::zero();
         let user_balance = Uint128::new(1000);
         let reward = calculate_decimal_rewards(global_index, user_index, user_balance);
         assert_eq!(reward.to_string(), "90");
@@ -243,14 +393,398 @@ mod tests {
 
     #[test]
     pub fn proper_get_decimals() {
-        let global_index = Decimal::from_ratio(Uint128::new(9999999), Uint128::new(100000000));
-        let user_index = Decimal::zero();
+        let global_index = Decimal256::from_ratio(9999999u64, 100000000u64);
+        let user_index = Decimal256::zero();
         let user_balance = Uint128::new(10);
         let decimal_reward = calculate_decimal_rewards(global_index, user_index, user_balance);
 
-        let rewards = decimal_reward * Uint128::new(1);
-        let decimals = decimal_reward - Decimal::from_ratio(rewards, Uint128::new(1));
+        let rewards = decimal_reward * Uint256::one();
+        let decimals = decimal_reward - Decimal256::from_uint256(rewards);
 
         assert_eq!(decimals.to_string(), "0.9999999");
     }
+
+    #[test]
+    pub fn proper_transfer_balance() {
+        use crate::state::{store_config, RewardIndex};
+        use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+        use cosmwasm_std::Api;
+
+        let mut deps = mock_dependencies();
+        let control = deps.api.addr_canonicalize("control").unwrap();
+        store_config(
+            deps.as_mut().storage,
+            &Config {
+                owner: control.clone(),
+                control_contract: control.clone(),
+                reward_contract: control.clone(),
+                custody_contract: control,
+                reward_denoms: vec![Denom::Native("usei".to_string())],
+                threshold: Uint256::zero(),
+                pool_contract: None,
+                stable_denom: None,
+                swap_adapter: None,
+                oracle_contract: None,
+                removed_reward_denoms: vec![],
+            },
+        )
+        .unwrap();
+        store_state(
+            deps.as_mut().storage,
+            &State {
+                total_balance: Uint128::new(1000),
+            },
+        )
+        .unwrap();
+        store_reward_index(
+            deps.as_mut().storage,
+            &Denom::Native("usei".to_string()),
+            &RewardIndex {
+                global_index: Decimal256::from_ratio(1u64, 10u64),
+                prev_reward_balance: Uint128::zero(),
+            },
+        )
+        .unwrap();
+        let from_raw = deps.api.addr_canonicalize("from").unwrap();
+        store_holder(
+            deps.as_mut().storage,
+            &from_raw,
+            &Holder {
+                balance: Uint128::new(1000),
+            },
+        )
+        .unwrap();
+
+        let err = execute_transfer_balance(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("from", &[]),
+            "from".to_string(),
+            "recipient".to_string(),
+            Uint128::new(400),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err,
+            ContractError::Unauthorized("execute_transfer_balance".to_string(), "from".to_string())
+        );
+
+        execute_transfer_balance(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("control", &[]),
+            "from".to_string(),
+            "recipient".to_string(),
+            Uint128::new(400),
+        )
+        .unwrap();
+
+        // the sender keeps the rewards accrued before the transfer
+        let from = query_holder(deps.as_ref(), "from".to_string()).unwrap();
+        assert_eq!(from.balance, Uint128::new(600));
+        assert_eq!(
+            from.rewards[0].pending_rewards,
+            Decimal256::from_uint256(Uint256::from(100u64))
+        );
+        let recipient = query_holder(deps.as_ref(), "recipient".to_string()).unwrap();
+        assert_eq!(recipient.balance, Uint128::new(400));
+        assert_eq!(recipient.rewards[0].index, Decimal256::from_ratio(1u64, 10u64));
+        assert_eq!(recipient.rewards[0].pending_rewards, Decimal256::zero());
+        assert_eq!(
+            read_state(deps.as_ref().storage).unwrap().total_balance,
+            Uint128::new(1000)
+        );
+    }
+
+    #[test]
+    pub fn proper_claim_rewards_of_every_denom() {
+        use crate::state::{store_config, RewardIndex};
+        use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+        use cosmwasm_std::{Addr, Api, SubMsg};
+
+        let mut deps = mock_dependencies();
+        let control = deps.api.addr_canonicalize("control").unwrap();
+        let native = Denom::Native("usei".to_string());
+        let cw20 = Denom::Cw20(Addr::unchecked("reward_token"));
+        store_config(
+            deps.as_mut().storage,
+            &Config {
+                owner: control.clone(),
+                control_contract: control.clone(),
+                reward_contract: control.clone(),
+                custody_contract: control,
+                reward_denoms: vec![native.clone(), cw20.clone()],
+                threshold: Uint256::zero(),
+                pool_contract: None,
+                stable_denom: None,
+                swap_adapter: None,
+                oracle_contract: None,
+                removed_reward_denoms: vec![],
+            },
+        )
+        .unwrap();
+        store_state(
+            deps.as_mut().storage,
+            &State {
+                total_balance: Uint128::new(1000),
+            },
+        )
+        .unwrap();
+        for (denom, global_index, prev_reward_balance) in
+            [(&native, 10u64, 100u128), (&cw20, 5u64, 50u128)]
+        {
+            store_reward_index(
+                deps.as_mut().storage,
+                denom,
+                &RewardIndex {
+                    global_index: Decimal256::percent(global_index),
+                    prev_reward_balance: Uint128::new(prev_reward_balance),
+                },
+            )
+            .unwrap();
+        }
+        let holder_raw = deps.api.addr_canonicalize("holder").unwrap();
+        store_holder(
+            deps.as_mut().storage,
+            &holder_raw,
+            &Holder {
+                balance: Uint128::new(1000),
+            },
+        )
+        .unwrap();
+
+        let accrued = query_accrued_rewards(deps.as_ref(), "holder".to_string()).unwrap();
+        assert_eq!(
+            accrued.rewards,
+            vec![
+                RewardAmount {
+                    denom: native.clone(),
+                    amount: Uint128::new(100),
+                },
+                RewardAmount {
+                    denom: cw20.clone(),
+                    amount: Uint128::new(50),
+                },
+            ]
+        );
+
+        let res = execute_claim_rewards(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("holder", &[]),
+            Some("recipient".to_string()),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(BankMsg::Send {
+                    to_address: "recipient".to_string(),
+                    amount: vec![Coin {
+                        denom: "usei".to_string(),
+                        amount: Uint128::new(100),
+                    }],
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "reward_token".to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: "recipient".to_string(),
+                        amount: Uint128::new(50),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+            ]
+        );
+        assert!(res.attributes.contains(&attr("rewards", "100usei,50reward_token")));
+        assert_eq!(res.events.len(), 2);
+        assert_eq!(
+            read_reward_index(deps.as_ref().storage, &cw20)
+                .unwrap()
+                .prev_reward_balance,
+            Uint128::zero()
+        );
+
+        let err = execute_claim_rewards(deps.as_mut(), mock_env(), mock_info("holder", &[]), None)
+            .unwrap_err();
+        assert_eq!(err, ContractError::NoRewardsHaveAccrued {});
+    }
+
+    #[test]
+    pub fn proper_auto_repay_claimed_rewards() {
+        use crate::repay::{execute_repay_swapped, execute_set_auto_repay};
+        use crate::state::{store_config, RewardIndex};
+        use cdp::central_control::LoanInfoResponse;
+        use cdp::reward_book::{AutoRepay, ExecuteMsg};
+        use cdp::stable_pool::ExecuteMsg as PoolExecuteMsg;
+        use cdp::oracle_pyth::PriceResponse;
+        use cdp::swap_adapter::ExecuteMsg as SwapExecuteMsg;
+        use cosmwasm_std::testing::{
+            mock_dependencies, mock_env, mock_info, MOCK_CONTRACT_ADDR,
+        };
+        use cosmwasm_std::{coins, Addr, Api, ContractResult, SubMsg, SystemResult, WasmQuery};
+
+        let mut deps = mock_dependencies();
+        let control = deps.api.addr_canonicalize("control").unwrap();
+        let native = Denom::Native("usei".to_string());
+        let stable = Denom::Native("kusd".to_string());
+        let pool = deps.api.addr_canonicalize("pool").unwrap();
+        let swap_adapter = deps.api.addr_canonicalize("swap_adapter").unwrap();
+        let oracle = deps.api.addr_canonicalize("oracle").unwrap();
+        store_config(
+            deps.as_mut().storage,
+            &Config {
+                owner: control.clone(),
+                control_contract: control.clone(),
+                reward_contract: control.clone(),
+                custody_contract: control,
+                reward_denoms: vec![native.clone(), stable.clone()],
+                threshold: Uint256::zero(),
+                pool_contract: Some(pool),
+                stable_denom: Some("kusd".to_string()),
+                swap_adapter: Some(swap_adapter),
+                oracle_contract: Some(oracle),
+                removed_reward_denoms: vec![],
+            },
+        )
+        .unwrap();
+        store_state(
+            deps.as_mut().storage,
+            &State {
+                total_balance: Uint128::new(1000),
+            },
+        )
+        .unwrap();
+        for (denom, global_index, prev_reward_balance) in
+            [(&native, 10u64, 100u128), (&stable, 2u64, 20u128)]
+        {
+            store_reward_index(
+                deps.as_mut().storage,
+                denom,
+                &RewardIndex {
+                    global_index: Decimal256::percent(global_index),
+                    prev_reward_balance: Uint128::new(prev_reward_balance),
+                },
+            )
+            .unwrap();
+        }
+        let holder_raw = deps.api.addr_canonicalize("holder").unwrap();
+        store_holder(
+            deps.as_mut().storage,
+            &holder_raw,
+            &Holder {
+                balance: Uint128::new(1000),
+            },
+        )
+        .unwrap();
+        deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(20, "kusd"));
+        deps.querier.update_wasm(|query| match query {
+            WasmQuery::Smart { contract_addr, .. } if contract_addr == "oracle" => {
+                SystemResult::Ok(ContractResult::Ok(
+                    to_binary(&PriceResponse {
+                        asset: "usei".to_string(),
+                        emv_price: Decimal256::from_uint256(2u64),
+                        emv_price_raw: 2,
+                        price: Decimal256::from_uint256(2u64),
+                        price_raw: 2,
+                        last_updated_base: 0,
+                        last_updated_quote: 0,
+                    })
+                    .unwrap(),
+                ))
+            }
+            _ => SystemResult::Ok(ContractResult::Ok(
+                to_binary(&LoanInfoResponse {
+                    minter: "holder".to_string(),
+                    vault_id: 1,
+                    loans: Uint256::from(150u64),
+                    max_mint_value: Uint256::from(1000u64),
+                })
+                .unwrap(),
+            )),
+        });
+
+        execute_set_auto_repay(
+            deps.as_mut(),
+            mock_info("holder", &[]),
+            Some(AutoRepay {
+                vault_id: Some(1),
+                max_slippage: Decimal256::percent(1),
+            }),
+        )
+        .unwrap();
+
+        // the rewards repay the holder's loan, they can not be sent elsewhere
+        let err = execute_claim_rewards(
+            deps.as_mut(),
+            mock_env(),
+            mock_info("holder", &[]),
+            Some("recipient".to_string()),
+        )
+        .unwrap_err();
+        assert_eq!(err, ContractError::AutoRepayRecipient {});
+
+        // rewards are swapped to kUSD instead of being paid out, 100 usei priced at 2 kUSD
+        // by the oracle less the 1% max slippage
+        let res = execute_claim_rewards(deps.as_mut(), mock_env(), mock_info("holder", &[]), None)
+            .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "swap_adapter".to_string(),
+                    msg: to_binary(&SwapExecuteMsg::Swap {
+                        ask_denom: "kusd".to_string(),
+                        min_receive: Uint128::new(198),
+                        recipient: None,
+                    })
+                    .unwrap(),
+                    funds: coins(100, "usei"),
+                }),
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: MOCK_CONTRACT_ADDR.to_string(),
+                    msg: to_binary(&ExecuteMsg::RepaySwapped {
+                        holder: "holder".to_string(),
+                        vault_id: Some(1),
+                        prev_balance: Uint128::new(20),
+                        direct_amount: Uint128::new(20),
+                    })
+                    .unwrap(),
+                    funds: vec![],
+                }),
+            ]
+        );
+        let holder = query_holder(deps.as_ref(), "holder".to_string()).unwrap();
+        assert!(holder.rewards.iter().all(|reward| reward.pending_rewards.is_zero()));
+
+        // the swap returned 198 kUSD, the loan takes 150 and the rest goes to the holder
+        deps.querier.update_balance(MOCK_CONTRACT_ADDR, coins(218, "kusd"));
+        let res = execute_repay_swapped(
+            deps.as_mut(),
+            mock_env(),
+            Addr::unchecked("holder"),
+            Some(1),
+            Uint128::new(20),
+            Uint128::new(20),
+        )
+        .unwrap();
+        assert_eq!(
+            res.messages,
+            vec![
+                SubMsg::new(WasmMsg::Execute {
+                    contract_addr: "pool".to_string(),
+                    msg: to_binary(&PoolExecuteMsg::RepayStableCoin {
+                        minter: Some("holder".to_string()),
+                        vault_id: Some(1),
+                    })
+                    .unwrap(),
+                    funds: coins(150, "kusd"),
+                }),
+                SubMsg::new(BankMsg::Send {
+                    to_address: "holder".to_string(),
+                    amount: coins(68, "kusd"),
+                }),
+            ]
+        );
+    }
 }
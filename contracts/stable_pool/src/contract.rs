@@ -18,10 +18,22 @@ use cosmwasm_bignumber::Uint256;
 use std::vec;
 
 use cdp::handle::optional_addr_validate;
+use cdp::events::{config_change_event, MODULE_STABLE_POOL};
+use cdp::migration::{assert_legacy_deployment, assert_upgrade};
+use cdp::rbac::{
+    assert_not_paused, execute_grant_role, execute_pause, execute_revoke_role, execute_unpause, grant_role,
+    query_has_role, query_paused, query_role_members, revoke_role, Role,
+};
+use cdp::timelock::{
+    execute_cancel_change, execute_matured_change, execute_propose_change, query_pending_change,
+    query_pending_changes, query_timelock_config, store_timelock_config, update_timelock_config,
+    TimelockConfig,
+};
 use cosmwasm_std::entry_point;
+use cw2::set_contract_version;
 use cosmwasm_std::{
-    attr, coin, to_binary, Addr, BankMsg, Binary, Coin, CosmosMsg, Deps, DepsMut, Env, MessageInfo,
-    Response, StdError, StdResult, SubMsg, Uint128,
+    attr, coin, to_binary, Addr, Api, BankMsg, Binary, CanonicalAddr, Coin, CosmosMsg, Deps,
+    DepsMut, Env, MessageInfo, Response, StdError, StdResult, Storage, SubMsg, Uint128,
 };
 use sei_cosmwasm::SeiMsg;
 
@@ -31,6 +43,10 @@ use cdp::stable_pool::{
     ConfigResponse, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, StateResponse,
 };
 
+// version info for migration
+const CONTRACT_NAME: &str = "crates.io:cdp-stable-pool";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -65,6 +81,22 @@ pub fn instantiate(
         }
     })?;
 
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    store_timelock_config(
+        deps.storage,
+        &TimelockConfig {
+            delay: msg.timelock_delay,
+        },
+    )?;
+
+    grant_role(deps.storage, Role::Admin, &config.owner_addr)?;
+    grant_role(
+        deps.storage,
+        Role::Guardian,
+        &deps.api.addr_canonicalize(&msg.guardian_addr)?,
+    )?;
+
     Ok(Response::new().add_message(create_stable_denom))
 }
 
@@ -76,37 +108,51 @@ pub fn execute(
     msg: ExecuteMsg,
 ) -> Result<Response<SeiMsg>, ContractError> {
     match msg {
-        ExecuteMsg::UpdateConfig {
-            control_contract,
-            min_redeem_value,
-        } => {
+        ExecuteMsg::UpdateConfig { .. }
+        | ExecuteMsg::UpdateTimelockConfig { .. }
+        | ExecuteMsg::SetOwner { .. }
+        | ExecuteMsg::GrantRole {
+            role: Role::Admin | Role::Guardian,
+            ..
+        } => propose_timelocked_change(deps, env, info, msg),
+        ExecuteMsg::ExecuteChange { id } => execute_timelocked_change(deps, env, info, id),
+        ExecuteMsg::CancelChange { id } => {
+            execute_cancel_change(deps, info, MODULE_STABLE_POOL, id)
+        }
+        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::GrantRole { role, address } => {
             let api = deps.api;
-            update_config(
-                deps,
-                info,
-                optional_addr_validate(api, control_contract)?,
-                min_redeem_value,
-            )
-        }    
-        ExecuteMsg::SetOwner { new_owner_addr } => {
+            execute_grant_role(deps, info, MODULE_STABLE_POOL, role, api.addr_validate(&address)?)
+        }
+        ExecuteMsg::RevokeRole { role, address } => {
             let api = deps.api;
-            set_new_owner(deps, info, api.addr_validate(&new_owner_addr)?)
+            execute_revoke_role(deps, info, MODULE_STABLE_POOL, role, api.addr_validate(&address)?)
         }
-        ExecuteMsg::AcceptOwnership {} => accept_ownership(deps, info),
+        ExecuteMsg::Pause {} => execute_pause(deps, info, MODULE_STABLE_POOL),
+        ExecuteMsg::Unpause {} => execute_unpause(deps, info, MODULE_STABLE_POOL),
 
         ExecuteMsg::MintStableCoin {
             minter,
             stable_amount,
         } => mint_stable_coin(deps, info, minter, stable_amount),
 
-        ExecuteMsg::RepayStableCoin {} => repay_stable_coin(deps, info),
+        ExecuteMsg::RepayStableCoin { minter, vault_id } => {
+            let api = deps.api;
+            let minter = minter
+                .map(|minter| api.addr_validate(&minter))
+                .transpose()?;
+            repay_stable_coin(deps, info, minter, vault_id)
+        }
+
+        ExecuteMsg::ClosePosition { vault_id } => close_position(deps, info, vault_id),
 
-        ExecuteMsg::RedeemStableCoin { minter } => {
+        ExecuteMsg::RedeemStableCoin { minter, vault_id } => {
             let api = deps.api;
-            redeem_stable_coin(deps, info, api.addr_validate(minter.as_str())?)
+            redeem_stable_coin(deps, info, api.addr_validate(minter.as_str())?, vault_id)
         }
         ExecuteMsg::RepayStableFromLiquidation {
             minter,
+            vault_id,
             pre_balance,
         } => {
             let api = deps.api;
@@ -115,32 +161,22 @@ pub fn execute(
                 env,
                 info,
                 api.addr_validate(minter.as_str())?,
+                vault_id,
                 pre_balance,
             )
         }
     }
 }
 
-pub fn set_new_owner(
-    deps: DepsMut,
-    info: MessageInfo,
-    new_owner_addr: Addr,
-) -> Result<Response<SeiMsg>, ContractError>  {
-    let config = read_config(deps.as_ref().storage)?;
-    let mut new_owner = read_new_owner(deps.as_ref().storage)?;
-    let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
-    if sender_raw != config.owner_addr {
-        return Err(ContractError::Unauthorized(
-            "set_new_owner".to_string(),
-            info.sender.to_string(),
-        ));
-    }
-    new_owner.new_owner_addr = deps.api.addr_canonicalize(&new_owner_addr.to_string())?;
-    store_new_owner(deps.storage, &new_owner)?;
-
-    Ok(Response::default())
+/// Applied by a matured `SetOwner` change, the new owner still has to accept
+pub fn set_new_owner(storage: &mut dyn Storage, new_owner_addr: CanonicalAddr) -> StdResult<()> {
+    let mut new_owner = read_new_owner(storage)?;
+    new_owner.new_owner_addr = new_owner_addr;
+    store_new_owner(storage, &new_owner)
 }
 
+/// Only the admin role of the previous owner is revoked, admins granted apart from the
+/// ownership keep their role until revoked
 pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response<SeiMsg>, ContractError>  {
     let new_owner = read_new_owner(deps.as_ref().storage)?;
     let sender_raw = deps.api.addr_canonicalize(&info.sender.to_string())?;
@@ -152,29 +188,104 @@ pub fn accept_ownership(deps: DepsMut, info: MessageInfo) -> Result<Response<Sei
         ));
     }
 
+    revoke_role(deps.storage, Role::Admin, &config.owner_addr);
+    grant_role(deps.storage, Role::Admin, &new_owner.new_owner_addr)?;
     config.owner_addr = new_owner.new_owner_addr;
     store_config(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "accept_ownership"),
+            attr("owner", info.sender.to_string()),
+        ])
+        .add_event(config_change_event(MODULE_STABLE_POOL, "accept_ownership", info.sender)))
 }
 
+fn change_roles(_msg: &ExecuteMsg) -> &'static [Role] {
+    &[Role::Admin]
+}
 
+/// Admin queues a config change, it can be executed once the timelock delay has passed
+pub fn propose_timelocked_change(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response<SeiMsg>, ContractError> {
+    validate_change(deps.api, &msg)?;
+    let roles = change_roles(&msg);
+    execute_propose_change(deps, &env, info, MODULE_STABLE_POOL, roles, to_binary(&msg)?)
+}
 
-pub fn update_config(
+pub fn execute_timelocked_change(
     deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    control_contract: Option<Addr>,
-    min_redeem_value: Option<Uint256>,
+    id: u64,
 ) -> Result<Response<SeiMsg>, ContractError> {
-    let mut config = read_config(deps.as_ref().storage)?;
-    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    execute_matured_change(deps, &env, info, MODULE_STABLE_POOL, id, change_roles, apply_change)
+}
 
-    if sender_raw != config.owner_addr {
-        return Err(ContractError::Unauthorized(
-            "update_config".to_string(),
-            info.sender.to_string(),
-        ));
+// addresses are validated when a change is proposed rather than once its delay has passed
+fn validate_change(api: &dyn Api, msg: &ExecuteMsg) -> StdResult<()> {
+    if let ExecuteMsg::UpdateConfig {
+        control_contract: Some(control_contract),
+        ..
+    } = msg
+    {
+        api.addr_validate(control_contract)?;
+    }
+    if let ExecuteMsg::SetOwner {
+        new_owner_addr: address,
     }
+    | ExecuteMsg::GrantRole { address, .. } = msg
+    {
+        api.addr_validate(address)?;
+    }
+    Ok(())
+}
+
+fn apply_change(deps: DepsMut, msg: ExecuteMsg) -> Result<&'static str, ContractError> {
+    let api = deps.api;
+    let action = match msg {
+        ExecuteMsg::UpdateConfig {
+            control_contract,
+            min_redeem_value,
+        } => {
+            update_config(
+                deps,
+                optional_addr_validate(api, control_contract)?,
+                min_redeem_value,
+            )?;
+            "update_config"
+        }
+        ExecuteMsg::UpdateTimelockConfig { delay } => {
+            update_timelock_config(deps.storage, delay)?;
+            "update_timelock_config"
+        }
+        ExecuteMsg::SetOwner { new_owner_addr } => {
+            set_new_owner(deps.storage, deps.api.addr_canonicalize(&new_owner_addr)?)?;
+            "set_new_owner"
+        }
+        ExecuteMsg::GrantRole { role, address } => {
+            grant_role(deps.storage, role, &deps.api.addr_canonicalize(&address)?)?;
+            "grant_role"
+        }
+        _ => {
+            return Err(ContractError::Std(StdError::generic_err(
+                "Unsupported timelocked change",
+            )))
+        }
+    };
+    Ok(action)
+}
+
+pub fn update_config(
+    deps: DepsMut,
+    control_contract: Option<Addr>,
+    min_redeem_value: Option<Uint256>,
+) -> Result<(), ContractError> {
+    let mut config = read_config(deps.as_ref().storage)?;
 
     if let Some(control_contract) = control_contract {
         config.control_contract = deps.api.addr_canonicalize(control_contract.as_str())?;
@@ -186,7 +297,7 @@ pub fn update_config(
 
     store_config(deps.storage, &config)?;
 
-    Ok(Response::default())
+    Ok(())
 }
 
 /// call only by central contral contract
@@ -259,6 +370,7 @@ pub fn repay_stable_from_liquidation(
     env: Env,
     info: MessageInfo,
     minter: Addr,
+    vault_id: Option<u64>,
     pre_balance: Uint256,
 ) -> Result<Response<SeiMsg>, ContractError> {
     let config = read_config(deps.storage)?;
@@ -285,16 +397,21 @@ pub fn repay_stable_from_liquidation(
         amount: (cur_balance - pre_balance).into(),
     }];
 
-    repay_stable_coin(deps, info)
+    repay_stable_coin(deps, info, None, vault_id)
 }
 
+/// repay the loan of `minter`'s vault, or of the sender when no minter is given
+/// the excess over the outstanding loan is refunded to the sender
 pub fn repay_stable_coin(
     deps: DepsMut,
     info: MessageInfo,
+    minter: Option<Addr>,
+    vault_id: Option<u64>,
 ) -> Result<Response<SeiMsg>, ContractError> {
     let config = read_config(deps.storage)?;
     let stable_denom = config.stable_denom;
     let sender = info.sender.clone();
+    let minter = minter.unwrap_or_else(|| sender.clone());
     // coin must have be sent along with transaction and it should be in underlying coin denom
     if info.funds.len() > 1usize {
         return Err(ContractError::Std(StdError::generic_err(
@@ -314,7 +431,8 @@ pub fn repay_stable_coin(
     let loan_info = query_control_loan_info(
         deps.as_ref(),
         api.addr_humanize(&config.control_contract)?.to_string(),
-        sender.clone().to_string(),
+        minter.to_string(),
+        vault_id,
     )?;
 
     let mut back_amount = Uint128::zero();
@@ -343,7 +461,9 @@ pub fn repay_stable_coin(
 
     // update loan info
     let repay_msg = ControlExecuteMsg::RepayStableCoin {
-        sender: sender.clone().to_string(),
+        minter: minter.to_string(),
+        vault_id,
+        payer: sender.to_string(),
         amount: repay_amount,
     };
 
@@ -359,16 +479,96 @@ pub fn repay_stable_coin(
         .add_submessages(messages)
         .add_attributes(vec![
             attr("action", "repay_stable_coin"),
-            attr("sender", sender.clone().to_string()),
+            attr("sender", sender.to_string()),
+            attr("minter", minter.to_string()),
             attr("amount", repay.amount),
         ]))
 }
 
+/// repay the whole loan of the sender's vault and withdraw all of its collaterals,
+/// the excess over the outstanding loan is refunded to the sender
+pub fn close_position(
+    deps: DepsMut,
+    info: MessageInfo,
+    vault_id: Option<u64>,
+) -> Result<Response<SeiMsg>, ContractError> {
+    let config = read_config(deps.storage)?;
+    let stable_denom = config.stable_denom.clone();
+    let sender = info.sender.clone();
+    if info.funds.len() > 1usize {
+        return Err(ContractError::Std(StdError::generic_err(
+            "More than one coin is sent; only one asset is supported",
+        )));
+    }
+    let api = deps.api;
+    let paid = info
+        .funds
+        .iter()
+        .find(|x| x.denom == stable_denom)
+        .map(|x| x.amount)
+        .unwrap_or_default();
+
+    let loan_info = query_control_loan_info(
+        deps.as_ref(),
+        api.addr_humanize(&config.control_contract)?.to_string(),
+        sender.to_string(),
+        vault_id,
+    )?;
+    if Uint256::from(paid) < loan_info.loans {
+        return Err(ContractError::CloseAmountTooSmall(loan_info.loans));
+    }
+    let repay_amount: Uint128 = loan_info.loans.into();
+    let back_amount = paid.checked_sub(repay_amount)?;
+
+    let mut messages = vec![];
+    if !repay_amount.is_zero() {
+        messages.extend(burn_stable_coin(deps, &info, repay_amount)?.messages);
+    }
+
+    // refund of overpayment balance
+    if back_amount > Uint128::zero() {
+        messages.push(SubMsg::new(BankMsg::Send {
+            to_address: sender.to_string(),
+            amount: vec![Coin {
+                denom: stable_denom,
+                amount: back_amount,
+            }],
+        }))
+    }
+
+    // clear the loan and withdraw the collaterals
+    let close_msg = ControlExecuteMsg::ClosePosition {
+        minter: sender.to_string(),
+        vault_id,
+        payer: sender.to_string(),
+        amount: repay_amount,
+    };
+
+    messages.push(SubMsg::new(CosmosMsg::Wasm(
+        cosmwasm_std::WasmMsg::Execute {
+            contract_addr: api.addr_humanize(&config.control_contract)?.to_string(),
+            msg: to_binary(&close_msg)?,
+            funds: vec![],
+        },
+    )));
+
+    Ok(Response::new()
+        .add_submessages(messages)
+        .add_attributes(vec![
+            attr("action", "close_position"),
+            attr("sender", sender.to_string()),
+            attr("amount", repay_amount),
+        ]))
+}
+
 pub fn redeem_stable_coin(
     deps: DepsMut,
     info: MessageInfo,
     minter: Addr,
+    vault_id: Option<u64>,
 ) -> Result<Response<SeiMsg>, ContractError> {
+    assert_not_paused::<ContractError>(deps.storage)?;
+
     let config = read_config(deps.storage)?;
     let stable_denom = config.stable_denom;
     let sender = info.sender.clone();
@@ -396,6 +596,7 @@ pub fn redeem_stable_coin(
         redeemer: sender.to_string(),
         amount: repay.amount,
         minter: minter.to_string(),
+        vault_id,
     };
 
     burn_stable_coin(deps, &info, repay.amount)?;
@@ -408,7 +609,6 @@ pub fn redeem_stable_coin(
         }))
         .add_attributes(vec![
             attr("action", "redeem_stable_coin"),
-            attr("contract", "stable_pool"),
             attr("sender", sender.to_string()),
             attr("amount", repay.amount.to_string()),
         ]))
@@ -419,6 +619,18 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::Config {} => to_binary(&query_config(deps)?),
         QueryMsg::State {} => to_binary(&query_state(deps)?),
+        QueryMsg::TimelockConfig {} => to_binary(&query_timelock_config(deps)?),
+        QueryMsg::PendingChange { id } => to_binary(&query_pending_change(deps, id)?),
+        QueryMsg::PendingChanges { start_after, limit } => {
+            to_binary(&query_pending_changes(deps, start_after, limit)?)
+        }
+        QueryMsg::HasRole { role, address } => to_binary(&query_has_role(deps, role, address)?),
+        QueryMsg::RoleMembers {
+            role,
+            start_after,
+            limit,
+        } => to_binary(&query_role_members(deps, role, start_after, limit)?),
+        QueryMsg::Paused {} => to_binary(&query_paused(deps)?),
     }
 }
 
@@ -444,6 +656,36 @@ pub fn query_state(deps: Deps) -> StdResult<StateResponse> {
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, msg: MigrateMsg) -> StdResult<Response> {
+    match msg {
+        MigrateMsg::FromLegacy {
+            guardian_addr,
+            timelock_delay,
+        } => {
+            assert_legacy_deployment(deps.storage)?;
+            let config = read_config(deps.storage)?;
+            store_timelock_config(
+                deps.storage,
+                &TimelockConfig {
+                    delay: timelock_delay,
+                },
+            )?;
+            grant_role(deps.storage, Role::Admin, &config.owner_addr)?;
+            grant_role(
+                deps.storage,
+                Role::Guardian,
+                &deps.api.addr_canonicalize(&guardian_addr)?,
+            )?;
+        }
+        MigrateMsg::Upgrade {} => {
+            assert_upgrade(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+        }
+    }
+
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    Ok(Response::new().add_attributes(vec![
+        attr("action", "migrate"),
+        attr("version", CONTRACT_VERSION),
+    ]))
 }
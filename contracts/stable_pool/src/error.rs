@@ -11,7 +11,9 @@
 // WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
 // See the License for the specific language governing permissions and
 // limitations under the License.
+use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{OverflowError, StdError};
+use cdp::rbac::AccessError;
 use thiserror::Error;
 
 #[derive(Error, Debug, PartialEq)]
@@ -28,6 +30,22 @@ pub enum ContractError {
     #[error("Cannot less than minimum redeem value {0} ukusd")]
     CannotLessThanMinRedeemValue (u128),
 
+    #[error("Not enough kUSD to close the position, loans: {0}")]
+    CloseAmountTooSmall(Uint256),
+
     #[error("Functionality deprecated")] 
     Deprecated {},
+
+    #[error("Contract is paused")]
+    Paused {},
+}
+
+impl AccessError for ContractError {
+    fn unauthorized(function: &str, sender: &str) -> Self {
+        ContractError::Unauthorized(function.to_string(), sender.to_string())
+    }
+
+    fn paused() -> Self {
+        ContractError::Paused {}
+    }
 }
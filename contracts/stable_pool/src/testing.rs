@@ -0,0 +1,278 @@
+use crate::contract::{execute, instantiate};
+use crate::error::ContractError;
+use crate::state::{read_config, read_state, store_state, State};
+
+use cdp::central_control::{ExecuteMsg as ControlExecuteMsg, LoanInfoResponse};
+use cdp::stable_pool::{ExecuteMsg, InstantiateMsg};
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{
+    attr, coin, coins, to_binary, Api, BankMsg, ContractResult, CosmosMsg, SubMsg, SystemResult,
+    Uint128, WasmMsg, WasmQuery,
+};
+use sei_cosmwasm::SeiMsg;
+
+#[test]
+fn repay_on_behalf_of_minter() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        sub_demon: "kusd".to_string(),
+        owner_addr: "owner".to_string(),
+        control_contract: "control".to_string(),
+        min_redeem_value: Uint256::zero(),
+        guardian_addr: "guardian".to_string(),
+        timelock_delay: 0u64,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    let stable_denom = read_config(deps.as_ref().storage).unwrap().stable_denom;
+
+    // the minter owes 100
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_supply: Uint256::from(100u64),
+        },
+    )
+    .unwrap();
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { .. } => SystemResult::Ok(ContractResult::Ok(
+            to_binary(&LoanInfoResponse {
+                minter: "minter".to_string(),
+                vault_id: 1u64,
+                loans: Uint256::from(100u64),
+                max_mint_value: Uint256::from(200u64),
+            })
+            .unwrap(),
+        )),
+        _ => unimplemented!(),
+    });
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("friend", &coins(150u128, stable_denom.clone())),
+        ExecuteMsg::RepayStableCoin {
+            minter: Some("minter".to_string()),
+            vault_id: Some(1u64),
+        },
+    )
+    .unwrap();
+
+    // the excess is refunded to the payer
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(BankMsg::Send {
+                to_address: "friend".to_string(),
+                amount: coins(50u128, stable_denom),
+            }),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "control".to_string(),
+                msg: to_binary(&ControlExecuteMsg::RepayStableCoin {
+                    minter: "minter".to_string(),
+                    vault_id: Some(1u64),
+                    payer: "friend".to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "repay_stable_coin"),
+            attr("sender", "friend"),
+            attr("minter", "minter"),
+            attr("amount", "150"),
+        ]
+    );
+}
+
+#[test]
+fn close_position_repays_full_loan() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        sub_demon: "kusd".to_string(),
+        owner_addr: "owner".to_string(),
+        control_contract: "control".to_string(),
+        min_redeem_value: Uint256::zero(),
+        guardian_addr: "guardian".to_string(),
+        timelock_delay: 0u64,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    let stable_denom = read_config(deps.as_ref().storage).unwrap().stable_denom;
+
+    store_state(
+        deps.as_mut().storage,
+        &State {
+            total_supply: Uint256::from(100u64),
+        },
+    )
+    .unwrap();
+    deps.querier.update_wasm(|query| match query {
+        WasmQuery::Smart { .. } => SystemResult::Ok(ContractResult::Ok(
+            to_binary(&LoanInfoResponse {
+                minter: "minter".to_string(),
+                vault_id: 1u64,
+                loans: Uint256::from(100u64),
+                max_mint_value: Uint256::from(200u64),
+            })
+            .unwrap(),
+        )),
+        _ => unimplemented!(),
+    });
+
+    // the whole loan must be covered
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &coins(99u128, stable_denom.clone())),
+        ExecuteMsg::ClosePosition {
+            vault_id: Some(1u64),
+        },
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::CloseAmountTooSmall(Uint256::from(100u64))
+    );
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("minter", &coins(120u128, stable_denom.clone())),
+        ExecuteMsg::ClosePosition {
+            vault_id: Some(1u64),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        res.messages,
+        vec![
+            SubMsg::new(SeiMsg::BurnTokens {
+                amount: coin(100u128, stable_denom.clone()),
+            }),
+            SubMsg::new(BankMsg::Send {
+                to_address: "minter".to_string(),
+                amount: coins(20u128, stable_denom),
+            }),
+            SubMsg::new(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: "control".to_string(),
+                msg: to_binary(&ControlExecuteMsg::ClosePosition {
+                    minter: "minter".to_string(),
+                    vault_id: Some(1u64),
+                    payer: "minter".to_string(),
+                    amount: Uint128::new(100),
+                })
+                .unwrap(),
+                funds: vec![],
+            })),
+        ]
+    );
+    assert_eq!(
+        read_state(deps.as_ref().storage).unwrap().total_supply,
+        Uint256::zero()
+    );
+}
+
+#[test]
+fn timelocked_config_change() {
+    let mut deps = mock_dependencies();
+    let msg = InstantiateMsg {
+        sub_demon: "kusd".to_string(),
+        owner_addr: "owner".to_string(),
+        control_contract: "control".to_string(),
+        min_redeem_value: Uint256::zero(),
+        guardian_addr: "guardian".to_string(),
+        timelock_delay: 100u64,
+    };
+    instantiate(deps.as_mut(), mock_env(), mock_info("owner", &[]), msg).unwrap();
+    let update_config = |control_contract: &str| ExecuteMsg::UpdateConfig {
+        control_contract: Some(control_contract.to_string()),
+        min_redeem_value: None,
+    };
+
+    // only an admin proposes, and addresses are validated right away
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("addr0000", &[]),
+        update_config("new_control"),
+    );
+    assert_eq!(
+        res.unwrap_err(),
+        ContractError::Unauthorized(
+            "propose_timelocked_change".to_string(),
+            "addr0000".to_string()
+        )
+    );
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config("NEW_CONTROL"),
+    )
+    .unwrap_err();
+
+    let res = execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config("new_control"),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("change_id", "1")));
+
+    // not executable before the delay has passed
+    let mut env = mock_env();
+    env.block.time = env.block.time.plus_seconds(99u64);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ExecuteChange { id: 1u64 },
+    )
+    .unwrap_err();
+
+    env.block.time = env.block.time.plus_seconds(1u64);
+    execute(
+        deps.as_mut(),
+        env.clone(),
+        mock_info("owner", &[]),
+        ExecuteMsg::ExecuteChange { id: 1u64 },
+    )
+    .unwrap();
+    assert_eq!(
+        read_config(deps.as_ref().storage).unwrap().control_contract,
+        deps.api.addr_canonicalize("new_control").unwrap()
+    );
+
+    // a guardian cancels a pending change, which can then no longer be executed
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("owner", &[]),
+        update_config("other_control"),
+    )
+    .unwrap();
+    execute(
+        deps.as_mut(),
+        mock_env(),
+        mock_info("guardian", &[]),
+        ExecuteMsg::CancelChange { id: 2u64 },
+    )
+    .unwrap();
+    env.block.time = env.block.time.plus_seconds(100u64);
+    execute(
+        deps.as_mut(),
+        env,
+        mock_info("owner", &[]),
+        ExecuteMsg::ExecuteChange { id: 2u64 },
+    )
+    .unwrap_err();
+    assert_eq!(
+        read_config(deps.as_ref().storage).unwrap().control_contract,
+        deps.api.addr_canonicalize("new_control").unwrap()
+    );
+}
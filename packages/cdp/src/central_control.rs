@@ -14,11 +14,28 @@
 
 
 use cosmwasm_bignumber::{Decimal256, Uint256};
-use cosmwasm_std::{Uint128};
+use cosmwasm_std::{Binary, Uint128};
 use cosmwasm_schema::{cw_serde, QueryResponses};
+use cw_utils::Expiration;
 
+use crate::cw721::{
+    ContractInfoResponse, NftInfoResponse, NumTokensResponse, OwnerOfResponse, TokensResponse,
+};
+
+use crate::rbac::{HasRoleResponse, PausedResponse, Role, RoleMembersResponse};
+use crate::timelock::{PendingChangeResponse, PendingChangesResponse, TimelockConfigResponse};
 use crate::tokens::{TokensHuman};
 
+/// Used when `max_liquidation_batch_size` is not set at instantiation
+pub const DEFAULT_MAX_LIQUIDATION_BATCH_SIZE: u32 = 30;
+
+/// Vault every minter holds without opening one. Positions opened before vaults
+/// were introduced belong to it, and messages without a `vault_id` address it
+pub const DEFAULT_VAULT_ID: u64 = 0;
+
+/// Used when `liquidation_record_retention` is not set at instantiation
+pub const DEFAULT_LIQUIDATION_RECORD_RETENTION: u64 = 1000;
+
 #[cw_serde]
 pub struct InstantiateMsg {
     /// Initial owner address
@@ -30,10 +47,38 @@ pub struct InstantiateMsg {
     pub stable_denom: String,
     pub epoch_period: u64,
     pub redeem_fee: Decimal256,
+    /// Address granted the guardian role, allowed to cancel pending config changes
+    pub guardian_addr: String,
+    /// Delay (seconds) before a proposed config change can be executed
+    pub timelock_delay: u64,
+    /// Maximum number of positions in a `LiquidateCollaterals` batch, bounds the gas of a batch
+    pub max_liquidation_batch_size: Option<u32>,
+    /// Reward paid to the caller of a liquidation, no reward when not set
+    pub keeper_reward: Option<KeeperReward>,
+    /// Minimum time (seconds) between two liquidations of the same position, none when not set
+    pub liquidation_cooldown: Option<u64>,
+    /// Number of liquidation records kept, the oldest are pruned beyond it. None are kept when 0
+    pub liquidation_record_retention: Option<u64>,
+}
+
+/// Reward paid in the stable denom to whoever triggers a liquidation. It is paid from
+/// the stable balance of the contract, funded by the bid fees of past liquidations and
+/// by anyone topping it up with a plain transfer. A reward beyond the balance is paid
+/// in part, the liquidation reports the unpaid amount as `keeper_reward_unfunded`
+#[cw_serde]
+#[derive(Default)]
+pub struct KeeperReward {
+    /// flat amount paid per liquidated position
+    pub flat_amount: Uint128,
+    /// share of the value of the liquidated collaterals, added to the flat amount
+    pub rate: Decimal256,
+    /// maximum reward paid per liquidated position
+    pub cap: Uint128,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
     UpdateConfig {
         oracle_contract: Option<String>,
         pool_contract: Option<String>,
@@ -42,29 +87,87 @@ pub enum ExecuteMsg {
         stable_denom: Option<String>,
         epoch_period: Option<u64>,
         redeem_fee: Option<Decimal256>,
+        max_liquidation_batch_size: Option<u32>,
+        keeper_reward: Option<KeeperReward>,
+        liquidation_cooldown: Option<u64>,
+        liquidation_record_retention: Option<u64>,
+    },
+
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
+    UpdateTimelockConfig {
+        delay: Option<u64>,
     },
 
+    /// Apply a pending change once its eta has passed
+    ExecuteChange {
+        id: u64,
+    },
+
+    /// Drop a pending change, callable by a guardian or an admin
+    CancelChange {
+        id: u64,
+    },
+
+    /// Admin only, admin and guardian grants are timelocked changes
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+
+    /// Admin only, an admin can not revoke its own admin role
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+
+    /// Guardian or admin, blocks minting kUSD, opening vaults and becoming a
+    /// redemption provider until unpaused
+    Pause {},
+
+    /// Admin only
+    Unpause {},
+
+    /// Timelocked, the new owner accepts the ownership once the change is executed
     SetOwner {
         new_owner_addr: String,
     },
 
+    /// Only the admin role of the previous owner is revoked
     AcceptOwnership {
     },
     ///mint stable coin kUSD call by custody contract.
+    ///the minter itself or an operator with the mint permission can call it as well
     MintStableCoin {
         minter: String,
         stable_amount: Uint128,
         collateral_amount: Option<Uint128>,
         collateral_contract: Option<String>,
         is_redemption_provider: Option<bool>,
+        vault_id: Option<u64>,
     },
 
+    /// Toggles the sender's position, or `minter`'s when the sender is an operator
+    /// with the toggle redemption permission
     BecomeRedemptionProvider {
         is_redemption_provider: bool,
+        minter: Option<String>,
+        vault_id: Option<u64>,
     },
 
+    /// Stable pool only, `payer` is the account the kUSD was sent from
     RepayStableCoin {
-        sender: String,
+        minter: String,
+        vault_id: Option<u64>,
+        payer: String,
+        amount: Uint128,
+    },
+
+    /// Stable pool only, `amount` must repay the whole loan. Every collateral of the
+    /// vault is withdrawn to the minter
+    ClosePosition {
+        minter: String,
+        vault_id: Option<u64>,
+        payer: String,
         amount: Uint128,
     },
 
@@ -72,23 +175,62 @@ pub enum ExecuteMsg {
         redeemer: String,
         amount: Uint128,
         minter: String,
+        vault_id: Option<u64>,
     },
 
+    /// Withdraws from the sender's position, or `minter`'s when the sender is an
+    /// operator with the withdraw permission. Collateral is always sent to the minter
     WithdrawCollateral {
         collateral_contract: String,
         collateral_amount: Uint128,
+        minter: Option<String>,
+        vault_id: Option<u64>,
     },
 
+    /// Custody contract only, the first deposit into the default vault mints its position token
     DepositCollateral {
         minter: String,
         collateral_contract: String,
         collateral_amount: Uint128,
+        vault_id: Option<u64>,
     },
 
+    /// Liquidates a single vault, the other vaults of the minter are left untouched
     LiquidateCollateral {
         minter: String,
+        vault_id: Option<u64>,
+    },
+
+    /// Liquidate every unsafe position of the batch, safe positions are skipped.
+    /// A failed liquidation leaves the other positions of the batch liquidated.
+    /// The response data holds a `LiquidateCollateralsResponse`
+    LiquidateCollaterals {
+        minters: Vec<LiquidationTarget>,
+    },
+
+    /// Contract itself only, a single liquidation of a `LiquidateCollaterals` batch
+    BatchLiquidateCollateral {
+        minter: String,
+        vault_id: u64,
+        liquidator: String,
+    },
+
+    /// Move the next positions stored in a former layout, callable by anyone after a
+    /// migration until all positions are moved. Other messages are rejected meanwhile
+    MigratePositions {
+        limit: Option<u32>,
+    },
+
+    /// Contract itself only, sent after the repay of a liquidation to record the
+    /// kUSD it repaid
+    SettleLiquidationRecord {
+        id: u64,
     },
 
+    /// Open a new isolated vault for the sender, the id is returned in the `vault_id` attribute.
+    /// A position token is minted to the sender, its id is returned in the `token_id` attribute
+    OpenVault {},
+
     WhitelistCollateral {
         name: String,
         symbol: String,
@@ -97,6 +239,60 @@ pub enum ExecuteMsg {
         collateral_contract: String,
         reward_book_contract: String,
     },
+
+    /// Allow `operator` to manage the sender's position. All permissions are
+    /// granted when `permissions` is not set, `expires_at` is a block time in seconds
+    ApproveOperator {
+        operator: String,
+        permissions: Option<Vec<OperatorPermission>>,
+        expires_at: Option<u64>,
+    },
+
+    RevokeOperator {
+        operator: String,
+    },
+
+    /// cw721 transfer of a position token. The vault is moved with its loan and
+    /// collaterals to a newly opened vault of the recipient, the vault of the sender
+    /// is closed. A default vault is left empty and gets a new token on its next deposit
+    TransferNft {
+        recipient: String,
+        token_id: String,
+    },
+
+    /// cw721 send of a position token, the vault is moved as with `TransferNft`
+    /// and `contract` is notified with `ReceiveNft`
+    SendNft {
+        contract: String,
+        token_id: String,
+        msg: Binary,
+    },
+
+    /// Allow `spender` to transfer the position token, cleared on transfer
+    Approve {
+        spender: String,
+        token_id: String,
+        expires: Option<Expiration>,
+    },
+
+    Revoke {
+        spender: String,
+        token_id: String,
+    },
+}
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum OperatorPermission {
+    Mint,
+    Withdraw,
+    ToggleRedemption,
+}
+
+#[cw_serde]
+pub struct LiquidationTarget {
+    pub minter: String,
+    pub vault_id: Option<u64>,
 }
 
 #[cw_serde]
@@ -108,7 +304,10 @@ pub enum QueryMsg {
     #[returns(ConfigResponse)]
     Config {},
     #[returns(LoanInfoResponse)]
-    LoanInfo { minter: String },
+    LoanInfo {
+        minter: String,
+        vault_id: Option<u64>,
+    },
     #[returns(WhitelistElemResponse)]
     CollateralElem { collateral: String },
     #[returns(WhitelistResponse)]
@@ -120,10 +319,13 @@ pub enum QueryMsg {
     #[returns(MinterCollateralResponse)]
     MinterCollateral {
         minter: String,
+        vault_id: Option<u64>,
     },
+    /// `vault_id` selects the vault of `minter`, or the vault of `start_after` to page from
     #[returns(RedemptionProviderListRespone)]
     RedemptionProviderList {
         minter: Option<String>,
+        vault_id: Option<u64>,
         start_after: Option<String>,
         limit: Option<u32>,
     },
@@ -132,11 +334,124 @@ pub enum QueryMsg {
     CollateralAvailable {
         minter: String,
         collateral_contract: String,
+        vault_id: Option<u64>,
+    },
+
+    /// Vaults opened by the minter, the default vault included
+    #[returns(VaultsResponse)]
+    Vaults {
+        minter: String,
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    #[returns(LiquidationRecordResponse)]
+    LiquidationRecord {
+        id: u64,
+    },
+
+    /// Liquidation records in the order they were made, of `minter` if set.
+    /// `start_time` and `end_time` are inclusive block times in seconds
+    #[returns(LiquidationHistoryResponse)]
+    LiquidationHistory {
+        minter: Option<String>,
+        start_time: Option<u64>,
+        end_time: Option<u64>,
+        start_after: Option<u64>,
+        limit: Option<u32>,
     },
+
+    #[returns(TimelockConfigResponse)]
+    TimelockConfig {},
+
+    #[returns(PendingChangeResponse)]
+    PendingChange {
+        id: u64,
+    },
+
+    #[returns(PendingChangesResponse)]
+    PendingChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    #[returns(HasRoleResponse)]
+    HasRole {
+        role: Role,
+        address: String,
+    },
+
+    #[returns(RoleMembersResponse)]
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(PausedResponse)]
+    Paused {},
+
+    #[returns(OperatorResponse)]
+    Operator {
+        minter: String,
+        operator: String,
+    },
+
+    #[returns(OperatorsResponse)]
+    Operators {
+        minter: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    /// cw721 owner of a position token
+    #[returns(OwnerOfResponse)]
+    OwnerOf {
+        token_id: String,
+        include_expired: Option<bool>,
+    },
+
+    /// cw721 token info, the extension is the current state of the vault
+    #[returns(NftInfoResponse<VaultResponse>)]
+    NftInfo {
+        token_id: String,
+    },
+
+    /// cw721 position tokens held by `owner`
+    #[returns(TokensResponse)]
+    Tokens {
+        owner: String,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(TokensResponse)]
+    AllTokens {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(NumTokensResponse)]
+    NumTokens {},
+
+    #[returns(ContractInfoResponse)]
+    ContractInfo {},
 }
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub enum MigrateMsg {
+    /// Upgrade a deployment instantiated before cw2 version tracking. Sets up the
+    /// timelock and the roles, the current owner is granted the admin role.
+    /// Also moves the loan, collateral and whitelist buckets to `cw_storage_plus` maps,
+    /// a first page of them is moved, see `MigratePositions`
+    FromLegacy {
+        guardian_addr: String,
+        timelock_delay: u64,
+    },
+    /// Upgrade between versioned releases. Positions stored per minter are moved to the
+    /// default vault of the minter, a first page of them is moved, see `MigratePositions`
+    Upgrade {},
+}
 
 #[cw_serde]
 pub struct ConfigResponse {
@@ -148,6 +463,10 @@ pub struct ConfigResponse {
     pub stable_denom: String,
     pub epoch_period: u64,
     pub redeem_fee: Decimal256,
+    pub max_liquidation_batch_size: u32,
+    pub keeper_reward: KeeperReward,
+    pub liquidation_cooldown: u64,
+    pub liquidation_record_retention: u64,
 }
 
 
@@ -155,6 +474,7 @@ pub struct ConfigResponse {
 #[cw_serde]
 pub struct LoanInfoResponse {
     pub minter: String,
+    pub vault_id: u64,
     pub loans: Uint256,
     pub max_mint_value: Uint256,
 }
@@ -163,6 +483,7 @@ pub struct LoanInfoResponse {
 #[cw_serde]
 pub struct CollateralsResponse {
     pub minter: String,
+    pub vault_id: u64,
     pub collaterals: TokensHuman, // <(Collateral Token, Amount)>
 }
 
@@ -192,6 +513,7 @@ pub struct MinterCollateralResponse {
 #[cw_serde]
 pub struct MinterLoanResponse {
     pub minter: String,
+    pub vault_id: u64,
     pub loans: Uint256,
     pub is_redemption_provider: bool,
 }
@@ -206,3 +528,86 @@ pub struct RedemptionProviderListRespone {
 pub struct CollateralAvailableRespone {
     pub available_balance: Uint128,
 }
+
+#[cw_serde]
+pub struct VaultResponse {
+    pub vault_id: u64,
+    pub loans: Uint256,
+    pub is_redemption_provider: bool,
+    pub collaterals: TokensHuman,
+}
+
+#[cw_serde]
+pub struct VaultsResponse {
+    pub vaults: Vec<VaultResponse>,
+}
+
+#[cw_serde]
+pub struct OperatorResponse {
+    pub minter: String,
+    pub operator: String,
+    pub permissions: Vec<OperatorPermission>,
+    pub expires_at: Option<u64>,
+}
+
+#[cw_serde]
+pub struct OperatorsResponse {
+    pub operators: Vec<OperatorResponse>,
+}
+
+#[cw_serde]
+pub enum LiquidationStatus {
+    Liquidated,
+    /// the position was safely collateralized and left untouched
+    Safe,
+    /// the position was liquidated too recently and left untouched
+    Cooldown,
+    /// the liquidation of the position failed, the rest of the batch went through
+    Failed { error: String },
+}
+
+#[cw_serde]
+pub struct LiquidationResult {
+    pub minter: String,
+    pub vault_id: u64,
+    pub status: LiquidationStatus,
+}
+
+#[cw_serde]
+pub struct LiquidateCollateralsResponse {
+    pub results: Vec<LiquidationResult>,
+}
+
+#[cw_serde]
+pub struct LiquidatedCollateral {
+    pub collateral_contract: String,
+    pub amount: Uint256,
+    /// oracle price used by the liquidation
+    pub price: Decimal256,
+}
+
+#[cw_serde]
+pub struct LiquidationRecordResponse {
+    pub id: u64,
+    pub minter: String,
+    pub vault_id: u64,
+    pub liquidator: String,
+    pub collaterals: Vec<LiquidatedCollateral>,
+    /// loans of the vault before the liquidation
+    pub loans: Uint256,
+    /// kUSD repaid with the proceeds of the collaterals
+    pub repaid: Uint256,
+    /// fees of the liquidation queue on the sale of the collaterals, simulated at the
+    /// liquidation prices since the liquidation queue does not report the fees it charged
+    pub estimated_bid_fee: Uint256,
+    pub estimated_liquidator_fee: Uint256,
+    pub keeper_reward: Uint128,
+    pub height: u64,
+    /// block time in seconds
+    pub time: u64,
+}
+
+#[cw_serde]
+pub struct LiquidationHistoryResponse {
+    pub records: Vec<LiquidationRecordResponse>,
+}
@@ -16,6 +16,8 @@ use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::{ Uint128};
 use cw20::Cw20ReceiveMsg;
 use cosmwasm_schema::{cw_serde,QueryResponses};
+use crate::rbac::{HasRoleResponse, PausedResponse, Role, RoleMembersResponse};
+use crate::timelock::{PendingChangeResponse, PendingChangesResponse, TimelockConfigResponse};
 use crate::tokens::{TokensHuman};
 
 #[cw_serde]
@@ -27,10 +29,15 @@ pub struct InstantiateMsg {
     pub collateral_contract: String,
     pub liquidation_contract: String,
     pub reward_book_contract: String,
+    /// Address granted the guardian role, allowed to cancel pending config changes
+    pub guardian_addr: String,
+    /// Delay (seconds) before a proposed config change can be executed
+    pub timelock_delay: u64,
 }
 
 #[cw_serde]
 pub enum ExecuteMsg {
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
     UpdateConfig {
         control_contract: Option<String>,
         pool_contract: Option<String>,
@@ -39,10 +46,45 @@ pub enum ExecuteMsg {
         reward_book_contract: Option<String>,
     },
 
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
+    UpdateTimelockConfig {
+        delay: Option<u64>,
+    },
+
+    /// Apply a pending change once its eta has passed
+    ExecuteChange {
+        id: u64,
+    },
+
+    /// Drop a pending change, callable by a guardian or an admin
+    CancelChange {
+        id: u64,
+    },
+
+    /// Admin only, admin and guardian grants are timelocked changes
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+
+    /// Admin only, an admin can not revoke its own admin role
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+
+    /// Guardian or admin, blocks collateral deposits until unpaused
+    Pause {},
+
+    /// Admin only
+    Unpause {},
+
+    /// Timelocked, the new owner accepts the ownership once the change is executed
     SetOwner {
         new_owner_addr: String,
     },
 
+    /// Only the admin role of the previous owner is revoked
     AcceptOwnership {
     },
     
@@ -79,9 +121,12 @@ pub enum Cw20HookMsg {
     MintStableCoin {
         stable_amount: Uint128, // mint stable amount, can not exceed ltv limit
         is_redemption_provider: Option<bool>,   //Whether to become a redemption provider
+        vault_id: Option<u64>, // vault of the sender, the default vault if not set
     },
 
-    DepositCollateral {},
+    DepositCollateral {
+        vault_id: Option<u64>,
+    },
 }
 
 #[cw_serde]
@@ -91,6 +136,36 @@ pub enum QueryMsg {
     Config {},
     #[returns(StateResponse)]
     State {},
+
+    #[returns(TimelockConfigResponse)]
+    TimelockConfig {},
+
+    #[returns(PendingChangeResponse)]
+    PendingChange {
+        id: u64,
+    },
+
+    #[returns(PendingChangesResponse)]
+    PendingChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    #[returns(HasRoleResponse)]
+    HasRole {
+        role: Role,
+        address: String,
+    },
+
+    #[returns(RoleMembersResponse)]
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(PausedResponse)]
+    Paused {},
 }
 
 #[cw_serde]
@@ -118,6 +193,15 @@ pub struct StateResponse {
 
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub enum MigrateMsg {
+    /// Upgrade a deployment instantiated before cw2 version tracking. Sets up the
+    /// timelock and the roles, the current owner is granted the admin role.
+    FromLegacy {
+        guardian_addr: String,
+        timelock_delay: u64,
+    },
+    /// Upgrade between versioned releases
+    Upgrade {},
+}
 
 
@@ -0,0 +1,84 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! The part of the cw721 interface implemented by the central control contract
+//! for position tokens. Messages and responses match cw721 on the wire, so
+//! marketplaces and lending contracts can handle positions as regular NFTs.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{to_binary, Binary, CosmosMsg, StdResult, WasmMsg};
+use cw_utils::Expiration;
+
+/// Sent to the receiving contract of a `SendNft`
+#[cw_serde]
+pub struct Cw721ReceiveMsg {
+    pub sender: String,
+    pub token_id: String,
+    pub msg: Binary,
+}
+
+impl Cw721ReceiveMsg {
+    /// serializes the message wrapped in `ReceiveNft`
+    pub fn into_binary(self) -> StdResult<Binary> {
+        to_binary(&ReceiverExecuteMsg::ReceiveNft(self))
+    }
+
+    pub fn into_cosmos_msg<T: Into<String>>(self, contract_addr: T) -> StdResult<CosmosMsg> {
+        Ok(WasmMsg::Execute {
+            contract_addr: contract_addr.into(),
+            msg: self.into_binary()?,
+            funds: vec![],
+        }
+        .into())
+    }
+}
+
+#[cw_serde]
+enum ReceiverExecuteMsg {
+    ReceiveNft(Cw721ReceiveMsg),
+}
+
+#[cw_serde]
+pub struct Approval {
+    pub spender: String,
+    pub expires: Expiration,
+}
+
+#[cw_serde]
+pub struct OwnerOfResponse {
+    pub owner: String,
+    pub approvals: Vec<Approval>,
+}
+
+#[cw_serde]
+pub struct NftInfoResponse<T> {
+    pub token_uri: Option<String>,
+    pub extension: T,
+}
+
+#[cw_serde]
+pub struct TokensResponse {
+    pub tokens: Vec<String>,
+}
+
+#[cw_serde]
+pub struct NumTokensResponse {
+    pub count: u64,
+}
+
+#[cw_serde]
+pub struct ContractInfoResponse {
+    pub name: String,
+    pub symbol: String,
+}
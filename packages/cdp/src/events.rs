@@ -0,0 +1,367 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Typed events for indexers.
+//!
+//! Every state transition emits `cdp-*` events, which the chain reports as
+//! `wasm-cdp-*`, one per change it makes: closing a position for instance emits a
+//! repay event followed by a withdraw event per collateral. Each event carries the
+//! emitting `contract_module` followed by a fixed set of attributes per event type,
+//! the module is not repeated as a plain response attribute. Position events (mint,
+//! repay, redeem, deposit, withdraw, liquidate) are emitted by the central
+//! control contract, which owns the loan and collateral accounting, and carry the
+//! `vault_id` of the position. Position token events (open, transfer) are emitted
+//! by the central control contract as well.
+
+use cosmwasm_bignumber::Uint256;
+use cosmwasm_std::{Event, Uint128};
+
+pub const MODULE_CENTRAL_CONTROL: &str = "central_control";
+pub const MODULE_CUSTODY: &str = "custody";
+pub const MODULE_STABLE_POOL: &str = "stable_pool";
+pub const MODULE_REWARD_BOOK: &str = "reward_book";
+pub const MODULE_LIQUIDATION_QUEUE: &str = "liquidation_queue";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CdpEvent {
+    Mint {
+        minter: String,
+        vault_id: u64,
+        stable_amount: Uint128,
+    },
+    Repay {
+        minter: String,
+        vault_id: u64,
+        payer: String,
+        amount: Uint128,
+    },
+    Redeem {
+        redeemer: String,
+        minter: String,
+        vault_id: u64,
+        amount: Uint128,
+    },
+    Deposit {
+        minter: String,
+        vault_id: u64,
+        collateral_contract: String,
+        amount: Uint128,
+    },
+    Withdraw {
+        minter: String,
+        vault_id: u64,
+        collateral_contract: String,
+        amount: Uint128,
+    },
+    Liquidate {
+        minter: String,
+        vault_id: u64,
+        liquidator: String,
+        loans: Uint256,
+    },
+    PositionOpen {
+        minter: String,
+        vault_id: u64,
+        token_id: String,
+    },
+    PositionTransfer {
+        token_id: String,
+        from: String,
+        from_vault_id: u64,
+        to: String,
+        to_vault_id: u64,
+    },
+    BidSubmit {
+        bid_idx: Uint128,
+        bidder: String,
+        collateral_token: String,
+        premium_slot: u8,
+        amount: Uint256,
+    },
+    BidActivate {
+        bidder: String,
+        collateral_token: String,
+        amount: Uint256,
+    },
+    BidRetract {
+        bid_idx: Uint128,
+        bidder: String,
+        amount: Uint256,
+    },
+    BidExecute {
+        liquidator: String,
+        collateral_token: String,
+        collateral_amount: Uint256,
+        repay_amount: Uint256,
+        bid_fee: Uint256,
+        liquidator_fee: Uint256,
+    },
+    BidClaim {
+        bidder: String,
+        collateral_token: String,
+        amount: Uint256,
+    },
+    BidPrune {
+        bid_idx: Uint128,
+        bidder: String,
+        keeper: String,
+        amount: Uint256,
+        collateral_amount: Uint256,
+        tip: Uint256,
+    },
+    RewardClaim {
+        holder: String,
+        recipient: String,
+        /// native denom or CW20 contract address
+        denom: String,
+        amount: Uint128,
+    },
+    ConfigChange {
+        action: String,
+        sender: String,
+    },
+}
+
+impl CdpEvent {
+    /// Event type without the `wasm-` prefix added by the chain
+    pub fn event_type(&self) -> &'static str {
+        match self {
+            CdpEvent::Mint { .. } => "cdp-mint",
+            CdpEvent::Repay { .. } => "cdp-repay",
+            CdpEvent::Redeem { .. } => "cdp-redeem",
+            CdpEvent::Deposit { .. } => "cdp-deposit",
+            CdpEvent::Withdraw { .. } => "cdp-withdraw",
+            CdpEvent::Liquidate { .. } => "cdp-liquidate",
+            CdpEvent::PositionOpen { .. } => "cdp-position-open",
+            CdpEvent::PositionTransfer { .. } => "cdp-position-transfer",
+            CdpEvent::BidSubmit { .. } => "cdp-bid-submit",
+            CdpEvent::BidActivate { .. } => "cdp-bid-activate",
+            CdpEvent::BidRetract { .. } => "cdp-bid-retract",
+            CdpEvent::BidExecute { .. } => "cdp-bid-execute",
+            CdpEvent::BidClaim { .. } => "cdp-bid-claim",
+            CdpEvent::BidPrune { .. } => "cdp-bid-prune",
+            CdpEvent::RewardClaim { .. } => "cdp-reward-claim",
+            CdpEvent::ConfigChange { .. } => "cdp-config-change",
+        }
+    }
+
+    pub fn into_event(self, contract_module: &str) -> Event {
+        let event = Event::new(self.event_type()).add_attribute("contract_module", contract_module);
+        match self {
+            CdpEvent::Mint {
+                minter,
+                vault_id,
+                stable_amount,
+            } => event.add_attributes(vec![
+                ("minter", minter),
+                ("vault_id", vault_id.to_string()),
+                ("stable_amount", stable_amount.to_string()),
+            ]),
+            CdpEvent::Repay {
+                minter,
+                vault_id,
+                payer,
+                amount,
+            } => event.add_attributes(vec![
+                ("minter", minter),
+                ("vault_id", vault_id.to_string()),
+                ("payer", payer),
+                ("amount", amount.to_string()),
+            ]),
+            CdpEvent::Redeem {
+                redeemer,
+                minter,
+                vault_id,
+                amount,
+            } => event.add_attributes(vec![
+                ("redeemer", redeemer),
+                ("minter", minter),
+                ("vault_id", vault_id.to_string()),
+                ("amount", amount.to_string()),
+            ]),
+            CdpEvent::Deposit {
+                minter,
+                vault_id,
+                collateral_contract,
+                amount,
+            }
+            | CdpEvent::Withdraw {
+                minter,
+                vault_id,
+                collateral_contract,
+                amount,
+            } => event.add_attributes(vec![
+                ("minter", minter),
+                ("vault_id", vault_id.to_string()),
+                ("collateral_contract", collateral_contract),
+                ("amount", amount.to_string()),
+            ]),
+            CdpEvent::Liquidate {
+                minter,
+                vault_id,
+                liquidator,
+                loans,
+            } => event.add_attributes(vec![
+                ("minter", minter),
+                ("vault_id", vault_id.to_string()),
+                ("liquidator", liquidator),
+                ("loans", loans.to_string()),
+            ]),
+            CdpEvent::PositionOpen {
+                minter,
+                vault_id,
+                token_id,
+            } => event.add_attributes(vec![
+                ("minter", minter),
+                ("vault_id", vault_id.to_string()),
+                ("token_id", token_id),
+            ]),
+            CdpEvent::PositionTransfer {
+                token_id,
+                from,
+                from_vault_id,
+                to,
+                to_vault_id,
+            } => event.add_attributes(vec![
+                ("token_id", token_id),
+                ("from", from),
+                ("from_vault_id", from_vault_id.to_string()),
+                ("to", to),
+                ("to_vault_id", to_vault_id.to_string()),
+            ]),
+            CdpEvent::BidSubmit {
+                bid_idx,
+                bidder,
+                collateral_token,
+                premium_slot,
+                amount,
+            } => event.add_attributes(vec![
+                ("bid_idx", bid_idx.to_string()),
+                ("bidder", bidder),
+                ("collateral_token", collateral_token),
+                ("premium_slot", premium_slot.to_string()),
+                ("amount", amount.to_string()),
+            ]),
+            CdpEvent::BidActivate {
+                bidder,
+                collateral_token,
+                amount,
+            }
+            | CdpEvent::BidClaim {
+                bidder,
+                collateral_token,
+                amount,
+            } => event.add_attributes(vec![
+                ("bidder", bidder),
+                ("collateral_token", collateral_token),
+                ("amount", amount.to_string()),
+            ]),
+            CdpEvent::BidRetract {
+                bid_idx,
+                bidder,
+                amount,
+            } => event.add_attributes(vec![
+                ("bid_idx", bid_idx.to_string()),
+                ("bidder", bidder),
+                ("amount", amount.to_string()),
+            ]),
+            CdpEvent::BidExecute {
+                liquidator,
+                collateral_token,
+                collateral_amount,
+                repay_amount,
+                bid_fee,
+                liquidator_fee,
+            } => event.add_attributes(vec![
+                ("liquidator", liquidator),
+                ("collateral_token", collateral_token),
+                ("collateral_amount", collateral_amount.to_string()),
+                ("repay_amount", repay_amount.to_string()),
+                ("bid_fee", bid_fee.to_string()),
+                ("liquidator_fee", liquidator_fee.to_string()),
+            ]),
+            CdpEvent::BidPrune {
+                bid_idx,
+                bidder,
+                keeper,
+                amount,
+                collateral_amount,
+                tip,
+            } => event.add_attributes(vec![
+                ("bid_idx", bid_idx.to_string()),
+                ("bidder", bidder),
+                ("keeper", keeper),
+                ("amount", amount.to_string()),
+                ("collateral_amount", collateral_amount.to_string()),
+                ("tip", tip.to_string()),
+            ]),
+            CdpEvent::RewardClaim {
+                holder,
+                recipient,
+                denom,
+                amount,
+            } => event.add_attributes(vec![
+                ("holder", holder),
+                ("recipient", recipient),
+                ("denom", denom),
+                ("amount", amount.to_string()),
+            ]),
+            CdpEvent::ConfigChange { action, sender } => {
+                event.add_attributes(vec![("action", action), ("sender", sender)])
+            }
+        }
+    }
+}
+
+/// Config change event for `action` sent by `sender`
+pub fn config_change_event(contract_module: &str, action: &str, sender: impl Into<String>) -> Event {
+    CdpEvent::ConfigChange {
+        action: action.to_string(),
+        sender: sender.into(),
+    }
+    .into_event(contract_module)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn event_schema() {
+        let event = CdpEvent::Deposit {
+            minter: "minter".to_string(),
+            vault_id: 1u64,
+            collateral_contract: "collateral".to_string(),
+            amount: Uint128::new(100),
+        }
+        .into_event(MODULE_CENTRAL_CONTROL);
+        assert_eq!(
+            event,
+            Event::new("cdp-deposit").add_attributes(vec![
+                ("contract_module", "central_control"),
+                ("minter", "minter"),
+                ("vault_id", "1"),
+                ("collateral_contract", "collateral"),
+                ("amount", "100"),
+            ])
+        );
+
+        let event = config_change_event(MODULE_CUSTODY, "set_new_owner", "owner");
+        assert_eq!(event.ty, "cdp-config-change");
+        assert_eq!(
+            event.attributes[0],
+            cosmwasm_std::attr("contract_module", "custody")
+        );
+    }
+}
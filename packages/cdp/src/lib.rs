@@ -1,11 +1,17 @@
 
 pub mod central_control;
 pub mod custody;
+pub mod cw721;
+pub mod events;
 pub mod stable_pool;
+pub mod swap_adapter;
 pub mod oracle_pyth;
 pub mod querier;
 pub mod tokens;
 pub mod liquidation_queue;
 pub mod handle;
+pub mod migration;
 pub mod reward_book;
-pub mod rewards;
\ No newline at end of file
+pub mod rewards;
+pub mod rbac;
+pub mod timelock;
\ No newline at end of file
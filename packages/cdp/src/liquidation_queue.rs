@@ -19,6 +19,8 @@ use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_std::Uint128;
 use cw20::Cw20ReceiveMsg;
 
+use crate::rbac::{HasRoleResponse, PausedResponse, Role, RoleMembersResponse};
+use crate::timelock::{PendingChangeResponse, PendingChangesResponse, TimelockConfigResponse};
 use crate::tokens::TokensHuman;
 
 #[cw_serde]
@@ -44,11 +46,89 @@ pub struct InstantiateMsg {
     /// Time period that needs to pass for a bid to be activated (seconds)
     pub waiting_period: u64,
     pub control_contract: String,
+    /// Maximum fraction of a position's loan repayable by a single liquidation,
+    /// defaults to one (no limit). Not applied below the liquidation threshold
+    pub close_factor: Option<Decimal256>,
+    /// Share of the refunded stable amount paid to the caller of `PruneExpiredBids`
+    pub prune_tip_rate: Option<Decimal256>,
+    /// Minimum stable amount of a bid with a max price, defaults to zero
+    pub min_limit_bid_amount: Option<Uint256>,
+    /// Swap adapter selling the liquidated collateral of auto rebidding bids,
+    /// see `cdp::swap_adapter`
+    pub swap_adapter: Option<String>,
+    /// Address granted the guardian role, allowed to cancel pending config changes
+    pub guardian_addr: String,
+    /// Delay (seconds) before a proposed config change can be executed
+    pub timelock_delay: u64,
 }
 
+/// Liquidated collateral of the bid is sold for the stable denom and bid again
+/// in the same premium slot
 #[cw_serde]
+pub struct AutoRebid {
+    /// Maximum shortfall of the swap return from the oracle value of the collateral
+    pub max_slippage: Decimal256,
+}
+
+/// Share of an auto rebid swap return
+#[cw_serde]
+pub struct RebidShare {
+    pub bidder: String,
+    pub premium_slot: u8,
+    pub collateral_amount: Uint256,
+    pub expires_at: Option<u64>,
+    pub max_price: Option<Decimal256>,
+    pub auto_rebid: AutoRebid,
+}
+
+/// Premium rate of each slot of a collateral
+#[cw_serde]
+pub enum PremiumSchedule {
+    /// slot × rate_per_slot
+    Linear { rate_per_slot: Decimal256 },
+    /// Rate of each slot, from slot 0 to the max slot
+    Explicit { rates: Vec<Decimal256> },
+    /// (slot, rate) points in slot order from slot 0, rates of the slots in
+    /// between are interpolated and the last rate applies beyond the last point
+    Piecewise { points: Vec<(u8, Decimal256)> },
+}
+
+impl PremiumSchedule {
+    pub fn premium_rate(&self, premium_slot: u8) -> Decimal256 {
+        match self {
+            PremiumSchedule::Linear { rate_per_slot } => {
+                *rate_per_slot * Decimal256::from_uint256(Uint256::from(premium_slot as u128))
+            }
+            PremiumSchedule::Explicit { rates } => rates
+                .get(premium_slot as usize)
+                .or_else(|| rates.last())
+                .copied()
+                .unwrap_or_default(),
+            PremiumSchedule::Piecewise { points } => {
+                let upper = match points.iter().position(|(slot, _)| *slot >= premium_slot) {
+                    Some(upper) => upper,
+                    None => return points.last().map(|(_, rate)| *rate).unwrap_or_default(),
+                };
+                let (upper_slot, upper_rate) = points[upper];
+                if upper == 0 || upper_slot == premium_slot {
+                    return upper_rate;
+                }
+
+                // rates are non-decreasing along the curve
+                let (lower_slot, lower_rate) = points[upper - 1];
+                lower_rate
+                    + (upper_rate - lower_rate)
+                        * Decimal256::from_ratio(premium_slot - lower_slot, upper_slot - lower_slot)
+            }
+        }
+    }
+}
+
+#[cw_serde]
+#[allow(clippy::large_enum_variant)]
 pub enum ExecuteMsg {
     Receive(Cw20ReceiveMsg),
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
     UpdateConfig {
         oracle_contract: Option<String>,
         safe_ratio: Option<Decimal256>,
@@ -59,30 +139,90 @@ pub enum ExecuteMsg {
         waiting_period: Option<u64>,
         control_contract: Option<String>,
         stable_denom: Option<String>,
+        close_factor: Option<Decimal256>,
+        prune_tip_rate: Option<Decimal256>,
+        min_limit_bid_amount: Option<Uint256>,
+        swap_adapter: Option<String>,
+    },
+
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
+    UpdateTimelockConfig {
+        delay: Option<u64>,
+    },
+
+    /// Apply a pending change once its eta has passed
+    ExecuteChange {
+        id: u64,
+    },
+
+    /// Drop a pending change, callable by a guardian or an admin
+    CancelChange {
+        id: u64,
     },
+
+    /// Admin only, admin and guardian grants are timelocked changes
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+
+    /// Admin only, an admin can not revoke its own admin role
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+
+    /// Guardian or admin, blocks submitting bids and rebidding until unpaused
+    Pause {},
+
+    /// Admin only
+    Unpause {},
+
+    /// Timelocked, the new owner accepts the ownership once the change is executed
     SetOwner {
         new_owner_addr: String,
     },
 
+    /// Only the admin role of the previous owner is revoked
     AcceptOwnership {
     },
 
-    /// Owner operation to whitelist a new collateral
+    /// Admin or risk manager operation to whitelist a new collateral. The fees,
+    /// waiting period and liquidation threshold of the config apply unless overridden
     WhitelistCollateral {
         collateral_token: String,
         bid_threshold: Uint256,
         max_slot: u8,
         premium_rate_per_slot: Decimal256,
+        /// Replaces the linear premium of `premium_rate_per_slot` when set
+        premium_schedule: Option<PremiumSchedule>,
+        bid_fee: Option<Decimal256>,
+        liquidator_fee: Option<Decimal256>,
+        waiting_period: Option<u64>,
+        liquidation_threshold: Option<Uint256>,
     },
+    /// A new premium schedule re-rates the existing bid pools, their bids
+    /// keep their amounts and can be retracted at any time
     UpdateCollateralInfo {
         collateral_token: String,
         bid_threshold: Option<Uint256>,
         max_slot: Option<u8>,
+        premium_schedule: Option<PremiumSchedule>,
+        bid_fee: Option<Decimal256>,
+        liquidator_fee: Option<Decimal256>,
+        waiting_period: Option<u64>,
+        liquidation_threshold: Option<Uint256>,
     },
     /// Submit a new bid to a bid pool
     SubmitBid {
         collateral_token: String,
         premium_slot: u8,
+        /// Block time (seconds) from which the bid is no longer used for liquidations
+        expires_at: Option<u64>,
+        /// Highest collateral price, in stable denom, at which the bid can be filled
+        max_price: Option<Decimal256>,
+        /// Sell the liquidated collateral through the swap adapter and bid again
+        auto_rebid: Option<AutoRebid>,
     },
     /// Withdraw a bid
     RetractBid {
@@ -94,18 +234,48 @@ pub enum ExecuteMsg {
         collateral_token: String,
         bids_idx: Option<Vec<Uint128>>,
     },
+    /// Activate the waiting bids of all bidders in wait end order,
+    /// callable by anyone
+    ActivateBidsFor {
+        collateral_token: String,
+        limit: Option<u8>,
+    },
     /// Claim the corresponding amount of liquidated collateral
     ClaimLiquidations {
         collateral_token: String,
         bids_idx: Option<Vec<Uint128>>,
     },
+    /// Refund the remaining amount and liquidated collateral of expired bids
+    /// to their bidders, callable by anyone for a tip
+    PruneExpiredBids {
+        collateral_token: String,
+        limit: Option<u8>,
+    },
+    /// Index by wait end the next bids stored before the wait end index existed,
+    /// callable by anyone after a migration until all bids are indexed
+    IndexWaitingBids {
+        limit: Option<u8>,
+    },
+    /// Sell the liquidated collateral of auto rebidding bids through the swap
+    /// adapter and bid the return again, callable by anyone
+    Rebid {
+        collateral_token: String,
+        bids_idx: Vec<Uint128>,
+    },
+    /// Internal, bids the swap return of `Rebid` for the shares
+    RebidSwapped {
+        collateral_token: String,
+        prev_balance: Uint256,
+        min_receive: Uint256,
+        rebids: Vec<RebidShare>,
+    },
 }
 
 #[cw_serde]
 pub enum Cw20HookMsg {
     /// Custody interface to liquidate the sent collateral
     ExecuteBid {
-        liquidator: String, // Legacy parameter, ignored
+        liquidator: String, // receives the liquidator fee
         fee_address: Option<String>,
         repay_address: Option<String>,
     },
@@ -138,6 +308,26 @@ pub enum QueryMsg {
         start_after: Option<Uint128>,
         limit: Option<u8>,
     },
+    /// Fill of a liquidation of `amount` collateral at `price` by the current bids,
+    /// state is left untouched
+    #[returns(SimulateExecuteBidResponse)]
+    SimulateExecuteBid {
+        collateral_token: String,
+        amount: Uint256,
+        price: Decimal256,
+    },
+    /// Bids available at each premium slot of the collateral at `price`
+    #[returns(BidDepthResponse)]
+    BidDepth {
+        collateral_token: String,
+        price: Decimal256,
+    },
+    /// Collateral claimable by the bidder and the state of each of its bids
+    #[returns(ClaimableLiquidationsResponse)]
+    ClaimableLiquidations {
+        bidder: String,
+        collateral_token: String,
+    },
     #[returns(BidPoolResponse)]
     BidPool {
         collateral_token: String,
@@ -149,6 +339,36 @@ pub enum QueryMsg {
         start_after: Option<u8>,
         limit: Option<u8>,
     },
+
+    #[returns(TimelockConfigResponse)]
+    TimelockConfig {},
+
+    #[returns(PendingChangeResponse)]
+    PendingChange {
+        id: u64,
+    },
+
+    #[returns(PendingChangesResponse)]
+    PendingChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    #[returns(HasRoleResponse)]
+    HasRole {
+        role: Role,
+        address: String,
+    },
+
+    #[returns(RoleMembersResponse)]
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(PausedResponse)]
+    Paused {},
 }
 
 #[cw_serde]
@@ -163,6 +383,10 @@ pub struct ConfigResponse {
     pub price_timeframe: u64,
     pub waiting_period: u64,
     pub control_contract: String,
+    pub close_factor: Decimal256,
+    pub prune_tip_rate: Decimal256,
+    pub min_limit_bid_amount: Uint256,
+    pub swap_adapter: Option<String>,
 }
 
 #[cw_serde]
@@ -181,6 +405,9 @@ pub struct BidResponse {
     pub sum_snapshot: Decimal256,
     pub pending_liquidated_collateral: Uint256,
     pub wait_end: Option<u64>,
+    pub expires_at: Option<u64>,
+    pub max_price: Option<Decimal256>,
+    pub auto_rebid: Option<AutoRebid>,
     pub epoch_snapshot: Uint128,
     pub scale_snapshot: Uint128,
 }
@@ -190,6 +417,74 @@ pub struct BidsResponse {
     pub bids: Vec<BidResponse>,
 }
 
+#[cw_serde]
+pub struct SlotFillResponse {
+    pub premium_slot: u8,
+    /// Stable amount consumed from the slot
+    pub bid_amount: Uint256,
+    pub collateral_amount: Uint256,
+}
+
+#[cw_serde]
+pub struct SimulateExecuteBidResponse {
+    /// Stable amount sent to the repay address, fees excluded
+    pub repay_amount: Uint256,
+    pub bid_fee: Uint256,
+    pub liquidator_fee: Uint256,
+    pub slots: Vec<SlotFillResponse>,
+    /// false when the bids can not absorb the whole amount
+    pub filled: bool,
+}
+
+#[cw_serde]
+pub struct BidDepthSlotResponse {
+    pub premium_slot: u8,
+    pub premium_rate: Decimal256,
+    /// Collateral price paid by the bids of the slot
+    pub purchase_price: Decimal256,
+    /// Stable amount of the active bids usable at the price
+    pub active_amount: Uint256,
+    /// Collateral absorbable by the active bids of this slot and the lower ones
+    pub cumulative_collateral: Uint256,
+    /// Stable amount of the bids waiting for activation
+    pub waiting_amount: Uint256,
+}
+
+#[cw_serde]
+pub struct BidDepthResponse {
+    pub collateral_token: String,
+    pub price: Decimal256,
+    pub slots: Vec<BidDepthSlotResponse>,
+}
+
+#[cw_serde]
+pub struct ClaimableBidResponse {
+    pub idx: Uint128,
+    pub premium_slot: u8,
+    pub remaining_amount: Uint256,
+    pub claimable_collateral: Uint256,
+}
+
+#[cw_serde]
+pub struct WaitingBidResponse {
+    pub idx: Uint128,
+    pub premium_slot: u8,
+    pub amount: Uint256,
+    pub wait_end: u64,
+}
+
+#[cw_serde]
+pub struct ClaimableLiquidationsResponse {
+    pub bidder: String,
+    pub collateral_token: String,
+    /// Total collateral received by `ClaimLiquidations` for all the bids
+    pub claimable_collateral: Uint256,
+    /// Activated bids
+    pub bids: Vec<ClaimableBidResponse>,
+    /// Bids waiting for activation
+    pub waiting_bids: Vec<WaitingBidResponse>,
+}
+
 #[cw_serde]
 pub struct BidPoolResponse {
     pub sum_snapshot: Decimal256,
@@ -206,6 +501,12 @@ pub struct CollateralInfoResponse {
     pub bid_threshold: Uint256,
     pub max_slot: u8,
     pub premium_rate_per_slot: Decimal256,
+    pub premium_schedule: PremiumSchedule,
+    /// Values in effect for the collateral, its overrides or the config values
+    pub bid_fee: Decimal256,
+    pub liquidator_fee: Decimal256,
+    pub waiting_period: u64,
+    pub liquidation_threshold: Uint256,
 }
 
 #[cw_serde]
@@ -214,4 +515,16 @@ pub struct BidPoolsResponse {
 }
 
 #[cw_serde]
-pub struct MigrateMsg{}
\ No newline at end of file
+pub enum MigrateMsg {
+    /// Upgrade a deployment instantiated before cw2 version tracking. Sets up the
+    /// timelock and the roles, the current owner is granted the admin role.
+    /// Migrates the premium schedules and bids like `Upgrade`
+    FromLegacy {
+        guardian_addr: String,
+        timelock_delay: u64,
+    },
+    /// Upgrade between versioned releases. Collaterals without a premium schedule
+    /// get the explicit schedule of the premium rates of their bid pools, and
+    /// a first page of waiting bids is indexed by wait end, see `IndexWaitingBids`
+    Upgrade {},
+}
\ No newline at end of file
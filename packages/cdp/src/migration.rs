@@ -0,0 +1,83 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! cw2 version checks shared by the contract migrations.
+
+use cosmwasm_std::{StdError, StdResult, Storage};
+use cw2::{get_contract_version, CONTRACT};
+use semver::Version;
+
+/// Deployments instantiated before cw2 tracking have no version stored
+pub fn assert_legacy_deployment(storage: &dyn Storage) -> StdResult<()> {
+    if let Some(stored) = CONTRACT.may_load(storage)? {
+        return Err(StdError::generic_err(format!(
+            "Contract is already at version {}, use the upgrade migration",
+            stored.version
+        )));
+    }
+    Ok(())
+}
+
+/// The stored contract must be the same contract at the same or an older version
+pub fn assert_upgrade(storage: &dyn Storage, contract_name: &str, contract_version: &str) -> StdResult<()> {
+    let stored = get_contract_version(storage)?;
+    if stored.contract != contract_name {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from {} to {}",
+            stored.contract, contract_name
+        )));
+    }
+
+    let stored_version = parse_version(&stored.version)?;
+    if stored_version > parse_version(contract_version)? {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from version {} to the older version {}",
+            stored.version, contract_version
+        )));
+    }
+    Ok(())
+}
+
+fn parse_version(version: &str) -> StdResult<Version> {
+    Version::parse(version).map_err(|e| StdError::generic_err(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+    use cw2::set_contract_version;
+
+    #[test]
+    fn legacy_deployment_has_no_version() {
+        let mut deps = mock_dependencies();
+        assert_legacy_deployment(deps.as_ref().storage).unwrap();
+
+        set_contract_version(deps.as_mut().storage, "crates.io:cdp-test", "0.0.1").unwrap();
+        assert_legacy_deployment(deps.as_ref().storage).unwrap_err();
+    }
+
+    #[test]
+    fn proper_upgrade_checks() {
+        let mut deps = mock_dependencies();
+        // nothing stored
+        assert_upgrade(deps.as_ref().storage, "crates.io:cdp-test", "0.0.1").unwrap_err();
+
+        set_contract_version(deps.as_mut().storage, "crates.io:cdp-test", "0.1.0").unwrap();
+        assert_upgrade(deps.as_ref().storage, "crates.io:cdp-test", "0.1.0").unwrap();
+        assert_upgrade(deps.as_ref().storage, "crates.io:cdp-test", "0.2.0").unwrap();
+        assert_upgrade(deps.as_ref().storage, "crates.io:cdp-test", "0.0.1").unwrap_err();
+        assert_upgrade(deps.as_ref().storage, "crates.io:cdp-other", "0.2.0").unwrap_err();
+    }
+}
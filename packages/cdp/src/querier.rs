@@ -33,7 +33,7 @@ use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, TokenInfoRespon
 
 use crate::central_control::{LoanInfoResponse, WhitelistElemResponse, WhitelistResponse};
 
-use crate::liquidation_queue::LiquidationAmountResponse;
+use crate::liquidation_queue::{LiquidationAmountResponse, SimulateExecuteBidResponse};
 use crate::oracle_pyth::{PriceResponse, QueryMsg as oraclePythQueryMsg};
 use crate::rewards::AccruedRewardsResponse;
 use crate::stable_pool::ConfigResponse;
@@ -133,10 +133,11 @@ pub fn query_control_loan_info(
     deps: Deps,
     control_contract: String,
     minter: String,
+    vault_id: Option<u64>,
 ) -> StdResult<LoanInfoResponse> {
     let loan_info = deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
         contract_addr: control_contract,
-        msg: to_binary(&ControlQueryMsg::LoanInfo { minter })?,
+        msg: to_binary(&ControlQueryMsg::LoanInfo { minter, vault_id })?,
     }))?;
 
     Ok(loan_info)
@@ -198,6 +199,23 @@ pub fn query_liquidation_amount(
     Ok(liquidation_amount_res)
 }
 
+pub fn query_simulate_execute_bid(
+    deps: Deps,
+    liquidation_contract: Addr,
+    collateral_token: String,
+    amount: Uint256,
+    price: Decimal256,
+) -> StdResult<SimulateExecuteBidResponse> {
+    deps.querier.query(&QueryRequest::Wasm(WasmQuery::Smart {
+        contract_addr: liquidation_contract.to_string(),
+        msg: to_binary(&LiquidationQueryMsg::SimulateExecuteBid {
+            collateral_token,
+            amount,
+            price,
+        })?,
+    }))
+}
+
 pub fn query_collaterals_accrued_rewards(
     deps: Deps,
     reward_contract: String,
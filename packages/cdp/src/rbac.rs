@@ -0,0 +1,354 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shared role based access control.
+//!
+//! Every contract keeps its own role table, an address can hold any number of
+//! roles and each role is granted and revoked independently by an admin.
+//! A guardian can pause the contract, which blocks the handlers a contract
+//! guards with [`assert_not_paused`] until an admin unpauses it.
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    attr, Addr, CanonicalAddr, Deps, DepsMut, MessageInfo, Order, Response, StdError, StdResult,
+    Storage,
+};
+use cw_storage_plus::{Bound, Item, Map};
+
+use crate::events::config_change_event;
+
+const ROLES: Map<(&str, &[u8]), bool> = Map::new("rbac_roles");
+const PAUSED: Item<bool> = Item::new("rbac_paused");
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[cw_serde]
+#[derive(Copy)]
+pub enum Role {
+    /// Ownership, role management and config changes
+    Admin,
+    /// Collateral whitelisting and collateral parameters
+    RiskManager,
+    /// Cancels pending timelocked changes and pauses the contract
+    Guardian,
+    /// Fee parameters
+    FeeManager,
+    /// Permissioned upkeep operations
+    Keeper,
+}
+
+impl Role {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Admin => "admin",
+            Role::RiskManager => "risk_manager",
+            Role::Guardian => "guardian",
+            Role::FeeManager => "fee_manager",
+            Role::Keeper => "keeper",
+        }
+    }
+}
+
+#[cw_serde]
+pub struct HasRoleResponse {
+    pub role: Role,
+    pub address: String,
+    pub has_role: bool,
+}
+
+#[cw_serde]
+pub struct RoleMembersResponse {
+    pub role: Role,
+    pub members: Vec<String>,
+}
+
+#[cw_serde]
+pub struct PausedResponse {
+    pub paused: bool,
+}
+
+/// Errors of the contracts using the shared role handlers
+pub trait AccessError: From<StdError> {
+    fn unauthorized(function: &str, sender: &str) -> Self;
+    fn paused() -> Self;
+}
+
+pub fn grant_role(storage: &mut dyn Storage, role: Role, addr: &CanonicalAddr) -> StdResult<()> {
+    ROLES.save(storage, (role.as_str(), addr.as_slice()), &true)
+}
+
+pub fn revoke_role(storage: &mut dyn Storage, role: Role, addr: &CanonicalAddr) {
+    ROLES.remove(storage, (role.as_str(), addr.as_slice()))
+}
+
+pub fn has_role(storage: &dyn Storage, role: Role, addr: &CanonicalAddr) -> StdResult<bool> {
+    Ok(ROLES
+        .may_load(storage, (role.as_str(), addr.as_slice()))?
+        .unwrap_or(false))
+}
+
+pub fn has_any_role(storage: &dyn Storage, roles: &[Role], addr: &CanonicalAddr) -> StdResult<bool> {
+    for role in roles {
+        if has_role(storage, *role, addr)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+pub fn is_paused(storage: &dyn Storage) -> StdResult<bool> {
+    Ok(PAUSED.may_load(storage)?.unwrap_or(false))
+}
+
+pub fn assert_not_paused<E: AccessError>(storage: &dyn Storage) -> Result<(), E> {
+    if is_paused(storage)? {
+        return Err(E::paused());
+    }
+    Ok(())
+}
+
+pub fn assert_any_role<E: AccessError>(
+    deps: &DepsMut,
+    info: &MessageInfo,
+    roles: &[Role],
+    function: &str,
+) -> Result<CanonicalAddr, E> {
+    let sender_raw = deps.api.addr_canonicalize(info.sender.as_str())?;
+    if !has_any_role(deps.storage, roles, &sender_raw)? {
+        return Err(E::unauthorized(function, info.sender.as_str()));
+    }
+    Ok(sender_raw)
+}
+
+/// Admin only
+pub fn execute_grant_role<C, E: AccessError>(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract_module: &str,
+    role: Role,
+    address: Addr,
+) -> Result<Response<C>, E> {
+    assert_any_role::<E>(&deps, &info, &[Role::Admin], "grant_role")?;
+
+    grant_role(
+        deps.storage,
+        role,
+        &deps.api.addr_canonicalize(address.as_str())?,
+    )?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "grant_role"),
+            attr("role", role.as_str()),
+            attr("address", address.to_string()),
+        ])
+        .add_event(config_change_event(contract_module, "grant_role", info.sender)))
+}
+
+/// Admin only, an admin can not revoke its own admin role
+pub fn execute_revoke_role<C, E: AccessError>(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract_module: &str,
+    role: Role,
+    address: Addr,
+) -> Result<Response<C>, E> {
+    let sender_raw = assert_any_role::<E>(&deps, &info, &[Role::Admin], "revoke_role")?;
+
+    let address_raw = deps.api.addr_canonicalize(address.as_str())?;
+    if role == Role::Admin && address_raw == sender_raw {
+        return Err(StdError::generic_err("Admin can not revoke its own admin role").into());
+    }
+    revoke_role(deps.storage, role, &address_raw);
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "revoke_role"),
+            attr("role", role.as_str()),
+            attr("address", address.to_string()),
+        ])
+        .add_event(config_change_event(contract_module, "revoke_role", info.sender)))
+}
+
+/// Guardian or admin
+pub fn execute_pause<C, E: AccessError>(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract_module: &str,
+) -> Result<Response<C>, E> {
+    assert_any_role::<E>(&deps, &info, &[Role::Guardian, Role::Admin], "pause")?;
+    PAUSED.save(deps.storage, &true)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "pause")])
+        .add_event(config_change_event(contract_module, "pause", info.sender)))
+}
+
+/// Admin only
+pub fn execute_unpause<C, E: AccessError>(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract_module: &str,
+) -> Result<Response<C>, E> {
+    assert_any_role::<E>(&deps, &info, &[Role::Admin], "unpause")?;
+    PAUSED.save(deps.storage, &false)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![attr("action", "unpause")])
+        .add_event(config_change_event(contract_module, "unpause", info.sender)))
+}
+
+pub fn query_paused(deps: Deps) -> StdResult<PausedResponse> {
+    Ok(PausedResponse {
+        paused: is_paused(deps.storage)?,
+    })
+}
+
+pub fn query_has_role(deps: Deps, role: Role, address: String) -> StdResult<HasRoleResponse> {
+    let addr_raw = deps.api.addr_canonicalize(&address)?;
+    Ok(HasRoleResponse {
+        role,
+        address,
+        has_role: has_role(deps.storage, role, &addr_raw)?,
+    })
+}
+
+pub fn query_role_members(
+    deps: Deps,
+    role: Role,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<RoleMembersResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start_after = start_after
+        .map(|addr| deps.api.addr_canonicalize(&addr))
+        .transpose()?;
+    let start = start_after.as_ref().map(|addr| Bound::exclusive(addr.as_slice()));
+
+    let members = ROLES
+        .prefix(role.as_str())
+        .keys(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|key| {
+            let addr = deps.api.addr_humanize(&CanonicalAddr::from(key?))?;
+            Ok(addr.to_string())
+        })
+        .collect::<StdResult<Vec<String>>>()?;
+
+    Ok(RoleMembersResponse { role, members })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_dependencies;
+    use cosmwasm_std::{Api, Empty};
+
+    #[test]
+    fn proper_grant_and_revoke() {
+        let mut deps = mock_dependencies();
+        let keeper = deps.api.addr_canonicalize("keeper").unwrap();
+
+        grant_role(deps.as_mut().storage, Role::Keeper, &keeper).unwrap();
+        assert!(has_role(deps.as_ref().storage, Role::Keeper, &keeper).unwrap());
+        // roles are independent
+        assert!(!has_role(deps.as_ref().storage, Role::Admin, &keeper).unwrap());
+        assert!(
+            has_any_role(deps.as_ref().storage, &[Role::Admin, Role::Keeper], &keeper).unwrap()
+        );
+
+        let res = query_has_role(deps.as_ref(), Role::Keeper, "keeper".to_string()).unwrap();
+        assert!(res.has_role);
+
+        revoke_role(deps.as_mut().storage, Role::Keeper, &keeper);
+        assert!(!has_role(deps.as_ref().storage, Role::Keeper, &keeper).unwrap());
+    }
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Std(StdError),
+        Unauthorized(String),
+        Paused,
+    }
+
+    impl From<StdError> for TestError {
+        fn from(err: StdError) -> Self {
+            TestError::Std(err)
+        }
+    }
+
+    impl AccessError for TestError {
+        fn unauthorized(function: &str, _sender: &str) -> Self {
+            TestError::Unauthorized(function.to_string())
+        }
+
+        fn paused() -> Self {
+            TestError::Paused
+        }
+    }
+
+    #[test]
+    fn proper_pause_and_unpause() {
+        use cosmwasm_std::testing::mock_info;
+
+        let mut deps = mock_dependencies();
+        let admin = deps.api.addr_canonicalize("admin").unwrap();
+        let guardian = deps.api.addr_canonicalize("guardian").unwrap();
+        grant_role(deps.as_mut().storage, Role::Admin, &admin).unwrap();
+        grant_role(deps.as_mut().storage, Role::Guardian, &guardian).unwrap();
+
+        let err = execute_pause::<Empty, TestError>(deps.as_mut(), mock_info("addr0000", &[]), "")
+            .unwrap_err();
+        assert_eq!(err, TestError::Unauthorized("pause".to_string()));
+
+        execute_pause::<Empty, TestError>(deps.as_mut(), mock_info("guardian", &[]), "").unwrap();
+        assert_eq!(
+            assert_not_paused::<TestError>(deps.as_ref().storage).unwrap_err(),
+            TestError::Paused
+        );
+
+        // only an admin unpauses
+        let err = execute_unpause::<Empty, TestError>(deps.as_mut(), mock_info("guardian", &[]), "")
+            .unwrap_err();
+        assert_eq!(err, TestError::Unauthorized("unpause".to_string()));
+        execute_unpause::<Empty, TestError>(deps.as_mut(), mock_info("admin", &[]), "").unwrap();
+        assert!(!query_paused(deps.as_ref()).unwrap().paused);
+    }
+
+    #[test]
+    fn query_members_paginates() {
+        let mut deps = mock_dependencies();
+        for addr in ["addr0000", "addr0001", "addr0002"] {
+            let addr_raw = deps.api.addr_canonicalize(addr).unwrap();
+            grant_role(deps.as_mut().storage, Role::RiskManager, &addr_raw).unwrap();
+        }
+        let other = deps.api.addr_canonicalize("addr0003").unwrap();
+        grant_role(deps.as_mut().storage, Role::FeeManager, &other).unwrap();
+
+        let res = query_role_members(deps.as_ref(), Role::RiskManager, None, Some(2u32)).unwrap();
+        assert_eq!(res.members.len(), 2);
+
+        let res = query_role_members(
+            deps.as_ref(),
+            Role::RiskManager,
+            res.members.last().cloned(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(res.members.len(), 1);
+        assert!(!res.members.contains(&"addr0003".to_string()));
+    }
+}
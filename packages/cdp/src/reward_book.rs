@@ -12,18 +12,39 @@
 // See the License for the specific language governing permissions and
 // limitations under the License.
 
-use cosmwasm_bignumber::Uint256;
+//! Rewards are accounted per holder address: the balance of a holder is the sum of
+//! its collateral over all of its vaults. Splitting the rewards by vault is out of
+//! scope, a vault id only selects the loan auto repaid with them.
+
+use cosmwasm_bignumber::{Decimal256, Uint256};
 use cosmwasm_schema::{cw_serde, QueryResponses};
 
-use cosmwasm_std::{Decimal, Uint128};
+use cosmwasm_std::Uint128;
+use cw20::Denom;
+
+use crate::rbac::{HasRoleResponse, PausedResponse, Role, RoleMembersResponse};
+use crate::timelock::{PendingChangeResponse, PendingChangesResponse, TimelockConfigResponse};
 
 #[cw_serde]
 pub struct InstantiateMsg {
     pub control_contract: String,
     pub reward_contract: String,
     pub custody_contract: String,
-    pub reward_denom: String,
+    /// Tokens the rewards are paid in, native or CW20, at least one
+    pub reward_denoms: Vec<Denom>,
     pub threshold: Uint256,
+    /// Stable pool the loans of auto repaying holders are repaid to
+    pub pool_contract: Option<String>,
+    /// kUSD denom, rewards in it are repaid without a swap
+    pub stable_denom: Option<String>,
+    /// Adapter other rewards are swapped to kUSD through
+    pub swap_adapter: Option<String>,
+    /// Oracle the minimum return of the reward swaps is priced with
+    pub oracle_contract: Option<String>,
+    /// Address granted the guardian role, allowed to cancel pending config changes
+    pub guardian_addr: String,
+    /// Delay (seconds) before a proposed config change can be executed
+    pub timelock_delay: u64,
 }
 
 #[cw_serde]
@@ -31,18 +52,60 @@ pub enum ExecuteMsg {
     ////////////////////
     /// Owner's operations
     ///////////////////
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
     UpdateConfig {
         control_contract: Option<String>, 
         reward_contract: Option<String>,
         custody_contract: Option<String>,
-        reward_denom: Option<String>,
+        /// Replaces the reward denoms. A removed denom stops accruing, its accrued rewards
+        /// stay claimable. An added denom accrues from the balance held when it is added
+        reward_denoms: Option<Vec<Denom>>,
         threshold: Option<Uint256>,
+        pool_contract: Option<String>,
+        stable_denom: Option<String>,
+        swap_adapter: Option<String>,
+        oracle_contract: Option<String>,
+    },
+
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
+    UpdateTimelockConfig {
+        delay: Option<u64>,
+    },
+
+    /// Apply a pending change once its eta has passed
+    ExecuteChange {
+        id: u64,
+    },
+
+    /// Drop a pending change, callable by a guardian or an admin
+    CancelChange {
+        id: u64,
+    },
+
+    /// Admin only, admin and guardian grants are timelocked changes
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+
+    /// Admin only, an admin can not revoke its own admin role
+    RevokeRole {
+        role: Role,
+        address: String,
     },
 
+    /// Guardian or admin, blocks setting and running auto repay until unpaused
+    Pause {},
+
+    /// Admin only
+    Unpause {},
+
+    /// Timelocked, the new owner accepts the ownership once the change is executed
     SetOwner {
         new_owner_addr: String,
     },
 
+    /// Only the admin role of the previous owner is revoked
     AcceptOwnership {
     },
     
@@ -51,7 +114,7 @@ pub enum ExecuteMsg {
     /// bAsset's operations
     ///////////////////
 
-    /// Increase user staking balance
+    /// Increase user staking balance, whatever the vault the collateral is in
     /// Withdraw rewards to pending rewards
     /// Set current reward index to global index
     IncreaseBalance { address: String, amount: Uint128 },
@@ -59,6 +122,10 @@ pub enum ExecuteMsg {
     /// Withdraw rewards to pending rewards
     /// Set current reward index to global index
     DecreaseBalance { address: String, amount: Uint128 },
+    /// Move staking balance between users, the total balance is unchanged
+    /// Withdraw rewards of both users to pending rewards
+    /// Set their reward index to global index
+    TransferBalance { from: String, to: String, amount: Uint128 },
 
     ////////////////////
     /// User's operations
@@ -70,8 +137,42 @@ pub enum ExecuteMsg {
     /// for test
     ExecuteUpdateGlobalIndex{},
 
-    /// return the accrued reward in uusd to the user.
+    /// return the accrued rewards of every reward denom to the user.
+    /// The rewards of an auto repaying holder repay its loan instead, without a recipient
     ClaimRewards { recipient: Option<String> },
+
+    /// Repay the sender's kUSD loan with its rewards instead of paying them out,
+    /// auto repay is turned off when not set
+    SetAutoRepay { auto_repay: Option<AutoRepay> },
+
+    /// Move the rewards of the next holders stored in a former layout, callable by anyone
+    /// after a migration until all holders are moved. Other messages are rejected meanwhile
+    MigrateHolders {
+        limit: Option<u32>,
+    },
+
+    /// Contract itself only, repays the loan of an auto repaying holder with its rewards
+    AutoRepay { holder: String },
+
+    /// Contract itself only, repays the kUSD received from the swaps of the holder's
+    /// rewards and `direct_amount` of rewards already in kUSD
+    RepaySwapped {
+        holder: String,
+        vault_id: Option<u64>,
+        prev_balance: Uint128,
+        direct_amount: Uint128,
+    },
+}
+
+/// Rewards of the holder are swapped to kUSD and repay the loan of its vault,
+/// any kUSD beyond the loan is sent to the holder. The rewards repaid are those
+/// of every vault of the holder
+#[cw_serde]
+pub struct AutoRepay {
+    /// the default vault when not set
+    pub vault_id: Option<u64>,
+    /// Maximum shortfall of the swap return from its simulation
+    pub max_slippage: Decimal256,
 }
 
 
@@ -102,6 +203,36 @@ pub enum QueryMsg {
         start_after: Option<String>,
         limit: Option<u32>,
     },
+
+    #[returns(TimelockConfigResponse)]
+    TimelockConfig {},
+
+    #[returns(PendingChangeResponse)]
+    PendingChange {
+        id: u64,
+    },
+
+    #[returns(PendingChangesResponse)]
+    PendingChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    #[returns(HasRoleResponse)]
+    HasRole {
+        role: Role,
+        address: String,
+    },
+
+    #[returns(RoleMembersResponse)]
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(PausedResponse)]
+    Paused {},
 }
 
 #[cw_serde]
@@ -109,28 +240,52 @@ pub struct ConfigResponse {
     pub control_contract: String,
     pub reward_contract: String,
     pub custody_contract: String,
-    pub reward_denom: String,
+    pub reward_denoms: Vec<Denom>,
     pub owner: String,
+    pub pool_contract: Option<String>,
+    pub stable_denom: Option<String>,
+    pub swap_adapter: Option<String>,
+    pub oracle_contract: Option<String>,
+}
+
+#[cw_serde]
+pub struct RewardIndexResponse {
+    pub denom: Denom,
+    pub global_index: Decimal256,
+    pub prev_reward_balance: Uint128,
 }
 
 #[cw_serde]
 pub struct StateResponse {
-    pub global_index: Decimal,
     pub total_balance: Uint128,
-    pub prev_reward_balance: Uint128,
+    pub reward_indexes: Vec<RewardIndexResponse>,
+}
+
+#[cw_serde]
+pub struct RewardAmount {
+    pub denom: Denom,
+    pub amount: Uint128,
 }
 
 #[cw_serde]
 pub struct AccruedRewardsResponse {
-    pub rewards: Uint128,
+    /// one entry per reward denom
+    pub rewards: Vec<RewardAmount>,
+}
+
+#[cw_serde]
+pub struct HolderRewardResponse {
+    pub denom: Denom,
+    pub index: Decimal256,
+    pub pending_rewards: Decimal256,
 }
 
 #[cw_serde]
 pub struct HolderResponse {
     pub address: String,
     pub balance: Uint128,
-    pub index: Decimal,
-    pub pending_rewards: Decimal,
+    pub rewards: Vec<HolderRewardResponse>,
+    pub auto_repay: Option<AutoRepay>,
 }
 
 #[cw_serde]
@@ -141,4 +296,15 @@ pub struct HoldersResponse {
 
 
 #[cw_serde]
-pub struct MigrateMsg {}
+pub enum MigrateMsg {
+    /// Upgrade a deployment instantiated before cw2 version tracking. Sets up the
+    /// timelock and the roles, the current owner is granted the admin role.
+    /// Also converts the `Decimal` reward indexes to `Decimal256`, a first page of
+    /// holders is converted, see `MigrateHolders`
+    FromLegacy {
+        guardian_addr: String,
+        timelock_delay: u64,
+    },
+    /// Upgrade between versioned releases
+    Upgrade {},
+}
@@ -16,27 +16,70 @@ use cosmwasm_bignumber::Uint256;
 use cosmwasm_std::Uint128;
 use cosmwasm_schema::{cw_serde,QueryResponses};
 
+use crate::rbac::{HasRoleResponse, PausedResponse, Role, RoleMembersResponse};
+use crate::timelock::{PendingChangeResponse, PendingChangesResponse, TimelockConfigResponse};
+
 #[cw_serde]
 pub struct InstantiateMsg {
     pub sub_demon: String,
     pub owner_addr: String, 
     pub control_contract: String, 
     pub min_redeem_value: Uint256,
+    /// Address granted the guardian role, allowed to cancel pending config changes
+    pub guardian_addr: String,
+    /// Delay (seconds) before a proposed config change can be executed
+    pub timelock_delay: u64,
 }
 
 
 #[cw_serde]
 pub enum ExecuteMsg {
 
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
     UpdateConfig {
         control_contract: Option<String>,
         min_redeem_value: Option<Uint256>,
     },
 
+    /// Timelocked, queued as a pending change and applied via `ExecuteChange`
+    UpdateTimelockConfig {
+        delay: Option<u64>,
+    },
+
+    /// Apply a pending change once its eta has passed
+    ExecuteChange {
+        id: u64,
+    },
+
+    /// Drop a pending change, callable by a guardian or an admin
+    CancelChange {
+        id: u64,
+    },
+
+    /// Admin only, admin and guardian grants are timelocked changes
+    GrantRole {
+        role: Role,
+        address: String,
+    },
+
+    /// Admin only, an admin can not revoke its own admin role
+    RevokeRole {
+        role: Role,
+        address: String,
+    },
+
+    /// Guardian or admin, blocks kUSD redemptions until unpaused
+    Pause {},
+
+    /// Admin only
+    Unpause {},
+
+    /// Timelocked, the new owner accepts the ownership once the change is executed
     SetOwner {
         new_owner_addr: String,
     },
 
+    /// Only the admin role of the previous owner is revoked
     AcceptOwnership {
     },
     
@@ -45,20 +88,42 @@ pub enum ExecuteMsg {
         stable_amount: Uint128,
     },
 
-    RepayStableCoin{ }, 
+    /// Repay kUSD sent along with the message, the loan of `minter` is repaid when
+    /// set and the loan of the sender otherwise. Any excess is refunded to the sender
+    RepayStableCoin {
+        minter: Option<String>,
+        vault_id: Option<u64>,
+    },
 
     RedeemStableCoin{
         minter: String,
+        vault_id: Option<u64>,
     },
 
     RepayStableFromLiquidation{
         minter: String,
+        vault_id: Option<u64>,
         pre_balance: Uint256,
-    }
+    },
+
+    /// Repay the full loan of the sender's vault with the kUSD sent along and withdraw
+    /// every collateral of the vault. Any excess is refunded to the sender
+    ClosePosition {
+        vault_id: Option<u64>,
+    },
 }
 
 #[cw_serde]
-pub struct MigrateMsg{}
+pub enum MigrateMsg {
+    /// Upgrade a deployment instantiated before cw2 version tracking. Sets up the
+    /// timelock and the roles, the current owner is granted the admin role.
+    FromLegacy {
+        guardian_addr: String,
+        timelock_delay: u64,
+    },
+    /// Upgrade between versioned releases
+    Upgrade {},
+}
 
 #[cw_serde]
 #[derive(QueryResponses)]
@@ -66,7 +131,37 @@ pub enum QueryMsg {
     #[returns(ConfigResponse)]
     Config {},
     #[returns(StateResponse)]
-    State{}
+    State{},
+
+    #[returns(TimelockConfigResponse)]
+    TimelockConfig {},
+
+    #[returns(PendingChangeResponse)]
+    PendingChange {
+        id: u64,
+    },
+
+    #[returns(PendingChangesResponse)]
+    PendingChanges {
+        start_after: Option<u64>,
+        limit: Option<u32>,
+    },
+
+    #[returns(HasRoleResponse)]
+    HasRole {
+        role: Role,
+        address: String,
+    },
+
+    #[returns(RoleMembersResponse)]
+    RoleMembers {
+        role: Role,
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+
+    #[returns(PausedResponse)]
+    Paused {},
 }
 
 
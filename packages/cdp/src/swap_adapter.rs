@@ -0,0 +1,62 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Interface of the swap adapters the liquidation queue sells the collateral of
+//! auto rebidding bids through, and the reward book converts rewards to kUSD with.
+//! An adapter wraps a dex, it receives cw20 or native tokens and sends the native
+//! ask asset back.
+
+use cosmwasm_schema::{cw_serde, QueryResponses};
+use cosmwasm_std::Uint128;
+use cw20::Cw20ReceiveMsg;
+
+#[cw_serde]
+pub enum ExecuteMsg {
+    Receive(Cw20ReceiveMsg),
+    /// Swap the native tokens sent along with the message, as `Cw20HookMsg::Swap`
+    Swap {
+        ask_denom: String,
+        min_receive: Uint128,
+        recipient: Option<String>,
+    },
+}
+
+#[cw_serde]
+pub enum Cw20HookMsg {
+    /// Swap the received tokens into `ask_denom`, failing when the return is
+    /// below `min_receive`. The return goes to the sender of the tokens unless
+    /// a recipient is given
+    Swap {
+        ask_denom: String,
+        min_receive: Uint128,
+        recipient: Option<String>,
+    },
+}
+
+#[cw_serde]
+#[derive(QueryResponses)]
+pub enum QueryMsg {
+    /// `offer_token` is a cw20 contract address or a native denom
+    #[returns(SimulateSwapResponse)]
+    SimulateSwap {
+        offer_token: String,
+        offer_amount: Uint128,
+        ask_denom: String,
+    },
+}
+
+#[cw_serde]
+pub struct SimulateSwapResponse {
+    pub return_amount: Uint128,
+}
@@ -0,0 +1,319 @@
+// Copyright 2023 Kryptonite Labs.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! Shared timelock for owner parameter changes.
+//!
+//! A contract stores its own serialized `ExecuteMsg` as a pending change with an
+//! eta of `now + delay`. Once the eta has passed the change can be executed, and
+//! until then a guardian (see [`crate::rbac::Role::Guardian`]) can cancel it.
+//! Contracts validate a change when it is proposed and apply it through
+//! [`execute_matured_change`].
+
+use cosmwasm_schema::cw_serde;
+use cosmwasm_std::{
+    attr, from_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError,
+    StdResult, Storage,
+};
+use cw_storage_plus::{Bound, Item, Map};
+use schemars::JsonSchema;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+
+use crate::events::config_change_event;
+use crate::rbac::{assert_any_role, AccessError, Role};
+
+const TIMELOCK_CONFIG: Item<TimelockConfig> = Item::new("timelock_config");
+const PENDING_CHANGES: Map<u64, PendingChange> = Map::new("timelock_pending");
+const NEXT_CHANGE_ID: Item<u64> = Item::new("timelock_next_id");
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TimelockConfig {
+    /// Seconds between proposing a change and being able to execute it
+    pub delay: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct PendingChange {
+    pub id: u64,
+    /// The contract's own serialized `ExecuteMsg`
+    pub msg: Binary,
+    /// Block time (seconds) after which the change can be executed
+    pub eta: u64,
+}
+
+#[cw_serde]
+pub struct TimelockConfigResponse {
+    pub delay: u64,
+}
+
+#[cw_serde]
+pub struct PendingChangeResponse {
+    pub id: u64,
+    pub msg: Binary,
+    pub eta: u64,
+}
+
+#[cw_serde]
+pub struct PendingChangesResponse {
+    pub changes: Vec<PendingChangeResponse>,
+}
+
+pub fn store_timelock_config(storage: &mut dyn Storage, config: &TimelockConfig) -> StdResult<()> {
+    TIMELOCK_CONFIG.save(storage, config)
+}
+
+pub fn read_timelock_config(storage: &dyn Storage) -> StdResult<TimelockConfig> {
+    TIMELOCK_CONFIG
+        .may_load(storage)?
+        .ok_or_else(|| StdError::generic_err("Timelock is not configured"))
+}
+
+/// Apply a matured `UpdateTimelockConfig` change
+pub fn update_timelock_config(storage: &mut dyn Storage, delay: Option<u64>) -> StdResult<()> {
+    let mut config = read_timelock_config(storage)?;
+
+    if let Some(delay) = delay {
+        config.delay = delay;
+    }
+
+    store_timelock_config(storage, &config)
+}
+
+/// Queue a change which becomes executable after the configured delay
+pub fn propose_change(storage: &mut dyn Storage, env: &Env, msg: Binary) -> StdResult<PendingChange> {
+    let config = read_timelock_config(storage)?;
+    let id = NEXT_CHANGE_ID.may_load(storage)?.unwrap_or(1u64);
+    NEXT_CHANGE_ID.save(storage, &(id + 1))?;
+
+    let change = PendingChange {
+        id,
+        msg,
+        eta: env.block.time.plus_seconds(config.delay).seconds(),
+    };
+    PENDING_CHANGES.save(storage, id, &change)?;
+
+    Ok(change)
+}
+
+/// Remove and return a pending change whose eta has passed
+pub fn take_matured_change(storage: &mut dyn Storage, env: &Env, id: u64) -> StdResult<PendingChange> {
+    let change = read_pending_change(storage, id)?;
+    if change.eta > env.block.time.seconds() {
+        return Err(StdError::generic_err(format!(
+            "Timelock has not expired, change {} executable at {}",
+            id, change.eta
+        )));
+    }
+    PENDING_CHANGES.remove(storage, id);
+
+    Ok(change)
+}
+
+pub fn cancel_change(storage: &mut dyn Storage, id: u64) -> StdResult<PendingChange> {
+    let change = read_pending_change(storage, id)?;
+    PENDING_CHANGES.remove(storage, id);
+
+    Ok(change)
+}
+
+pub fn read_pending_change(storage: &dyn Storage, id: u64) -> StdResult<PendingChange> {
+    PENDING_CHANGES
+        .may_load(storage, id)?
+        .ok_or_else(|| StdError::generic_err(format!("No pending change with id {}", id)))
+}
+
+/// Queue `msg`, the contract's own `ExecuteMsg`, proposed by a holder of one of `roles`
+pub fn execute_propose_change<C, E: AccessError>(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    contract_module: &str,
+    roles: &[Role],
+    msg: Binary,
+) -> Result<Response<C>, E> {
+    assert_any_role::<E>(&deps, &info, roles, "propose_timelocked_change")?;
+
+    let change = propose_change(deps.storage, env, msg)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "propose_timelocked_change"),
+            attr("change_id", change.id.to_string()),
+            attr("eta", change.eta.to_string()),
+        ])
+        .add_event(config_change_event(
+            contract_module,
+            "propose_timelocked_change",
+            info.sender,
+        )))
+}
+
+/// Apply a matured change with `apply`, which returns the action of the change.
+/// The sender must hold one of the roles `roles` returns for the change
+pub fn execute_matured_change<T, C, E, F>(
+    deps: DepsMut,
+    env: &Env,
+    info: MessageInfo,
+    contract_module: &str,
+    id: u64,
+    roles: fn(&T) -> &'static [Role],
+    apply: F,
+) -> Result<Response<C>, E>
+where
+    T: DeserializeOwned,
+    E: AccessError,
+    F: FnOnce(DepsMut, T) -> Result<&'static str, E>,
+{
+    let change = take_matured_change(deps.storage, env, id)?;
+    let msg: T = from_binary(&change.msg)?;
+    assert_any_role::<E>(&deps, &info, roles(&msg), "execute_timelocked_change")?;
+
+    let action = apply(deps, msg)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "execute_timelocked_change"),
+            attr("change_id", id.to_string()),
+        ])
+        .add_event(config_change_event(contract_module, action, info.sender)))
+}
+
+/// Guardian or admin
+pub fn execute_cancel_change<C, E: AccessError>(
+    deps: DepsMut,
+    info: MessageInfo,
+    contract_module: &str,
+    id: u64,
+) -> Result<Response<C>, E> {
+    assert_any_role::<E>(
+        &deps,
+        &info,
+        &[Role::Guardian, Role::Admin],
+        "cancel_timelocked_change",
+    )?;
+
+    cancel_change(deps.storage, id)?;
+
+    Ok(Response::new()
+        .add_attributes(vec![
+            attr("action", "cancel_timelocked_change"),
+            attr("change_id", id.to_string()),
+        ])
+        .add_event(config_change_event(
+            contract_module,
+            "cancel_timelocked_change",
+            info.sender,
+        )))
+}
+
+pub fn query_timelock_config(deps: Deps) -> StdResult<TimelockConfigResponse> {
+    let config = read_timelock_config(deps.storage)?;
+    Ok(TimelockConfigResponse {
+        delay: config.delay,
+    })
+}
+
+pub fn query_pending_change(deps: Deps, id: u64) -> StdResult<PendingChangeResponse> {
+    let change = read_pending_change(deps.storage, id)?;
+    Ok(PendingChangeResponse {
+        id: change.id,
+        msg: change.msg,
+        eta: change.eta,
+    })
+}
+
+pub fn query_pending_changes(
+    deps: Deps,
+    start_after: Option<u64>,
+    limit: Option<u32>,
+) -> StdResult<PendingChangesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+
+    let changes = PENDING_CHANGES
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|elem| {
+            let (_, change) = elem?;
+            Ok(PendingChangeResponse {
+                id: change.id,
+                msg: change.msg,
+                eta: change.eta,
+            })
+        })
+        .collect::<StdResult<Vec<PendingChangeResponse>>>()?;
+
+    Ok(PendingChangesResponse { changes })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::{mock_dependencies, mock_env};
+    use cosmwasm_std::to_binary;
+
+    #[test]
+    fn proper_propose_and_execute() {
+        let mut deps = mock_dependencies();
+        let mut env = mock_env();
+        store_timelock_config(deps.as_mut().storage, &TimelockConfig { delay: 100u64 }).unwrap();
+
+        let msg = to_binary(&"update").unwrap();
+        let change = propose_change(deps.as_mut().storage, &env, msg.clone()).unwrap();
+        assert_eq!(change.id, 1u64);
+        assert_eq!(change.eta, env.block.time.seconds() + 100u64);
+
+        let res = query_pending_changes(deps.as_ref(), None, None).unwrap();
+        assert_eq!(res.changes.len(), 1);
+        assert_eq!(res.changes[0].msg, msg);
+
+        // not yet executable
+        env.block.time = env.block.time.plus_seconds(99u64);
+        take_matured_change(deps.as_mut().storage, &env, 1u64).unwrap_err();
+
+        env.block.time = env.block.time.plus_seconds(1u64);
+        let change = take_matured_change(deps.as_mut().storage, &env, 1u64).unwrap();
+        assert_eq!(change.msg, msg);
+
+        // a change can only be executed once
+        take_matured_change(deps.as_mut().storage, &env, 1u64).unwrap_err();
+    }
+
+    #[test]
+    fn proper_cancel() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        store_timelock_config(deps.as_mut().storage, &TimelockConfig { delay: 100u64 }).unwrap();
+
+        propose_change(deps.as_mut().storage, &env, to_binary(&"first").unwrap()).unwrap();
+        propose_change(deps.as_mut().storage, &env, to_binary(&"second").unwrap()).unwrap();
+        cancel_change(deps.as_mut().storage, 1u64).unwrap();
+
+        let res = query_pending_changes(deps.as_ref(), None, None).unwrap();
+        assert_eq!(res.changes.len(), 1);
+        assert_eq!(res.changes[0].id, 2u64);
+        query_pending_change(deps.as_ref(), 1u64).unwrap_err();
+    }
+
+    #[test]
+    fn propose_without_config_fails() {
+        let mut deps = mock_dependencies();
+        let env = mock_env();
+        propose_change(deps.as_mut().storage, &env, to_binary(&"update").unwrap()).unwrap_err();
+    }
+}